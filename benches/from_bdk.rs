@@ -0,0 +1,69 @@
+//! Conversion of BDK transactions for the ordinals crate
+//!
+//! Compares `from_bdk`, which goes through consensus encoding, with the
+//! field-by-field conversion it replaced, over a block's worth of
+//! transactions. Run with `cargo bench --bench from_bdk`.
+
+use bdk::bitcoin::consensus::deserialize;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use deezel_cli::runestone_enhanced::from_bdk;
+use std::str::FromStr;
+
+/// A mainnet alkane mint, one input with a witness and two outputs
+const TX_HEX: &str = "0200000000010141de32694c6aece390828c54475862396edfd46289bbd0f7b78f3e34ee80b7880300000000fdffffff024a010000000000002251200e5843aef2fa13444715b7002071678368e2ae5a6da415e0395448ad1cc9c2200000000000000000116a5d0eff7f818cec82d08bc0a882cdd215024830450221008c8de39854dfea97bfc0cac9f2d0843664b413eb6e135fd99896fb4b03b2e26402207003b3ec1950edd4593130ad934a2551ee4cb7249511a73263441ee6cc37b73a01210287698f1cd27599d8d32fdd5a29fa500d54d8bb2ef5355ca6753107539c47a9b500000000";
+
+/// Transactions in the benchmarked block
+const BLOCK_SIZE: usize = 2_000;
+
+/// The previous conversion, through txid strings and copied fields
+fn from_bdk_by_fields(v: &bdk::bitcoin::Transaction) -> bitcoin::Transaction {
+    let input = v.input.iter()
+        .map(|input| bitcoin::TxIn {
+            previous_output: bitcoin::OutPoint {
+                txid: bitcoin::Txid::from_str(&input.previous_output.txid.to_string()).unwrap(),
+                vout: input.previous_output.vout,
+            },
+            script_sig: bitcoin::ScriptBuf::from_bytes(input.script_sig.as_bytes().to_vec()),
+            sequence: bitcoin::Sequence(input.sequence.0),
+            witness: bitcoin::Witness::from_slice(&input.witness.to_vec()),
+        })
+        .collect();
+    let output = v.output.iter()
+        .map(|output| bitcoin::TxOut {
+            value: bitcoin::Amount::from_sat(output.value),
+            script_pubkey: bitcoin::ScriptBuf::from_bytes(output.script_pubkey.as_bytes().to_vec()),
+        })
+        .collect();
+    
+    bitcoin::Transaction {
+        version: bitcoin::transaction::Version(v.version),
+        lock_time: bitcoin::absolute::LockTime::from_consensus(v.lock_time.to_consensus_u32()),
+        input,
+        output,
+    }
+}
+
+fn bench_from_bdk(c: &mut Criterion) {
+    let tx: bdk::bitcoin::Transaction = deserialize(&hex::decode(TX_HEX).unwrap()).unwrap();
+    let block = vec![tx; BLOCK_SIZE];
+    
+    let mut group = c.benchmark_group("from_bdk");
+    group.bench_function("consensus_encoding", |b| {
+        b.iter(|| {
+            for tx in &block {
+                black_box(from_bdk(black_box(tx)).unwrap());
+            }
+        })
+    });
+    group.bench_function("field_by_field", |b| {
+        b.iter(|| {
+            for tx in &block {
+                black_box(from_bdk_by_fields(black_box(tx)));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_from_bdk);
+criterion_main!(benches);
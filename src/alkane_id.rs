@@ -0,0 +1,142 @@
+//! Alkane and protorune identifiers
+//!
+//! Alkanes and protorunes are identified by the block and transaction index
+//! of their creation, written `block:tx` (e.g. `2:0` for DIESEL).
+
+use protorune_support::balance_sheet::ProtoruneRuneId;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Identifier of an alkane or protorune
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct AlkaneId {
+    /// Block component
+    pub block: u128,
+    /// Transaction component
+    pub tx: u128,
+}
+
+/// Error parsing an `AlkaneId` from a string
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseAlkaneIdError {
+    /// The string isn't two components separated by a colon
+    #[error("Invalid ID '{0}'. Expected 'block:tx'")]
+    Format(String),
+    /// A component isn't an unsigned integer
+    #[error("Invalid {component} in ID '{id}'. Expected a number")]
+    Component {
+        /// Name of the component, `block` or `tx`
+        component: &'static str,
+        /// The whole string being parsed
+        id: String,
+    },
+}
+
+impl AlkaneId {
+    /// Create an ID from its components
+    pub const fn new(block: u128, tx: u128) -> Self {
+        Self { block, tx }
+    }
+}
+
+impl fmt::Display for AlkaneId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.block, self.tx)
+    }
+}
+
+impl FromStr for AlkaneId {
+    type Err = ParseAlkaneIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (block, tx) = s.split_once(':').ok_or_else(|| ParseAlkaneIdError::Format(s.to_string()))?;
+        let component = |value: &str, component| value.parse::<u128>()
+            .map_err(|_| ParseAlkaneIdError::Component { component, id: s.to_string() });
+
+        Ok(Self {
+            block: component(block, "block")?,
+            tx: component(tx, "tx")?,
+        })
+    }
+}
+
+impl From<AlkaneId> for String {
+    fn from(id: AlkaneId) -> Self {
+        id.to_string()
+    }
+}
+
+impl TryFrom<String> for AlkaneId {
+    type Error = ParseAlkaneIdError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<(u128, u128)> for AlkaneId {
+    fn from((block, tx): (u128, u128)) -> Self {
+        Self { block, tx }
+    }
+}
+
+impl From<AlkaneId> for (u128, u128) {
+    fn from(id: AlkaneId) -> Self {
+        (id.block, id.tx)
+    }
+}
+
+impl From<ProtoruneRuneId> for AlkaneId {
+    fn from(id: ProtoruneRuneId) -> Self {
+        Self { block: id.block, tx: id.tx }
+    }
+}
+
+impl From<AlkaneId> for ProtoruneRuneId {
+    fn from(id: AlkaneId) -> Self {
+        ProtoruneRuneId { block: id.block, tx: id.tx }
+    }
+}
+
+impl From<ordinals::RuneId> for AlkaneId {
+    fn from(id: ordinals::RuneId) -> Self {
+        Self { block: u128::from(id.block), tx: u128::from(id.tx) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        let id: AlkaneId = "2:0".parse().unwrap();
+        assert_eq!(id, AlkaneId::new(2, 0));
+        assert_eq!(id.to_string(), "2:0");
+        assert_eq!(format!("{}:1", u128::MAX).parse::<AlkaneId>().unwrap(), AlkaneId::new(u128::MAX, 1));
+
+        assert!(matches!("2".parse::<AlkaneId>(), Err(ParseAlkaneIdError::Format(_))));
+        assert!(matches!("2:0:1".parse::<AlkaneId>(), Err(ParseAlkaneIdError::Component { component: "tx", .. })));
+        assert!(matches!("x:0".parse::<AlkaneId>(), Err(ParseAlkaneIdError::Component { component: "block", .. })));
+        assert!(matches!("2:-1".parse::<AlkaneId>(), Err(ParseAlkaneIdError::Component { component: "tx", .. })));
+    }
+
+    #[test]
+    fn test_serde_uses_the_string_form() {
+        let id = AlkaneId::new(4, 100);
+        assert_eq!(serde_json::to_value(id).unwrap(), serde_json::json!("4:100"));
+        assert_eq!(serde_json::from_value::<AlkaneId>(serde_json::json!("4:100")).unwrap(), id);
+        assert!(serde_json::from_value::<AlkaneId>(serde_json::json!("4")).is_err());
+    }
+
+    #[test]
+    fn test_conversions() {
+        let edict_id = ProtoruneRuneId { block: 2, tx: 1 };
+        assert_eq!(AlkaneId::from(edict_id), AlkaneId::new(2, 1));
+        assert_eq!(AlkaneId::from(ordinals::RuneId { block: 840_000, tx: 3 }), AlkaneId::new(840_000, 3));
+        assert_eq!(<(u128, u128)>::from(AlkaneId::new(2, 0)), (2, 0));
+    }
+}
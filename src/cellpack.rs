@@ -0,0 +1,163 @@
+//! Alkanes cellpacks
+//!
+//! The message of an alkanes protostone is a cellpack: LEB128 varints
+//! naming the target contract, `block` then `tx`, followed by the inputs of
+//! the call, the first of which is the opcode. The DIESEL mint is
+//! `[2, 0, 77]`, opcode 77 of contract `2:0`.
+
+use serde::Serialize;
+use std::fmt;
+use thiserror::Error;
+
+use crate::alkane_id::AlkaneId;
+use crate::runestone::{diesel_id, encode_cellpack};
+use crate::varint;
+
+/// Opcode of the DIESEL mint
+pub const MINT_OPCODE: u128 = 77;
+
+/// Opcode contracts run when deployed
+pub const INITIALIZE_OPCODE: u128 = 0;
+
+/// A contract call decoded from a protostone message
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Cellpack {
+    /// Contract called, or the deployment target
+    pub target: AlkaneId,
+    /// Inputs of the call, the opcode first
+    #[serde(serialize_with = "decimal_seq")]
+    pub inputs: Vec<u128>,
+}
+
+/// Error decoding a `Cellpack` from a protostone message
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CellpackError {
+    /// The message isn't a sequence of LEB128 varints
+    #[error("Invalid cellpack varint: {0}")]
+    Varint(String),
+    /// The message has fewer than the two values of a target
+    #[error("Cellpack has {0} value(s), a target takes 2")]
+    MissingTarget(usize),
+}
+
+impl Cellpack {
+    /// Create a cellpack calling `target` with `inputs`
+    pub fn new(target: AlkaneId, inputs: Vec<u128>) -> Self {
+        Self { target, inputs }
+    }
+
+    /// Decode a protostone message
+    ///
+    /// Every byte must belong to a varint, so a message ending in the middle
+    /// of one (a continuation bit on its last byte) is rejected.
+    pub fn decode(message: &[u8]) -> Result<Self, CellpackError> {
+        let values = varint::decode_all(message).map_err(|e| CellpackError::Varint(format!("{:#}", e)))?;
+        Self::from_values(&values)
+    }
+
+    /// Split cellpack values into the target and the inputs
+    pub fn from_values(values: &[u128]) -> Result<Self, CellpackError> {
+        match values {
+            [block, tx, inputs @ ..] => Ok(Self::new(AlkaneId::new(*block, *tx), inputs.to_vec())),
+            _ => Err(CellpackError::MissingTarget(values.len())),
+        }
+    }
+
+    /// The target and inputs as a flat list of values
+    pub fn to_values(&self) -> Vec<u128> {
+        [self.target.block, self.target.tx].into_iter().chain(self.inputs.iter().copied()).collect()
+    }
+
+    /// Encode the cellpack as a protostone message
+    pub fn encode(&self) -> Vec<u8> {
+        encode_cellpack(&self.to_values())
+    }
+
+    /// Opcode of the call, the first input
+    pub fn opcode(&self) -> Option<u128> {
+        self.inputs.first().copied()
+    }
+
+    /// Inputs following the opcode
+    pub fn arguments(&self) -> &[u128] {
+        self.inputs.get(1..).unwrap_or_default()
+    }
+
+    /// Whether the target deploys a contract rather than calling one
+    ///
+    /// Block 1 deploys new bytecode, 3 deploys to a reserved `4:tx` ID, and
+    /// 5 and 6 clone the contracts at `2:tx` and `4:tx`.
+    pub fn is_deploy(&self) -> bool {
+        matches!(self.target.block, 1 | 3 | 5 | 6)
+    }
+
+    /// Whether the cellpack mints DIESEL: opcode 77 of contract `2:0`
+    pub fn is_diesel_mint(&self) -> bool {
+        self.target == AlkaneId::new(diesel_id::BLOCK, diesel_id::TX) && self.opcode() == Some(MINT_OPCODE)
+    }
+}
+
+impl fmt::Display for Cellpack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.target)?;
+        if let Some(opcode) = self.opcode() {
+            write!(f, " opcode {}", opcode)?;
+        }
+        if !self.arguments().is_empty() {
+            let arguments: Vec<String> = self.arguments().iter().map(u128::to_string).collect();
+            write!(f, " [{}]", arguments.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Serialize values as decimal strings, which hold any u128 in JSON
+fn decimal_seq<S: serde::Serializer>(values: &[u128], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(values.iter().map(u128::to_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diesel_mint() {
+        let cellpack = Cellpack::decode(&[2, 0, 77]).unwrap();
+        assert_eq!(cellpack, Cellpack::new(AlkaneId::new(2, 0), vec![77]));
+        assert!(cellpack.is_diesel_mint());
+        assert!(!cellpack.is_deploy());
+        assert_eq!(cellpack.to_string(), "2:0 opcode 77");
+        assert_eq!(cellpack.encode(), vec![2, 0, 77]);
+
+        // Opcode 77 of another contract isn't a DIESEL mint
+        assert!(!Cellpack::decode(&[2, 19, 77]).unwrap().is_diesel_mint());
+    }
+
+    #[test]
+    fn test_contract_call_with_arguments() {
+        let values = [4, 0x1234, 101, 1_000_000, u128::MAX];
+        let cellpack = Cellpack::decode(&encode_cellpack(&values)).unwrap();
+        assert_eq!(cellpack.target, AlkaneId::new(4, 0x1234));
+        assert_eq!(cellpack.opcode(), Some(101));
+        assert_eq!(cellpack.arguments(), &[1_000_000, u128::MAX]);
+        assert_eq!(cellpack.to_values(), values);
+        assert_eq!(cellpack.to_string(), format!("4:4660 opcode 101 [1000000, {}]", u128::MAX));
+        assert_eq!(serde_json::to_value(&cellpack).unwrap(), serde_json::json!({
+            "target": "4:4660",
+            "inputs": ["101", "1000000", u128::MAX.to_string()],
+        }));
+
+        // A deployment without inputs
+        let deploy = Cellpack::decode(&[3, 0x80, 0x01]).unwrap();
+        assert!(deploy.is_deploy());
+        assert_eq!((deploy.target, deploy.opcode()), (AlkaneId::new(3, 128), None));
+    }
+
+    #[test]
+    fn test_malformed_messages() {
+        // A trailing byte with its continuation bit set
+        assert!(matches!(Cellpack::decode(&[2, 0, 77, 0x80]), Err(CellpackError::Varint(_))));
+        assert!(matches!(Cellpack::decode(&[2]), Err(CellpackError::MissingTarget(1))));
+        assert!(matches!(Cellpack::decode(&[]), Err(CellpackError::MissingTarget(0))));
+    }
+}
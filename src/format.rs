@@ -0,0 +1,109 @@
+//! Display formatting for protorune amounts
+//!
+//! Protorune balances are raw integers scaled by the rune's divisibility,
+//! so `123456789` with divisibility 8 is `1.23456789` tokens. These helpers
+//! convert between the two without going through floating point.
+
+use anyhow::{anyhow, Result};
+
+/// Largest divisibility representable in a u128 amount
+pub const MAX_DIVISIBILITY: u8 = 38;
+
+/// Format a raw amount as a decimal string
+///
+/// Trailing zeros of the fractional part are trimmed, and the decimal point
+/// is omitted for whole amounts: `format_rune_amount(150_000_000, 8)` is
+/// `"1.5"`.
+pub fn format_rune_amount(raw: u128, divisibility: u8) -> String {
+    if divisibility == 0 {
+        return raw.to_string();
+    }
+
+    let digits = format!("{:0>width$}", raw, width = usize::from(divisibility) + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - usize::from(divisibility));
+    let fraction = fraction.trim_end_matches('0');
+
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
+
+/// Parse a decimal string into a raw amount
+///
+/// The inverse of `format_rune_amount`. Amounts with more fractional digits
+/// than `divisibility` are rejected rather than rounded.
+pub fn parse_rune_amount(s: &str, divisibility: u8) -> Result<u128> {
+    if divisibility > MAX_DIVISIBILITY {
+        return Err(anyhow!("Divisibility {} exceeds the maximum of {}", divisibility, MAX_DIVISIBILITY));
+    }
+
+    let (whole, fraction) = match s.split_once('.') {
+        Some((whole, fraction)) => (whole, fraction),
+        None => (s, ""),
+    };
+    let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !is_digits(whole) || !is_digits(fraction) {
+        return Err(anyhow!("Invalid amount: {}", s));
+    }
+    if fraction.len() > usize::from(divisibility) {
+        return Err(anyhow!("Amount {} has more than {} decimal places", s, divisibility));
+    }
+
+    let scale = 10u128.pow(u32::from(divisibility));
+    let fraction_scale = 10u128.pow(u32::from(divisibility) - fraction.len() as u32);
+    let whole: u128 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| anyhow!("Amount {} is too large", s))? };
+    let fraction: u128 = if fraction.is_empty() { 0 } else { fraction.parse()? };
+
+    whole.checked_mul(scale)
+        .and_then(|whole| whole.checked_add(fraction * fraction_scale))
+        .ok_or_else(|| anyhow!("Amount {} is too large", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_rune_amount() {
+        assert_eq!(format_rune_amount(123456789, 8), "1.23456789");
+        assert_eq!(format_rune_amount(150_000_000, 8), "1.5");
+        assert_eq!(format_rune_amount(100_000_000, 8), "1");
+        assert_eq!(format_rune_amount(1, 8), "0.00000001");
+        assert_eq!(format_rune_amount(0, 8), "0");
+    }
+
+    #[test]
+    fn test_zero_divisibility() {
+        assert_eq!(format_rune_amount(1500, 0), "1500");
+        assert_eq!(parse_rune_amount("1500", 0).unwrap(), 1500);
+        assert!(parse_rune_amount("1.5", 0).is_err());
+    }
+
+    #[test]
+    fn test_max_divisibility() {
+        assert_eq!(format_rune_amount(u128::MAX, MAX_DIVISIBILITY), "3.40282366920938463463374607431768211455");
+        assert_eq!(parse_rune_amount("3.40282366920938463463374607431768211455", MAX_DIVISIBILITY).unwrap(), u128::MAX);
+        assert!(parse_rune_amount("4", MAX_DIVISIBILITY).is_err());
+        assert!(parse_rune_amount("1", MAX_DIVISIBILITY + 1).is_err());
+    }
+
+    #[test]
+    fn test_parse_is_rounding_free() {
+        assert_eq!(parse_rune_amount("1.23456789", 8).unwrap(), 123456789);
+        assert_eq!(parse_rune_amount("0.1", 8).unwrap(), 10_000_000);
+        assert_eq!(parse_rune_amount(".5", 2).unwrap(), 50);
+        assert_eq!(parse_rune_amount("2.", 2).unwrap(), 200);
+        // Too precise amounts are rejected, not rounded
+        assert!(parse_rune_amount("1.234567891", 8).is_err());
+        assert!(parse_rune_amount("", 8).is_err());
+        assert!(parse_rune_amount(".", 8).is_err());
+        assert!(parse_rune_amount("-1", 8).is_err());
+        assert!(parse_rune_amount("1e5", 8).is_err());
+
+        for raw in [0, 1, 10, 123456789, u64::MAX as u128] {
+            assert_eq!(parse_rune_amount(&format_rune_amount(raw, 8), 8).unwrap(), raw);
+        }
+    }
+}
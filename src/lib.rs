@@ -2,21 +2,44 @@
 //!
 //! This library provides functionality for automated DIESEL token minting
 //! and management using BDK and Sandshrew RPC.
+//!
+//! # Features
+//!
+//! | Feature  | Default | Enables |
+//! |----------|---------|---------|
+//! | `server` | yes     | The `deezel-cli` HTTP server and `monitor::sse`, pulling in axum |
+//! | `cli`    | yes     | The `deezel` command-line binary; implies `server` |
+//! | `metrics`| no      | Block monitor statistics through the `metrics` facade |
+//!
+//! Without default features the crate builds as a plain library, with the
+//! wallet, transaction, RPC, monitor and decoding APIs re-exported below:
+//!
+//! ```toml
+//! deezel-cli = { version = "0.1", default-features = false }
+//! ```
 
+pub mod alkane_id;
+pub mod cellpack;
 pub mod wallet;
+pub mod format;
 pub mod monitor;
 pub mod network;
 pub mod transaction;
 pub mod rpc;
+pub mod orchestrator;
 pub mod runestone;
 pub mod runestone_enhanced;
+pub mod varint;
 
 
 // Re-export key types for convenience
+pub use alkane_id::AlkaneId;
+pub use cellpack::Cellpack;
 pub use wallet::WalletManager;
 pub use monitor::BlockMonitor;
+pub use orchestrator::MintOrchestrator;
 pub use transaction::TransactionConstructor;
 pub use rpc::RpcClient;
 pub use runestone::Runestone;
 pub use network::NetworkParams;
-pub use runestone_enhanced::{decode_runestone, format_runestone};
+pub use runestone_enhanced::{decode_from_hex, decode_from_path, decode_runestone, decode_runestone_json, decode_runestone_with_outputs, format_runestone, DecodeError, DecodedRunes, DecodedRunestone};
@@ -0,0 +1,179 @@
+//! Tracking of incoming transactions to an address
+//!
+//! The tracker is fed esplora transaction lists (from the mempool or the
+//! address history) and reports each transaction paying the address once
+//! per confirmation state it reaches, so repeated polls of the same lists
+//! don't repeat lines.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Confirmation state of a transaction paying a watched address
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityState {
+    /// Seen in the mempool
+    Mempool,
+    /// In a block, with fewer confirmations than required
+    Mined,
+    /// Has the required confirmations
+    Confirmed,
+}
+
+impl fmt::Display for ActivityState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActivityState::Mempool => write!(f, "mempool"),
+            ActivityState::Mined => write!(f, "mined"),
+            ActivityState::Confirmed => write!(f, "confirmed"),
+        }
+    }
+}
+
+/// A transaction paying a watched address reaching a new state
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct AddressActivity {
+    /// Transaction ID
+    pub txid: String,
+    /// Satoshis paid to the address by the transaction
+    pub value: u64,
+    /// State the transaction reached
+    pub state: ActivityState,
+    /// Confirmation count, 0 in the mempool
+    pub confirmations: u64,
+    /// Height of the block including the transaction, if any
+    pub block_height: Option<u64>,
+}
+
+impl fmt::Display for AddressActivity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} sats {}", self.txid, self.value, self.state)?;
+        if let Some(height) = self.block_height {
+            write!(f, " at height {} ({} confirmations)", height, self.confirmations)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reports transactions paying an address once per state transition
+pub struct ActivityTracker {
+    /// Watched address
+    address: String,
+    /// Confirmations after which a transaction is `Confirmed`
+    confirmations: u64,
+    /// Last reported state of each transaction
+    states: HashMap<String, ActivityState>,
+}
+
+impl ActivityTracker {
+    /// Create a tracker for `address`, confirming at `confirmations` (at least 1)
+    pub fn new(address: String, confirmations: u64) -> Self {
+        Self {
+            address,
+            confirmations: confirmations.max(1),
+            states: HashMap::new(),
+        }
+    }
+
+    /// Record the current state of `txs` without reporting them
+    ///
+    /// Used at startup so that existing history isn't reported as new.
+    pub fn prime(&mut self, txs: &[Value], tip: u64) {
+        self.update(txs, tip);
+    }
+
+    /// Record esplora transactions `txs` with the chain at `tip`
+    ///
+    /// Returns the transactions paying the address whose state changed since
+    /// they were last seen. Transactions absent from `txs` keep their state,
+    /// so partial lists such as the mempool alone can be fed in.
+    pub fn update(&mut self, txs: &[Value], tip: u64) -> Vec<AddressActivity> {
+        let mut activity = Vec::new();
+        for tx in txs {
+            let txid = match tx["txid"].as_str() {
+                Some(txid) => txid,
+                None => continue,
+            };
+            let value = self.incoming_value(tx);
+            if value == 0 {
+                continue;
+            }
+
+            let block_height = tx["status"]["block_height"].as_u64()
+                .filter(|_| tx["status"]["confirmed"].as_bool().unwrap_or(false));
+            let confirmations = block_height.map_or(0, |height| tip.saturating_sub(height) + 1);
+            let state = match block_height {
+                None => ActivityState::Mempool,
+                Some(_) if confirmations >= self.confirmations => ActivityState::Confirmed,
+                Some(_) => ActivityState::Mined,
+            };
+
+            if self.states.insert(txid.to_string(), state) != Some(state) {
+                activity.push(AddressActivity { txid: txid.to_string(), value, state, confirmations, block_height });
+            }
+        }
+        activity
+    }
+
+    /// Satoshis the outputs of `tx` pay to the watched address
+    fn incoming_value(&self, tx: &Value) -> u64 {
+        tx["vout"].as_array()
+            .map(|outputs| outputs.iter()
+                .filter(|output| output["scriptpubkey_address"].as_str() == Some(self.address.as_str()))
+                .filter_map(|output| output["value"].as_u64())
+                .sum())
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tx(txid: &str, to: &str, value: u64, block_height: Option<u64>) -> Value {
+        json!({
+            "txid": txid,
+            "vout": [{ "scriptpubkey_address": to, "value": value }, { "scriptpubkey_address": "change", "value": 1 }],
+            "status": { "confirmed": block_height.is_some(), "block_height": block_height },
+        })
+    }
+
+    #[test]
+    fn test_reports_each_state_once() {
+        let mut tracker = ActivityTracker::new("deposit".to_string(), 2);
+
+        let activity = tracker.update(&[tx("aa", "deposit", 5_000, None), tx("bb", "elsewhere", 9, None)], 100);
+        assert_eq!(activity, vec![AddressActivity {
+            txid: "aa".to_string(),
+            value: 5_000,
+            state: ActivityState::Mempool,
+            confirmations: 0,
+            block_height: None,
+        }]);
+        assert!(tracker.update(&[tx("aa", "deposit", 5_000, None)], 100).is_empty());
+
+        // Mined in the next block, confirmed one block later
+        let activity = tracker.update(&[tx("aa", "deposit", 5_000, Some(101))], 101);
+        assert_eq!((activity[0].state, activity[0].confirmations), (ActivityState::Mined, 1));
+        let activity = tracker.update(&[tx("aa", "deposit", 5_000, Some(101))], 102);
+        assert_eq!((activity[0].state, activity[0].confirmations), (ActivityState::Confirmed, 2));
+        assert_eq!(activity[0].to_string(), "aa 5000 sats confirmed at height 101 (2 confirmations)");
+        assert!(tracker.update(&[tx("aa", "deposit", 5_000, Some(101))], 103).is_empty());
+
+        // A mempool-only poll doesn't touch confirmed transactions
+        assert!(tracker.update(&[], 103).is_empty());
+    }
+
+    #[test]
+    fn test_prime_skips_existing_history() {
+        let mut tracker = ActivityTracker::new("deposit".to_string(), 1);
+        tracker.prime(&[tx("aa", "deposit", 1_000, Some(90)), tx("bb", "deposit", 2_000, None)], 100);
+
+        let activity = tracker.update(&[tx("aa", "deposit", 1_000, Some(90)), tx("bb", "deposit", 2_000, Some(101)), tx("cc", "deposit", 3_000, None)], 101);
+        let reported: Vec<(&str, ActivityState)> = activity.iter().map(|a| (a.txid.as_str(), a.state)).collect();
+        assert_eq!(reported, vec![("bb", ActivityState::Confirmed), ("cc", ActivityState::Mempool)]);
+    }
+}
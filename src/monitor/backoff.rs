@@ -0,0 +1,110 @@
+//! Retry backoff for the block monitor's polling loop
+//!
+//! Failed polls are retried after an exponentially growing delay with
+//! jitter, so that many monitors recovering from the same outage don't hit
+//! the RPC in lockstep.
+
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use std::time::Duration;
+
+/// Exponential backoff with "equal jitter"
+///
+/// The n-th delay is drawn uniformly from `[cap / 2, cap]` where
+/// `cap = min(max, base * 2^n)`.
+pub struct Backoff<R: RngCore = StdRng> {
+    /// Delay cap of the first retry
+    base: Duration,
+    /// Upper bound of any delay
+    max: Duration,
+    /// Number of delays handed out since the last reset
+    attempt: u32,
+    /// Source of jitter
+    rng: R,
+}
+
+impl Backoff<StdRng> {
+    /// Create a backoff seeded from system entropy
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self::with_rng(base, max, StdRng::from_entropy())
+    }
+}
+
+impl<R: RngCore> Backoff<R> {
+    /// Create a backoff using the given RNG for jitter
+    pub fn with_rng(base: Duration, max: Duration, rng: R) -> Self {
+        Self {
+            base,
+            max,
+            attempt: 0,
+            rng,
+        }
+    }
+
+    /// Get the delay before the next retry
+    pub fn next_delay(&mut self) -> Duration {
+        let factor = 1u32.checked_shl(self.attempt).unwrap_or(u32::MAX);
+        let cap = self.base.saturating_mul(factor).min(self.max);
+        self.attempt = self.attempt.saturating_add(1);
+
+        let cap_ms = cap.as_millis() as u64;
+        Duration::from_millis(self.rng.gen_range(cap_ms / 2..=cap_ms))
+    }
+
+    /// Start over from the base delay after a successful poll
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seeded(base_secs: u64, max_secs: u64) -> Backoff {
+        Backoff::with_rng(
+            Duration::from_secs(base_secs),
+            Duration::from_secs(max_secs),
+            StdRng::seed_from_u64(42),
+        )
+    }
+
+    #[test]
+    fn test_delays_grow_within_jitter_bounds_and_cap() {
+        let mut backoff = seeded(5, 60);
+        let caps = [5, 10, 20, 40, 60, 60, 60];
+
+        for cap in caps {
+            let delay = backoff.next_delay();
+            let cap = Duration::from_secs(cap);
+            assert!(delay >= cap / 2 && delay <= cap, "{:?} outside [{:?}, {:?}]", delay, cap / 2, cap);
+        }
+    }
+
+    #[test]
+    fn test_seeded_sequence_is_deterministic() {
+        let mut a = seeded(5, 300);
+        let mut b = seeded(5, 300);
+        let first: Vec<Duration> = (0..10).map(|_| a.next_delay()).collect();
+        let second: Vec<Duration> = (0..10).map(|_| b.next_delay()).collect();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_reset_returns_to_base() {
+        let mut backoff = seeded(5, 300);
+        for _ in 0..8 {
+            backoff.next_delay();
+        }
+        backoff.reset();
+        assert!(backoff.next_delay() <= Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_long_outage_does_not_overflow() {
+        let mut backoff = seeded(5, 3600);
+        for _ in 0..100 {
+            assert!(backoff.next_delay() <= Duration::from_secs(3600));
+        }
+    }
+}
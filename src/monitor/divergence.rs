@@ -0,0 +1,105 @@
+//! Height divergence detection across redundant Metashrew instances
+//!
+//! When several Metashrew instances serve the same index, a lagging one can
+//! make its clients act on stale state. The tracker compares the heights
+//! reported by every instance on each poll and reports when they have
+//! disagreed by more than a threshold for longer than a grace period, and
+//! again once they agree.
+
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use super::BlockEvent;
+
+/// Tracks whether Metashrew instances disagree about the chain height
+pub(crate) struct DivergenceTracker {
+    /// Largest tolerated height spread between instances
+    threshold: u64,
+    /// How long the spread must exceed the threshold before reporting
+    grace: Duration,
+    /// When the current run of divergent polls started
+    since: Option<Instant>,
+    /// Whether `EndpointDiverged` was emitted for the current run
+    reported: bool,
+}
+
+impl DivergenceTracker {
+    /// Create a tracker with the given threshold (in blocks) and grace period
+    pub(crate) fn new(threshold: u64, grace: Duration) -> Self {
+        Self {
+            threshold,
+            grace,
+            since: None,
+            reported: false,
+        }
+    }
+
+    /// Record the heights of one poll, returning an event on state changes
+    ///
+    /// Emits `EndpointDiverged` once the spread has stayed above the
+    /// threshold for the grace period, and `EndpointsConverged` when it drops
+    /// back after a reported divergence.
+    pub(crate) fn observe(&mut self, heights: &BTreeMap<String, u64>, now: Instant) -> Option<BlockEvent> {
+        let spread = match (heights.values().max(), heights.values().min()) {
+            (Some(max), Some(min)) => max - min,
+            _ => 0,
+        };
+
+        if spread <= self.threshold {
+            self.since = None;
+            if std::mem::take(&mut self.reported) {
+                return Some(BlockEvent::EndpointsConverged { observed_at: Utc::now() });
+            }
+            return None;
+        }
+
+        let since = *self.since.get_or_insert(now);
+        if !self.reported && now.duration_since(since) >= self.grace {
+            self.reported = true;
+            return Some(BlockEvent::EndpointDiverged { heights: heights.clone(), observed_at: Utc::now() });
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heights(a: u64, b: u64) -> BTreeMap<String, u64> {
+        BTreeMap::from([("http://a".to_string(), a), ("http://b".to_string(), b)])
+    }
+
+    #[test]
+    fn test_divergence_reported_after_grace_period() {
+        let mut tracker = DivergenceTracker::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+
+        // Within the threshold
+        assert_eq!(tracker.observe(&heights(100, 98), start), None);
+        // Diverged, but not for long enough
+        assert_eq!(tracker.observe(&heights(103, 98), start), None);
+        assert_eq!(tracker.observe(&heights(104, 98), start + Duration::from_secs(30)), None);
+
+        let event = tracker.observe(&heights(105, 98), start + Duration::from_secs(60));
+        assert!(matches!(event, Some(BlockEvent::EndpointDiverged { heights: h, .. }) if h == heights(105, 98)));
+        // Reported once per divergence
+        assert_eq!(tracker.observe(&heights(106, 98), start + Duration::from_secs(90)), None);
+
+        let event = tracker.observe(&heights(106, 106), start + Duration::from_secs(120));
+        assert!(matches!(event, Some(BlockEvent::EndpointsConverged { .. })));
+        assert_eq!(tracker.observe(&heights(107, 107), start + Duration::from_secs(150)), None);
+    }
+
+    #[test]
+    fn test_brief_divergence_is_not_reported() {
+        let mut tracker = DivergenceTracker::new(0, Duration::from_secs(60));
+        let start = Instant::now();
+
+        assert_eq!(tracker.observe(&heights(101, 100), start), None);
+        assert_eq!(tracker.observe(&heights(101, 101), start + Duration::from_secs(30)), None);
+        // The grace period restarts with the next divergence
+        assert_eq!(tracker.observe(&heights(102, 101), start + Duration::from_secs(70)), None);
+    }
+}
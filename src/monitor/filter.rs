@@ -0,0 +1,155 @@
+//! Server-side filtering of block monitor events
+//!
+//! Subscribers that only care about a few events (e.g. confirmations of their
+//! own transaction) describe them with an `EventFilter` instead of draining
+//! the whole broadcast channel and filtering client-side.
+
+use anyhow::{anyhow, Result};
+
+use super::BlockEvent;
+
+/// Event types accepted in `EventFilter::kinds`
+const EVENT_TYPES: [&str; 9] = [
+    "new_block",
+    "transaction_confirmed",
+    "transaction_dropped",
+    "token_balance_changed",
+    "endpoint_diverged",
+    "endpoints_converged",
+    "degraded",
+    "recovered",
+    "error",
+];
+
+/// Predicate over block events
+///
+/// Each non-empty criterion must hold for an event to match (AND semantics);
+/// within a list any entry may match. Txid and address criteria reject events
+/// that carry no txid or address, while `min_height` only constrains events
+/// that carry a height.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    /// Event types to match (e.g. "transaction_confirmed"); empty means all
+    pub kinds: Vec<String>,
+    /// Transaction ids to match; empty means any
+    pub txids: Vec<String>,
+    /// Addresses to match; empty means any
+    pub addresses: Vec<String>,
+    /// Lowest block height to match
+    pub min_height: Option<u64>,
+}
+
+impl EventFilter {
+    /// Build a filter from URL query pairs
+    ///
+    /// Recognised keys are `type`, `txid`, `address` and `min_height`; the
+    /// first three may be repeated. Unknown keys and event types are rejected
+    /// so typos don't silently turn into an unfiltered stream.
+    pub fn from_query(pairs: &[(String, String)]) -> Result<Self> {
+        let mut filter = Self::default();
+        for (key, value) in pairs {
+            match key.as_str() {
+                "type" => {
+                    if !EVENT_TYPES.contains(&value.as_str()) {
+                        return Err(anyhow!("Unknown event type: {}", value));
+                    }
+                    filter.kinds.push(value.clone());
+                },
+                "txid" => filter.txids.push(value.clone()),
+                "address" => filter.addresses.push(value.clone()),
+                "min_height" => {
+                    let height = value.parse::<u64>()
+                        .map_err(|_| anyhow!("Invalid min_height: {}", value))?;
+                    filter.min_height = Some(height);
+                },
+                _ => return Err(anyhow!("Unknown filter parameter: {}", key)),
+            }
+        }
+        Ok(filter)
+    }
+
+    /// Check whether an event satisfies every criterion of the filter
+    pub fn matches(&self, event: &BlockEvent) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.iter().any(|kind| kind == event.event_type()) {
+            return false;
+        }
+
+        if !self.txids.is_empty() {
+            let txid = match event {
+                BlockEvent::TransactionConfirmed { txid, .. }
+                | BlockEvent::TransactionDropped { txid, .. } => txid,
+                _ => return false,
+            };
+            if !self.txids.contains(txid) {
+                return false;
+            }
+        }
+
+        if !self.addresses.is_empty() {
+            match event {
+                BlockEvent::TokenBalanceChanged { address, .. } if self.addresses.contains(address) => {},
+                _ => return false,
+            }
+        }
+
+        if let (Some(min_height), BlockEvent::NewBlock { height, .. }) = (self.min_height, event) {
+            if *height < min_height {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn pairs(query: &[(&str, &str)]) -> Vec<(String, String)> {
+        query.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_filter_combines_criteria() {
+        let filter = EventFilter {
+            kinds: vec!["transaction_confirmed".to_string()],
+            txids: vec!["aa".to_string()],
+            ..EventFilter::default()
+        };
+        let confirmed = |txid: &str| BlockEvent::TransactionConfirmed { txid: txid.to_string(), confirmations: 1, observed_at: Utc::now() };
+
+        assert!(filter.matches(&confirmed("aa")));
+        assert!(!filter.matches(&confirmed("bb")));
+        assert!(!filter.matches(&BlockEvent::TransactionDropped { txid: "aa".to_string(), observed_at: Utc::now() }));
+        assert!(!filter.matches(&BlockEvent::NewBlock { height: 1, hash: "00".to_string(), replayed: false, observed_at: Utc::now() }));
+    }
+
+    #[test]
+    fn test_min_height_only_constrains_blocks() {
+        let filter = EventFilter { min_height: Some(100), ..EventFilter::default() };
+        let block = |height| BlockEvent::NewBlock { height, hash: "00".to_string(), replayed: false, observed_at: Utc::now() };
+
+        assert!(!filter.matches(&block(99)));
+        assert!(filter.matches(&block(100)));
+        assert!(filter.matches(&BlockEvent::Recovered { failures: 1, observed_at: Utc::now() }));
+    }
+
+    #[test]
+    fn test_from_query() {
+        let filter = EventFilter::from_query(&pairs(&[
+            ("type", "transaction_confirmed"),
+            ("txid", "aa"),
+            ("txid", "bb"),
+            ("min_height", "840000"),
+        ])).unwrap();
+        assert_eq!(filter.kinds, vec!["transaction_confirmed"]);
+        assert_eq!(filter.txids, vec!["aa", "bb"]);
+        assert_eq!(filter.min_height, Some(840000));
+
+        assert!(EventFilter::from_query(&pairs(&[("type", "confirmed")])).is_err());
+        assert!(EventFilter::from_query(&pairs(&[("txids", "aa")])).is_err());
+        assert!(EventFilter::from_query(&pairs(&[("min_height", "tip")])).is_err());
+    }
+}
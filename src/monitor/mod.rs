@@ -5,38 +5,100 @@
 //! - Rate limiting
 //! - Transaction confirmation tracking
 //! - Event notifications for new blocks
+//! - Webhook delivery of events to external consumers
+//! - Tracking of transactions paying a watched address
 
-use anyhow::{Context, Result};
+mod activity;
+pub(crate) mod backoff;
+mod divergence;
+mod filter;
+#[cfg(feature = "server")]
+pub mod sse;
+mod stats;
+pub mod webhook;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures_util::Stream;
 use log::{debug, info, warn, error};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::{Mutex, mpsc};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, broadcast, watch};
+use tokio::task::JoinHandle;
 use tokio::time::sleep;
+use thiserror::Error;
 
-use crate::rpc::RpcClient;
+use crate::rpc::{parse_protorune_balances, RpcClient, SyncStatus, TxStatus};
+use self::backoff::Backoff;
+use self::divergence::DivergenceTracker;
+use self::stats::Stats;
+pub use self::activity::{ActivityState, ActivityTracker, AddressActivity};
+pub use self::filter::EventFilter;
+pub use self::stats::MonitorStats;
+pub use self::webhook::{WebhookConfig, WebhookEndpoint, WebhookNotifier};
 
 /// Block monitor configuration
 pub struct BlockMonitorConfig {
     /// Polling interval in seconds
     pub polling_interval: u64,
-    /// Maximum number of retries for failed requests
+    /// Consecutive failed polls before reporting the monitor as `Degraded`
+    ///
+    /// Polling never stops on failures; this only sets when an outage is
+    /// reported. 0 reports on the first failure.
     pub max_retries: u32,
-    /// Retry delay in seconds
+    /// Initial retry delay in seconds, doubled (with jitter) after each failure
     pub retry_delay: u64,
+    /// Upper bound of the retry delay in seconds
+    pub max_retry_delay: u64,
+    /// Path of the file persisting watched address balance snapshots
+    pub state_path: Option<String>,
+    /// Replay historical blocks from this height before live polling
+    pub start_height: Option<u64>,
+    /// Maximum replayed blocks per second, 0 for no throttling
+    pub replay_rate: u32,
+    /// Largest tolerated height difference between Metashrew instances
+    pub divergence_threshold: u64,
+    /// Seconds instances may exceed the threshold before `EndpointDiverged`
+    pub divergence_duration: u64,
 }
 
 impl Default for BlockMonitorConfig {
     fn default() -> Self {
         Self {
             polling_interval: 30, // 30 seconds between polls
-            max_retries: 5,       // Retry 5 times before giving up
-            retry_delay: 5,       // 5 seconds between retries
+            max_retries: 5,       // Report an outage after 5 failed polls
+            retry_delay: 5,       // 5 seconds before the first retry
+            max_retry_delay: 300, // Back off to at most 5 minutes
+            state_path: None,     // Keep monitor state in memory only
+            start_height: None,   // Start at the current tip
+            replay_rate: 0,       // Replay as fast as the RPC and subscribers allow
+            divergence_threshold: 2,  // Instances may briefly lag by a block or two
+            divergence_duration: 120, // Report after 2 minutes of disagreement
         }
     }
 }
 
+/// Unread events at which block replay waits for subscribers to catch up
+///
+/// Half the channel capacity, leaving room for the live events sent meanwhile.
+const REPLAY_BACKLOG: usize = 50;
+
+/// Version of the `BlockEvent` JSON wire format
+///
+/// Bump this whenever the serialized form of an event changes.
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
 /// Block monitor events
-#[derive(Debug, Clone)]
+///
+/// Events serialize to a flat JSON object tagged by `type`, e.g.
+/// `{"version":1,"type":"new_block","height":840000,...}`. The wire format is
+/// written out by hand rather than derived so that changes to this enum
+/// can't silently change what external consumers receive.
+#[derive(Debug, Clone, PartialEq)]
 pub enum BlockEvent {
     /// New block detected
     NewBlock {
@@ -44,6 +106,10 @@ pub enum BlockEvent {
         height: u64,
         /// Block hash
         hash: String,
+        /// Whether the block was replayed from history rather than observed live
+        replayed: bool,
+        /// When the event was observed
+        observed_at: DateTime<Utc>,
     },
     /// Transaction confirmed
     TransactionConfirmed {
@@ -51,11 +117,270 @@ pub enum BlockEvent {
         txid: String,
         /// Confirmation count
         confirmations: u32,
+        /// When the event was observed
+        observed_at: DateTime<Utc>,
+    },
+    /// A previously confirmed transaction is no longer in the chain (reorg)
+    TransactionDropped {
+        /// Transaction ID
+        txid: String,
+        /// When the event was observed
+        observed_at: DateTime<Utc>,
+    },
+    /// A watched address's protorune balance changed
+    TokenBalanceChanged {
+        /// Watched address
+        address: String,
+        /// Rune ID (block:tx)
+        rune_id: String,
+        /// Previous balance
+        old: u128,
+        /// New balance
+        new: u128,
+        /// When the event was observed
+        observed_at: DateTime<Utc>,
+    },
+    /// Metashrew instances have disagreed about the height for too long
+    EndpointDiverged {
+        /// Height reported by each reachable instance, keyed by URL
+        heights: BTreeMap<String, u64>,
+        /// When the event was observed
+        observed_at: DateTime<Utc>,
+    },
+    /// Metashrew instances agree again after a reported divergence
+    EndpointsConverged {
+        /// When the event was observed
+        observed_at: DateTime<Utc>,
+    },
+    /// Polling has failed `max_retries` times in a row; the monitor keeps retrying
+    Degraded {
+        /// Number of consecutive failed polls so far
+        failures: u32,
+        /// Error of the latest failed poll
+        message: String,
+        /// When the event was observed
+        observed_at: DateTime<Utc>,
+    },
+    /// Polling succeeded again after a `Degraded` outage
+    Recovered {
+        /// Number of consecutive failed polls before the recovery
+        failures: u32,
+        /// When the event was observed
+        observed_at: DateTime<Utc>,
     },
     /// Error occurred
-    Error(String),
+    Error {
+        /// Error message
+        message: String,
+        /// When the event was observed
+        observed_at: DateTime<Utc>,
+    },
 }
 
+impl BlockEvent {
+    /// Get the snake_case name of the event type
+    ///
+    /// This is the name used in webhook payloads and event filters.
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            BlockEvent::NewBlock { .. } => "new_block",
+            BlockEvent::TransactionConfirmed { .. } => "transaction_confirmed",
+            BlockEvent::TransactionDropped { .. } => "transaction_dropped",
+            BlockEvent::TokenBalanceChanged { .. } => "token_balance_changed",
+            BlockEvent::EndpointDiverged { .. } => "endpoint_diverged",
+            BlockEvent::EndpointsConverged { .. } => "endpoints_converged",
+            BlockEvent::Degraded { .. } => "degraded",
+            BlockEvent::Recovered { .. } => "recovered",
+            BlockEvent::Error { .. } => "error",
+        }
+    }
+    
+    /// Get the time the event was observed
+    pub fn observed_at(&self) -> DateTime<Utc> {
+        match self {
+            BlockEvent::NewBlock { observed_at, .. }
+            | BlockEvent::TransactionConfirmed { observed_at, .. }
+            | BlockEvent::TransactionDropped { observed_at, .. }
+            | BlockEvent::TokenBalanceChanged { observed_at, .. }
+            | BlockEvent::EndpointDiverged { observed_at, .. }
+            | BlockEvent::EndpointsConverged { observed_at, .. }
+            | BlockEvent::Degraded { observed_at, .. }
+            | BlockEvent::Recovered { observed_at, .. }
+            | BlockEvent::Error { observed_at, .. } => *observed_at,
+        }
+    }
+    
+    /// Serialize the event to its JSON wire format
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("BlockEvent serialization cannot fail")
+    }
+    
+    /// Parse an event from its JSON wire format
+    fn from_wire(value: &serde_json::Value) -> Result<Self> {
+        let version = value["version"].as_u64().context("Missing event schema version")?;
+        if version != u64::from(EVENT_SCHEMA_VERSION) {
+            return Err(anyhow!("Unsupported event schema version {}", version));
+        }
+        
+        let string = |field: &str| value[field].as_str()
+            .map(|s| s.to_string())
+            .with_context(|| format!("Missing string field '{}'", field));
+        // u128 balances travel as decimal strings
+        let amount = |field: &str| -> Result<u128> {
+            string(field)?.parse().with_context(|| format!("Invalid amount in '{}'", field))
+        };
+        let observed_at = DateTime::parse_from_rfc3339(&string("observed_at")?)
+            .context("Invalid observed_at timestamp")?
+            .with_timezone(&Utc);
+        
+        let event = match value["type"].as_str().context("Missing event type")? {
+            "new_block" => BlockEvent::NewBlock {
+                height: value["height"].as_u64().context("Missing height")?,
+                hash: string("hash")?,
+                replayed: value["replayed"].as_bool().context("Missing replayed flag")?,
+                observed_at,
+            },
+            "transaction_confirmed" => BlockEvent::TransactionConfirmed {
+                txid: string("txid")?,
+                confirmations: value["confirmations"].as_u64()
+                    .and_then(|c| u32::try_from(c).ok())
+                    .context("Missing confirmations")?,
+                observed_at,
+            },
+            "transaction_dropped" => BlockEvent::TransactionDropped {
+                txid: string("txid")?,
+                observed_at,
+            },
+            "token_balance_changed" => BlockEvent::TokenBalanceChanged {
+                address: string("address")?,
+                rune_id: string("rune_id")?,
+                old: amount("old")?,
+                new: amount("new")?,
+                observed_at,
+            },
+            "endpoint_diverged" => BlockEvent::EndpointDiverged {
+                heights: value["heights"].as_object()
+                    .context("Missing heights")?
+                    .iter()
+                    .map(|(url, height)| {
+                        height.as_u64()
+                            .map(|height| (url.clone(), height))
+                            .with_context(|| format!("Invalid height for '{}'", url))
+                    })
+                    .collect::<Result<_>>()?,
+                observed_at,
+            },
+            "endpoints_converged" => BlockEvent::EndpointsConverged { observed_at },
+            "degraded" => BlockEvent::Degraded {
+                failures: value["failures"].as_u64()
+                    .and_then(|f| u32::try_from(f).ok())
+                    .context("Missing failures")?,
+                message: string("message")?,
+                observed_at,
+            },
+            "recovered" => BlockEvent::Recovered {
+                failures: value["failures"].as_u64()
+                    .and_then(|f| u32::try_from(f).ok())
+                    .context("Missing failures")?,
+                observed_at,
+            },
+            "error" => BlockEvent::Error {
+                message: string("message")?,
+                observed_at,
+            },
+            other => return Err(anyhow!("Unknown event type '{}'", other)),
+        };
+        
+        Ok(event)
+    }
+}
+
+impl Serialize for BlockEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("version", &EVENT_SCHEMA_VERSION)?;
+        map.serialize_entry("type", self.event_type())?;
+        
+        match self {
+            BlockEvent::NewBlock { height, hash, replayed, .. } => {
+                map.serialize_entry("height", height)?;
+                map.serialize_entry("hash", hash)?;
+                map.serialize_entry("replayed", replayed)?;
+            },
+            BlockEvent::TransactionConfirmed { txid, confirmations, .. } => {
+                map.serialize_entry("txid", txid)?;
+                map.serialize_entry("confirmations", confirmations)?;
+            },
+            BlockEvent::TransactionDropped { txid, .. } => {
+                map.serialize_entry("txid", txid)?;
+            },
+            BlockEvent::TokenBalanceChanged { address, rune_id, old, new, .. } => {
+                // JSON numbers can't hold a u128
+                map.serialize_entry("address", address)?;
+                map.serialize_entry("rune_id", rune_id)?;
+                map.serialize_entry("old", &old.to_string())?;
+                map.serialize_entry("new", &new.to_string())?;
+            },
+            BlockEvent::EndpointDiverged { heights, .. } => {
+                map.serialize_entry("heights", heights)?;
+            },
+            BlockEvent::EndpointsConverged { .. } => {},
+            BlockEvent::Degraded { failures, message, .. } => {
+                map.serialize_entry("failures", failures)?;
+                map.serialize_entry("message", message)?;
+            },
+            BlockEvent::Recovered { failures, .. } => {
+                map.serialize_entry("failures", failures)?;
+            },
+            BlockEvent::Error { message, .. } => {
+                map.serialize_entry("message", message)?;
+            },
+        }
+        
+        let observed_at = self.observed_at().to_rfc3339_opts(SecondsFormat::Millis, true);
+        map.serialize_entry("observed_at", &observed_at)?;
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for BlockEvent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        Self::from_wire(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Lifecycle status of the monitoring task
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MonitorStatus {
+    /// The polling task is running
+    Running,
+    /// The monitor has not been started or was stopped explicitly
+    Stopped,
+    /// The polling task exited because of an unrecoverable error
+    Failed(String),
+}
+
+/// Reasons a `wait_for_*` helper can fail
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum WaitError {
+    /// The condition was not met before the timeout elapsed
+    #[error("Timed out after {0:?}")]
+    Timeout(Duration),
+    /// The awaited transaction was dropped from the chain by a reorg
+    #[error("Transaction {0} was dropped by a reorg")]
+    Dropped(String),
+    /// The monitor's event channel was closed
+    #[error("Block monitor event channel closed")]
+    Closed,
+}
+
+/// Protorune balances of a watched address, keyed by rune ID (block:tx)
+type RuneBalances = HashMap<String, u128>;
+
+/// Watched addresses and their last balance snapshot, if one was taken
+type AddressSnapshots = HashMap<String, Option<RuneBalances>>;
+
 /// Block monitor for tracking new blocks and transaction confirmations
 pub struct BlockMonitor {
     /// RPC client for blockchain queries
@@ -63,58 +388,129 @@ pub struct BlockMonitor {
     /// Monitor configuration
     config: BlockMonitorConfig,
     /// Current block height
-    current_height: Mutex<u64>,
-    /// Event sender
-    event_sender: mpsc::Sender<BlockEvent>,
-    /// Event receiver
-    event_receiver: Mutex<mpsc::Receiver<BlockEvent>>,
-    /// Running flag
-    running: Mutex<bool>,
+    current_height: Arc<Mutex<u64>>,
+    /// Hash of the block at the current height
+    current_hash: Arc<Mutex<String>>,
+    /// Watched transactions and their last known confirmation count
+    tracked: Arc<Mutex<HashMap<String, u32>>>,
+    /// Operational statistics
+    stats: Arc<Stats>,
+    /// Watched addresses and their protorune balance snapshots
+    watched_addresses: Arc<Mutex<AddressSnapshots>>,
+    /// Event sender, shared by all subscribers
+    event_sender: broadcast::Sender<BlockEvent>,
+    /// Status of the polling task, also used to notify on termination
+    status: Arc<watch::Sender<MonitorStatus>>,
+    /// Handle of the polling task
+    task: Mutex<Option<JoinHandle<()>>>,
 }
 
 impl BlockMonitor {
     /// Create a new block monitor
     pub fn new(rpc_client: Arc<RpcClient>, config: BlockMonitorConfig) -> Self {
-        let (tx, rx) = mpsc::channel(100); // Buffer up to 100 events
+        let (tx, _) = broadcast::channel(100); // Buffer up to 100 events per subscriber
+        
+        // Restore watched addresses and their snapshots from a previous run
+        let watched_addresses = match &config.state_path {
+            Some(path) => Self::load_address_snapshots(path).unwrap_or_else(|e| {
+                warn!("Failed to load monitor state from {}: {}", path, e);
+                HashMap::new()
+            }),
+            None => HashMap::new(),
+        };
         
         Self {
             rpc_client,
             config,
-            current_height: Mutex::new(0),
+            current_height: Arc::new(Mutex::new(0)),
+            current_hash: Arc::new(Mutex::new(String::new())),
+            tracked: Arc::new(Mutex::new(HashMap::new())),
+            stats: Arc::new(Stats::default()),
+            watched_addresses: Arc::new(Mutex::new(watched_addresses)),
             event_sender: tx,
-            event_receiver: Mutex::new(rx),
-            running: Mutex::new(false),
+            status: Arc::new(watch::channel(MonitorStatus::Stopped).0),
+            task: Mutex::new(None),
         }
     }
     
     /// Start monitoring for new blocks
     pub async fn start(&self) -> Result<()> {
-        let mut running = self.running.lock().await;
-        if *running {
+        let mut task = self.task.lock().await;
+        if self.status() == MonitorStatus::Running {
             warn!("Block monitor is already running");
             return Ok(());
         }
         
-        *running = true;
+        self.status.send_replace(MonitorStatus::Running);
         info!("Starting block monitor");
         
         // Clone necessary values for the monitoring task
         let rpc_client = Arc::clone(&self.rpc_client);
         let polling_interval = self.config.polling_interval;
-        let max_retries = self.config.max_retries;
-        let retry_delay = self.config.retry_delay;
+        let degraded_after = self.config.max_retries.max(1);
+        let retry_delay = Duration::from_secs(self.config.retry_delay);
+        let max_retry_delay = Duration::from_secs(self.config.max_retry_delay);
         let event_sender = self.event_sender.clone();
-        let current_height = Arc::new(Mutex::new(0u64)); // Create a new Mutex
+        let current_height = Arc::clone(&self.current_height);
+        let current_hash = Arc::clone(&self.current_hash);
+        let tracked = Arc::clone(&self.tracked);
+        let stats = Arc::clone(&self.stats);
+        let watched_addresses = Arc::clone(&self.watched_addresses);
+        let state_path = self.config.state_path.clone();
+        let start_height = self.config.start_height;
+        let replay_rate = self.config.replay_rate;
+        let mut divergence = DivergenceTracker::new(
+            self.config.divergence_threshold,
+            Duration::from_secs(self.config.divergence_duration),
+        );
+        let status = Arc::clone(&self.status);
         
         // Spawn a task to monitor for new blocks
-        tokio::spawn(async move {
+        *task = Some(tokio::spawn(async move {
             let mut retry_count = 0;
+            let mut backoff = Backoff::new(retry_delay, max_retry_delay);
+            
+            // Catch up on history before switching to live polling
+            if let Some(start_height) = start_height {
+                if let Err(e) = Self::replay_blocks(&rpc_client, start_height, replay_rate, &current_height, &current_hash, &stats, &event_sender).await {
+                    stats.record_error("replay");
+                    error!("Error replaying blocks, stopping block monitor: {}", e);
+                    let reason = format!("Replay failed: {}", e);
+                    let _ = event_sender.send(BlockEvent::Error { message: reason.clone(), observed_at: Utc::now() });
+                    status.send_replace(MonitorStatus::Failed(reason));
+                    return;
+                }
+            }
             
             loop {
-                match Self::check_for_new_block(&rpc_client, &current_height, &event_sender).await {
+                // After a failure this poll is the half-open probe: a single
+                // request whose success closes the circuit again
+                let result = Self::check_for_new_block(&rpc_client, &current_height, &current_hash, &mut divergence, &stats, &event_sender).await;
+                
+                if result.is_ok() && retry_count > 0 {
+                    if retry_count >= degraded_after {
+                        info!("Block monitor recovered after {} failed poll(s)", retry_count);
+                        let _ = event_sender.send(BlockEvent::Recovered { failures: retry_count, observed_at: Utc::now() });
+                    }
+                    retry_count = 0;
+                    backoff.reset();
+                }
+                
+                match result {
                     Ok(true) => {
-                        // Successfully found a new block, reset retry counter
-                        retry_count = 0;
+                        // Update confirmations of watched transactions
+                        let height = *current_height.lock().await;
+                        if let Err(e) = Self::check_tracked_transactions(&rpc_client, &tracked, height, &event_sender).await {
+                            stats.record_error("tracked_transactions");
+                            warn!("Error checking watched transactions: {}", e);
+                        }
+                        stats.record_tracked(tracked.lock().await.len());
+                        
+                        // Report protorune balance changes of watched addresses
+                        if let Err(e) = Self::check_watched_addresses(&rpc_client, &watched_addresses, state_path.as_deref(), &event_sender).await {
+                            stats.record_error("watched_addresses");
+                            warn!("Error checking watched addresses: {}", e);
+                        }
                     },
                     Ok(false) => {
                         // No new block, continue polling
@@ -123,18 +519,23 @@ impl BlockMonitor {
                     Err(e) => {
                         // Error occurred, increment retry counter
                         retry_count += 1;
+                        stats.record_error("poll");
                         error!("Error checking for new block: {}", e);
                         
-                        if retry_count >= max_retries {
-                            error!("Maximum retry count reached, stopping block monitor");
-                            let _ = event_sender.send(BlockEvent::Error(
-                                format!("Maximum retry count reached: {}", e)
-                            )).await;
-                            break;
+                        // Report the outage once, but keep polling through it
+                        if retry_count == degraded_after {
+                            error!("Block monitor degraded after {} failed poll(s), still retrying", retry_count);
+                            let _ = event_sender.send(BlockEvent::Degraded {
+                                failures: retry_count,
+                                message: e.to_string(),
+                                observed_at: Utc::now(),
+                            });
                         }
                         
-                        // Wait before retrying
-                        sleep(Duration::from_secs(retry_delay)).await;
+                        // Back off before probing again
+                        let delay = backoff.next_delay();
+                        warn!("Retrying in {:?} (failure {})", delay, retry_count);
+                        sleep(delay).await;
                         continue;
                     }
                 }
@@ -142,7 +543,7 @@ impl BlockMonitor {
                 // Wait for the next polling interval
                 sleep(Duration::from_secs(polling_interval)).await;
             }
-        });
+        }));
         
         info!("Block monitor started");
         Ok(())
@@ -150,26 +551,108 @@ impl BlockMonitor {
     
     /// Stop monitoring for new blocks
     pub async fn stop(&self) -> Result<()> {
-        let mut running = self.running.lock().await;
-        if !*running {
+        let mut task = self.task.lock().await;
+        if self.status() != MonitorStatus::Running {
             warn!("Block monitor is not running");
             return Ok(());
         }
         
-        *running = false;
         info!("Stopping block monitor");
         
-        // In a real implementation, we would need to cancel the monitoring task
+        // Cancel the polling task and wait for it to wind down
+        if let Some(handle) = task.take() {
+            handle.abort();
+            let _ = handle.await;
+        }
+        self.status.send_replace(MonitorStatus::Stopped);
         
         info!("Block monitor stopped");
         Ok(())
     }
     
+    /// Get a snapshot of the monitor's operational statistics
+    pub async fn snapshot(&self) -> MonitorStats {
+        self.stats.record_tracked(self.tracked.lock().await.len());
+        self.stats.snapshot()
+    }
+    
+    /// Get the current status of the polling task
+    pub fn status(&self) -> MonitorStatus {
+        self.status.borrow().clone()
+    }
+    
+    /// Get a receiver that is notified when the polling task terminates
+    ///
+    /// The receiver observes every status change; when the loop exits, the
+    /// status changes to `Stopped` or `Failed(reason)`, letting the caller
+    /// decide whether to restart the monitor or exit non-zero.
+    pub fn on_terminate(&self) -> watch::Receiver<MonitorStatus> {
+        self.status.subscribe()
+    }
+    
+    /// Emit `NewBlock` events for every height from `start_height` to the tip
+    ///
+    /// Events are flagged as `replayed`. The tip is re-read after each pass so
+    /// blocks mined during the replay are not skipped; once caught up, the
+    /// current height is the tip and live polling continues seamlessly.
+    ///
+    /// Replay pauses while `REPLAY_BACKLOG` events are still unread by some
+    /// subscriber, so a slow subscriber holds it back instead of lagging and
+    /// losing history.
+    async fn replay_blocks(
+        rpc_client: &RpcClient,
+        start_height: u64,
+        replay_rate: u32,
+        current_height: &Mutex<u64>,
+        current_hash: &Mutex<String>,
+        stats: &Stats,
+        event_sender: &broadcast::Sender<BlockEvent>,
+    ) -> Result<()> {
+        // Resume after the last seen block when restarted
+        let mut next = match *current_height.lock().await {
+            0 => start_height,
+            current => start_height.max(current + 1),
+        };
+        let throttle = (replay_rate > 0).then(|| Duration::from_secs(1) / replay_rate);
+        
+        loop {
+            let tip = rpc_client.get_block_count().await?;
+            if next > tip {
+                break;
+            }
+            
+            info!("Replaying blocks {} to {}", next, tip);
+            for height in next..=tip {
+                let hash = rpc_client.get_block_hash(height).await
+                    .with_context(|| format!("Failed to get hash of block {}", height))?;
+                
+                while event_sender.len() >= REPLAY_BACKLOG {
+                    sleep(Duration::from_millis(10)).await;
+                }
+                
+                *current_height.lock().await = height;
+                *current_hash.lock().await = hash.clone();
+                let _ = event_sender.send(BlockEvent::NewBlock { height, hash, replayed: true, observed_at: Utc::now() });
+                stats.record_new_block();
+                
+                if let Some(throttle) = throttle {
+                    sleep(throttle).await;
+                }
+            }
+            next = tip + 1;
+        }
+        
+        Ok(())
+    }
+    
     /// Check for new blocks
     async fn check_for_new_block(
         rpc_client: &RpcClient,
         current_height: &Mutex<u64>,
-        event_sender: &mpsc::Sender<BlockEvent>,
+        current_hash: &Mutex<String>,
+        divergence: &mut DivergenceTracker,
+        stats: &Stats,
+        event_sender: &broadcast::Sender<BlockEvent>,
     ) -> Result<bool> {
         // TODO: Implement actual block checking logic using RPC client
         // This is a placeholder implementation
@@ -178,15 +661,17 @@ impl BlockMonitor {
         let bitcoin_height = rpc_client.get_block_count().await?;
         
         // Get current block height from Metashrew RPC
-        let metashrew_height = rpc_client.get_metashrew_height().await?;
+        let metashrew_height = Self::check_metashrew_heights(rpc_client, divergence, event_sender).await?;
+        stats.record_heights(bitcoin_height, metashrew_height);
         
-        // Verify that Metashrew height is Bitcoin height + 1
-        if metashrew_height != bitcoin_height + 1 {
+        // Keep following Bitcoin while the indexer lags; consumers see the
+        // lag through the stats
+        let sync_status = SyncStatus::from_heights(bitcoin_height, metashrew_height);
+        if !sync_status.in_sync {
             warn!(
-                "Metashrew height ({}) is not Bitcoin height ({}) + 1",
-                metashrew_height, bitcoin_height
+                "Metashrew height ({}) is not Bitcoin height ({}) + 1 (lag {})",
+                metashrew_height, bitcoin_height, sync_status.indexer_lag
             );
-            // Continue anyway, but log the warning
         }
         
         // Check if we have a new block
@@ -194,15 +679,20 @@ impl BlockMonitor {
         if bitcoin_height > *current {
             // New block found
             info!("New block detected at height {}", bitcoin_height);
+            let hash = rpc_client.get_block_hash(bitcoin_height).await?;
             
-            // Update current height
+            // Update current height and hash
             *current = bitcoin_height;
+            *current_hash.lock().await = hash.clone();
             
-            // Send new block event
+            // Send new block event (an error only means there are no subscribers)
             let _ = event_sender.send(BlockEvent::NewBlock {
                 height: bitcoin_height,
-                hash: "placeholder_hash".to_string(), // In a real implementation, we would get the actual hash
-            }).await;
+                hash,
+                replayed: false,
+                observed_at: Utc::now(),
+            });
+            stats.record_new_block();
             
             // Return true to indicate a new block was found
             return Ok(true);
@@ -212,10 +702,381 @@ impl BlockMonitor {
         Ok(false)
     }
     
-    /// Get the event receiver for listening to block events
-    pub async fn get_event_receiver(&self) -> mpsc::Sender<BlockEvent> {
-        // Return a clone of the sender instead
-        self.event_sender.clone()
+    /// Query every Metashrew instance and return the highest height
+    ///
+    /// Unreachable instances are skipped as long as one answers. Heights of
+    /// the reachable ones feed the divergence tracker, whose state changes are
+    /// emitted as events.
+    async fn check_metashrew_heights(
+        rpc_client: &RpcClient,
+        divergence: &mut DivergenceTracker,
+        event_sender: &broadcast::Sender<BlockEvent>,
+    ) -> Result<u64> {
+        let mut heights = BTreeMap::new();
+        let mut last_error = None;
+        for (url, result) in rpc_client.get_metashrew_heights().await {
+            match result {
+                Ok(height) => {
+                    heights.insert(url, height);
+                },
+                Err(e) => {
+                    warn!("Failed to get Metashrew height from {}: {}", url, e);
+                    last_error = Some(e);
+                },
+            }
+        }
+        
+        let max_height = match heights.values().max() {
+            Some(height) => *height,
+            None => return Err(last_error.unwrap_or_else(|| anyhow!("No Metashrew endpoints configured"))),
+        };
+        
+        if let Some(event) = divergence.observe(&heights, Instant::now()) {
+            match &event {
+                BlockEvent::EndpointDiverged { heights, .. } => warn!("Metashrew instances diverged: {:?}", heights),
+                _ => info!("Metashrew instances converged at height {}", max_height),
+            }
+            let _ = event_sender.send(event);
+        }
+        
+        Ok(max_height)
+    }
+    
+    /// Check the confirmation status of every watched transaction
+    async fn check_tracked_transactions(
+        rpc_client: &RpcClient,
+        tracked: &Mutex<HashMap<String, u32>>,
+        tip_height: u64,
+        event_sender: &broadcast::Sender<BlockEvent>,
+    ) -> Result<()> {
+        // Snapshot the watch list so the lock isn't held across RPC calls
+        let watched: Vec<(String, u32)> = tracked.lock().await
+            .iter()
+            .map(|(txid, confirmations)| (txid.clone(), *confirmations))
+            .collect();
+        
+        for (txid, previous) in watched {
+            let status = rpc_client.get_tx_status(&txid).await
+                .with_context(|| format!("Failed to get status for {}", txid))?;
+            
+            let confirmations = confirmations_at(&status, tip_height);
+            
+            if previous > 0 && confirmations == 0 {
+                // The transaction was confirmed but is no longer in the chain
+                warn!("Watched transaction {} dropped out of the chain", txid);
+                tracked.lock().await.remove(&txid);
+                let _ = event_sender.send(BlockEvent::TransactionDropped { txid, observed_at: Utc::now() });
+                continue;
+            }
+            
+            if confirmations > previous {
+                debug!("Transaction {} has {} confirmation(s)", txid, confirmations);
+                tracked.lock().await.insert(txid.clone(), confirmations);
+                let _ = event_sender.send(BlockEvent::TransactionConfirmed { txid, confirmations, observed_at: Utc::now() });
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Query protorune balances of watched addresses and emit changes
+    async fn check_watched_addresses(
+        rpc_client: &RpcClient,
+        watched_addresses: &Mutex<AddressSnapshots>,
+        state_path: Option<&str>,
+        event_sender: &broadcast::Sender<BlockEvent>,
+    ) -> Result<()> {
+        let addresses: Vec<String> = watched_addresses.lock().await.keys().cloned().collect();
+        if addresses.is_empty() {
+            return Ok(());
+        }
+        
+        // Query every watched address in a single batch
+        let calls: Vec<(&str, serde_json::Value)> = addresses.iter()
+            .map(|address| ("alkanes_protorunesbyaddress", serde_json::json!([address])))
+            .collect();
+        let results = rpc_client.call_batch(&calls).await?;
+        
+        let mut watched = watched_addresses.lock().await;
+        for (address, result) in addresses.into_iter().zip(results) {
+            let balances: RuneBalances = match result {
+                Ok(value) => parse_protorune_balances(&value)
+                    .into_iter()
+                    .map(|b| (format!("{}:{}", b.block, b.tx), b.amount))
+                    .collect(),
+                Err(e) => {
+                    warn!("Failed to get protorunes for {}: {}", address, e);
+                    continue;
+                }
+            };
+            
+            // The first snapshot of an address is a silent baseline
+            if let Some(Some(previous)) = watched.get(&address) {
+                let mut rune_ids: Vec<&String> = previous.keys().chain(balances.keys()).collect();
+                rune_ids.sort();
+                rune_ids.dedup();
+                
+                for rune_id in rune_ids {
+                    let old = previous.get(rune_id).copied().unwrap_or(0);
+                    let new = balances.get(rune_id).copied().unwrap_or(0);
+                    if old != new {
+                        info!("Balance of {} on {} changed from {} to {}", rune_id, address, old, new);
+                        let _ = event_sender.send(BlockEvent::TokenBalanceChanged {
+                            address: address.clone(),
+                            rune_id: rune_id.clone(),
+                            old,
+                            new,
+                            observed_at: Utc::now(),
+                        });
+                    }
+                }
+            }
+            
+            watched.insert(address, Some(balances));
+        }
+        
+        if let Some(path) = state_path {
+            Self::save_address_snapshots(path, &watched)?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Load watched address snapshots from a state file
+    fn load_address_snapshots(path: &str) -> Result<AddressSnapshots> {
+        if !Path::new(path).exists() {
+            return Ok(HashMap::new());
+        }
+        
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read monitor state from {}", path))?;
+        
+        // Balances are stored as strings since JSON numbers can't hold a u128
+        let stored: HashMap<String, Option<HashMap<String, String>>> = serde_json::from_str(&contents)
+            .context("Failed to parse monitor state")?;
+        
+        stored.into_iter()
+            .map(|(address, balances)| {
+                let balances = balances
+                    .map(|balances| balances.into_iter()
+                        .map(|(rune_id, amount)| Ok((rune_id, amount.parse::<u128>()?)))
+                        .collect::<Result<RuneBalances>>())
+                    .transpose()?;
+                Ok((address, balances))
+            })
+            .collect()
+    }
+    
+    /// Save watched address snapshots to a state file
+    fn save_address_snapshots(path: &str, snapshots: &AddressSnapshots) -> Result<()> {
+        let stored: HashMap<&String, Option<HashMap<&String, String>>> = snapshots.iter()
+            .map(|(address, balances)| {
+                let balances = balances.as_ref().map(|balances| balances.iter()
+                    .map(|(rune_id, amount)| (rune_id, amount.to_string()))
+                    .collect());
+                (address, balances)
+            })
+            .collect();
+        
+        std::fs::write(path, serde_json::to_string_pretty(&stored)?)
+            .with_context(|| format!("Failed to write monitor state to {}", path))?;
+        Ok(())
+    }
+    
+    /// Watch an address for protorune balance changes
+    ///
+    /// After each new block the monitor queries the balances of all watched
+    /// addresses and emits `TokenBalanceChanged` for every rune whose balance
+    /// differs from the previous snapshot. The first query of a new address
+    /// only records a baseline. With `state_path` configured, snapshots and
+    /// the watch list survive restarts.
+    pub async fn watch_address(&self, address: String) {
+        self.watched_addresses.lock().await.entry(address).or_insert(None);
+    }
+    
+    /// Watch a transaction for confirmations
+    ///
+    /// After each new block the monitor emits `TransactionConfirmed` whenever
+    /// the confirmation count of a watched transaction increases, and
+    /// `TransactionDropped` if a confirmed transaction disappears in a reorg.
+    /// Returns `false` if the transaction was already being watched.
+    pub async fn watch_transaction(&self, txid: &str) -> bool {
+        let mut tracked = self.tracked.lock().await;
+        if tracked.contains_key(txid) {
+            return false;
+        }
+        tracked.insert(txid.to_string(), 0);
+        true
+    }
+    
+    /// Stop watching a transaction
+    pub async fn unwatch_transaction(&self, txid: &str) {
+        self.tracked.lock().await.remove(txid);
+    }
+    
+    /// Wait until the chain reaches `height`
+    ///
+    /// Resolves immediately with the current tip if it is already at or above
+    /// `height`; otherwise waits for the first matching `NewBlock` event. A
+    /// timeout is reported as `WaitError::Timeout`.
+    pub async fn wait_for_block(&self, height: u64, timeout: Duration) -> Result<BlockEvent> {
+        // Subscribe before checking the tip so no block can slip in between
+        let mut events = self.subscribe();
+        
+        let current = *self.current_height.lock().await;
+        if current >= height {
+            return Ok(BlockEvent::NewBlock {
+                height: current,
+                hash: self.current_hash.lock().await.clone(),
+                replayed: false,
+                observed_at: Utc::now(),
+            });
+        }
+        
+        let result = tokio::time::timeout(timeout, async {
+            loop {
+                match events.recv().await {
+                    Ok(event) if matches!(event, BlockEvent::NewBlock { height: h, .. } if h >= height) => {
+                        return Ok(event);
+                    },
+                    Ok(_) => {},
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Block waiter lagged behind, skipped {} event(s)", skipped);
+                    },
+                    Err(broadcast::error::RecvError::Closed) => return Err(WaitError::Closed),
+                }
+            }
+        }).await;
+        
+        Ok(result.map_err(|_| WaitError::Timeout(timeout))??)
+    }
+    
+    /// Wait until a transaction reaches `n` confirmations
+    ///
+    /// Returns the confirmation count once the target is reached, immediately
+    /// if the transaction is already watched or the node already reports
+    /// enough confirmations. The node is asked again whenever the waiter lags
+    /// behind the event channel, so skipped events aren't waited on. Fails
+    /// with `WaitError::Timeout` if the timeout elapses first or
+    /// `WaitError::Dropped` if the transaction is dropped by a reorg.
+    pub async fn wait_for_confirmations(&self, txid: &str, n: u32, timeout: Duration) -> Result<u32> {
+        // Subscribe before watching so no confirmation event can be missed
+        let mut events = self.subscribe();
+        
+        if let Some(confirmations) = self.tracked.lock().await.get(txid).copied() {
+            if confirmations >= n {
+                return Ok(confirmations);
+            }
+        }
+        let added = self.watch_transaction(txid).await;
+        
+        let result = tokio::time::timeout(timeout, async {
+            // The transaction may be confirmed already, watched or not
+            if let Some(confirmations) = self.query_confirmations(txid).await.filter(|c| *c >= n) {
+                return Ok(confirmations);
+            }
+            loop {
+                match events.recv().await {
+                    Ok(BlockEvent::TransactionConfirmed { txid: id, confirmations, .. }) if id == txid => {
+                        if confirmations >= n {
+                            return Ok(confirmations);
+                        }
+                    },
+                    Ok(BlockEvent::TransactionDropped { txid: id, .. }) if id == txid => {
+                        return Err(WaitError::Dropped(id));
+                    },
+                    Ok(_) => {},
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Confirmation waiter lagged behind, skipped {} event(s)", skipped);
+                        // The skipped events may include the one we were waiting for
+                        if let Some(confirmations) = self.query_confirmations(txid).await.filter(|c| *c >= n) {
+                            return Ok(confirmations);
+                        }
+                    },
+                    Err(broadcast::error::RecvError::Closed) => return Err(WaitError::Closed),
+                }
+            }
+        }).await;
+        
+        // Only stop watching if this call started it
+        if added {
+            self.unwatch_transaction(txid).await;
+        }
+        
+        Ok(result.map_err(|_| WaitError::Timeout(timeout))??)
+    }
+    
+    /// Ask the node how many confirmations a transaction has
+    ///
+    /// Returns `None` if either query fails, so callers fall back to waiting
+    /// for events.
+    async fn query_confirmations(&self, txid: &str) -> Option<u32> {
+        let status = async {
+            let tip_height = self.rpc_client.get_block_count().await?;
+            let status = self.rpc_client.get_tx_status(txid).await?;
+            Ok::<_, anyhow::Error>(confirmations_at(&status, tip_height))
+        }.await;
+        
+        match status {
+            Ok(confirmations) => Some(confirmations),
+            Err(e) => {
+                debug!("Failed to query confirmations of {}: {}", txid, e);
+                None
+            },
+        }
+    }
+    
+    /// Subscribe to block events
+    ///
+    /// Every subscriber receives its own copy of each event emitted after it
+    /// subscribed. Slow subscribers that fall more than 100 events behind will
+    /// observe a `RecvError::Lagged` and skip the missed events, except during
+    /// block replay, which waits for them to catch up.
+    pub fn subscribe(&self) -> broadcast::Receiver<BlockEvent> {
+        self.event_sender.subscribe()
+    }
+    
+    /// Subscribe to the block events matching a filter
+    ///
+    /// Events are filtered as they come off the broadcast channel, so a
+    /// subscriber only wakes up for events it asked for. Lagged events are
+    /// skipped as with `subscribe`, and the stream ends when the monitor is
+    /// dropped.
+    pub fn subscribe_filtered(&self, filter: EventFilter) -> impl Stream<Item = BlockEvent> {
+        futures_util::stream::unfold((self.subscribe(), filter), |(mut receiver, filter)| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) if filter.matches(&event) => return Some((event, (receiver, filter))),
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Filtered subscriber lagged, skipped {} events", skipped);
+                        continue;
+                    },
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+}
+
+impl Drop for BlockMonitor {
+    /// Abort the polling task so a dropped monitor stops hitting the RPC
+    fn drop(&mut self) {
+        if let Some(handle) = self.task.get_mut().take() {
+            debug!("Block monitor dropped while running, aborting polling task");
+            handle.abort();
+            self.status.send_replace(MonitorStatus::Stopped);
+        }
+    }
+}
+
+/// Confirmations of a transaction given the current tip height
+fn confirmations_at(status: &TxStatus, tip_height: u64) -> u32 {
+    match (status.confirmed, status.block_height) {
+        (true, Some(block_height)) if block_height <= tip_height => {
+            (tip_height - block_height + 1) as u32
+        },
+        _ => 0,
     }
 }
 
@@ -230,6 +1091,7 @@ mod tests {
         let rpc_config = RpcConfig {
             bitcoin_rpc_url: "http://localhost:18332".to_string(),
             metashrew_rpc_url: "http://localhost:8080".to_string(),
+            ..Default::default()
         };
         let rpc_client = Arc::new(RpcClient::new(rpc_config));
         
@@ -241,4 +1103,582 @@ mod tests {
         let current_height = monitor.current_height.lock().await;
         assert_eq!(*current_height, 0);
     }
+    
+    fn test_monitor() -> BlockMonitor {
+        let rpc_config = RpcConfig {
+            bitcoin_rpc_url: "http://localhost:18332".to_string(),
+            metashrew_rpc_url: "http://localhost:8080".to_string(),
+            ..Default::default()
+        };
+        BlockMonitor::new(Arc::new(RpcClient::new(rpc_config)), BlockMonitorConfig::default())
+    }
+    
+    fn observed_at() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-04-20T00:09:27.000Z").unwrap().with_timezone(&Utc)
+    }
+    
+    #[test]
+    fn test_event_wire_format() {
+        let cases = vec![
+            (
+                BlockEvent::NewBlock { height: 840000, hash: "00ab".to_string(), replayed: false, observed_at: observed_at() },
+                r#"{"version":1,"type":"new_block","height":840000,"hash":"00ab","replayed":false,"observed_at":"2024-04-20T00:09:27.000Z"}"#,
+            ),
+            (
+                BlockEvent::TransactionConfirmed { txid: "ff".to_string(), confirmations: 3, observed_at: observed_at() },
+                r#"{"version":1,"type":"transaction_confirmed","txid":"ff","confirmations":3,"observed_at":"2024-04-20T00:09:27.000Z"}"#,
+            ),
+            (
+                BlockEvent::TransactionDropped { txid: "ff".to_string(), observed_at: observed_at() },
+                r#"{"version":1,"type":"transaction_dropped","txid":"ff","observed_at":"2024-04-20T00:09:27.000Z"}"#,
+            ),
+            (
+                BlockEvent::TokenBalanceChanged {
+                    address: "bc1qwatched".to_string(),
+                    rune_id: "2:0".to_string(),
+                    old: 0,
+                    new: u128::MAX,
+                    observed_at: observed_at(),
+                },
+                r#"{"version":1,"type":"token_balance_changed","address":"bc1qwatched","rune_id":"2:0","old":"0","new":"340282366920938463463374607431768211455","observed_at":"2024-04-20T00:09:27.000Z"}"#,
+            ),
+            (
+                BlockEvent::EndpointDiverged {
+                    heights: BTreeMap::from([("http://a".to_string(), 840000), ("http://b".to_string(), 839990)]),
+                    observed_at: observed_at(),
+                },
+                r#"{"version":1,"type":"endpoint_diverged","heights":{"http://a":840000,"http://b":839990},"observed_at":"2024-04-20T00:09:27.000Z"}"#,
+            ),
+            (
+                BlockEvent::EndpointsConverged { observed_at: observed_at() },
+                r#"{"version":1,"type":"endpoints_converged","observed_at":"2024-04-20T00:09:27.000Z"}"#,
+            ),
+            (
+                BlockEvent::Degraded { failures: 5, message: "timeout".to_string(), observed_at: observed_at() },
+                r#"{"version":1,"type":"degraded","failures":5,"message":"timeout","observed_at":"2024-04-20T00:09:27.000Z"}"#,
+            ),
+            (
+                BlockEvent::Recovered { failures: 7, observed_at: observed_at() },
+                r#"{"version":1,"type":"recovered","failures":7,"observed_at":"2024-04-20T00:09:27.000Z"}"#,
+            ),
+            (
+                BlockEvent::Error { message: "boom".to_string(), observed_at: observed_at() },
+                r#"{"version":1,"type":"error","message":"boom","observed_at":"2024-04-20T00:09:27.000Z"}"#,
+            ),
+        ];
+        
+        for (event, json) in cases {
+            assert_eq!(event.to_json(), json);
+            assert_eq!(serde_json::from_str::<BlockEvent>(json).unwrap(), event);
+        }
+    }
+    
+    #[test]
+    fn test_event_wire_format_rejects_unknown_version() {
+        let json = r#"{"version":2,"type":"transaction_dropped","txid":"ff","observed_at":"2024-04-20T00:09:27.000Z"}"#;
+        assert!(serde_json::from_str::<BlockEvent>(json).is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_status_transitions() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        server.set("btc_getblockcount", serde_json::json!(100));
+        server.set("metashrew_height", serde_json::json!(101));
+        server.set("btc_getblockhash", serde_json::json!("00ff"));
+        
+        let monitor = BlockMonitor::new(Arc::new(RpcClient::new(server.config())), BlockMonitorConfig::default());
+        assert_eq!(monitor.status(), MonitorStatus::Stopped);
+        
+        monitor.start().await.unwrap();
+        assert_eq!(monitor.status(), MonitorStatus::Running);
+        
+        monitor.stop().await.unwrap();
+        assert_eq!(monitor.status(), MonitorStatus::Stopped);
+    }
+    
+    #[tokio::test]
+    async fn test_degraded_after_max_retries() {
+        // No canned responses, so every poll fails
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let config = BlockMonitorConfig {
+            polling_interval: 0,
+            max_retries: 2,
+            retry_delay: 0,
+            ..BlockMonitorConfig::default()
+        };
+        let monitor = BlockMonitor::new(Arc::new(RpcClient::new(server.config())), config);
+        let mut events = monitor.subscribe();
+        
+        monitor.start().await.unwrap();
+        
+        // The outage is reported once the threshold is reached
+        let event = tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap();
+        assert!(matches!(event, BlockEvent::Degraded { failures: 2, .. }));
+        
+        // ...and only once, while polling carries on
+        let calls = server.calls().len();
+        while server.calls().len() < calls + 10 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(monitor.status(), MonitorStatus::Running);
+        assert!(events.try_recv().is_err());
+        
+        monitor.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_snapshot_after_scripted_polls() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let monitor = BlockMonitor::new(Arc::new(RpcClient::new(server.config())), BlockMonitorConfig::default());
+        let mut divergence = DivergenceTracker::new(
+            monitor.config.divergence_threshold,
+            Duration::from_secs(monitor.config.divergence_duration),
+        );
+        async fn poll(monitor: &BlockMonitor, divergence: &mut DivergenceTracker) -> Result<bool> {
+            BlockMonitor::check_for_new_block(
+                &monitor.rpc_client, &monitor.current_height, &monitor.current_hash, divergence, &monitor.stats, &monitor.event_sender,
+            ).await
+        }
+        
+        // A new block, then the same block again with Metashrew lagging
+        server.set("btc_getblockcount", serde_json::json!(100));
+        server.set("metashrew_height", serde_json::json!(101));
+        server.set("btc_getblockhash", serde_json::json!("00ff"));
+        assert!(poll(&monitor, &mut divergence).await.unwrap());
+        server.set("metashrew_height", serde_json::json!(98));
+        assert!(!poll(&monitor, &mut divergence).await.unwrap());
+        monitor.watch_transaction("aa").await;
+        
+        let stats = monitor.snapshot().await;
+        assert_eq!(stats.bitcoin_height, Some(100));
+        assert_eq!(stats.metashrew_height, Some(98));
+        assert_eq!(stats.indexer_lag, Some(3));
+        assert_eq!(stats.new_blocks, 1);
+        assert_eq!(stats.seconds_since_last_block, Some(0));
+        assert_eq!(stats.tracked_transactions, 1);
+        assert!(stats.errors.is_empty());
+        
+        // A failed poll is counted by the loop
+        server.unset("btc_getblockcount");
+        let config = BlockMonitorConfig {
+            polling_interval: 0,
+            max_retries: 1,
+            retry_delay: 0,
+            ..BlockMonitorConfig::default()
+        };
+        let failing = BlockMonitor::new(Arc::new(RpcClient::new(server.config())), config);
+        let mut events = failing.subscribe();
+        failing.start().await.unwrap();
+        assert!(matches!(events.recv().await.unwrap(), BlockEvent::Degraded { .. }));
+        failing.stop().await.unwrap();
+        assert!(matches!(failing.snapshot().await.errors.get("poll"), Some(count) if *count >= 1));
+    }
+    
+    #[tokio::test]
+    async fn test_drop_aborts_polling_task() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        server.set("btc_getblockcount", serde_json::json!(100));
+        server.set("metashrew_height", serde_json::json!(101));
+        server.set("btc_getblockhash", serde_json::json!("00ff"));
+        
+        let config = BlockMonitorConfig {
+            polling_interval: 0,
+            ..BlockMonitorConfig::default()
+        };
+        let monitor = BlockMonitor::new(Arc::new(RpcClient::new(server.config())), config);
+        let mut events = monitor.subscribe();
+        let terminated = monitor.on_terminate();
+        monitor.start().await.unwrap();
+        drop(monitor);
+        
+        assert_eq!(*terminated.borrow(), MonitorStatus::Stopped);
+        
+        // The channel only closes once the task has released its sender
+        let closed = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Err(broadcast::error::RecvError::Closed) = events.recv().await {
+                    return;
+                }
+            }
+        }).await;
+        assert!(closed.is_ok());
+        
+        // No more polls reach the RPC once in-flight requests have landed
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let calls = server.calls().len();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(server.calls().len(), calls);
+    }
+    
+    #[tokio::test]
+    async fn test_recovery_after_outage() {
+        // No canned responses, so polls fail until the outage ends
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let config = BlockMonitorConfig {
+            polling_interval: 0,
+            max_retries: 0,
+            retry_delay: 0,
+            ..BlockMonitorConfig::default()
+        };
+        let monitor = BlockMonitor::new(Arc::new(RpcClient::new(server.config())), config);
+        let mut events = monitor.subscribe();
+        monitor.start().await.unwrap();
+        
+        // Polling never gives up, however many failures there are
+        while server.calls().len() < 10 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(monitor.status(), MonitorStatus::Running);
+        
+        server.set("btc_getblockcount", serde_json::json!(100));
+        server.set("metashrew_height", serde_json::json!(101));
+        server.set("btc_getblockhash", serde_json::json!("00ff"));
+        
+        let failures = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let BlockEvent::Recovered { failures, .. } = events.recv().await.unwrap() {
+                    return failures;
+                }
+            }
+        }).await.unwrap();
+        assert!(failures >= 10);
+        
+        monitor.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_endpoint_divergence_and_convergence() {
+        let primary = crate::rpc::mock::MockRpcServer::start().await;
+        let replica = crate::rpc::mock::MockRpcServer::start().await;
+        primary.set("btc_getblockcount", serde_json::json!(100));
+        primary.set("btc_getblockhash", serde_json::json!("00ff"));
+        primary.set("metashrew_height", serde_json::json!(101));
+        replica.set("metashrew_height", serde_json::json!(101));
+        
+        let rpc_config = RpcConfig {
+            metashrew_replica_urls: vec![replica.url.clone()],
+            ..primary.config()
+        };
+        let config = BlockMonitorConfig {
+            polling_interval: 0,
+            divergence_threshold: 2,
+            divergence_duration: 0,
+            ..BlockMonitorConfig::default()
+        };
+        let monitor = BlockMonitor::new(Arc::new(RpcClient::new(rpc_config)), config);
+        let mut events = monitor.subscribe();
+        monitor.start().await.unwrap();
+        
+        async fn next_endpoint_event(events: &mut broadcast::Receiver<BlockEvent>) -> BlockEvent {
+            loop {
+                let event = tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap();
+                if matches!(event, BlockEvent::EndpointDiverged { .. } | BlockEvent::EndpointsConverged { .. }) {
+                    return event;
+                }
+            }
+        }
+        
+        // The replica falls behind by more than the threshold
+        replica.set("metashrew_height", serde_json::json!(97));
+        match next_endpoint_event(&mut events).await {
+            BlockEvent::EndpointDiverged { heights, .. } => {
+                assert_eq!(heights.get(&primary.url), Some(&101));
+                assert_eq!(heights.get(&replica.url), Some(&97));
+            },
+            other => panic!("unexpected event {:?}", other),
+        }
+        // The highest instance is taken as the truth
+        while monitor.snapshot().await.metashrew_height.is_none() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(monitor.snapshot().await.metashrew_height, Some(101));
+        
+        // Catching up clears the divergence
+        replica.set("metashrew_height", serde_json::json!(101));
+        assert!(matches!(next_endpoint_event(&mut events).await, BlockEvent::EndpointsConverged { .. }));
+        
+        monitor.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_replay_then_live() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        server.set("btc_getblockcount", serde_json::json!(5));
+        server.set("metashrew_height", serde_json::json!(6));
+        for height in 3..=6 {
+            server.set_with_params("btc_getblockhash", serde_json::json!([height]), serde_json::json!(format!("{:02x}", height)));
+        }
+        
+        let config = BlockMonitorConfig {
+            polling_interval: 0,
+            start_height: Some(3),
+            ..BlockMonitorConfig::default()
+        };
+        let monitor = BlockMonitor::new(Arc::new(RpcClient::new(server.config())), config);
+        let mut events = monitor.subscribe();
+        monitor.start().await.unwrap();
+        
+        async fn next_block(events: &mut broadcast::Receiver<BlockEvent>) -> (u64, String, bool) {
+            loop {
+                let event = tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap();
+                if let BlockEvent::NewBlock { height, hash, replayed, .. } = event {
+                    return (height, hash, replayed);
+                }
+            }
+        }
+        
+        // History is replayed in order and flagged
+        for height in 3..=5 {
+            assert_eq!(next_block(&mut events).await, (height, format!("{:02x}", height), true));
+        }
+        
+        // The next block at the tip arrives as a live event
+        server.set("btc_getblockcount", serde_json::json!(6));
+        assert_eq!(next_block(&mut events).await, (6, "06".to_string(), false));
+        
+        monitor.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_replay_waits_for_slow_subscriber() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        server.set("btc_getblockcount", serde_json::json!(300));
+        server.set("metashrew_height", serde_json::json!(301));
+        server.set("btc_getblockhash", serde_json::json!("00ff"));
+        
+        let config = BlockMonitorConfig {
+            start_height: Some(1),
+            ..BlockMonitorConfig::default()
+        };
+        let monitor = BlockMonitor::new(Arc::new(RpcClient::new(server.config())), config);
+        let mut events = monitor.subscribe();
+        monitor.start().await.unwrap();
+        
+        // Far more history than the channel holds, read slowly, without a gap
+        let mut expected = 1;
+        while expected <= 300 {
+            let event = tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap();
+            if let BlockEvent::NewBlock { height, replayed: true, .. } = event {
+                assert_eq!(height, expected);
+                expected += 1;
+            }
+            sleep(Duration::from_millis(1)).await;
+        }
+        
+        monitor.stop().await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_watch_address_balance_changes() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let state_path = std::env::temp_dir()
+            .join(format!("deezel_monitor_state_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&state_path);
+        let path = state_path.to_string_lossy().to_string();
+        let config = || BlockMonitorConfig {
+            state_path: Some(path.clone()),
+            ..BlockMonitorConfig::default()
+        };
+        
+        let balance = |amount: &str| serde_json::json!([
+            { "rune": { "id": { "block": "2", "tx": "0" } }, "balance": amount }
+        ]);
+        let check = |monitor: &BlockMonitor| {
+            let rpc_client = Arc::clone(&monitor.rpc_client);
+            let watched = Arc::clone(&monitor.watched_addresses);
+            let sender = monitor.event_sender.clone();
+            let path = path.clone();
+            async move {
+                BlockMonitor::check_watched_addresses(&rpc_client, &watched, Some(&path), &sender).await.unwrap();
+            }
+        };
+        
+        let monitor = BlockMonitor::new(Arc::new(RpcClient::new(server.config())), config());
+        monitor.watch_address("bc1qwatched".to_string()).await;
+        let mut events = monitor.subscribe();
+        
+        // The first snapshot is a silent baseline
+        server.set("alkanes_protorunesbyaddress", balance("100"));
+        check(&monitor).await;
+        assert!(events.try_recv().is_err());
+        
+        server.set("alkanes_protorunesbyaddress", balance("250"));
+        check(&monitor).await;
+        match events.try_recv().unwrap() {
+            BlockEvent::TokenBalanceChanged { address, rune_id, old, new, .. } => {
+                assert_eq!(address, "bc1qwatched");
+                assert_eq!(rune_id, "2:0");
+                assert_eq!((old, new), (100, 250));
+            },
+            other => panic!("Unexpected event: {:?}", other),
+        }
+        
+        // A restarted monitor resumes from the persisted snapshot
+        let restarted = BlockMonitor::new(Arc::new(RpcClient::new(server.config())), config());
+        let mut events = restarted.subscribe();
+        check(&restarted).await;
+        assert!(events.try_recv().is_err());
+        
+        server.set("alkanes_protorunesbyaddress", serde_json::json!([]));
+        check(&restarted).await;
+        assert!(matches!(events.try_recv().unwrap(), BlockEvent::TokenBalanceChanged { old: 250, new: 0, .. }));
+        
+        let _ = std::fs::remove_file(&state_path);
+    }
+    
+    #[tokio::test]
+    async fn test_wait_for_block_already_satisfied() {
+        let monitor = test_monitor();
+        *monitor.current_height.lock().await = 100;
+        *monitor.current_hash.lock().await = "00ff".to_string();
+        
+        let event = monitor.wait_for_block(90, Duration::from_millis(10)).await.unwrap();
+        match event {
+            BlockEvent::NewBlock { height, hash, .. } => {
+                assert_eq!(height, 100);
+                assert_eq!(hash, "00ff");
+            },
+            other => panic!("Unexpected event: {:?}", other),
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_wait_for_block_from_event() {
+        let monitor = test_monitor();
+        let sender = monitor.event_sender.clone();
+        
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(20)).await;
+            let _ = sender.send(BlockEvent::NewBlock { height: 4, hash: "04".to_string(), replayed: false, observed_at: Utc::now() });
+            let _ = sender.send(BlockEvent::NewBlock { height: 5, hash: "05".to_string(), replayed: false, observed_at: Utc::now() });
+        });
+        
+        let event = monitor.wait_for_block(5, Duration::from_secs(5)).await.unwrap();
+        assert!(matches!(event, BlockEvent::NewBlock { height: 5, .. }));
+    }
+    
+    #[tokio::test]
+    async fn test_wait_for_block_timeout() {
+        let monitor = test_monitor();
+        
+        let err = monitor.wait_for_block(1, Duration::from_millis(20)).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<WaitError>(), Some(&WaitError::Timeout(Duration::from_millis(20))));
+    }
+    
+    #[tokio::test]
+    async fn test_wait_for_confirmations_already_satisfied() {
+        let monitor = test_monitor();
+        monitor.tracked.lock().await.insert("aa".to_string(), 3);
+        
+        let confirmations = monitor.wait_for_confirmations("aa", 2, Duration::from_millis(10)).await.unwrap();
+        assert_eq!(confirmations, 3);
+    }
+    
+    #[tokio::test]
+    async fn test_wait_for_confirmations_queries_node() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        server.set("btc_getblockcount", serde_json::json!(100));
+        server.set("esplora_tx::status", serde_json::json!({"confirmed": true, "block_height": 98, "block_hash": "00ff"}));
+        
+        // Neither started nor watching the transaction, the node alone answers
+        let monitor = BlockMonitor::new(Arc::new(RpcClient::new(server.config())), BlockMonitorConfig::default());
+        let confirmations = monitor.wait_for_confirmations("dd", 2, Duration::from_secs(5)).await.unwrap();
+        assert_eq!(confirmations, 3);
+        assert!(!monitor.tracked.lock().await.contains_key("dd"));
+        
+        // Not enough yet, so the waiter keeps listening until the timeout
+        let err = monitor.wait_for_confirmations("dd", 4, Duration::from_millis(50)).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<WaitError>(), Some(WaitError::Timeout(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_wait_for_confirmations_after_lag() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        server.set("btc_getblockcount", serde_json::json!(100));
+        
+        let monitor = BlockMonitor::new(Arc::new(RpcClient::new(server.config())), BlockMonitorConfig::default());
+        let sender = monitor.event_sender.clone();
+        let waiter = monitor.wait_for_confirmations("ee", 1, Duration::from_secs(5));
+        
+        let flood = async {
+            // Let the waiter's first query miss before the status appears
+            while !server.calls().iter().any(|(method, _)| method == "esplora_tx::status") {
+                sleep(Duration::from_millis(5)).await;
+            }
+            server.set("esplora_tx::status", serde_json::json!({"confirmed": true, "block_height": 100, "block_hash": "00ff"}));
+            // Overflow the channel so the confirmation event itself is lost
+            let _ = sender.send(BlockEvent::TransactionConfirmed { txid: "ee".to_string(), confirmations: 1, observed_at: Utc::now() });
+            for height in 0..150 {
+                let _ = sender.send(BlockEvent::NewBlock { height, hash: String::new(), replayed: false, observed_at: Utc::now() });
+            }
+        };
+        
+        let (confirmations, ()) = tokio::join!(waiter, flood);
+        assert_eq!(confirmations.unwrap(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_wait_for_confirmations_timeout_and_cleanup() {
+        let monitor = test_monitor();
+        
+        let err = monitor.wait_for_confirmations("bb", 1, Duration::from_millis(20)).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<WaitError>(), Some(WaitError::Timeout(_))));
+        
+        // The waiter registered the watch itself, so it must have removed it again
+        assert!(!monitor.tracked.lock().await.contains_key("bb"));
+    }
+    
+    #[tokio::test]
+    async fn test_wait_for_confirmations_dropped() {
+        let monitor = test_monitor();
+        let sender = monitor.event_sender.clone();
+        
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(20)).await;
+            let _ = sender.send(BlockEvent::TransactionDropped { txid: "cc".to_string(), observed_at: Utc::now() });
+        });
+        
+        let err = monitor.wait_for_confirmations("cc", 1, Duration::from_secs(5)).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<WaitError>(), Some(&WaitError::Dropped("cc".to_string())));
+    }
+    
+    #[tokio::test]
+    async fn test_concurrent_filtered_subscribers() {
+        use futures_util::StreamExt;
+        
+        let monitor = test_monitor();
+        let mine = Box::pin(monitor.subscribe_filtered(EventFilter {
+            kinds: vec!["transaction_confirmed".to_string()],
+            txids: vec!["aa".to_string()],
+            ..EventFilter::default()
+        }));
+        let blocks = Box::pin(monitor.subscribe_filtered(EventFilter {
+            kinds: vec!["new_block".to_string()],
+            min_height: Some(101),
+            ..EventFilter::default()
+        }));
+        let everything = Box::pin(monitor.subscribe_filtered(EventFilter::default()));
+        
+        let sent = vec![
+            BlockEvent::NewBlock { height: 100, hash: "00".to_string(), replayed: false, observed_at: Utc::now() },
+            BlockEvent::TransactionConfirmed { txid: "bb".to_string(), confirmations: 1, observed_at: Utc::now() },
+            BlockEvent::TransactionConfirmed { txid: "aa".to_string(), confirmations: 1, observed_at: Utc::now() },
+            BlockEvent::NewBlock { height: 101, hash: "01".to_string(), replayed: false, observed_at: Utc::now() },
+            BlockEvent::TransactionConfirmed { txid: "aa".to_string(), confirmations: 2, observed_at: Utc::now() },
+        ];
+        for event in &sent {
+            monitor.event_sender.send(event.clone()).unwrap();
+        }
+        // Dropping the monitor closes the channel and ends every stream
+        drop(monitor);
+        
+        let (mine, blocks, everything) = tokio::join!(
+            mine.collect::<Vec<_>>(),
+            blocks.collect::<Vec<_>>(),
+            everything.collect::<Vec<_>>(),
+        );
+        assert_eq!(mine, vec![sent[2].clone(), sent[4].clone()]);
+        assert_eq!(blocks, vec![sent[3].clone()]);
+        assert_eq!(everything, sent);
+    }
 }
\ No newline at end of file
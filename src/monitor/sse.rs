@@ -0,0 +1,39 @@
+//! Server-sent events endpoint for block monitor events
+//!
+//! `GET /events` streams monitor events as SSE, one JSON payload per event.
+//! Query parameters are mapped to an `EventFilter`, e.g.
+//! `/events?type=transaction_confirmed&txid=<txid>`.
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use futures_util::StreamExt;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use super::{BlockMonitor, EventFilter};
+
+/// Build a router serving the monitor's events at `/events`
+pub fn router(monitor: Arc<BlockMonitor>) -> Router {
+    Router::new()
+        .route("/events", get(events))
+        .with_state(monitor)
+}
+
+async fn events(
+    State(monitor): State<Arc<BlockMonitor>>,
+    Query(query): Query<Vec<(String, String)>>,
+) -> Response {
+    let filter = match EventFilter::from_query(&query) {
+        Ok(filter) => filter,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
+    let stream = monitor.subscribe_filtered(filter).map(|event| {
+        Ok::<_, Infallible>(Event::default().event(event.event_type()).data(event.to_json()))
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default()).into_response()
+}
@@ -0,0 +1,101 @@
+//! Operational statistics of the block monitor
+//!
+//! Counters and gauges are always kept in memory for `BlockMonitor::snapshot`.
+//! With the `metrics` feature they are also exported through the `metrics`
+//! facade so any installed recorder (e.g. a Prometheus exporter) sees them.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Point-in-time view of the monitor's statistics
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MonitorStats {
+    /// Bitcoin height seen on the last successful poll
+    pub bitcoin_height: Option<u64>,
+    /// Metashrew height seen on the last successful poll
+    pub metashrew_height: Option<u64>,
+    /// Blocks Metashrew is behind Bitcoin (it normally indexes height + 1)
+    pub indexer_lag: Option<u64>,
+    /// Seconds since the last new block was observed
+    pub seconds_since_last_block: Option<u64>,
+    /// Number of `NewBlock` events emitted
+    pub new_blocks: u64,
+    /// Number of errors by class (e.g. "poll", "tracked_transactions")
+    pub errors: HashMap<String, u64>,
+    /// Number of transactions being watched for confirmations
+    pub tracked_transactions: usize,
+}
+
+/// Mutable statistics shared between the monitor and its polling task
+#[derive(Default)]
+pub(crate) struct Stats {
+    inner: Mutex<StatsInner>,
+}
+
+#[derive(Default)]
+struct StatsInner {
+    stats: MonitorStats,
+    last_block_at: Option<Instant>,
+}
+
+impl Stats {
+    /// Record the chain heights seen by a poll
+    pub(crate) fn record_heights(&self, bitcoin_height: u64, metashrew_height: u64) {
+        let lag = (bitcoin_height + 1).saturating_sub(metashrew_height);
+        let mut inner = self.inner.lock().unwrap();
+        inner.stats.bitcoin_height = Some(bitcoin_height);
+        inner.stats.metashrew_height = Some(metashrew_height);
+        inner.stats.indexer_lag = Some(lag);
+
+        #[cfg(feature = "metrics")]
+        {
+            metrics::gauge!("deezel_monitor_bitcoin_height").set(bitcoin_height as f64);
+            metrics::gauge!("deezel_monitor_metashrew_height").set(metashrew_height as f64);
+            metrics::gauge!("deezel_monitor_indexer_lag").set(lag as f64);
+        }
+    }
+
+    /// Record an emitted `NewBlock` event
+    pub(crate) fn record_new_block(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.stats.new_blocks += 1;
+        inner.last_block_at = Some(Instant::now());
+
+        #[cfg(feature = "metrics")]
+        {
+            metrics::counter!("deezel_monitor_new_blocks_total").increment(1);
+            // Exported as a timestamp so scrapers can compute time since the last block
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs_f64())
+                .unwrap_or(0.0);
+            metrics::gauge!("deezel_monitor_last_block_timestamp_seconds").set(now);
+        }
+    }
+
+    /// Record an error of the given class
+    pub(crate) fn record_error(&self, class: &'static str) {
+        let mut inner = self.inner.lock().unwrap();
+        *inner.stats.errors.entry(class.to_string()).or_insert(0) += 1;
+
+        #[cfg(feature = "metrics")]
+        metrics::counter!("deezel_monitor_errors_total", "class" => class).increment(1);
+    }
+
+    /// Record the number of watched transactions
+    pub(crate) fn record_tracked(&self, count: usize) {
+        self.inner.lock().unwrap().stats.tracked_transactions = count;
+
+        #[cfg(feature = "metrics")]
+        metrics::gauge!("deezel_monitor_tracked_transactions").set(count as f64);
+    }
+
+    /// Take a snapshot of the current statistics
+    pub(crate) fn snapshot(&self) -> MonitorStats {
+        let inner = self.inner.lock().unwrap();
+        let mut stats = inner.stats.clone();
+        stats.seconds_since_last_block = inner.last_block_at.map(|at| at.elapsed().as_secs());
+        stats
+    }
+}
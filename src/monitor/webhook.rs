@@ -0,0 +1,314 @@
+//! Webhook notifications for block monitor events
+//!
+//! This module forwards `BlockEvent`s to external HTTP endpoints so that
+//! alerting stacks can react to new blocks and confirmations without
+//! embedding deezel as a library.
+
+use anyhow::{anyhow, Context, Result};
+use bdk::bitcoin::hashes::hmac::{Hmac, HmacEngine};
+use bdk::bitcoin::hashes::{sha256, Hash, HashEngine};
+use log::{debug, error, info, warn};
+use reqwest::{Client, header};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+use super::BlockEvent;
+
+/// Header carrying the HMAC-SHA256 signature of the request body
+pub const SIGNATURE_HEADER: &str = "X-Deezel-Signature";
+
+/// A single webhook destination
+#[derive(Clone, Debug)]
+pub struct WebhookEndpoint {
+    /// URL to POST event payloads to
+    pub url: String,
+    /// Event types to deliver (e.g. "new_block"); empty means all events
+    pub events: Vec<String>,
+}
+
+impl WebhookEndpoint {
+    /// Create an endpoint that receives every event type
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Check whether this endpoint wants the given event
+    pub fn accepts(&self, event: &BlockEvent) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| e == event.event_type())
+    }
+}
+
+/// Webhook notifier configuration
+#[derive(Clone, Debug)]
+pub struct WebhookConfig {
+    /// Destinations to deliver events to
+    pub endpoints: Vec<WebhookEndpoint>,
+    /// Optional shared secret used to sign payloads with HMAC-SHA256
+    pub secret: Option<String>,
+    /// Maximum number of retries after the first failed delivery
+    pub max_retries: u32,
+    /// Initial retry delay in milliseconds, doubled after each failure
+    pub retry_delay_ms: u64,
+    /// Timeout for a single delivery attempt in seconds
+    pub timeout: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            secret: None,
+            max_retries: 3,       // Retry 3 times before dropping the event
+            retry_delay_ms: 500,  // 0.5s, 1s, 2s between retries
+            timeout: 10,          // 10 seconds per delivery attempt
+        }
+    }
+}
+
+/// Delivers block events to configured webhook endpoints
+pub struct WebhookNotifier {
+    /// HTTP client
+    client: Client,
+    /// Webhook configuration
+    config: WebhookConfig,
+}
+
+impl WebhookNotifier {
+    /// Create a new webhook notifier
+    pub fn new(config: WebhookConfig) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout))
+            .build()
+            .context("Failed to create webhook HTTP client")?;
+
+        Ok(Self { client, config })
+    }
+
+    /// Spawn a task that forwards every event from `receiver` to the webhooks
+    ///
+    /// The task ends when the monitor's event channel is closed.
+    pub fn spawn(self, mut receiver: broadcast::Receiver<BlockEvent>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            info!("Webhook notifier started with {} endpoint(s)", self.config.endpoints.len());
+
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => self.notify(&event).await,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Webhook notifier lagged behind, skipped {} event(s)", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+
+            info!("Webhook notifier stopped");
+        })
+    }
+
+    /// Deliver an event to every endpoint that accepts it
+    ///
+    /// Delivery failures are logged rather than returned so that one broken
+    /// endpoint does not prevent delivery to the others.
+    pub async fn notify(&self, event: &BlockEvent) {
+        let body = event.to_json();
+
+        for endpoint in self.config.endpoints.iter().filter(|e| e.accepts(event)) {
+            if let Err(e) = self.deliver(&endpoint.url, &body).await {
+                error!("Failed to deliver {} event to {}: {}", event.event_type(), endpoint.url, e);
+            }
+        }
+    }
+
+    /// POST a payload to a single URL, retrying with exponential backoff
+    pub async fn deliver(&self, url: &str, body: &str) -> Result<()> {
+        let mut delay = Duration::from_millis(self.config.retry_delay_ms);
+        let mut attempt = 0;
+
+        loop {
+            match self.send(url, body).await {
+                Ok(()) => {
+                    debug!("Delivered webhook to {} after {} attempt(s)", url, attempt + 1);
+                    return Ok(());
+                }
+                Err(e) if attempt < self.config.max_retries => {
+                    attempt += 1;
+                    warn!("Webhook delivery to {} failed ({}), retry {}/{} in {:?}",
+                        url, e, attempt, self.config.max_retries, delay);
+                    sleep(delay).await;
+                    delay *= 2;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Perform a single delivery attempt
+    async fn send(&self, url: &str, body: &str) -> Result<()> {
+        let mut request = self.client
+            .post(url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+
+        if let Some(secret) = &self.config.secret {
+            request = request.header(SIGNATURE_HEADER, format!("sha256={}", sign(secret, body)));
+        }
+
+        let response = request.send().await.context("Failed to send webhook request")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow!("Webhook endpoint returned status: {}", status));
+        }
+
+        Ok(())
+    }
+}
+
+/// Compute the hex-encoded HMAC-SHA256 of `body` keyed with `secret`
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut engine = HmacEngine::<sha256::Hash>::new(secret.as_bytes());
+    engine.input(body.as_bytes());
+    let mac = Hmac::<sha256::Hash>::from_engine(engine);
+    hex::encode(mac.to_byte_array())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, http::{HeaderMap, StatusCode}, routing::post, Router};
+    use chrono::Utc;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::Mutex;
+
+    /// Local webhook receiver that fails the first request with a 500
+    #[derive(Default)]
+    struct Receiver {
+        attempts: AtomicUsize,
+        received: Mutex<Vec<(Option<String>, String)>>,
+    }
+
+    async fn receive(
+        State(receiver): State<Arc<Receiver>>,
+        headers: HeaderMap,
+        body: String,
+    ) -> StatusCode {
+        if receiver.attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+
+        let signature = headers
+            .get(SIGNATURE_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+        receiver.received.lock().await.push((signature, body));
+        StatusCode::OK
+    }
+
+    async fn start_receiver() -> (String, Arc<Receiver>) {
+        let receiver = Arc::new(Receiver::default());
+        let app = Router::new()
+            .route("/hook", post(receive))
+            .with_state(Arc::clone(&receiver));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        (format!("http://{}/hook", addr), receiver)
+    }
+
+    #[tokio::test]
+    async fn test_webhook_delivery_with_retry_and_signature() {
+        let (url, receiver) = start_receiver().await;
+
+        let notifier = WebhookNotifier::new(WebhookConfig {
+            endpoints: vec![WebhookEndpoint::new(&url)],
+            secret: Some("s3cret".to_string()),
+            retry_delay_ms: 10,
+            ..WebhookConfig::default()
+        }).unwrap();
+
+        notifier.notify(&BlockEvent::NewBlock {
+            height: 840000,
+            hash: "00ab".to_string(),
+            replayed: false,
+            observed_at: Utc::now(),
+        }).await;
+
+        // First attempt got a 500, the retry succeeded
+        assert_eq!(receiver.attempts.load(Ordering::SeqCst), 2);
+
+        let received = receiver.received.lock().await;
+        assert_eq!(received.len(), 1);
+        let (signature, body) = &received[0];
+
+        let event: BlockEvent = serde_json::from_str(body).unwrap();
+        assert!(matches!(event, BlockEvent::NewBlock { height: 840000, .. }));
+
+        let expected = format!("sha256={}", reference_hmac(b"s3cret", body.as_bytes()));
+        assert_eq!(signature.as_deref(), Some(expected.as_str()));
+    }
+
+    /// HMAC-SHA256 built from the bare hash (RFC 2104), independent of `sign`
+    fn reference_hmac(key: &[u8], message: &[u8]) -> String {
+        let mut block = [0u8; 64];
+        block[..key.len()].copy_from_slice(key);
+        let pad = |byte: u8| block.iter().map(|b| b ^ byte).collect::<Vec<u8>>();
+        let mut inner = pad(0x36);
+        inner.extend_from_slice(message);
+        let mut outer = pad(0x5c);
+        outer.extend_from_slice(&sha256::Hash::hash(&inner).to_byte_array());
+        hex::encode(sha256::Hash::hash(&outer).to_byte_array())
+    }
+
+    #[test]
+    fn test_sign_test_vectors() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+        );
+        let body = r#"{"type":"new_block","height":840000}"#;
+        assert_eq!(sign("s3cret", body), "49c4f32a240e12f2a69f17af5dca8ab5dc5ae41175d75fe9d955ad8ffbc83f2b");
+        assert_eq!(reference_hmac(b"s3cret", body.as_bytes()), sign("s3cret", body));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_event_filtering() {
+        let (url, receiver) = start_receiver().await;
+
+        let notifier = WebhookNotifier::new(WebhookConfig {
+            endpoints: vec![WebhookEndpoint {
+                url,
+                events: vec!["transaction_confirmed".to_string()],
+            }],
+            retry_delay_ms: 10,
+            ..WebhookConfig::default()
+        }).unwrap();
+
+        notifier.notify(&BlockEvent::NewBlock {
+            height: 1,
+            hash: "00".to_string(),
+            replayed: false,
+            observed_at: Utc::now(),
+        }).await;
+        assert_eq!(receiver.attempts.load(Ordering::SeqCst), 0);
+
+        notifier.notify(&BlockEvent::TransactionConfirmed {
+            txid: "ff".repeat(32),
+            confirmations: 1,
+            observed_at: Utc::now(),
+        }).await;
+        let received = receiver.received.lock().await;
+        assert_eq!(received.len(), 1);
+        assert!(received[0].0.is_none());
+    }
+}
@@ -1,9 +1,15 @@
 //! Network parameters for different Bitcoin networks
 //!
 //! This module provides functionality for configuring network parameters
-//! for different Bitcoin networks, including custom networks.
+//! for different Bitcoin networks, including custom networks. It also
+//! converts addresses between the `bitcoin` version used by BDK and the
+//! one used by the ordinals crate.
 
-use bdk::bitcoin::Network;
+use anyhow::{anyhow, Context, Result};
+use bdk::bitcoin::{Network, Script};
+use bitcoin::base58;
+use bitcoin::bech32::{segwit, Fe32, Hrp};
+use std::str::FromStr;
 
 /// Network parameters for address encoding
 #[derive(Clone, Debug)]
@@ -75,6 +81,25 @@ impl NetworkParams {
         })
     }
 
+    /// Encode the address of `script` with these prefixes
+    ///
+    /// Returns `None` for scripts without an address form, such as
+    /// OP_RETURN, bare public keys and non-standard scripts.
+    pub fn address_from_script(&self, script: &Script) -> Option<String> {
+        let bytes = script.as_bytes();
+        if script.is_p2pkh() {
+            Some(base58_address(self.p2pkh_prefix, &bytes[3..23]))
+        } else if script.is_p2sh() {
+            Some(base58_address(self.p2sh_prefix, &bytes[2..22]))
+        } else if script.is_witness_program() {
+            let version = Fe32::try_from(script.witness_version()?.to_num()).ok()?;
+            let hrp = Hrp::parse(&self.bech32_prefix).ok()?;
+            segwit::encode(hrp, version, &bytes[2..]).ok()
+        } else {
+            None
+        }
+    }
+
     /// Get the network parameters for a given provider preset
     pub fn from_provider(provider: &str) -> Result<Self, String> {
         match provider {
@@ -86,6 +111,40 @@ impl NetworkParams {
     }
 }
 
+/// A base58check address: the version byte, then the hash
+fn base58_address(prefix: u8, hash: &[u8]) -> String {
+    let mut payload = Vec::with_capacity(1 + hash.len());
+    payload.push(prefix);
+    payload.extend_from_slice(hash);
+    base58::encode_check(&payload)
+}
+
+/// Kind of an output script, as named in decoded output
+///
+/// One of `p2pkh`, `p2sh`, `p2wpkh`, `p2wsh`, `p2tr`, `witness_v<N>` for
+/// other witness programs, `p2pk`, `op_return` or `nonstandard`.
+pub fn script_type(script: &Script) -> String {
+    if script.is_p2pkh() {
+        "p2pkh".to_string()
+    } else if script.is_p2sh() {
+        "p2sh".to_string()
+    } else if script.is_v0_p2wpkh() {
+        "p2wpkh".to_string()
+    } else if script.is_v0_p2wsh() {
+        "p2wsh".to_string()
+    } else if script.is_v1_p2tr() {
+        "p2tr".to_string()
+    } else if let Some(version) = script.witness_version() {
+        format!("witness_v{}", version.to_num())
+    } else if script.is_p2pk() {
+        "p2pk".to_string()
+    } else if script.is_op_return() {
+        "op_return".to_string()
+    } else {
+        "nonstandard".to_string()
+    }
+}
+
 /// Get the RPC URL for a given provider preset
 pub fn get_rpc_url(provider: &str) -> String {
     match provider {
@@ -95,4 +154,103 @@ pub fn get_rpc_url(provider: &str) -> String {
         url if url.starts_with("http://") || url.starts_with("https://") => url.to_string(),
         _ => "https://mainnet.sandshrew.io/v2/lasereyes".to_string(),
     }
-}
\ No newline at end of file
+}
+/// Convert a BDK network to the ordinals crate's `bitcoin` network
+pub fn to_bitcoin_network(network: Network) -> Result<bitcoin::Network> {
+    bitcoin::Network::from_core_arg(network.to_core_arg())
+        .map_err(|_| anyhow!("Unsupported network: {}", network))
+}
+
+/// Convert a BDK address to the ordinals crate's `bitcoin` address
+///
+/// The address is re-parsed from its string form and checked to encode the
+/// same network and script, so nothing is silently reinterpreted.
+pub fn to_bitcoin_address(address: &bdk::bitcoin::Address) -> Result<bitcoin::Address> {
+    let network = to_bitcoin_network(address.network)?;
+    let converted = bitcoin::Address::from_str(&address.to_string())
+        .with_context(|| format!("Failed to parse address {}", address))?
+        .require_network(network)
+        .with_context(|| format!("Address {} is not valid for {}", address, network))?;
+    if converted.script_pubkey().as_bytes() != address.script_pubkey().as_bytes() {
+        return Err(anyhow!("Address {} changed script in conversion", address));
+    }
+    Ok(converted)
+}
+
+/// Convert an ordinals crate `bitcoin` address to a BDK address on `network`
+pub fn to_bdk_address(address: &bitcoin::Address, network: Network) -> Result<bdk::bitcoin::Address> {
+    let converted = bdk::bitcoin::Address::from_str(&address.to_string())
+        .with_context(|| format!("Failed to parse address {}", address))?
+        .require_network(network)
+        .with_context(|| format!("Address {} is not valid for {}", address, network))?;
+    if converted.script_pubkey().as_bytes() != address.script_pubkey().as_bytes() {
+        return Err(anyhow!("Address {} changed script in conversion", address));
+    }
+    Ok(converted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bdk::bitcoin::secp256k1::{Secp256k1, SecretKey};
+    use bdk::bitcoin::{Address, PublicKey, ScriptBuf};
+
+    fn addresses(network: Network) -> Vec<Address> {
+        let secp = Secp256k1::new();
+        let secret = SecretKey::from_slice(&[1u8; 32]).unwrap();
+        let public_key = PublicKey::new(secret.public_key(&secp));
+        let (xonly, _) = secret.x_only_public_key(&secp);
+        let script = ScriptBuf::new_p2pkh(&public_key.pubkey_hash());
+
+        vec![
+            Address::p2pkh(&public_key, network),
+            Address::p2sh(&script, network).unwrap(),
+            Address::p2wpkh(&public_key, network).unwrap(),
+            Address::p2wsh(&script, network),
+            Address::p2tr(&secp, xonly, None, network),
+        ]
+    }
+
+    #[test]
+    fn test_address_round_trip() {
+        for network in [Network::Bitcoin, Network::Testnet] {
+            for address in addresses(network) {
+                let converted = to_bitcoin_address(&address).unwrap();
+                assert_eq!(converted.to_string(), address.to_string());
+                assert_eq!(converted.script_pubkey().as_bytes(), address.script_pubkey().as_bytes());
+                assert_eq!(to_bdk_address(&converted, network).unwrap(), address);
+            }
+        }
+    }
+
+    #[test]
+    fn test_address_from_script() {
+        // The standard prefixes encode as BDK does
+        for (network, params) in [(Network::Bitcoin, NetworkParams::mainnet()), (Network::Testnet, NetworkParams::testnet())] {
+            for address in addresses(network) {
+                assert_eq!(params.address_from_script(&address.script_pubkey()), Some(address.to_string()));
+            }
+        }
+
+        // Custom prefixes
+        let params = NetworkParams::from_magic("05:00:dz").unwrap();
+        let testnet = addresses(Network::Testnet);
+        let taproot = params.address_from_script(&testnet[4].script_pubkey()).unwrap();
+        assert!(taproot.starts_with("dz1p"), "{}", taproot);
+        let p2pkh = params.address_from_script(&testnet[0].script_pubkey()).unwrap();
+        assert_eq!(p2pkh, addresses(Network::Bitcoin)[0].to_string());
+
+        let op_return = ScriptBuf::from(vec![0x6a, 0x03, 1, 2, 3]);
+        assert_eq!(params.address_from_script(&op_return), None);
+        assert_eq!(script_type(&op_return), "op_return");
+        let types: Vec<String> = testnet.iter().map(|address| script_type(&address.script_pubkey())).collect();
+        assert_eq!(types, vec!["p2pkh", "p2sh", "p2wpkh", "p2wsh", "p2tr"]);
+    }
+
+    #[test]
+    fn test_to_bdk_address_checks_network() {
+        let mainnet = to_bitcoin_address(&addresses(Network::Bitcoin)[4]).unwrap();
+        assert!(to_bdk_address(&mainnet, Network::Testnet).is_err());
+        assert_eq!(to_bitcoin_network(Network::Regtest).unwrap(), bitcoin::Network::Regtest);
+    }
+}
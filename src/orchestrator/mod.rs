@@ -0,0 +1,660 @@
+//! Automated DIESEL minting driven by the block monitor
+//!
+//! This module handles:
+//! - Reacting to new block events
+//! - Balance and fee-rate gating before minting
+//! - Skipping blocks where a simulated mint would pay nothing
+//! - Skipping blocks where a mint isn't expected to pay for its fee
+//! - Skipping blocks while a previous mint is unconfirmed
+//! - Escalating a mint that stays unconfirmed as the next block approaches
+//! - Pausing while Metashrew instances disagree about the chain height
+//! - Recording the outcome of every mint attempt
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, warn};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, watch, Mutex};
+
+use crate::monitor::{BlockEvent, BlockMonitor};
+use crate::transaction::{DieselPrice, EscalationStrategy, MintArtifact, MintResult, MintStatus, TransactionConstructor};
+use crate::wallet::WalletManager;
+
+/// Expected time between blocks
+const BLOCK_INTERVAL: Duration = Duration::from_secs(600);
+
+/// How often the pending mint is checked for escalation
+const ESCALATION_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How a stuck mint is escalated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EscalationMode {
+    /// Replace the mint with one paying a higher fee
+    Rbf,
+    /// Spend the mint's change in a child paying for both
+    Cpfp,
+}
+
+/// Mint orchestrator configuration
+#[derive(Clone, Debug)]
+pub struct OrchestratorConfig {
+    /// Maximum fee rate in satoshis per vbyte to mint at
+    pub max_fee_rate: f64,
+    /// Minimum confirmed wallet balance in satoshis required to mint
+    pub min_balance: u64,
+    /// Skip new blocks while the previous mint is still unconfirmed
+    pub skip_if_pending: bool,
+    /// Attempt mints for blocks replayed from history
+    pub act_on_replayed: bool,
+    /// Skip new blocks while the monitor reports diverged Metashrew instances
+    pub pause_on_divergence: bool,
+    /// Simulate each mint and skip blocks where it would pay nothing
+    pub simulate: bool,
+    /// Skip blocks whose mint can't be simulated instead of minting anyway
+    pub require_simulation: bool,
+    /// Escalate a mint still unconfirmed this long after broadcast (never if `None`)
+    pub escalate_after: Option<Duration>,
+    /// How to escalate a stuck mint
+    pub escalation: EscalationMode,
+    /// Minimum fee rate of an escalation, as a multiple of the mint's fee rate
+    pub escalation_fee_multiplier: f64,
+    /// Only escalate within this long of the next expected block
+    pub escalation_window: Duration,
+    /// Skip blocks where the mint's expected profit is below this many sats
+    /// (never if `None`); requires `diesel_price`
+    pub min_expected_profit_sats: Option<i64>,
+    /// Price the expected DIESEL payout is valued at
+    pub diesel_price: Option<DieselPrice>,
+}
+
+impl Default for OrchestratorConfig {
+    fn default() -> Self {
+        Self {
+            max_fee_rate: 50.0,     // 50 sat/vbyte ceiling
+            min_balance: 10_000,    // 10,000 sats
+            skip_if_pending: true,  // One outstanding mint at a time
+            act_on_replayed: false, // Only mint on live blocks
+            pause_on_divergence: true, // Don't mint against a possibly stale index
+            simulate: false,           // Mint without simulating first
+            require_simulation: false, // Mint when the outcome is unknown
+            escalate_after: None,      // Leave stuck mints alone
+            escalation: EscalationMode::Rbf,
+            escalation_fee_multiplier: 1.5,
+            escalation_window: Duration::from_secs(120),
+            min_expected_profit_sats: None, // Mint whatever it costs
+            diesel_price: None,
+        }
+    }
+}
+
+/// Outcome of a mint attempt for a single block
+#[derive(Clone, Debug, PartialEq)]
+pub enum MintOutcome {
+    /// Mint transaction was broadcast
+    Broadcast {
+        /// Transaction ID of the mint
+        txid: String,
+    },
+    /// Block was skipped without attempting a mint
+    Skipped(String),
+    /// Mint attempt failed
+    Failed(String),
+}
+
+/// Record of a mint attempt
+#[derive(Clone, Debug, PartialEq)]
+pub struct MintRecord {
+    /// Block height that triggered the attempt
+    pub height: u64,
+    /// Outcome of the attempt
+    pub outcome: MintOutcome,
+}
+
+/// A broadcast mint awaiting confirmation
+struct PendingMint {
+    /// The mint, or its replacement once bumped
+    mint: MintResult,
+    /// Whether the mint was already escalated (or the attempt failed)
+    escalated: bool,
+}
+
+/// Mints DIESEL once per new block
+pub struct MintOrchestrator {
+    /// Block monitor providing new block events
+    monitor: Arc<BlockMonitor>,
+    /// Transaction constructor used to build and broadcast mints
+    constructor: Arc<TransactionConstructor>,
+    /// Wallet manager used for balance checks
+    wallet_manager: Arc<WalletManager>,
+    /// Orchestrator configuration
+    config: OrchestratorConfig,
+    /// Mint awaiting confirmation
+    pending: Mutex<Option<PendingMint>>,
+    /// When the last live block was seen
+    last_block_at: Mutex<Option<DateTime<Utc>>>,
+    /// Outcome of every mint attempt
+    history: Mutex<Vec<MintRecord>>,
+    /// Whether Metashrew instances are currently diverged
+    diverged: Mutex<bool>,
+    /// Shutdown signal
+    shutdown: watch::Sender<bool>,
+}
+
+impl MintOrchestrator {
+    /// Create a new mint orchestrator
+    pub fn new(
+        monitor: Arc<BlockMonitor>,
+        constructor: Arc<TransactionConstructor>,
+        wallet_manager: Arc<WalletManager>,
+        config: OrchestratorConfig,
+    ) -> Self {
+        let (shutdown, _) = watch::channel(false);
+
+        Self {
+            monitor,
+            constructor,
+            wallet_manager,
+            config,
+            pending: Mutex::new(None),
+            last_block_at: Mutex::new(None),
+            history: Mutex::new(Vec::new()),
+            diverged: Mutex::new(false),
+            shutdown,
+        }
+    }
+
+    /// Run the orchestrator until `shutdown` is called or the monitor stops
+    pub async fn run(&self) -> Result<()> {
+        let mut events = self.monitor.subscribe();
+        let mut shutdown = self.shutdown.subscribe();
+        let mut escalation_check = tokio::time::interval(ESCALATION_CHECK_INTERVAL);
+        info!("Mint orchestrator started");
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => self.handle_event(event).await,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Mint orchestrator lagged behind, skipped {} event(s)", skipped);
+                    },
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = escalation_check.tick() => self.escalate_stuck_mint(Utc::now()).await,
+                _ = shutdown.changed() => break,
+            }
+        }
+
+        info!("Mint orchestrator stopped");
+        Ok(())
+    }
+
+    /// Signal the orchestrator to stop after the current event
+    pub fn shutdown(&self) {
+        let _ = self.shutdown.send(true);
+    }
+
+    /// Handle a single block monitor event
+    pub async fn handle_event(&self, event: BlockEvent) {
+        match event {
+            BlockEvent::NewBlock { height, replayed, observed_at, .. } => {
+                if !replayed {
+                    *self.last_block_at.lock().await = Some(observed_at);
+                }
+                if replayed && !self.config.act_on_replayed {
+                    debug!("Block {}: ignoring replayed block", height);
+                    return;
+                }
+
+                let outcome = self.mint_for_block().await;
+                match &outcome {
+                    MintOutcome::Broadcast { txid } => info!("Block {}: mint broadcast {}", height, txid),
+                    MintOutcome::Skipped(reason) => info!("Block {}: skipped ({})", height, reason),
+                    MintOutcome::Failed(reason) => error!("Block {}: mint failed ({})", height, reason),
+                }
+                self.history.lock().await.push(MintRecord { height, outcome });
+            },
+            BlockEvent::TransactionConfirmed { txid, confirmations, .. } => {
+                let mut pending = self.pending.lock().await;
+                if pending.as_ref().map(|pending| pending.mint.txid.as_str()) == Some(txid.as_str()) {
+                    info!("Mint {} confirmed with {} confirmation(s)", txid, confirmations);
+                    *pending = None;
+                    self.monitor.unwatch_transaction(&txid).await;
+                }
+            },
+            BlockEvent::TransactionDropped { txid, .. } => {
+                let mut pending = self.pending.lock().await;
+                if pending.as_ref().map(|pending| pending.mint.txid.as_str()) == Some(txid.as_str()) {
+                    warn!("Mint {} was dropped by a reorg", txid);
+                    *pending = None;
+                }
+            },
+            BlockEvent::EndpointDiverged { .. } if self.config.pause_on_divergence => {
+                warn!("Pausing minting while Metashrew instances are diverged");
+                *self.diverged.lock().await = true;
+            },
+            BlockEvent::EndpointsConverged { .. } => {
+                if std::mem::take(&mut *self.diverged.lock().await) {
+                    info!("Resuming minting, Metashrew instances converged");
+                }
+            },
+            _ => {},
+        }
+    }
+
+    /// Attempt a mint for the current block
+    async fn mint_for_block(&self) -> MintOutcome {
+        if *self.diverged.lock().await {
+            return MintOutcome::Skipped("Metashrew instances are diverged".to_string());
+        }
+
+        if self.config.skip_if_pending {
+            if let Some(pending) = self.pending.lock().await.as_ref() {
+                return MintOutcome::Skipped(format!("previous mint {} is unconfirmed", pending.mint.txid));
+            }
+        }
+
+        match self.wallet_manager.get_balance().await {
+            Ok(balance) if balance.confirmed < self.config.min_balance => {
+                return MintOutcome::Skipped(format!(
+                    "confirmed balance {} sats is below minimum {} sats",
+                    balance.confirmed, self.config.min_balance
+                ));
+            },
+            Ok(_) => {},
+            Err(e) => return MintOutcome::Failed(format!("failed to get balance: {}", e)),
+        }
+
+        match self.constructor.resolve_fee_rate().await {
+            Ok(fee_rate) if fee_rate > self.config.max_fee_rate => {
+                return MintOutcome::Skipped(format!(
+                    "fee rate {} sat/vB exceeds maximum {} sat/vB",
+                    fee_rate, self.config.max_fee_rate
+                ));
+            },
+            Ok(_) => {},
+            Err(e) => return MintOutcome::Failed(format!("failed to determine fee rate: {}", e)),
+        }
+
+        if self.config.simulate || self.config.require_simulation {
+            match self.constructor.simulate_mint().await {
+                Ok(outcome) if outcome.expected_amount == 0 => {
+                    return MintOutcome::Skipped(match outcome.error {
+                        Some(error) => format!("simulated mint fails: {}", error),
+                        None => "simulated mint pays no reward".to_string(),
+                    });
+                },
+                Ok(outcome) => debug!("Simulated mint pays {}", outcome.expected_amount),
+                Err(e) if self.config.require_simulation => {
+                    return MintOutcome::Skipped(format!("mint could not be simulated: {}", e));
+                },
+                Err(e) => warn!("Mint outcome unknown, minting anyway: {:#}", e),
+            }
+        }
+
+        let (tx, quote) = match self.constructor.create_minting_transaction_with_quote().await {
+            Ok((MintArtifact::Signed(tx), quote)) => (tx, quote),
+            Ok((MintArtifact::Psbt(_), _)) => return MintOutcome::Failed("wallet is watch-only and cannot sign mints".to_string()),
+            Err(e) => return MintOutcome::Failed(format!("failed to create transaction: {}", e)),
+        };
+
+        if let Some(min_profit) = self.config.min_expected_profit_sats {
+            match self.constructor.estimate_profitability(&quote, self.config.diesel_price).await {
+                Ok(report) => match report.expected_profit_sats {
+                    Some(profit) if profit < min_profit => {
+                        return MintOutcome::Skipped(format!(
+                            "expected profit {} sats is below minimum {} sats",
+                            profit, min_profit
+                        ));
+                    },
+                    Some(_) => {},
+                    None => return MintOutcome::Skipped("expected profit unknown without a DIESEL price".to_string()),
+                },
+                Err(e) => return MintOutcome::Failed(format!("failed to estimate profitability: {}", e)),
+            }
+        }
+
+        let txid = match self.constructor.broadcast_transaction(&tx).await {
+            Ok(txid) => txid,
+            Err(e) => return MintOutcome::Failed(format!("failed to broadcast: {}", e)),
+        };
+
+        debug!("Tracking confirmations of mint {}", txid);
+        self.monitor.watch_transaction(&txid).await;
+        let mint = MintResult {
+            txid: txid.clone(),
+            fee_sats: quote.fee,
+            vsize: quote.vsize,
+            fee_rate: quote.fee_rate,
+            block_target: None,
+            broadcast_at: Utc::now(),
+            trace: None,
+            status: MintStatus::Pending,
+        };
+        *self.pending.lock().await = Some(PendingMint { mint, escalated: false });
+
+        MintOutcome::Broadcast { txid }
+    }
+
+    /// Escalate the pending mint if it is overdue and a block is near
+    ///
+    /// A mint is escalated at most once, and only when it has been
+    /// unconfirmed for `escalate_after` and the next block is expected
+    /// within `escalation_window` (or no block has been seen yet). The fee
+    /// rate is the current one, at least `escalation_fee_multiplier` times
+    /// the mint's, capped at `max_fee_rate`.
+    async fn escalate_stuck_mint(&self, now: DateTime<Utc>) {
+        let escalate_after = match self.config.escalate_after {
+            Some(escalate_after) => escalate_after,
+            None => return,
+        };
+        let mint = match self.pending.lock().await.as_ref() {
+            Some(pending) if !pending.escalated => pending.mint.clone(),
+            _ => return,
+        };
+
+        if (now - mint.broadcast_at).to_std().unwrap_or_default() < escalate_after {
+            return;
+        }
+        if let Some(last_block_at) = *self.last_block_at.lock().await {
+            let since_block = (now - last_block_at).to_std().unwrap_or_default();
+            if since_block + self.config.escalation_window < BLOCK_INTERVAL {
+                return;
+            }
+        }
+
+        let fee_rate = match self.constructor.resolve_fee_rate().await {
+            Ok(fee_rate) => fee_rate.max(mint.fee_rate * self.config.escalation_fee_multiplier).min(self.config.max_fee_rate),
+            Err(e) => {
+                error!("Not escalating mint {}: failed to determine fee rate: {}", mint.txid, e);
+                return;
+            },
+        };
+        if fee_rate <= mint.fee_rate {
+            debug!("Not escalating mint {}: already at the maximum fee rate", mint.txid);
+            return;
+        }
+
+        let strategy = match self.config.escalation {
+            EscalationMode::Rbf => EscalationStrategy::RbfBump { new_fee_rate: fee_rate },
+            EscalationMode::Cpfp => EscalationStrategy::Cpfp { package_fee_rate: fee_rate },
+        };
+        let result = self.constructor.escalate(&mint, strategy).await;
+
+        let mut pending = self.pending.lock().await;
+        let current = match pending.as_mut() {
+            Some(current) if current.mint.txid == mint.txid => current,
+            // Confirmed or dropped in the meantime
+            _ => return,
+        };
+        current.escalated = true;
+        match result {
+            Ok(escalation) if self.config.escalation == EscalationMode::Rbf => {
+                info!("Mint {} replaced by {} at {} sat/vB", mint.txid, escalation.txid, fee_rate);
+                self.monitor.unwatch_transaction(&mint.txid).await;
+                self.monitor.watch_transaction(&escalation.txid).await;
+                current.mint = escalation;
+            },
+            Ok(escalation) => info!("Mint {} accelerated by child {} at {} sat/vB", mint.txid, escalation.txid, fee_rate),
+            Err(e) => error!("Failed to escalate mint {}: {:#}", mint.txid, e),
+        }
+    }
+
+    /// Get the transaction ID of the mint awaiting confirmation, if any
+    pub async fn pending(&self) -> Option<String> {
+        self.pending.lock().await.as_ref().map(|pending| pending.mint.txid.clone())
+    }
+
+    /// Get the outcome of every mint attempt so far
+    pub async fn history(&self) -> Vec<MintRecord> {
+        self.history.lock().await.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::BlockMonitorConfig;
+    use crate::rpc::mock::MockRpcServer;
+    use crate::rpc::RpcClient;
+    use crate::transaction::TransactionConfig;
+    use crate::wallet::WalletConfig;
+    use bdk::bitcoin::Network;
+    use chrono::Utc;
+    use serde_json::json;
+
+    async fn test_orchestrator(server: &MockRpcServer, config: OrchestratorConfig) -> MintOrchestrator {
+        let rpc_client = Arc::new(RpcClient::new(server.config()));
+        let (descriptor, change_descriptor) = crate::wallet::TEST_SIGNING_DESCRIPTORS;
+        let wallet_manager = Arc::new(WalletManager::from_descriptors(WalletConfig {
+            wallet_path: "test_wallet.dat".to_string(),
+            network: Network::Testnet,
+            bitcoin_rpc_url: server.url.clone(),
+            metashrew_rpc_url: server.url.clone(),
+            esplora_url: None,
+        }, descriptor, Some(change_descriptor)).await.unwrap());
+        let constructor = Arc::new(TransactionConstructor::new(
+            Arc::clone(&wallet_manager),
+            Arc::clone(&rpc_client),
+            TransactionConfig::default(),
+        ));
+        let monitor = Arc::new(BlockMonitor::new(rpc_client, BlockMonitorConfig::default()));
+
+        MintOrchestrator::new(monitor, constructor, wallet_manager, config)
+    }
+
+    /// Give the test wallet a plain UTXO large enough to fund mints
+    fn fund_wallet(server: &MockRpcServer) {
+        server.set("esplora_address::utxo", json!([{ "txid": "11".repeat(32), "vout": 0, "value": 100_000 }]));
+        server.set("ord_output", json!({ "inscriptions": [], "runes": {} }));
+        server.set("alkanes_protorunesbyoutpoint", json!([]));
+        server.set("alkanes_trace", json!([]));
+        server.set("btc_getblockcount", json!(800_000));
+    }
+
+    fn new_block(height: u64) -> BlockEvent {
+        BlockEvent::NewBlock { height, hash: format!("{:064x}", height), replayed: false, observed_at: Utc::now() }
+    }
+
+    fn broadcast_count(history: &[MintRecord]) -> usize {
+        history.iter().filter(|r| matches!(r.outcome, MintOutcome::Broadcast { .. })).count()
+    }
+
+    #[tokio::test]
+    async fn test_one_mint_per_block() {
+        let server = MockRpcServer::start().await;
+        fund_wallet(&server);
+
+        let orchestrator = test_orchestrator(&server, OrchestratorConfig {
+            min_balance: 0,
+            skip_if_pending: false,
+            ..OrchestratorConfig::default()
+        }).await;
+
+        for height in 1..=3 {
+            orchestrator.handle_event(new_block(height)).await;
+        }
+
+        let history = orchestrator.history().await;
+        assert_eq!(history.len(), 3);
+        assert_eq!(broadcast_count(&history), 3);
+    }
+
+    #[tokio::test]
+    async fn test_skip_while_previous_mint_pending() {
+        let server = MockRpcServer::start().await;
+        fund_wallet(&server);
+
+        let orchestrator = test_orchestrator(&server, OrchestratorConfig {
+            min_balance: 0,
+            ..OrchestratorConfig::default()
+        }).await;
+
+        orchestrator.handle_event(new_block(1)).await;
+        let txid = orchestrator.pending().await.expect("first mint should be pending");
+
+        orchestrator.handle_event(new_block(2)).await;
+        let history = orchestrator.history().await;
+        assert!(matches!(history[1].outcome, MintOutcome::Skipped(_)));
+
+        // Confirmation clears the pending mint so the next block mints again
+        orchestrator.handle_event(BlockEvent::TransactionConfirmed { txid, confirmations: 1, observed_at: Utc::now() }).await;
+        orchestrator.handle_event(new_block(3)).await;
+
+        let history = orchestrator.history().await;
+        assert_eq!(broadcast_count(&history), 2);
+        assert!(matches!(history[2].outcome, MintOutcome::Broadcast { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_pause_on_divergence() {
+        let server = MockRpcServer::start().await;
+        fund_wallet(&server);
+
+        let orchestrator = test_orchestrator(&server, OrchestratorConfig {
+            min_balance: 0,
+            skip_if_pending: false,
+            ..OrchestratorConfig::default()
+        }).await;
+
+        orchestrator.handle_event(BlockEvent::EndpointDiverged { heights: Default::default(), observed_at: Utc::now() }).await;
+        orchestrator.handle_event(new_block(1)).await;
+        orchestrator.handle_event(BlockEvent::EndpointsConverged { observed_at: Utc::now() }).await;
+        orchestrator.handle_event(new_block(2)).await;
+
+        let history = orchestrator.history().await;
+        assert!(matches!(history[0].outcome, MintOutcome::Skipped(_)));
+        assert!(matches!(history[1].outcome, MintOutcome::Broadcast { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_skip_when_simulation_predicts_no_reward() {
+        let server = MockRpcServer::start().await;
+        fund_wallet(&server);
+        server.set("alkanes_simulate", json!({ "status": 0, "gasUsed": 800, "execution": { "alkanes": [] } }));
+
+        let orchestrator = test_orchestrator(&server, OrchestratorConfig {
+            min_balance: 0,
+            skip_if_pending: false,
+            simulate: true,
+            ..OrchestratorConfig::default()
+        }).await;
+        orchestrator.handle_event(new_block(1)).await;
+
+        server.set("alkanes_simulate", json!({
+            "status": 0,
+            "gasUsed": 1200,
+            "execution": { "alkanes": [{ "id": { "block": 2, "tx": 0 }, "value": 100 }] },
+        }));
+        orchestrator.handle_event(new_block(2)).await;
+
+        let history = orchestrator.history().await;
+        assert!(matches!(history[0].outcome, MintOutcome::Skipped(_)));
+        assert!(matches!(history[1].outcome, MintOutcome::Broadcast { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_skip_unprofitable_mints() {
+        let server = MockRpcServer::start().await;
+        fund_wallet(&server);
+        let orchestrator_at = |price: Option<f64>| test_orchestrator(&server, OrchestratorConfig {
+            min_balance: 0,
+            skip_if_pending: false,
+            min_expected_profit_sats: Some(1_000),
+            diesel_price: price.map(|sats_per_diesel| DieselPrice { sats_per_diesel }),
+            ..OrchestratorConfig::default()
+        });
+
+        // 6.25 DIESEL at block 800,001, worth 6 sats or 62,500 sats
+        let cheap = orchestrator_at(Some(1.0)).await;
+        cheap.handle_event(new_block(1)).await;
+        let dear = orchestrator_at(Some(10_000.0)).await;
+        dear.handle_event(new_block(1)).await;
+        let unpriced = orchestrator_at(None).await;
+        unpriced.handle_event(new_block(1)).await;
+
+        assert!(matches!(&cheap.history().await[0].outcome, MintOutcome::Skipped(reason) if reason.contains("expected profit")));
+        assert!(matches!(dear.history().await[0].outcome, MintOutcome::Broadcast { .. }));
+        assert!(matches!(unpriced.history().await[0].outcome, MintOutcome::Skipped(_)));
+    }
+
+    #[tokio::test]
+    async fn test_simulation_failure_only_blocks_when_required() {
+        let server = MockRpcServer::start().await;
+        fund_wallet(&server);
+
+        for (require_simulation, broadcast) in [(false, true), (true, false)] {
+            let orchestrator = test_orchestrator(&server, OrchestratorConfig {
+                min_balance: 0,
+                simulate: true,
+                require_simulation,
+                ..OrchestratorConfig::default()
+            }).await;
+            orchestrator.handle_event(new_block(1)).await;
+
+            let history = orchestrator.history().await;
+            assert_eq!(matches!(history[0].outcome, MintOutcome::Broadcast { .. }), broadcast);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_escalate_only_overdue_mints_near_a_block() {
+        let server = MockRpcServer::start().await;
+        fund_wallet(&server);
+
+        let orchestrator = test_orchestrator(&server, OrchestratorConfig {
+            min_balance: 0,
+            escalate_after: Some(Duration::from_secs(300)),
+            ..OrchestratorConfig::default()
+        }).await;
+        orchestrator.handle_event(new_block(1)).await;
+        let txid = orchestrator.pending().await.expect("mint should be pending");
+        let escalation_attempts = || server.methods().iter().filter(|m| *m == "esplora_tx::hex").count();
+
+        // Not overdue yet, then overdue but right after a block
+        orchestrator.escalate_stuck_mint(Utc::now() + chrono::Duration::seconds(60)).await;
+        assert_eq!(escalation_attempts(), 0);
+        orchestrator.handle_event(new_block(2)).await;
+        orchestrator.escalate_stuck_mint(Utc::now() + chrono::Duration::seconds(301)).await;
+        assert_eq!(escalation_attempts(), 0);
+
+        // Overdue with the next block due; the mint can't be fetched, so the
+        // attempt fails, and it is not retried
+        orchestrator.escalate_stuck_mint(Utc::now() + chrono::Duration::seconds(540)).await;
+        assert_eq!(escalation_attempts(), 1);
+        orchestrator.escalate_stuck_mint(Utc::now() + chrono::Duration::seconds(600)).await;
+        assert_eq!(escalation_attempts(), 1);
+        assert_eq!(orchestrator.pending().await, Some(txid));
+    }
+
+    #[tokio::test]
+    async fn test_replayed_blocks_ignored_by_default() {
+        let server = MockRpcServer::start().await;
+
+        let orchestrator = test_orchestrator(&server, OrchestratorConfig {
+            min_balance: 0,
+            ..OrchestratorConfig::default()
+        }).await;
+        orchestrator.handle_event(BlockEvent::NewBlock {
+            height: 1,
+            hash: format!("{:064x}", 1),
+            replayed: true,
+            observed_at: Utc::now(),
+        }).await;
+
+        assert!(orchestrator.history().await.is_empty());
+        assert!(server.methods().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_skip_below_min_balance() {
+        let server = MockRpcServer::start().await;
+
+        let orchestrator = test_orchestrator(&server, OrchestratorConfig::default()).await;
+        orchestrator.handle_event(new_block(1)).await;
+
+        let history = orchestrator.history().await;
+        assert!(matches!(history[0].outcome, MintOutcome::Skipped(_)));
+        assert!(server.methods().is_empty());
+    }
+}
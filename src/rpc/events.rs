@@ -0,0 +1,193 @@
+//! Decoding of alkanes event logs
+//!
+//! Contracts emit events as a topic and a data blob. Events reach the client
+//! two ways: LEB128-framed in the message of an event protostone (protocol
+//! tag 5), or as JSON from the indexer, in `alkanes_events` results and in
+//! execution traces.
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::fmt;
+
+use crate::varint;
+
+/// An event emitted by a contract
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct AlkaneEvent {
+    /// Event topic, usually a short name
+    #[serde(serialize_with = "hex_bytes")]
+    pub topic: Vec<u8>,
+    /// Event payload
+    #[serde(serialize_with = "hex_bytes")]
+    pub data: Vec<u8>,
+}
+
+impl AlkaneEvent {
+    /// Decode an event as reported by the indexer
+    ///
+    /// `topic` and `data` are hex strings, optionally `0x`-prefixed; a missing
+    /// `data` is an empty payload.
+    pub fn from_value(value: &Value) -> Result<Self> {
+        let field = |key: &str| -> Result<Vec<u8>> {
+            match value.get(key) {
+                Some(Value::String(text)) => hex::decode(text.strip_prefix("0x").unwrap_or(text))
+                    .with_context(|| format!("Invalid event {}: {}", key, text)),
+                Some(Value::Null) | None => Ok(Vec::new()),
+                Some(other) => Err(anyhow!("Invalid event {}: {}", key, other)),
+            }
+        };
+
+        let topic = field("topic")?;
+        if topic.is_empty() {
+            return Err(anyhow!("Invalid event: missing topic"));
+        }
+        Ok(Self { topic, data: field("data")? })
+    }
+
+    /// Topic as text if it is printable UTF-8, hex otherwise
+    pub fn topic_name(&self) -> String {
+        match std::str::from_utf8(&self.topic) {
+            Ok(text) if text.chars().all(|c| !c.is_control()) => text.to_string(),
+            _ => hex::encode(&self.topic),
+        }
+    }
+}
+
+impl fmt::Display for AlkaneEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.topic_name())?;
+        if !self.data.is_empty() {
+            write!(f, " {}", hex::encode(&self.data))?;
+        }
+        Ok(())
+    }
+}
+
+/// Serialize bytes as a hex string
+fn hex_bytes<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex::encode(bytes))
+}
+
+/// Decode the events framed in an event protostone's message
+///
+/// Each event is its topic length, the topic, its data length and the data,
+/// lengths being LEB128 varints. A message may carry any number of events
+/// back to back.
+pub fn decode_events(message: &[u8]) -> Result<Vec<AlkaneEvent>> {
+    let mut events = Vec::new();
+    let mut rest = message;
+    while !rest.is_empty() {
+        let topic = take_field(&mut rest, "topic")?;
+        let data = take_field(&mut rest, "data")?;
+        events.push(AlkaneEvent { topic, data });
+    }
+    Ok(events)
+}
+
+/// Take one length-prefixed field off the front of `rest`
+fn take_field(rest: &mut &[u8], name: &str) -> Result<Vec<u8>> {
+    let (length, size) = varint::decode(rest)
+        .with_context(|| format!("Invalid event {} length", name))?;
+    let length = usize::try_from(length)
+        .ok()
+        .filter(|length| *length <= rest.len() - size)
+        .ok_or_else(|| anyhow!("Truncated event {}: {} bytes declared, {} left", name, length, rest.len() - size))?;
+    let field = rest[size..size + length].to_vec();
+    *rest = &rest[size + length..];
+    Ok(field)
+}
+
+/// Parse an `alkanes_events` result, a bare array or an object with `events`
+pub fn parse_events(value: &Value) -> Result<Vec<AlkaneEvent>> {
+    let events = value.as_array()
+        .or_else(|| value.get("events").and_then(|v| v.as_array()))
+        .ok_or_else(|| anyhow!("Unexpected events response: {}", value))?;
+    events.iter().map(AlkaneEvent::from_value).collect()
+}
+
+/// Collect the events reported anywhere in an execution trace
+///
+/// Traces nest calls, so every `events` list is gathered in order of
+/// appearance. Entries that don't decode as events are skipped.
+pub fn trace_events(trace: &Value) -> Vec<AlkaneEvent> {
+    let mut events = Vec::new();
+    collect_trace_events(trace, &mut events);
+    events
+}
+
+fn collect_trace_events(value: &Value, events: &mut Vec<AlkaneEvent>) {
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| collect_trace_events(item, events)),
+        Value::Object(object) => {
+            for (key, item) in object {
+                match (key.as_str(), item) {
+                    ("events", Value::Array(entries)) => {
+                        events.extend(entries.iter().filter_map(|entry| AlkaneEvent::from_value(entry).ok()));
+                    },
+                    _ => collect_trace_events(item, events),
+                }
+            }
+        },
+        _ => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn framed(events: &[(&[u8], &[u8])]) -> Vec<u8> {
+        let mut message = Vec::new();
+        for (topic, data) in events {
+            varint::encode_to_vec(topic.len() as u128, &mut message);
+            message.extend_from_slice(topic);
+            varint::encode_to_vec(data.len() as u128, &mut message);
+            message.extend_from_slice(data);
+        }
+        message
+    }
+
+    #[test]
+    fn test_decode_events() {
+        let long_data = vec![7u8; 200];
+        let message = framed(&[(b"Transfer", &[1, 2, 3]), (b"Mint", &long_data), (b"Ping", &[])]);
+        let events = decode_events(&message).unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], AlkaneEvent { topic: b"Transfer".to_vec(), data: vec![1, 2, 3] });
+        assert_eq!(events[1].data, long_data);
+        assert_eq!(events[2].to_string(), "Ping");
+        assert_eq!(events[0].to_string(), "Transfer 010203");
+        assert!(decode_events(&[]).unwrap().is_empty());
+
+        // Lengths running past the end of the message
+        assert!(decode_events(&message[..message.len() - 1]).is_err());
+        assert!(decode_events(&[5, b'a']).is_err());
+        assert!(decode_events(&[0x80]).is_err());
+    }
+
+    #[test]
+    fn test_parse_events_and_trace_events() {
+        let events = parse_events(&json!([
+            { "topic": "0x5472616e73666572", "data": "00ff" },
+            { "topic": "01" },
+        ])).unwrap();
+        assert_eq!(events[0].topic_name(), "Transfer");
+        assert_eq!(events[0].data, vec![0x00, 0xff]);
+        assert_eq!(events[1].topic_name(), "01");
+        assert_eq!(serde_json::to_value(&events[0]).unwrap(), json!({ "topic": "5472616e73666572", "data": "00ff" }));
+        assert_eq!(parse_events(&json!({ "events": [] })).unwrap(), vec![]);
+        assert!(parse_events(&json!([{ "data": "00" }])).is_err());
+        assert!(parse_events(&json!("0x")).is_err());
+
+        // Events of nested calls, in order, skipping malformed entries
+        let trace = json!([
+            { "event": "enter", "data": {} },
+            { "event": "return", "data": { "response": { "events": [{ "topic": "aa" }, { "topic": "zz" }] } } },
+            { "event": "return", "data": { "events": [{ "topic": "bb", "data": "01" }] } },
+        ]);
+        let topics: Vec<String> = trace_events(&trace).iter().map(|event| event.topic_name()).collect();
+        assert_eq!(topics, vec!["aa", "bb"]);
+    }
+}
@@ -0,0 +1,308 @@
+//! Typed decoding of `alkanes_meta` responses
+//!
+//! Contracts describe their name and callable methods in their metadata.
+//! Older contracts omit some fields or the whole method list, so only a
+//! method's opcode is required; everything else falls back to a default.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+use super::value_to_u128;
+
+/// Type of a method input or output
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(into = "String")]
+pub enum AbiType {
+    /// Unsigned 8-bit integer
+    U8,
+    /// Unsigned 16-bit integer
+    U16,
+    /// Unsigned 32-bit integer
+    U32,
+    /// Unsigned 64-bit integer
+    U64,
+    /// Unsigned 128-bit integer
+    U128,
+    /// Boolean, encoded as 0 or 1
+    Bool,
+    /// UTF-8 string
+    String,
+    /// Byte vector
+    Bytes,
+    /// Alkane ID, encoded as block and tx
+    AlkaneId,
+    /// No value
+    Void,
+    /// A type this client doesn't know, kept by name
+    Other(String),
+}
+
+impl AbiType {
+    /// Parse a type name as reported in contract metadata
+    pub fn parse(name: &str) -> Self {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "u8" => AbiType::U8,
+            "u16" => AbiType::U16,
+            "u32" => AbiType::U32,
+            "u64" => AbiType::U64,
+            "u128" => AbiType::U128,
+            "bool" => AbiType::Bool,
+            "string" | "str" | "&str" => AbiType::String,
+            "bytes" | "vec<u8>" => AbiType::Bytes,
+            "alkaneid" => AbiType::AlkaneId,
+            "" | "void" | "()" => AbiType::Void,
+            _ => AbiType::Other(name.trim().to_string()),
+        }
+    }
+
+    /// Number of cellpack values the type occupies, if fixed
+    pub fn width(&self) -> Option<usize> {
+        match self {
+            AbiType::U8 | AbiType::U16 | AbiType::U32 | AbiType::U64 | AbiType::U128 | AbiType::Bool => Some(1),
+            AbiType::AlkaneId => Some(2),
+            AbiType::Void => Some(0),
+            AbiType::String | AbiType::Bytes | AbiType::Other(_) => None,
+        }
+    }
+
+    /// Largest value of a type occupying a single cellpack value
+    fn max_value(&self) -> Option<u128> {
+        match self {
+            AbiType::U8 => Some(u8::MAX.into()),
+            AbiType::U16 => Some(u16::MAX.into()),
+            AbiType::U32 => Some(u32::MAX.into()),
+            AbiType::U64 => Some(u64::MAX.into()),
+            AbiType::U128 => Some(u128::MAX),
+            AbiType::Bool => Some(1),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AbiType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AbiType::U8 => write!(f, "u8"),
+            AbiType::U16 => write!(f, "u16"),
+            AbiType::U32 => write!(f, "u32"),
+            AbiType::U64 => write!(f, "u64"),
+            AbiType::U128 => write!(f, "u128"),
+            AbiType::Bool => write!(f, "bool"),
+            AbiType::String => write!(f, "String"),
+            AbiType::Bytes => write!(f, "Vec<u8>"),
+            AbiType::AlkaneId => write!(f, "AlkaneId"),
+            AbiType::Void => write!(f, "void"),
+            AbiType::Other(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl From<AbiType> for String {
+    fn from(abi_type: AbiType) -> Self {
+        abi_type.to_string()
+    }
+}
+
+/// A callable method of a contract
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct MethodMeta {
+    /// Opcode selecting the method, the first cellpack input
+    pub selector: u32,
+    /// Method name (empty if not reported)
+    pub name: String,
+    /// Types of the inputs following the opcode
+    pub inputs: Vec<AbiType>,
+    /// Types of the returned values
+    pub outputs: Vec<AbiType>,
+}
+
+impl fmt::Display for MethodMeta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |types: &[AbiType]| types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ");
+        write!(f, "{}: {}({})", self.selector, self.name, join(&self.inputs))?;
+        match &self.outputs[..] {
+            [] | [AbiType::Void] => Ok(()),
+            [output] => write!(f, " -> {}", output),
+            outputs => write!(f, " -> ({})", join(outputs)),
+        }
+    }
+}
+
+/// Decoded `alkanes_meta` response
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ContractMeta {
+    /// Contract name (empty if not reported)
+    pub name: String,
+    /// Token symbol, if the contract is a token
+    pub symbol: Option<String>,
+    /// Callable methods, empty for contracts that don't describe them
+    pub methods: Vec<MethodMeta>,
+}
+
+impl ContractMeta {
+    /// Decode an `alkanes_meta` result
+    ///
+    /// The name is read from `name` or `contract`, a method's opcode from
+    /// `opcode` or `selector`, its inputs from `params` or `inputs` and its
+    /// outputs from `returns` or `outputs`. Types may be given as strings or
+    /// as objects with a `type` field.
+    pub fn from_value(value: &Value) -> Result<Self> {
+        let object = value.as_object().ok_or_else(|| anyhow!("Invalid contract metadata: expected an object"))?;
+        let text = |key: &str| object.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+
+        let methods = match object.get("methods").and_then(|v| v.as_array()) {
+            Some(methods) => methods.iter().map(parse_method).collect::<Result<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            name: text("name").or_else(|| text("contract")).unwrap_or_default(),
+            symbol: text("symbol"),
+            methods,
+        })
+    }
+
+    /// Find the method selected by `opcode`
+    pub fn method(&self, opcode: u128) -> Option<&MethodMeta> {
+        self.methods.iter().find(|method| u128::from(method.selector) == opcode)
+    }
+
+    /// Check cellpack inputs (opcode first) against the method they select
+    ///
+    /// Contracts that don't describe their methods accept anything. Inputs
+    /// are only counted and range-checked up to the first variable-width
+    /// type, since where it ends can't be known.
+    pub fn validate_call(&self, inputs: &[u128]) -> Result<()> {
+        if self.methods.is_empty() {
+            return Ok(());
+        }
+
+        let opcode = *inputs.first().ok_or_else(|| anyhow!("Missing opcode"))?;
+        let method = self.method(opcode).ok_or_else(|| {
+            let available: Vec<String> = self.methods.iter().map(|method| method.to_string()).collect();
+            anyhow!("{} has no method with opcode {}; available methods: {}", self.display_name(), opcode, available.join("; "))
+        })?;
+
+        let args = &inputs[1..];
+        let mut position = 0;
+        for abi_type in &method.inputs {
+            let width = match abi_type.width() {
+                Some(width) => width,
+                None => return Ok(()),
+            };
+            if position + width > args.len() {
+                break;
+            }
+            if let Some(max) = abi_type.max_value() {
+                if args[position] > max {
+                    return Err(anyhow!("Input {} of {} is {}, out of range for {}", position, method.name, args[position], abi_type));
+                }
+            }
+            position += width;
+        }
+
+        let expected: usize = method.inputs.iter().filter_map(|abi_type| abi_type.width()).sum();
+        if args.len() != expected {
+            return Err(anyhow!("{} takes {} input(s) after the opcode ({}), got {}", method.name, expected, method, args.len()));
+        }
+        Ok(())
+    }
+
+    /// Name to show in messages, falling back to a placeholder
+    fn display_name(&self) -> &str {
+        if self.name.is_empty() { "The contract" } else { &self.name }
+    }
+}
+
+/// Decode one entry of the `methods` list
+fn parse_method(value: &Value) -> Result<MethodMeta> {
+    let selector = value.get("opcode").or_else(|| value.get("selector"))
+        .and_then(value_to_u128)
+        .and_then(|selector| u32::try_from(selector).ok())
+        .ok_or_else(|| anyhow!("Invalid contract metadata: method without a valid opcode"))?;
+
+    Ok(MethodMeta {
+        selector,
+        name: value.get("name").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+        inputs: parse_types(value.get("params").or_else(|| value.get("inputs"))),
+        outputs: parse_types(value.get("returns").or_else(|| value.get("outputs"))),
+    })
+}
+
+/// Decode a type list, a single type or nothing
+fn parse_types(value: Option<&Value>) -> Vec<AbiType> {
+    let parse_type = |value: &Value| match value {
+        Value::String(name) => Some(AbiType::parse(name)),
+        Value::Object(object) => object.get("type").and_then(|v| v.as_str()).map(AbiType::parse),
+        _ => None,
+    };
+
+    match value {
+        Some(Value::Array(types)) => types.iter().filter_map(parse_type).collect(),
+        Some(value) => parse_type(value).into_iter().collect(),
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn token_meta() -> ContractMeta {
+        ContractMeta::from_value(&json!({
+            "contract": "Diesel",
+            "symbol": "DIESEL",
+            "methods": [
+                { "name": "initialize", "opcode": 0, "params": [], "returns": "void" },
+                { "name": "transfer", "opcode": "0x5", "params": [{ "name": "to", "type": "AlkaneId" }, { "name": "amount", "type": "u128" }], "returns": "bool" },
+                { "name": "set_name", "opcode": 6, "params": ["String"] },
+                { "name": "set_decimals", "opcode": 7, "params": ["u8"] },
+                { "name": "mint", "opcode": 77 },
+            ],
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_from_value() {
+        let meta = token_meta();
+        assert_eq!(meta.name, "Diesel");
+        assert_eq!(meta.symbol.as_deref(), Some("DIESEL"));
+        assert_eq!(meta.methods.len(), 5);
+        assert_eq!(meta.methods[1], MethodMeta {
+            selector: 5,
+            name: "transfer".to_string(),
+            inputs: vec![AbiType::AlkaneId, AbiType::U128],
+            outputs: vec![AbiType::Bool],
+        });
+        assert_eq!(meta.methods[1].to_string(), "5: transfer(AlkaneId, u128) -> bool");
+        assert_eq!(meta.methods[0].to_string(), "0: initialize()");
+
+        // Older contracts report little more than a name
+        let old = ContractMeta::from_value(&json!({ "name": "Legacy" })).unwrap();
+        assert_eq!(old, ContractMeta { name: "Legacy".to_string(), ..ContractMeta::default() });
+        assert!(ContractMeta::from_value(&json!({ "methods": [{ "name": "no_opcode" }] })).is_err());
+        assert!(ContractMeta::from_value(&json!("0x")).is_err());
+    }
+
+    #[test]
+    fn test_validate_call() {
+        let meta = token_meta();
+        assert!(meta.validate_call(&[77]).is_ok());
+        assert!(meta.validate_call(&[5, 2, 1, 1000]).is_ok());
+        assert!(meta.validate_call(&[7, 18]).is_ok());
+
+        // Unknown opcode, wrong input count and out-of-range value
+        assert!(meta.validate_call(&[99]).unwrap_err().to_string().contains("5: transfer(AlkaneId, u128) -> bool"));
+        assert!(meta.validate_call(&[5, 2, 1]).is_err());
+        assert!(meta.validate_call(&[77, 1]).is_err());
+        assert!(meta.validate_call(&[7, 256]).is_err());
+        assert!(meta.validate_call(&[]).is_err());
+
+        // Variable-width inputs can't be counted
+        assert!(meta.validate_call(&[6, 1, 2, 3]).is_ok());
+        assert!(ContractMeta::default().validate_call(&[1, 2, 3]).is_ok());
+    }
+}
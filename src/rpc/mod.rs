@@ -5,13 +5,49 @@
 //! - Communication with Metashrew RPC
 //! - Request/response serialization
 //! - Error handling and retries
+//! - Typed decoding of contract metadata and event logs
+
+mod events;
+mod meta;
 
 use anyhow::{Context, Result, anyhow};
-use log::debug;
+use bdk::bitcoin::{Transaction, TxOut, Txid};
+use bdk::bitcoin::consensus::deserialize;
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+use log::{debug, warn};
 use reqwest::{Client, header};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
 use std::time::Duration;
+use tokio::sync::watch;
+
+use crate::alkane_id::AlkaneId;
+use crate::format::format_rune_amount;
+use crate::runestone_enhanced::{decode_runestone, protocol_tags, DecodedRunestone};
+pub use self::events::{decode_events, parse_events, trace_events, AlkaneEvent};
+pub use self::meta::{AbiType, ContractMeta, MethodMeta};
+
+/// Transactions fetched concurrently while scanning a block
+const BLOCK_SCAN_CONCURRENCY: usize = 8;
+
+/// Blocks traced ahead of the one being reported by `scan_block_range`
+const BLOCK_RANGE_CONCURRENCY: usize = 4;
+
+/// Transactions fetched per batch request when a block comes from esplora
+const BLOCK_FETCH_BATCH_SIZE: usize = 25;
+
+/// Transactions kept by the transaction cache of each client
+const TRANSACTION_CACHE_SIZE: usize = 1024;
+
+/// Block component of the alkane IDs assigned to deployed contracts
+const DEPLOYED_ALKANE_BLOCK: u128 = 2;
+
+/// Idle connections kept open per host by default
+pub const DEFAULT_POOL_MAX_IDLE_PER_HOST: usize = 8;
+
+/// How long an idle connection is kept open by default
+pub const DEFAULT_POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
 
 /// RPC client configuration
 #[derive(Clone, Debug)]
@@ -20,6 +56,31 @@ pub struct RpcConfig {
     pub bitcoin_rpc_url: String,
     /// Metashrew RPC URL
     pub metashrew_rpc_url: String,
+    /// Dedicated URL for `esplora_*` methods, if any
+    pub esplora_rpc_url: Option<String>,
+    /// Per-method URL overrides, consulted before any prefix routing
+    pub method_overrides: HashMap<String, String>,
+    /// Additional Metashrew instances indexing the same chain, only used to
+    /// cross-check heights
+    pub metashrew_replica_urls: Vec<String>,
+    /// Idle connections kept open per host for reuse; 0 disables reuse
+    pub pool_max_idle_per_host: usize,
+    /// How long an idle connection is kept open, or forever if `None`
+    pub pool_idle_timeout: Option<Duration>,
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            bitcoin_rpc_url: String::new(),
+            metashrew_rpc_url: String::new(),
+            esplora_rpc_url: None,
+            method_overrides: HashMap::new(),
+            metashrew_replica_urls: Vec::new(),
+            pool_max_idle_per_host: DEFAULT_POOL_MAX_IDLE_PER_HOST,
+            pool_idle_timeout: Some(DEFAULT_POOL_IDLE_TIMEOUT),
+        }
+    }
 }
 
 /// RPC request
@@ -46,6 +107,22 @@ struct RpcResponse {
     id: u64,
 }
 
+impl RpcResponse {
+    /// Convert the response into its result or an error
+    fn into_result(self) -> Result<Value> {
+        match self.result {
+            Some(result) => Ok(result),
+            None => {
+                let error = self.error.unwrap_or(RpcError {
+                    code: -1,
+                    message: "Unknown error".to_string(),
+                });
+                Err(anyhow!("RPC error: {} (code: {})", error.message, error.code))
+            }
+        }
+    }
+}
+
 /// RPC error
 #[derive(Deserialize, Debug)]
 struct RpcError {
@@ -55,43 +132,210 @@ struct RpcError {
     message: String,
 }
 
+/// Confirmation status of a transaction
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TxStatus {
+    /// Whether the transaction is included in a block
+    pub confirmed: bool,
+    /// Height of the including block, if confirmed
+    pub block_height: Option<u64>,
+    /// Hash of the including block, if confirmed
+    pub block_hash: Option<String>,
+}
+
+/// Result of a `testmempoolaccept` check
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MempoolAcceptResult {
+    /// Whether the transaction would be accepted into the mempool
+    pub allowed: bool,
+    /// Reason the transaction was rejected, if it was
+    pub reject_reason: Option<String>,
+    /// Base fee in satoshis, if the transaction was accepted
+    pub fees: Option<u64>,
+}
+
+/// Balance of a single protorune
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProtoruneBalance {
+    /// Block component of the rune ID
+    pub block: u128,
+    /// Transaction component of the rune ID
+    pub tx: u128,
+    /// Rune name, if reported
+    pub name: Option<String>,
+    /// Number of decimal places of the amount (0 if not reported)
+    pub divisibility: u8,
+    /// Raw balance amount
+    pub amount: u128,
+}
+
+/// A protorune holding with its contract metadata resolved
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Holding {
+    /// Block component of the rune ID
+    pub block: u128,
+    /// Transaction component of the rune ID
+    pub tx: u128,
+    /// Token name, from the contract metadata or the balance sheet
+    pub name: Option<String>,
+    /// Token symbol, from the contract metadata
+    pub symbol: Option<String>,
+    /// Number of decimal places of the amount
+    pub divisibility: u8,
+    /// Raw amount summed over all outpoints, as a decimal string
+    pub amount: String,
+    /// Amount scaled by the divisibility
+    pub formatted_amount: String,
+}
+
+/// How far Metashrew is behind Bitcoin
+///
+/// Metashrew is in sync when its height is the Bitcoin height + 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct SyncStatus {
+    /// Bitcoin block count
+    pub bitcoin_height: u64,
+    /// Metashrew height
+    pub metashrew_height: u64,
+    /// Blocks Metashrew is behind (negative if ahead)
+    pub indexer_lag: i64,
+    /// Whether Metashrew height is Bitcoin height + 1
+    pub in_sync: bool,
+}
+
+impl SyncStatus {
+    /// Compare the heights reported by Bitcoin and Metashrew
+    pub fn from_heights(bitcoin_height: u64, metashrew_height: u64) -> Self {
+        let indexer_lag = (bitcoin_height as i64 + 1) - metashrew_height as i64;
+        Self {
+            bitcoin_height,
+            metashrew_height,
+            indexer_lag,
+            in_sync: indexer_lag == 0,
+        }
+    }
+}
+
+/// Reachability and sync state of the Bitcoin and Metashrew backends
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct HealthStatus {
+    /// Bitcoin block count, if Bitcoin RPC is reachable
+    pub bitcoin_height: Option<u64>,
+    /// Metashrew height, if Metashrew RPC is reachable
+    pub metashrew_height: Option<u64>,
+    /// Errors from unreachable backends
+    pub errors: Vec<String>,
+    /// Whether Metashrew height is Bitcoin height + 1
+    pub in_sync: bool,
+}
+
+impl HealthStatus {
+    /// Blocks between the expected and actual Metashrew height, either way
+    pub fn lag(&self) -> Option<u64> {
+        match (self.bitcoin_height, self.metashrew_height) {
+            (Some(bitcoin), Some(metashrew)) => Some((bitcoin + 1).abs_diff(metashrew)),
+            _ => None,
+        }
+    }
+    
+    /// Check that both backends are reachable and at most `max_lag` blocks apart
+    pub fn is_healthy(&self, max_lag: u64) -> bool {
+        matches!(self.lag(), Some(lag) if lag <= max_lag)
+    }
+}
+
 /// RPC client for Bitcoin and Metashrew
 pub struct RpcClient {
     /// HTTP client
     client: Client,
     /// RPC configuration
     config: RpcConfig,
-    /// Request ID counter
+    /// Request ID counter, seeded randomly so that clients sharing a server
+    /// are unlikely to collide
     request_id: std::sync::atomic::AtomicU64,
+    /// Transactions fetched so far, by ID
+    transactions: std::sync::Mutex<TransactionCache>,
+}
+
+/// Bounded cache of fetched transactions, evicting the oldest first
+#[derive(Default)]
+struct TransactionCache {
+    /// Cached transactions
+    entries: HashMap<Txid, Transaction>,
+    /// IDs in insertion order
+    order: VecDeque<Txid>,
+}
+
+impl TransactionCache {
+    fn get(&self, txid: &Txid) -> Option<Transaction> {
+        self.entries.get(txid).cloned()
+    }
+    
+    fn insert(&mut self, txid: Txid, tx: Transaction) {
+        if self.entries.insert(txid, tx).is_some() {
+            return;
+        }
+        self.order.push_back(txid);
+        if self.order.len() > TRANSACTION_CACHE_SIZE {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
 }
 
 impl RpcClient {
     /// Create a new RPC client
     pub fn new(config: RpcConfig) -> Self {
-        // Create HTTP client with appropriate timeouts
+        // Create HTTP client with appropriate timeouts, keeping connections
+        // open so sequential and batched calls don't reconnect every time
         let client = Client::builder()
             .timeout(Duration::from_secs(30))
+            .pool_max_idle_per_host(config.pool_max_idle_per_host)
+            .pool_idle_timeout(config.pool_idle_timeout)
             .build()
             .expect("Failed to create HTTP client");
         
         Self {
             client,
             config,
-            request_id: std::sync::atomic::AtomicU64::new(0),
+            // A u32 seed keeps ids well below 2^53 for servers parsing them as doubles
+            request_id: std::sync::atomic::AtomicU64::new(u64::from(rand::random::<u32>())),
+            transactions: std::sync::Mutex::new(TransactionCache::default()),
         }
     }
     
+    /// Determine which RPC endpoint and JSON-RPC version to use for a method
+    ///
+    /// Explicit method overrides win, then `esplora_*` methods go to the
+    /// dedicated esplora URL if configured, then `btc_*` methods go to the
+    /// Bitcoin RPC and everything else to Metashrew.
+    fn endpoint(&self, method: &str) -> (&str, &'static str) {
+        let jsonrpc_version = if method.starts_with("btc_") { "1.0" } else { "2.0" };
+        
+        if let Some(url) = self.config.method_overrides.get(method) {
+            return (url, jsonrpc_version);
+        }
+        
+        let url = match &self.config.esplora_rpc_url {
+            Some(url) if method.starts_with("esplora_") => url,
+            _ if method.starts_with("btc_") => &self.config.bitcoin_rpc_url,
+            _ => &self.config.metashrew_rpc_url,
+        };
+        (url, jsonrpc_version)
+    }
+    
     /// Generic method to call any RPC method
     pub async fn _call(&self, method: &str, params: Value) -> Result<Value> {
         debug!("Calling RPC method: {}", method);
         
         // Determine which RPC endpoint to use based on the method prefix
-        let (url, jsonrpc_version) = if method.starts_with("btc_") {
-            (&self.config.bitcoin_rpc_url, "1.0")
-        } else {
-            (&self.config.metashrew_rpc_url, "2.0")
-        };
-        
+        let (url, jsonrpc_version) = self.endpoint(method);
+        self.call_url(url, jsonrpc_version, method, params).await
+    }
+    
+    /// Call an RPC method on a specific endpoint
+    async fn call_url(&self, url: &str, jsonrpc_version: &str, method: &str, params: Value) -> Result<Value> {
         let request = RpcRequest {
             jsonrpc: jsonrpc_version.to_string(),
             method: method.to_string(),
@@ -117,16 +361,65 @@ impl RpcClient {
             .await
             .context("Failed to parse RPC response")?;
         
-        match response_body.result {
-            Some(result) => Ok(result),
-            None => {
-                let error = response_body.error.unwrap_or(RpcError {
-                    code: -1,
-                    message: "Unknown error".to_string(),
-                });
-                Err(anyhow!("RPC error: {} (code: {})", error.message, error.code))
-            }
+        response_body.into_result()
+    }
+    
+    /// Call several RPC methods in a single JSON-RPC batch request
+    ///
+    /// All methods must route to the same endpoint. The outer `Result` fails if
+    /// the batch as a whole could not be sent or parsed; each inner `Result`
+    /// carries the outcome of the corresponding call, in input order.
+    pub async fn call_batch(&self, calls: &[(&str, Value)]) -> Result<Vec<Result<Value>>> {
+        let (url, jsonrpc_version) = match calls.first() {
+            Some((method, _)) => self.endpoint(method),
+            None => return Ok(Vec::new()),
+        };
+        if calls.iter().any(|(method, _)| self.endpoint(method).0 != url) {
+            return Err(anyhow!("Batched RPC methods must all use the same endpoint"));
+        }
+        
+        debug!("Calling {} RPC methods in a batch", calls.len());
+        
+        let first_id = self.next_request_ids(calls.len() as u64);
+        let requests: Vec<RpcRequest> = calls.iter()
+            .zip(first_id..)
+            .map(|((method, params), id)| RpcRequest {
+                jsonrpc: jsonrpc_version.to_string(),
+                method: method.to_string(),
+                params: params.clone(),
+                id,
+            })
+            .collect();
+        
+        let response = self.client
+            .post(url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .json(&requests)
+            .send()
+            .await
+            .context("Failed to send RPC batch request")?;
+        
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow!("RPC batch request failed with status: {}", status));
         }
+        
+        let mut responses = response
+            .json::<Vec<RpcResponse>>()
+            .await
+            .context("Failed to parse RPC batch response")?;
+        
+        // Servers may answer a batch in any order, so match responses by ID
+        let results = requests.iter()
+            .map(|request| {
+                match responses.iter().position(|r| r.id == request.id) {
+                    Some(index) => responses.swap_remove(index).into_result(),
+                    None => Err(anyhow!("Missing response for {} in RPC batch", request.method)),
+                }
+            })
+            .collect();
+        
+        Ok(results)
     }
     
     /// Get the current block count from Bitcoin RPC
@@ -140,6 +433,191 @@ impl RpcClient {
         Ok(height)
     }
     
+    /// Get the hash of the block at the given height from Bitcoin RPC
+    pub async fn get_block_hash(&self, height: u64) -> Result<String> {
+        debug!("Getting block hash for height: {}", height);
+        
+        let result = self._call("btc_getblockhash", json!([height])).await?;
+        
+        let hash = result.as_str().context("Invalid block hash")?.to_string();
+        debug!("Block hash at height {}: {}", height, hash);
+        Ok(hash)
+    }
+    
+    /// Get the txids of the block at the given height, in block order
+    pub async fn get_block_txids(&self, height: u64) -> Result<Vec<String>> {
+        let hash = self.get_block_hash(height).await?;
+        debug!("Getting txids of block {} ({})", height, hash);
+        
+        let result = self._call("esplora_block::txids", json!([hash])).await?;
+        
+        let txids = result.as_array()
+            .context("Invalid block txids response")?
+            .iter()
+            .map(|txid| txid.as_str().map(|s| s.to_string()).context("Invalid txid in block"))
+            .collect::<Result<Vec<_>>>()?;
+        debug!("Block {} has {} transaction(s)", height, txids.len());
+        Ok(txids)
+    }
+    
+    /// Get every transaction of the block at the given height, in block order
+    ///
+    /// The block comes from `btc_getblock` at verbosity 2. If that fails, the
+    /// txids come from esplora and the transactions are fetched in batches of
+    /// `BLOCK_FETCH_BATCH_SIZE`, at most `BLOCK_SCAN_CONCURRENCY` batches at a
+    /// time. A transaction that can't be fetched or parsed gets its own error
+    /// instead of failing the block.
+    pub async fn get_block_transactions(&self, height: u64) -> Result<Vec<(Txid, Result<Transaction>)>> {
+        let hash = self.get_block_hash(height).await?;
+        debug!("Getting transactions of block {} ({})", height, hash);
+        
+        match self._call("btc_getblock", json!([hash, 2])).await {
+            Ok(block) => parse_verbose_block(&block),
+            Err(e) => {
+                debug!("btc_getblock failed for {}, fetching transactions from esplora: {}", hash, e);
+                let txids = self.get_block_txids(height).await?
+                    .iter()
+                    .map(|txid| txid.parse::<Txid>().with_context(|| format!("Invalid txid in block: {}", txid)))
+                    .collect::<Result<Vec<_>>>()?;
+                
+                let batches: Vec<Vec<(Txid, Result<Transaction>)>> = stream::iter(txids.chunks(BLOCK_FETCH_BATCH_SIZE))
+                    .map(|batch| self.fetch_transaction_batch(batch))
+                    .buffered(BLOCK_SCAN_CONCURRENCY)
+                    .try_collect()
+                    .await?;
+                Ok(batches.into_iter().flatten().collect())
+            },
+        }
+    }
+    
+    /// Fetch the hex of each transaction in one batch request and parse it
+    async fn fetch_transaction_batch(&self, txids: &[Txid]) -> Result<Vec<(Txid, Result<Transaction>)>> {
+        let calls: Vec<(&str, Value)> = txids.iter()
+            .map(|txid| ("esplora_tx::hex", json!([txid.to_string()])))
+            .collect();
+        let results = self.call_batch(&calls).await?;
+        
+        Ok(txids.iter()
+            .zip(results)
+            .map(|(txid, result)| {
+                let tx = result.and_then(|hex| parse_transaction_hex(hex.as_str().context("Invalid transaction hex")?))
+                    .with_context(|| format!("Failed to fetch transaction {}", txid));
+                (*txid, tx)
+            })
+            .collect())
+    }
+    
+    /// Decode the runestone of every transaction in a block that has one
+    ///
+    /// Returns `(txid, decoded)` pairs in block order. At most
+    /// `BLOCK_SCAN_CONCURRENCY` transactions are fetched at a time; any
+    /// failed fetch fails the scan.
+    pub async fn scan_block_runestones(&self, height: u64) -> Result<Vec<(String, DecodedRunestone)>> {
+        let txids = self.get_block_txids(height).await?;
+        
+        let decoded: Vec<Option<(String, DecodedRunestone)>> = stream::iter(txids)
+            .map(|txid| self.fetch_runestone(txid))
+            .buffered(BLOCK_SCAN_CONCURRENCY)
+            .try_collect()
+            .await?;
+        
+        let runestones: Vec<(String, DecodedRunestone)> = decoded.into_iter().flatten().collect();
+        debug!("Found {} runestone(s) in block {}", runestones.len(), height);
+        Ok(runestones)
+    }
+    
+    /// Fetch a transaction and decode its runestone, if it has one
+    async fn fetch_runestone(&self, txid: String) -> Result<Option<(String, DecodedRunestone)>> {
+        let tx = self.get_transaction(&txid).await?;
+        
+        // Transactions without a (valid) runestone are simply skipped
+        match decode_runestone(&tx) {
+            Ok(runestone) => Ok(Some((txid, runestone))),
+            Err(e) => {
+                debug!("No runestone in {}: {}", txid, e);
+                Ok(None)
+            },
+        }
+    }
+    
+    /// Trace the alkanes protostones of every block from `from` to `to`
+    ///
+    /// `on_block` is called once per block, in height order, with the
+    /// `(outpoint, trace)` of each protostone addressed to alkanes (protocol
+    /// tag 1), so callers can persist each block and resume after it. Up to
+    /// `BLOCK_RANGE_CONCURRENCY` blocks are traced ahead of the one being
+    /// reported.
+    ///
+    /// The scan stops without error once `cancel` turns true, after the
+    /// block being reported, if any. A block that can't be fully fetched or
+    /// traced, or an error from `on_block`, fails the scan; blocks already
+    /// reported stay reported.
+    pub async fn scan_block_range(
+        &self,
+        from: u64,
+        to: u64,
+        mut cancel: watch::Receiver<bool>,
+        mut on_block: impl FnMut(u64, Vec<(String, Value)>) -> Result<()>,
+    ) -> Result<()> {
+        if from > to {
+            return Err(anyhow!("Invalid block range: {} is after {}", from, to));
+        }
+        
+        let mut blocks = std::pin::pin!(stream::iter(from..=to)
+            .map(|height| async move { (height, self.trace_block_protostones(height).await) })
+            .buffered(BLOCK_RANGE_CONCURRENCY));
+        loop {
+            if *cancel.borrow() {
+                debug!("Block range scan cancelled");
+                return Ok(());
+            }
+            // Once the sender is gone `changed` fails and is no longer polled
+            let next = tokio::select! {
+                next = blocks.next() => next,
+                Ok(()) = cancel.changed() => continue,
+            };
+            let (height, traces) = match next {
+                Some(block) => block,
+                None => return Ok(()),
+            };
+            let traces = traces.with_context(|| format!("Failed to trace block {}", height))?;
+            on_block(height, traces)?;
+        }
+    }
+    
+    /// Trace every alkanes protostone of the block at `height`
+    ///
+    /// Returns `(outpoint, trace)` pairs in block order, the outpoint being
+    /// the protostone's virtual output as `txid:vout`.
+    async fn trace_block_protostones(&self, height: u64) -> Result<Vec<(String, Value)>> {
+        let mut outpoints = Vec::new();
+        for (txid, tx) in self.get_block_transactions(height).await? {
+            let tx = tx?;
+            let decoded = match decode_runestone(&tx) {
+                Ok(decoded) => decoded,
+                Err(_) => continue,
+            };
+            // Protostone i is virtual output outputs + 1 + i
+            for (index, protostone) in decoded.protostones.iter().enumerate() {
+                if protostone.protocol_tag == protocol_tags::DIESEL {
+                    outpoints.push((txid.to_string(), tx.output.len() + 1 + index));
+                }
+            }
+        }
+        
+        let traces: Vec<(String, Value)> = stream::iter(outpoints)
+            .map(|(txid, vout)| async move {
+                let trace = self.trace_transaction(&txid, vout).await
+                    .with_context(|| format!("Failed to trace {}:{}", txid, vout))?;
+                Ok::<_, anyhow::Error>((format!("{}:{}", txid, vout), trace))
+            })
+            .buffered(BLOCK_SCAN_CONCURRENCY)
+            .try_collect()
+            .await?;
+        debug!("Traced {} protostone(s) in block {}", traces.len(), height);
+        Ok(traces)
+    }
+    
     /// Get the current block height from Metashrew RPC
     pub async fn get_metashrew_height(&self) -> Result<u64> {
         debug!("Getting block height from Metashrew RPC");
@@ -151,6 +629,57 @@ impl RpcClient {
         Ok(height)
     }
     
+    /// Compare the Bitcoin and Metashrew heights
+    ///
+    /// Unlike `health`, this fails if either backend is unreachable. Being
+    /// out of sync is not an error; callers decide whether to proceed.
+    pub async fn check_sync_status(&self) -> Result<SyncStatus> {
+        let (bitcoin, metashrew) = tokio::join!(self.get_block_count(), self.get_metashrew_height());
+        let status = SyncStatus::from_heights(bitcoin?, metashrew?);
+        debug!("Sync status: {:?}", status);
+        Ok(status)
+    }
+    
+    /// Check both backends and compare their heights
+    ///
+    /// Unreachable backends are reported in `errors` rather than failing the
+    /// call, so callers can always describe what is wrong.
+    pub async fn health(&self) -> Result<HealthStatus> {
+        let (bitcoin, metashrew) = tokio::join!(self.get_block_count(), self.get_metashrew_height());
+        
+        let mut status = HealthStatus::default();
+        match bitcoin {
+            Ok(height) => status.bitcoin_height = Some(height),
+            Err(e) => status.errors.push(format!("Bitcoin RPC: {}", e)),
+        }
+        match metashrew {
+            Ok(height) => status.metashrew_height = Some(height),
+            Err(e) => status.errors.push(format!("Metashrew RPC: {}", e)),
+        }
+        status.in_sync = status.lag() == Some(0);
+        
+        Ok(status)
+    }
+    
+    /// Get the height of every configured Metashrew instance
+    ///
+    /// The primary instance comes first, followed by the replicas in
+    /// configuration order. Instances are queried concurrently and each
+    /// result is reported separately so one unreachable replica doesn't hide
+    /// the others.
+    pub async fn get_metashrew_heights(&self) -> Vec<(String, Result<u64>)> {
+        let urls: Vec<&String> = std::iter::once(&self.config.metashrew_rpc_url)
+            .chain(&self.config.metashrew_replica_urls)
+            .collect();
+        
+        let heights = futures_util::future::join_all(urls.iter().map(|url| async move {
+            let result = self.call_url(url, "2.0", "metashrew_height", json!([])).await?;
+            result.as_u64().with_context(|| format!("Invalid block height from {}", url))
+        })).await;
+        
+        urls.into_iter().map(|url| url.to_string()).zip(heights).collect()
+    }
+    
     /// Get spendable UTXOs by address from Metashrew RPC
     pub async fn get_spendables_by_address(&self, address: &str) -> Result<Value> {
         debug!("Getting spendables for address: {}", address);
@@ -171,6 +700,16 @@ impl RpcClient {
         Ok(result)
     }
     
+    /// Get ordinal information (inscriptions, runes, sat ranges) for an outpoint
+    pub async fn get_ord_output(&self, outpoint: &str) -> Result<Value> {
+        debug!("Getting ordinal info for outpoint: {}", outpoint);
+        
+        let result = self._call("ord_output", json!([outpoint])).await?;
+        
+        debug!("Got ordinal info for outpoint: {}", outpoint);
+        Ok(result)
+    }
+    
     /// Get DIESEL token balance from Metashrew RPC
     pub async fn get_protorunes_by_address(&self, address: &str) -> Result<Value> {
         debug!("Getting protorunes for address: {}", address);
@@ -184,9 +723,11 @@ impl RpcClient {
     pub async fn trace_transaction(&self, txid: &str, vout: usize) -> Result<Value> {
         debug!("Tracing transaction: {} vout: {}", txid, vout);
         
-        // In a real implementation, we would reverse the txid bytes
-        // For now, just use the txid as-is
-        let reversed_txid = txid.to_string();
+        // alkanes_trace expects the txid in internal byte order, the reverse
+        // of how txids are displayed
+        let mut txid_bytes = hex::decode(txid).with_context(|| format!("Invalid txid: {}", txid))?;
+        txid_bytes.reverse();
+        let reversed_txid = hex::encode(txid_bytes);
         
         let result = self._call("alkanes_trace", json!([reversed_txid, vout])).await?;
         
@@ -215,42 +756,121 @@ impl RpcClient {
     }
     
     /// Simulate a contract execution
-    pub async fn simulate(&self, block: &str, tx: &str, inputs: &[String]) -> Result<Value> {
-        debug!("Simulating contract execution: {}:{} with {} inputs", block, tx, inputs.len());
+    pub async fn simulate(&self, id: &AlkaneId, inputs: &[String]) -> Result<Value> {
+        debug!("Simulating contract execution: {} with {} inputs", id, inputs.len());
         
         // Create params array with block, tx, and inputs
         let mut params = Vec::new();
-        params.push(json!(block));
-        params.push(json!(tx));
+        params.push(json!(id.block.to_string()));
+        params.push(json!(id.tx.to_string()));
         for input in inputs {
             params.push(json!(input));
         }
         
         let result = self._call("alkanes_simulate", json!(params)).await?;
         
-        debug!("Simulation result for contract: {}:{}", block, tx);
+        debug!("Simulation result for contract: {}", id);
         Ok(result)
     }
     
     /// Get contract metadata
-    pub async fn get_contract_meta(&self, block: &str, tx: &str) -> Result<Value> {
-        debug!("Getting metadata for contract: {}:{}", block, tx);
+    pub async fn get_contract_meta(&self, id: &AlkaneId) -> Result<Value> {
+        debug!("Getting metadata for contract: {}", id);
         
-        let result = self._call("alkanes_meta", json!([block, tx])).await?;
+        let result = self._call("alkanes_meta", json!([id.block.to_string(), id.tx.to_string()])).await?;
         
-        debug!("Got metadata for contract: {}:{}", block, tx);
+        debug!("Got metadata for contract: {}", id);
         Ok(result)
     }
     
+    /// Get contract metadata decoded into its name and method signatures
+    pub async fn get_contract_meta_typed(&self, id: &AlkaneId) -> Result<ContractMeta> {
+        let meta = self.get_contract_meta(id).await?;
+        ContractMeta::from_value(&meta)
+            .with_context(|| format!("Failed to decode metadata of contract {}", id))
+    }
+    
+    /// Get the events a contract emitted from `from_height` on
+    ///
+    /// Needs an indexer serving `alkanes_events`; others answer with a
+    /// method-not-found error.
+    pub async fn get_events(&self, contract: AlkaneId, from_height: u64) -> Result<Vec<AlkaneEvent>> {
+        debug!("Getting events of contract {} from height {}", contract, from_height);
+        
+        let result = self._call(
+            "alkanes_events",
+            json!([contract.block.to_string(), contract.tx.to_string(), from_height])
+        ).await
+            .with_context(|| format!("Failed to get events of contract {}", contract))?;
+        
+        let events = parse_events(&result)
+            .with_context(|| format!("Failed to decode events of contract {}", contract))?;
+        debug!("Got {} events of contract {}", events.len(), contract);
+        Ok(events)
+    }
+    
+    /// Get the protorune holdings of an address with metadata resolved
+    ///
+    /// Balances of the same rune are summed. Metadata is fetched once per
+    /// distinct rune; if it can't be fetched the holding falls back to what
+    /// the balance sheet reports.
+    pub async fn get_portfolio(&self, address: &str) -> Result<Vec<Holding>> {
+        let balances = parse_protorune_balances(&self.get_protorunes_by_address(address).await?);
+        
+        let mut metadata: HashMap<(u128, u128), Value> = HashMap::new();
+        let mut holdings: Vec<(Holding, u128)> = Vec::new();
+        for balance in balances {
+            let id = (balance.block, balance.tx);
+            if !metadata.contains_key(&id) {
+                let meta = match self.get_contract_meta(&AlkaneId::new(balance.block, balance.tx)).await {
+                    Ok(meta) => meta,
+                    Err(e) => {
+                        warn!("Failed to get metadata for {}:{}: {}", balance.block, balance.tx, e);
+                        Value::Null
+                    },
+                };
+                metadata.insert(id, meta);
+            }
+            
+            match holdings.iter_mut().find(|(holding, _)| (holding.block, holding.tx) == id) {
+                Some((_, amount)) => *amount = amount.saturating_add(balance.amount),
+                None => {
+                    let meta = &metadata[&id];
+                    let text = |key: &str| meta.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+                    let holding = Holding {
+                        block: balance.block,
+                        tx: balance.tx,
+                        name: text("name").or(balance.name),
+                        symbol: text("symbol"),
+                        divisibility: meta.get("divisibility")
+                            .and_then(|v| v.as_u64())
+                            .and_then(|d| u8::try_from(d).ok())
+                            .unwrap_or(balance.divisibility),
+                        ..Holding::default()
+                    };
+                    holdings.push((holding, balance.amount));
+                },
+            }
+        }
+        
+        Ok(holdings.into_iter()
+            .map(|(holding, amount)| Holding {
+                amount: amount.to_string(),
+                formatted_amount: format_rune_amount(amount, holding.divisibility),
+                ..holding
+            })
+            .collect())
+    }
+    
     /// Get contract bytecode
-    pub async fn get_bytecode(&self, block: &str, tx: &str) -> Result<String> {
-        debug!("Getting bytecode for contract: {}:{}", block, tx);
+    pub async fn get_bytecode(&self, id: &AlkaneId) -> Result<String> {
+        debug!("Getting bytecode for contract: {}", id);
         
         let result = self._call(
             "metashrew_view",
             json!([{
                 "method": "getbytecode",
-                "params": [block, tx]
+                "params": [id.block.to_string(), id.tx.to_string()]
             }])
         ).await?;
         
@@ -258,19 +878,72 @@ impl RpcClient {
             .context("Invalid bytecode response")?
             .to_string();
         
-        debug!("Got bytecode for contract: {}:{}", block, tx);
+        debug!("Got bytecode for contract: {}", id);
         Ok(bytecode)
     }
     
-    /// Get transaction hex by transaction ID
-    pub async fn get_transaction_hex(&self, txid: &str) -> Result<String> {
-        debug!("Getting transaction hex for txid: {}", txid);
+    /// Get the alkane ID a contract deployed now would receive
+    ///
+    /// Deployments take `2:n` where `n` is the indexer's current sequence
+    /// number. This is only a prediction: the sequence advances with every
+    /// deployment indexed before ours, so under contention, or if the deploy
+    /// lands in a later block than expected, the actual ID differs.
+    pub async fn get_next_alkane_id(&self) -> Result<AlkaneId> {
+        debug!("Getting the alkanes sequence number");
         
         let result = self._call(
-            "esplora_gettransaction",
-            json!([txid])
+            "metashrew_view",
+            json!([{
+                "method": "sequence",
+                "params": []
+            }])
         ).await?;
         
+        let sequence = value_to_u128(&result)
+            .context("Invalid sequence response")?;
+        
+        let id = AlkaneId::new(DEPLOYED_ALKANE_BLOCK, sequence);
+        debug!("Next alkane ID: {}", id);
+        Ok(id)
+    }
+    
+    /// Get a transaction by transaction ID, decoded
+    ///
+    /// Tries the esplora `esplora_tx::hex` method first and falls back to
+    /// Bitcoin Core's `btc_getrawtransaction`, so either backend alone is
+    /// enough. Fetched transactions are cached, as a txid always names the
+    /// same transaction.
+    pub async fn get_transaction(&self, txid: &str) -> Result<Transaction> {
+        let parsed: Option<Txid> = txid.parse().ok();
+        if let Some(tx) = parsed.and_then(|txid| self.transactions.lock().unwrap().get(&txid)) {
+            debug!("Using cached transaction {}", txid);
+            return Ok(tx);
+        }
+        
+        let tx_hex = self.get_transaction_hex(txid).await
+            .with_context(|| format!("Failed to fetch transaction {}", txid))?;
+        let bytes = hex::decode(tx_hex.trim())
+            .with_context(|| format!("Invalid hex for transaction {}", txid))?;
+        let tx: Transaction = deserialize(&bytes)
+            .with_context(|| format!("Failed to deserialize transaction {}", txid))?;
+        
+        self.transactions.lock().unwrap().insert(tx.txid(), tx.clone());
+        Ok(tx)
+    }
+    
+    /// Get transaction hex by transaction ID from esplora, then Bitcoin Core
+    async fn get_transaction_hex(&self, txid: &str) -> Result<String> {
+        debug!("Getting transaction hex for txid: {}", txid);
+        
+        let result = match self._call("esplora_tx::hex", json!([txid])).await {
+            Ok(result) => result,
+            Err(e) => {
+                debug!("esplora_tx::hex failed for {}, trying btc_getrawtransaction: {}", txid, e);
+                self._call("btc_getrawtransaction", json!([txid])).await
+                    .map_err(|fallback| anyhow!("{}; btc_getrawtransaction also failed: {}", e, fallback))?
+            },
+        };
+        
         let tx_hex = result.as_str()
             .context("Invalid transaction hex response")?
             .to_string();
@@ -279,11 +952,371 @@ impl RpcClient {
         Ok(tx_hex)
     }
     
+    /// Get the outputs spent by each input of `tx`, in input order
+    ///
+    /// Each previous transaction is fetched once, however many of its
+    /// outputs are spent. Fails for coinbase transactions, which spend no
+    /// outputs.
+    pub async fn get_prevouts(&self, tx: &Transaction) -> Result<Vec<TxOut>> {
+        if tx.is_coin_base() {
+            return Err(anyhow!("Coinbase transaction {} spends no outputs", tx.txid()));
+        }
+        
+        let mut previous: HashMap<Txid, Transaction> = HashMap::new();
+        let mut prevouts = Vec::with_capacity(tx.input.len());
+        for input in &tx.input {
+            let outpoint = input.previous_output;
+            if !previous.contains_key(&outpoint.txid) {
+                let fetched = self.get_transaction(&outpoint.txid.to_string()).await?;
+                previous.insert(outpoint.txid, fetched);
+            }
+            let prevout = previous[&outpoint.txid].output.get(outpoint.vout as usize)
+                .ok_or_else(|| anyhow!("Transaction {} has no output {}", outpoint.txid, outpoint.vout))?;
+            prevouts.push(prevout.clone());
+        }
+        Ok(prevouts)
+    }
+    
+    /// Get the confirmation status of a transaction
+    pub async fn get_tx_status(&self, txid: &str) -> Result<TxStatus> {
+        debug!("Getting status for transaction: {}", txid);
+        
+        let result = self._call("esplora_tx::status", json!([txid])).await?;
+        
+        let status = TxStatus {
+            confirmed: result.get("confirmed").and_then(|v| v.as_bool()).unwrap_or(false),
+            block_height: result.get("block_height").and_then(|v| v.as_u64()),
+            block_hash: result.get("block_hash").and_then(|v| v.as_str()).map(|s| s.to_string()),
+        };
+        
+        debug!("Transaction {} confirmed: {}", txid, status.confirmed);
+        Ok(status)
+    }
+    
+    /// Check whether bitcoind would accept a transaction without broadcasting it
+    pub async fn test_mempool_accept(&self, tx_hex: &str) -> Result<MempoolAcceptResult> {
+        debug!("Testing mempool acceptance");
+        
+        let result = self._call("btc_testmempoolaccept", json!([[tx_hex]])).await?;
+        
+        // bitcoind returns one entry per submitted transaction
+        let entry = result.as_array()
+            .and_then(|entries| entries.first())
+            .context("Invalid testmempoolaccept response")?;
+        
+        let accept = MempoolAcceptResult {
+            allowed: entry.get("allowed").and_then(|v| v.as_bool()).unwrap_or(false),
+            reject_reason: entry.get("reject-reason").and_then(|v| v.as_str()).map(|s| s.to_string()),
+            fees: entry.get("fees")
+                .and_then(|fees| fees.get("base"))
+                .and_then(|v| v.as_f64())
+                .map(|btc| (btc * 100_000_000.0).round() as u64),
+        };
+        
+        debug!("Mempool acceptance: {:?}", accept);
+        Ok(accept)
+    }
+    
+    /// Broadcast a raw transaction, returning its transaction ID
+    pub async fn send_raw_transaction(&self, tx_hex: &str) -> Result<String> {
+        debug!("Sending raw transaction");
+
+        let result = self._call("btc_sendrawtransaction", json!([tx_hex])).await?;
+
+        let txid = result.as_str()
+            .context("Invalid sendrawtransaction response")?
+            .to_string();
+
+        debug!("Sent transaction: {}", txid);
+        Ok(txid)
+    }
+
+    /// Estimate the fee rate in sat/vB needed to confirm within `target` blocks
+    pub async fn estimate_smart_fee(&self, target: u16) -> Result<f64> {
+        debug!("Estimating smart fee for {} block(s)", target);
+        
+        let result = self._call("btc_estimatesmartfee", json!([target])).await?;
+        
+        // bitcoind reports BTC/kvB, and omits the rate when it lacks data
+        let btc_per_kvb = result.get("feerate").and_then(|v| v.as_f64()).ok_or_else(|| {
+            let errors = result.get("errors").map(|e| e.to_string()).unwrap_or_default();
+            anyhow!("No fee estimate available for {} block(s) {}", target, errors)
+        })?;
+        let fee_rate = btc_per_kvb * 100_000_000.0 / 1000.0;
+        
+        debug!("Estimated fee rate: {} sat/vB", fee_rate);
+        Ok(fee_rate)
+    }
+    
+    /// Get the mempool fee histogram as `(feerate, vsize)` buckets
+    ///
+    /// Buckets are sorted by fee rate, highest first, matching esplora's
+    /// `fee_histogram` where each bucket's vsize counts transactions paying at
+    /// least that fee rate but less than the previous bucket's.
+    pub async fn get_mempool_fee_histogram(&self) -> Result<Vec<(f64, u64)>> {
+        debug!("Getting mempool fee histogram");
+        
+        let result = self._call("esplora_mempool", json!([])).await?;
+        let histogram = parse_fee_histogram(&result)?;
+        
+        debug!("Got {} fee histogram buckets", histogram.len());
+        Ok(histogram)
+    }
     
     /// Get the next request ID
+    ///
+    /// IDs are unique within a client instance only; other clients start
+    /// from a different random base.
     fn next_request_id(&self) -> u64 {
-        // Use atomic fetch_add for thread safety
-        self.request_id.fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+        self.next_request_ids(1)
+    }
+    
+    /// Reserve `count` contiguous request IDs, returning the first
+    fn next_request_ids(&self, count: u64) -> u64 {
+        // A single fetch_add keeps the block contiguous under concurrency
+        self.request_id.fetch_add(count, std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+/// Parse a u128 from a JSON number, decimal string, or `0x`-prefixed hex string
+pub fn value_to_u128(value: &Value) -> Option<u128> {
+    match value {
+        Value::Number(n) => n.as_u64().map(u128::from),
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => u128::from_str_radix(hex, 16).ok(),
+            None => s.parse::<u128>().ok(),
+        },
+        _ => None,
+    }
+}
+
+/// Parse a transaction from its hex serialization
+fn parse_transaction_hex(tx_hex: &str) -> Result<Transaction> {
+    let bytes = hex::decode(tx_hex.trim()).context("Invalid transaction hex")?;
+    deserialize(&bytes).context("Failed to deserialize transaction")
+}
+
+/// Parse the transactions of a `btc_getblock` verbosity 2 response
+///
+/// The response as a whole must list its transactions with their txids;
+/// a transaction whose hex doesn't parse only gets an error of its own.
+fn parse_verbose_block(block: &Value) -> Result<Vec<(Txid, Result<Transaction>)>> {
+    block["tx"].as_array()
+        .context("Invalid block response: missing transactions")?
+        .iter()
+        .map(|entry| {
+            let txid: Txid = entry["txid"].as_str()
+                .and_then(|txid| txid.parse().ok())
+                .with_context(|| format!("Invalid txid in block: {}", entry["txid"]))?;
+            let tx = entry["hex"].as_str()
+                .context("Missing transaction hex")
+                .and_then(parse_transaction_hex)
+                .with_context(|| format!("Invalid transaction {} in block", txid));
+            Ok((txid, tx))
+        })
+        .collect()
+}
+
+/// Parse protorune balances from an `alkanes_protorunesby*` response
+///
+/// Accepts either a bare array of balance entries or an object carrying them
+/// under `balance_sheet.entries`, `balanceSheet.entries` or `entries`. Each
+/// entry carries its ID either directly (`id`) or under `rune.id`, and its
+/// amount as `balance` or `amount`. Entries that can't be parsed are skipped.
+pub fn parse_protorune_balances(value: &Value) -> Vec<ProtoruneBalance> {
+    let entries = value.as_array()
+        .or_else(|| value.pointer("/balance_sheet/entries").and_then(|v| v.as_array()))
+        .or_else(|| value.pointer("/balanceSheet/entries").and_then(|v| v.as_array()))
+        .or_else(|| value.get("entries").and_then(|v| v.as_array()));
+    
+    let entries = match entries {
+        Some(entries) => entries,
+        None => return Vec::new(),
+    };
+    
+    entries.iter()
+        .filter_map(|entry| {
+            let rune = entry.get("rune").unwrap_or(entry);
+            let id = rune.get("id")?;
+            let amount = entry.get("balance").or_else(|| entry.get("amount"))?;
+            
+            Some(ProtoruneBalance {
+                block: value_to_u128(id.get("block")?)?,
+                tx: value_to_u128(id.get("tx")?)?,
+                name: rune.get("name").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                divisibility: rune.get("divisibility")
+                    .and_then(|v| v.as_u64())
+                    .and_then(|d| u8::try_from(d).ok())
+                    .unwrap_or(0),
+                amount: value_to_u128(amount)?,
+            })
+        })
+        .collect()
+}
+
+/// Parse a fee histogram from either an array of `[feerate, vsize]` pairs,
+/// an object with a `fee_histogram` field, or an object mapping fee rates to vsizes
+fn parse_fee_histogram(value: &Value) -> Result<Vec<(f64, u64)>> {
+    let mut histogram = match value {
+        Value::Array(pairs) => pairs.iter()
+            .map(|pair| {
+                let feerate = pair.get(0).and_then(|v| v.as_f64());
+                let vsize = pair.get(1).and_then(|v| v.as_u64());
+                feerate.zip(vsize).context("Invalid fee histogram bucket")
+            })
+            .collect::<Result<Vec<_>>>()?,
+        Value::Object(map) => match map.get("fee_histogram") {
+            Some(inner) => return parse_fee_histogram(inner),
+            None => map.iter()
+                .map(|(feerate, vsize)| {
+                    let feerate = feerate.parse::<f64>().ok();
+                    feerate.zip(vsize.as_u64()).context("Invalid fee histogram bucket")
+                })
+                .collect::<Result<Vec<_>>>()?,
+        },
+        _ => return Err(anyhow!("Unexpected fee histogram response: {}", value)),
+    };
+    
+    histogram.sort_by(|a, b| b.0.total_cmp(&a.0));
+    Ok(histogram)
+}
+
+/// In-process JSON-RPC server for tests
+///
+/// Answers each method with a canned result and records every call so tests
+/// can assert which RPCs were made and in what order.
+#[cfg(test)]
+pub(crate) mod mock {
+    use super::RpcConfig;
+    use axum::{extract::{ConnectInfo, State}, routing::post, Json, Router};
+    use serde_json::{json, Value};
+    use std::collections::{HashMap, HashSet};
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
+    
+    /// Shared mock state
+    #[derive(Default)]
+    struct MockState {
+        /// Canned results by method name
+        responses: Mutex<HashMap<String, Value>>,
+        /// Canned results by method name and exact params, checked first
+        param_responses: Mutex<HashMap<(String, String), Value>>,
+        /// Errors as (code, message) by method name, checked before results
+        errors: Mutex<HashMap<String, (i64, String)>>,
+        /// Calls received as (method, params)
+        calls: Mutex<Vec<(String, Value)>>,
+        /// Client addresses seen, one per connection
+        peers: Mutex<HashSet<SocketAddr>>,
+    }
+    
+    /// A running mock RPC server
+    pub struct MockRpcServer {
+        /// Base URL of the server
+        pub url: String,
+        /// Shared mock state
+        state: Arc<MockState>,
+    }
+    
+    async fn handle(
+        State(state): State<Arc<MockState>>,
+        ConnectInfo(peer): ConnectInfo<SocketAddr>,
+        Json(request): Json<Value>,
+    ) -> Json<Value> {
+        state.peers.lock().unwrap().insert(peer);
+        match request {
+            Value::Array(requests) => Json(Value::Array(
+                requests.iter().map(|request| respond(&state, request)).collect()
+            )),
+            request => Json(respond(&state, &request)),
+        }
+    }
+    
+    fn respond(state: &MockState, request: &Value) -> Value {
+        let method = request["method"].as_str().unwrap_or_default().to_string();
+        let id = request["id"].clone();
+        state.calls.lock().unwrap().push((method.clone(), request["params"].clone()));
+        
+        if let Some((code, message)) = state.errors.lock().unwrap().get(&method).cloned() {
+            return json!({ "result": null, "error": { "code": code, "message": message }, "id": id });
+        }
+        let by_params = state.param_responses.lock().unwrap()
+            .get(&(method.clone(), request["params"].to_string()))
+            .cloned();
+        let result = by_params.or_else(|| state.responses.lock().unwrap().get(&method).cloned());
+        
+        match result {
+            Some(result) => json!({ "result": result, "error": null, "id": id }),
+            None => json!({
+                "result": null,
+                "error": { "code": -32601, "message": format!("Method not found: {}", method) },
+                "id": id,
+            }),
+        }
+    }
+    
+    impl MockRpcServer {
+        /// Start a mock server on an ephemeral local port
+        pub async fn start() -> Self {
+            let state = Arc::new(MockState::default());
+            let app = Router::new()
+                .route("/", post(handle))
+                .with_state(Arc::clone(&state));
+            
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/", listener.local_addr().unwrap());
+            tokio::spawn(async move {
+                axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
+            });
+            
+            Self { url, state }
+        }
+        
+        /// Set the result returned for a method, replacing any error
+        pub fn set(&self, method: &str, result: Value) {
+            self.state.errors.lock().unwrap().remove(method);
+            self.state.responses.lock().unwrap().insert(method.to_string(), result);
+        }
+        
+        /// Make every call to a method fail with a JSON-RPC error
+        pub fn set_error(&self, method: &str, code: i64, message: &str) {
+            self.state.errors.lock().unwrap().insert(method.to_string(), (code, message.to_string()));
+        }
+        
+        /// Set the result returned for a method when called with exactly `params`
+        pub fn set_with_params(&self, method: &str, params: Value, result: Value) {
+            self.state.param_responses.lock().unwrap()
+                .insert((method.to_string(), params.to_string()), result);
+        }
+        
+        /// Remove the canned result for a method so calls to it fail
+        pub fn unset(&self, method: &str) {
+            self.state.errors.lock().unwrap().remove(method);
+            self.state.responses.lock().unwrap().remove(method);
+        }
+        
+        /// Get all calls received so far
+        pub fn calls(&self) -> Vec<(String, Value)> {
+            self.state.calls.lock().unwrap().clone()
+        }
+        
+        /// Get the number of connections calls were received on
+        pub fn connections(&self) -> usize {
+            self.state.peers.lock().unwrap().len()
+        }
+        
+        /// Get the names of all methods called so far, in order
+        pub fn methods(&self) -> Vec<String> {
+            self.calls().into_iter().map(|(method, _)| method).collect()
+        }
+        
+        /// RPC configuration pointing both endpoints at this server
+        pub fn config(&self) -> RpcConfig {
+            RpcConfig {
+                bitcoin_rpc_url: self.url.clone(),
+                metashrew_rpc_url: self.url.clone(),
+                ..Default::default()
+            }
+        }
     }
 }
 
@@ -296,6 +1329,7 @@ mod tests {
         let config = RpcConfig {
             bitcoin_rpc_url: "http://localhost:18332".to_string(),
             metashrew_rpc_url: "http://localhost:8080".to_string(),
+            ..Default::default()
         };
         
         let client = RpcClient::new(config.clone());
@@ -303,4 +1337,463 @@ mod tests {
         assert_eq!(client.config.bitcoin_rpc_url, config.bitcoin_rpc_url);
         assert_eq!(client.config.metashrew_rpc_url, config.metashrew_rpc_url);
     }
+    
+    #[test]
+    fn test_endpoint_routing() {
+        let default = RpcClient::new(RpcConfig {
+            bitcoin_rpc_url: "http://bitcoin".to_string(),
+            metashrew_rpc_url: "http://metashrew".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(default.endpoint("btc_getblockcount"), ("http://bitcoin", "1.0"));
+        assert_eq!(default.endpoint("esplora_tx::hex"), ("http://metashrew", "2.0"));
+        
+        let mut method_overrides = HashMap::new();
+        method_overrides.insert("metashrew_height".to_string(), "http://gateway".to_string());
+        method_overrides.insert("btc_sendrawtransaction".to_string(), "http://broadcaster".to_string());
+        let configured = RpcClient::new(RpcConfig {
+            bitcoin_rpc_url: "http://bitcoin".to_string(),
+            metashrew_rpc_url: "http://metashrew".to_string(),
+            esplora_rpc_url: Some("http://esplora".to_string()),
+            method_overrides,
+            ..Default::default()
+        });
+        assert_eq!(configured.endpoint("esplora_tx::hex"), ("http://esplora", "2.0"));
+        assert_eq!(configured.endpoint("metashrew_height"), ("http://gateway", "2.0"));
+        assert_eq!(configured.endpoint("btc_sendrawtransaction"), ("http://broadcaster", "1.0"));
+        assert_eq!(configured.endpoint("btc_getblockcount"), ("http://bitcoin", "1.0"));
+        assert_eq!(configured.endpoint("alkanes_trace"), ("http://metashrew", "2.0"));
+    }
+    
+    #[tokio::test]
+    async fn test_sequential_calls_reuse_connections() {
+        const CALLS: usize = 20;
+        
+        let pooled_server = mock::MockRpcServer::start().await;
+        pooled_server.set("btc_getblockcount", json!(840000));
+        let pooled = RpcClient::new(pooled_server.config());
+        for _ in 0..CALLS {
+            pooled.get_block_count().await.unwrap();
+        }
+        assert_eq!(pooled_server.connections(), 1);
+        
+        // Without idle connections every call connects anew
+        let unpooled_server = mock::MockRpcServer::start().await;
+        unpooled_server.set("btc_getblockcount", json!(840000));
+        let unpooled = RpcClient::new(RpcConfig { pool_max_idle_per_host: 0, ..unpooled_server.config() });
+        for _ in 0..CALLS {
+            unpooled.get_block_count().await.unwrap();
+        }
+        assert_eq!(unpooled_server.connections(), CALLS);
+    }
+    
+    #[test]
+    fn test_request_ids_are_unique() {
+        let client = RpcClient::new(RpcConfig::default());
+        
+        let first = client.next_request_id();
+        let second = client.next_request_id();
+        assert_ne!(first, second);
+        
+        // A batch gets a contiguous block that later calls don't reuse
+        let batch = client.next_request_ids(3);
+        assert_eq!(batch, second + 1);
+        assert_eq!(client.next_request_id(), batch + 3);
+    }
+    
+    #[tokio::test]
+    async fn test_get_prevouts() {
+        use bdk::bitcoin::{OutPoint, ScriptBuf, TxIn};
+        use bdk::bitcoin::absolute::LockTime;
+        use bdk::bitcoin::consensus::encode::serialize;
+        
+        let server = mock::MockRpcServer::start().await;
+        let client = RpcClient::new(server.config());
+        let previous = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![
+                TxOut { value: 1_000, script_pubkey: ScriptBuf::new() },
+                TxOut { value: 2_000, script_pubkey: ScriptBuf::new() },
+            ],
+        };
+        let txid = previous.txid();
+        server.set_with_params("esplora_tx::hex", json!([txid.to_string()]), json!(hex::encode(serialize(&previous))));
+        let spend = |vout| TxIn { previous_output: OutPoint { txid, vout }, ..TxIn::default() };
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![spend(1), spend(0)],
+            output: Vec::new(),
+        };
+        
+        let values: Vec<u64> = client.get_prevouts(&tx).await.unwrap().iter().map(|prevout| prevout.value).collect();
+        assert_eq!(values, vec![2_000, 1_000]);
+        // The shared previous transaction is fetched once
+        assert_eq!(server.methods(), vec!["esplora_tx::hex"]);
+        
+        let missing = Transaction { input: vec![spend(2)], ..tx.clone() };
+        assert!(client.get_prevouts(&missing).await.is_err());
+        assert!(client.get_prevouts(&previous).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_get_transaction_falls_back_and_caches() {
+        use bdk::bitcoin::consensus::encode::serialize;
+        use bdk::bitcoin::TxIn;
+        
+        let server = mock::MockRpcServer::start().await;
+        let client = RpcClient::new(server.config());
+        let tx = Transaction {
+            version: 2,
+            lock_time: bdk::bitcoin::absolute::LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut { value: 546, script_pubkey: bdk::bitcoin::ScriptBuf::new() }],
+        };
+        let txid = tx.txid().to_string();
+        
+        // Esplora doesn't know the transaction, Bitcoin Core does
+        server.set_error("esplora_tx::hex", 404, "Transaction not found");
+        server.set_with_params("btc_getrawtransaction", json!([txid]), json!(hex::encode(serialize(&tx))));
+        assert_eq!(client.get_transaction(&txid).await.unwrap(), tx);
+        assert_eq!(server.methods(), vec!["esplora_tx::hex", "btc_getrawtransaction"]);
+        
+        // Served from the cache the second time
+        assert_eq!(client.get_transaction(&txid).await.unwrap(), tx);
+        assert_eq!(server.methods().len(), 2);
+        
+        // Both backends failing reports both errors
+        let error = client.get_transaction(&format!("{:064x}", 1)).await.unwrap_err();
+        assert!(format!("{:#}", error).contains("btc_getrawtransaction also failed"));
+    }
+    
+    #[tokio::test]
+    async fn test_test_mempool_accept() {
+        let server = mock::MockRpcServer::start().await;
+        let client = RpcClient::new(server.config());
+        
+        server.set("btc_testmempoolaccept", json!([{
+            "txid": "ab".repeat(32),
+            "allowed": true,
+            "vsize": 141,
+            "fees": { "base": 0.00000282 }
+        }]));
+        let accepted = client.test_mempool_accept("00").await.unwrap();
+        assert_eq!(accepted, MempoolAcceptResult { allowed: true, reject_reason: None, fees: Some(282) });
+        
+        server.set("btc_testmempoolaccept", json!([{
+            "txid": "ab".repeat(32),
+            "allowed": false,
+            "reject-reason": "min relay fee not met"
+        }]));
+        let rejected = client.test_mempool_accept("00").await.unwrap();
+        assert!(!rejected.allowed);
+        assert_eq!(rejected.reject_reason.as_deref(), Some("min relay fee not met"));
+        
+        assert_eq!(server.calls()[0].1, json!([["00"]]));
+    }
+    
+    #[tokio::test]
+    async fn test_get_next_alkane_id() {
+        let server = mock::MockRpcServer::start().await;
+        let client = RpcClient::new(server.config());
+        
+        server.set("metashrew_view", json!("0x1f"));
+        assert_eq!(client.get_next_alkane_id().await.unwrap(), AlkaneId::new(2, 31));
+        assert_eq!(server.calls()[0].1, json!([{ "method": "sequence", "params": [] }]));
+        
+        server.set("metashrew_view", json!(null));
+        assert!(client.get_next_alkane_id().await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_get_events() {
+        let server = mock::MockRpcServer::start().await;
+        let client = RpcClient::new(server.config());
+        
+        server.set("alkanes_events", json!([{ "topic": "4d696e74", "data": "0a" }, { "topic": "4d696e74" }]));
+        let events = client.get_events(AlkaneId::new(2, 0), 840000).await.unwrap();
+        assert_eq!(events, vec![
+            AlkaneEvent { topic: b"Mint".to_vec(), data: vec![10] },
+            AlkaneEvent { topic: b"Mint".to_vec(), data: vec![] },
+        ]);
+        assert_eq!(server.calls()[0].1, json!(["2", "0", 840000]));
+        
+        // Indexers without event support
+        server.unset("alkanes_events");
+        assert!(client.get_events(AlkaneId::new(2, 0), 840000).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_check_sync_status() {
+        let server = mock::MockRpcServer::start().await;
+        let client = RpcClient::new(server.config());
+        
+        server.set("btc_getblockcount", json!(100));
+        server.set("metashrew_height", json!(101));
+        let status = client.check_sync_status().await.unwrap();
+        assert_eq!(status, SyncStatus { bitcoin_height: 100, metashrew_height: 101, indexer_lag: 0, in_sync: true });
+        
+        server.set("metashrew_height", json!(97));
+        let status = client.check_sync_status().await.unwrap();
+        assert_eq!(status.indexer_lag, 4);
+        assert!(!status.in_sync);
+        
+        // An indexer ahead of bitcoind is out of sync too
+        assert_eq!(SyncStatus::from_heights(100, 103).indexer_lag, -2);
+        
+        server.unset("metashrew_height");
+        assert!(client.check_sync_status().await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_health() {
+        let server = mock::MockRpcServer::start().await;
+        let client = RpcClient::new(server.config());
+        
+        server.set("btc_getblockcount", json!(100));
+        server.set("metashrew_height", json!(101));
+        let status = client.health().await.unwrap();
+        assert_eq!(status.bitcoin_height, Some(100));
+        assert!(status.in_sync);
+        assert!(status.is_healthy(0));
+        
+        server.set("metashrew_height", json!(95));
+        let status = client.health().await.unwrap();
+        assert!(!status.in_sync);
+        assert_eq!(status.lag(), Some(6));
+        assert!(status.is_healthy(6) && !status.is_healthy(5));
+        
+        server.unset("metashrew_height");
+        let status = client.health().await.unwrap();
+        assert_eq!(status.metashrew_height, None);
+        assert_eq!(status.errors.len(), 1);
+        assert!(!status.is_healthy(u64::MAX));
+    }
+    
+    #[tokio::test]
+    async fn test_scan_block_runestones() {
+        use bdk::bitcoin::{ScriptBuf, TxIn, TxOut};
+        
+        let server = mock::MockRpcServer::start().await;
+        let client = RpcClient::new(server.config());
+        let tx_with_output = |script_pubkey: ScriptBuf| Transaction {
+            version: 2,
+            lock_time: bdk::bitcoin::absolute::LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut { value: 546, script_pubkey }],
+        };
+        let mint = tx_with_output(crate::runestone::Runestone::new_diesel().encipher().unwrap());
+        let plain = tx_with_output(ScriptBuf::from_bytes(vec![0; 22]));
+        let txids = [plain.txid().to_string(), mint.txid().to_string()];
+        
+        server.set("btc_getblockhash", json!("00ff"));
+        server.set_with_params("esplora_block::txids", json!(["00ff"]), json!(txids));
+        for tx in [&plain, &mint] {
+            server.set_with_params("esplora_tx::hex", json!([tx.txid().to_string()]),
+                json!(hex::encode(bdk::bitcoin::consensus::encode::serialize(tx))));
+        }
+        
+        assert_eq!(client.get_block_txids(840000).await.unwrap(), txids);
+        let runestones = client.scan_block_runestones(840000).await.unwrap();
+        assert_eq!(runestones.len(), 1);
+        assert_eq!(runestones[0].0, txids[1]);
+        assert_eq!(runestones[0].1.txid, txids[1]);
+        
+        // A transaction that can't be fetched fails the scan
+        server.set_with_params("esplora_block::txids", json!(["00ff"]), json!([txids[0], "ab".repeat(32)]));
+        assert!(client.scan_block_runestones(840000).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_decode_block_at_height() {
+        use crate::runestone_enhanced::decode_block_at_height;
+        use bdk::bitcoin::{ScriptBuf, TxIn, TxOut};
+        
+        let server = mock::MockRpcServer::start().await;
+        let client = RpcClient::new(server.config());
+        let tx_with_output = |script_pubkey: ScriptBuf| Transaction {
+            version: 2,
+            lock_time: bdk::bitcoin::absolute::LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut { value: 546, script_pubkey }],
+        };
+        let mint = tx_with_output(crate::runestone::Runestone::new_diesel().encipher().unwrap());
+        let plain = tx_with_output(ScriptBuf::from_bytes(vec![0; 22]));
+        let tx_hex = |tx: &Transaction| json!(hex::encode(bdk::bitcoin::consensus::encode::serialize(tx)));
+        let missing = "ab".repeat(32);
+        
+        // Without btc_getblock, transactions come from esplora in a batch
+        server.set("btc_getblockhash", json!("00ff"));
+        server.set_with_params("esplora_block::txids", json!(["00ff"]),
+            json!([plain.txid().to_string(), mint.txid().to_string(), missing]));
+        for tx in [&plain, &mint] {
+            server.set_with_params("esplora_tx::hex", json!([tx.txid().to_string()]), tx_hex(tx));
+        }
+        
+        let txs = client.get_block_transactions(840000).await.unwrap();
+        assert_eq!(txs.len(), 3);
+        assert_eq!(txs[1].1.as_ref().unwrap().txid(), mint.txid());
+        assert!(txs[2].1.is_err());
+        
+        // The unfetchable transaction is reported along with the runestone
+        let decoded = decode_block_at_height(&client, 840000).await.unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].0, mint.txid());
+        assert_eq!(decoded[0].1.as_ref().unwrap().txid, mint.txid().to_string());
+        assert_eq!(decoded[1].0.to_string(), missing);
+        assert!(decoded[1].1.is_err());
+        
+        // A verbose block is used when available, and bad hex stays per-tx
+        server.set_with_params("btc_getblock", json!(["00ff", 2]), json!({ "tx": [
+            { "txid": plain.txid().to_string(), "hex": tx_hex(&plain) },
+            { "txid": mint.txid().to_string(), "hex": tx_hex(&mint) },
+            { "txid": missing, "hex": "00" },
+        ] }));
+        let decoded = decode_block_at_height(&client, 840000).await.unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert!(decoded[0].1.is_ok());
+        assert!(decoded[1].1.as_ref().unwrap_err().to_string().contains("Invalid transaction"));
+        
+        server.set_with_params("btc_getblock", json!(["00ff", 2]), json!({ "hash": "00ff" }));
+        assert!(client.get_block_transactions(840000).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_scan_block_range() {
+        use bdk::bitcoin::{ScriptBuf, TxIn, TxOut};
+        
+        let server = mock::MockRpcServer::start().await;
+        let client = RpcClient::new(server.config());
+        let tx_with_output = |lock_time: u32, script_pubkey: ScriptBuf| Transaction {
+            version: 2,
+            lock_time: bdk::bitcoin::absolute::LockTime::from_consensus(lock_time),
+            input: vec![TxIn::default()],
+            output: vec![TxOut { value: 546, script_pubkey }],
+        };
+        let diesel = crate::runestone::Runestone::new_diesel().encipher().unwrap();
+        let blocks = [
+            vec![tx_with_output(0, diesel.clone()), tx_with_output(0, ScriptBuf::from_bytes(vec![0; 22]))],
+            vec![],
+            vec![tx_with_output(1, diesel)],
+        ];
+        for (height, txs) in (100u64..).zip(&blocks) {
+            let hash = format!("{:064x}", height);
+            server.set_with_params("btc_getblockhash", json!([height]), json!(hash));
+            let txs: Vec<Value> = txs.iter()
+                .map(|tx| json!({ "txid": tx.txid().to_string(), "hex": hex::encode(bdk::bitcoin::consensus::encode::serialize(tx)) }))
+                .collect();
+            server.set_with_params("btc_getblock", json!([hash, 2]), json!({ "tx": txs }));
+        }
+        server.set("alkanes_trace", json!([{ "event": "return", "data": { "status": "success" } }]));
+        
+        // Every block is reported in order, mints traced at their virtual output
+        let (_cancel, cancelled) = watch::channel(false);
+        let mut scanned = Vec::new();
+        client.scan_block_range(100, 102, cancelled.clone(), |height, traces| {
+            scanned.push((height, traces));
+            Ok(())
+        }).await.unwrap();
+        let heights: Vec<u64> = scanned.iter().map(|(height, _)| *height).collect();
+        assert_eq!(heights, vec![100, 101, 102]);
+        assert_eq!(scanned[0].1, vec![(format!("{}:2", blocks[0][0].txid()), json!([{ "event": "return", "data": { "status": "success" } }]))]);
+        assert!(scanned[1].1.is_empty());
+        assert_eq!(scanned[2].1[0].0, format!("{}:2", blocks[2][0].txid()));
+        
+        // Cancelling from the callback stops after the current block
+        let (cancel, cancelled) = watch::channel(false);
+        let mut heights = Vec::new();
+        client.scan_block_range(100, 102, cancelled, |height, _| {
+            heights.push(height);
+            cancel.send(true).unwrap();
+            Ok(())
+        }).await.unwrap();
+        assert_eq!(heights, vec![100]);
+        
+        // Callback and trace errors fail the scan, as does an empty range
+        let (_cancel, cancelled) = watch::channel(false);
+        assert!(client.scan_block_range(100, 102, cancelled.clone(), |_, _| Err(anyhow!("disk full"))).await.is_err());
+        assert!(client.scan_block_range(102, 100, cancelled.clone(), |_, _| Ok(())).await.is_err());
+        server.unset("alkanes_trace");
+        let mut heights = Vec::new();
+        let error = client.scan_block_range(100, 102, cancelled, |height, _| {
+            heights.push(height);
+            Ok(())
+        }).await.unwrap_err();
+        assert!(format!("{:#}", error).contains("Failed to trace block 100"));
+        assert!(heights.is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_get_portfolio() {
+        let server = mock::MockRpcServer::start().await;
+        let client = RpcClient::new(server.config());
+        
+        server.set("alkanes_protorunesbyaddress", json!([
+            { "rune": { "id": { "block": "2", "tx": "0" }, "name": "DIESEL" }, "balance": "150000000" },
+            { "rune": { "id": { "block": "2", "tx": "7" } }, "balance": "5" },
+            { "rune": { "id": { "block": "2", "tx": "0" }, "name": "DIESEL" }, "balance": "50000000" },
+        ]));
+        server.set_with_params("alkanes_meta", json!(["2", "0"]), json!({ "name": "Diesel", "symbol": "DIESEL", "divisibility": 8 }));
+        
+        let portfolio = client.get_portfolio("bc1qtest").await.unwrap();
+        assert_eq!(portfolio.len(), 2);
+        assert_eq!(portfolio[0].name.as_deref(), Some("Diesel"));
+        assert_eq!(portfolio[0].symbol.as_deref(), Some("DIESEL"));
+        assert_eq!(portfolio[0].amount, "200000000");
+        assert_eq!(portfolio[0].formatted_amount, "2");
+        // Missing metadata falls back to the balance sheet
+        assert_eq!(portfolio[1].name, None);
+        assert_eq!(portfolio[1].formatted_amount, "5");
+        
+        // One metadata lookup per distinct rune
+        let meta_calls = server.methods().iter().filter(|m| *m == "alkanes_meta").count();
+        assert_eq!(meta_calls, 2);
+    }
+    
+    #[tokio::test]
+    async fn test_estimate_smart_fee() {
+        let server = mock::MockRpcServer::start().await;
+        let client = RpcClient::new(server.config());
+        
+        server.set("btc_estimatesmartfee", json!({ "feerate": 0.00012, "blocks": 6 }));
+        let fee_rate = client.estimate_smart_fee(6).await.unwrap();
+        assert!((fee_rate - 12.0).abs() < 1e-9);
+        assert_eq!(server.calls()[0].1, json!([6]));
+        
+        server.set("btc_estimatesmartfee", json!({ "errors": ["Insufficient data or no feerate found"], "blocks": 0 }));
+        assert!(client.estimate_smart_fee(6).await.is_err());
+    }
+    
+    #[test]
+    fn test_parse_protorune_balances() {
+        let expected = vec![ProtoruneBalance { block: 2, tx: 0, name: Some("DIESEL".to_string()), divisibility: 8, amount: 1500 }];
+        
+        let bare = json!([{ "rune": { "id": { "block": "2", "tx": "0" }, "name": "DIESEL", "divisibility": 8 }, "balance": "1500" }]);
+        assert_eq!(parse_protorune_balances(&bare), expected);
+        
+        let sheet = json!({ "balance_sheet": { "entries": [
+            { "rune": { "id": { "block": "0x2", "tx": 0 }, "name": "DIESEL", "divisibility": 8 }, "balance": 1500 }
+        ] } });
+        assert_eq!(parse_protorune_balances(&sheet), expected);
+        
+        assert!(parse_protorune_balances(&json!({})).is_empty());
+    }
+    
+    #[test]
+    fn test_parse_fee_histogram_shapes() {
+        let expected = vec![(20.5, 1000), (10.0, 5000), (1.0, 20000)];
+        
+        let pairs = json!([[20.5, 1000], [10.0, 5000], [1.0, 20000]]);
+        assert_eq!(parse_fee_histogram(&pairs).unwrap(), expected);
+        
+        let wrapped = json!({ "count": 3, "fee_histogram": [[1.0, 20000], [20.5, 1000], [10.0, 5000]] });
+        assert_eq!(parse_fee_histogram(&wrapped).unwrap(), expected);
+        
+        let map = json!({ "1.0": 20000, "10": 5000, "20.5": 1000 });
+        assert_eq!(parse_fee_histogram(&map).unwrap(), expected);
+        
+        assert!(parse_fee_histogram(&json!("nope")).is_err());
+    }
 }
\ No newline at end of file
@@ -2,271 +2,559 @@
 //!
 //! This module provides functionality for creating Runestone transactions
 //! with Protostones for DIESEL token minting.
+//!
+//! Protostones are carried in the runestone's protocol field (tag 16383):
+//! the protostone list is serialized to integers by `protorune_support`,
+//! and each integer is written after its own protocol tag, as the ordinals
+//! crate does.
 
 use bdk::bitcoin::{Script as ScriptBuf, Transaction, TxOut};
 use bdk::bitcoin::blockdata::script::{Builder, Instruction};
 use bdk::bitcoin::blockdata::opcodes;
+use anyhow::{anyhow, Context, Result};
 use log::debug;
-use std::convert::TryInto;
+use protorune_support::protostone::{Protostone, Protostones};
 
-/// Maximum size of a script element
-const MAX_SCRIPT_ELEMENT_SIZE: usize = 520;
+use crate::transaction::ProtostoneSpec;
+use crate::varint;
+use std::convert::TryInto;
 
-/// Runestone for DIESEL token minting
+/// Runestone carrying protostones, in order
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct Runestone {
-    /// Protocol tag and message
-    pub protocol: Option<Vec<u128>>,
+    /// Protostones, encoded in this order
+    pub protostones: Vec<ProtostoneSpec>,
+}
+
+/// Builder of a `Runestone` from its protostones
+#[derive(Default, Debug, Clone)]
+pub struct RunestoneBuilder {
+    /// Protostones added so far
+    protostones: Vec<ProtostoneSpec>,
+}
+
+impl RunestoneBuilder {
+    /// Append a protostone
+    pub fn add_protostone(mut self, spec: ProtostoneSpec) -> Self {
+        self.protostones.push(spec);
+        self
+    }
+    
+    /// Finish the runestone
+    pub fn build(self) -> Runestone {
+        Runestone { protostones: self.protostones }
+    }
 }
 
-/// Protocol tag for DIESEL token minting
+/// Runestone tags
 pub mod tag {
-    /// Protocol tag
-    pub const PROTOCOL: u128 = 0x0d;
+    /// Tag of the protocol field values, carrying the protostones
+    pub const PROTOCOL: u128 = 16383;
+    /// Tag of the edicts body, which runs to the end of the payload
+    pub const BODY: u128 = 0;
+    /// Tag of the output receiving runes not assigned by edicts
+    pub const POINTER: u128 = 22;
+}
+
+/// Rune ID of the DIESEL token
+pub mod diesel_id {
+    /// Block component of the DIESEL rune ID
+    pub const BLOCK: u128 = 2;
+    /// Transaction component of the DIESEL rune ID
+    pub const TX: u128 = 0;
+}
+
+/// Encode cellpack values as a protostone message
+///
+/// A cellpack is the target contract's `block` and `tx` followed by its
+/// inputs, each LEB128-encoded.
+pub fn encode_cellpack(values: &[u128]) -> Vec<u8> {
+    let mut message = Vec::new();
+    for value in values {
+        varint::encode_to_vec(*value, &mut message);
+    }
+    message
+}
+
+/// Check that output `index`, named `field` in errors, can receive runes
+///
+/// The output must exist among `outputs` and must not be an OP_RETURN.
+pub fn check_output(outputs: &[TxOut], index: u32, field: &str) -> Result<()> {
+    match outputs.get(index as usize) {
+        None => Err(anyhow!("Protostone {} {} is out of range for {} outputs", field, index, outputs.len())),
+        Some(output) if output.script_pubkey.is_op_return() => {
+            Err(anyhow!("Protostone {} {} targets an OP_RETURN output", field, index))
+        },
+        Some(_) => Ok(()),
+    }
 }
 
-/// Varint encoding/decoding utilities
-pub mod varint {
-    use anyhow::{anyhow, Result};
+/// Shadow vout of the protostone at `protostone_index` in a transaction
+/// with `num_outputs` outputs
+///
+/// Protostones are numbered after the real outputs and the virtual output
+/// implied by the OP_RETURN.
+pub fn protostone_vout(protostone_index: usize, num_outputs: usize) -> usize {
+    num_outputs + 1 + protostone_index
+}
+
+/// Encode protostones as an OP_RETURN runestone script
+///
+/// This is the encoding behind `ProtostoneSpec::encipher_all`, taking
+/// `protorune_support` protostones whose message may be any bytes rather
+/// than a cellpack. `pointer` is the runestone's own pointer.
+pub fn encipher_protostones(protostones: Vec<Protostone>, pointer: Option<u32>) -> Result<bdk::bitcoin::ScriptBuf> {
+    let protocol = protostones.encipher()
+        .context("Failed to encode protostones")?;
+    let runestone = ordinals::runestone::Runestone {
+        pointer,
+        protocol: Some(protocol),
+        ..Default::default()
+    };
+    Ok(bdk::bitcoin::ScriptBuf::from_bytes(runestone.encipher().into_bytes()))
+}
 
-    /// Encode a u128 as a variable-length integer
-    pub fn encode(mut value: u128) -> Vec<u8> {
-        let mut result = Vec::new();
+/// Extract the protocol values of the first runestone in a transaction
+///
+/// OP_RETURN outputs without the runestone magic are skipped, as are
+/// candidates whose payload is malformed (an opcode among the pushes or a
+/// bad varint), so a runestone after them is still found. Note that the
+/// ordinals crate treats such a first candidate as a cenotaph instead.
+pub fn extract_protocol(transaction: &Transaction) -> Option<Vec<u128>> {
+    // Search transaction outputs for Runestone
+    for output in &transaction.output {
+        let mut instructions = output.script_pubkey.instructions();
         
-        loop {
-            let mut byte = (value & 0x7f) as u8;
-            value >>= 7;
-            
-            if value != 0 {
-                byte |= 0x80;
-            }
-            
-            result.push(byte);
-            
-            if value == 0 {
-                break;
-            }
+        // Check for OP_RETURN
+        if instructions.next() != Some(Ok(Instruction::Op(opcodes::all::OP_RETURN))) {
+            continue;
         }
         
-        result
-    }
-    
-    /// Encode a u128 to a vector
-    pub fn encode_to_vec(value: u128, vec: &mut Vec<u8>) {
-        vec.extend(encode(value));
-    }
-    
-    /// Decode a variable-length integer from bytes
-    pub fn decode(bytes: &[u8]) -> Result<(u128, usize)> {
-        let mut result: u128 = 0;
-        let mut shift = 0;
+        // Check for magic number
+        if instructions.next() != Some(Ok(Instruction::Op(Runestone::MAGIC_NUMBER))) {
+            continue;
+        }
+        
+        // Concatenate the data pushes, whatever their encoding (direct,
+        // PUSHDATA1/2/4 or empty); any other opcode or a truncated push
+        // makes the candidate malformed
+        let pushes: Option<Vec<&[u8]>> = instructions
+            .map(|instruction| match instruction {
+                Ok(Instruction::PushBytes(push)) => Some(push.as_bytes()),
+                Ok(Instruction::Op(_)) | Err(_) => None,
+            })
+            .collect();
+        let payload = match pushes {
+            Some(pushes) => pushes.concat(),
+            None => continue,
+        };
+        
+        // Decode the integers from the payload
+        let integers = match varint::decode_all(&payload) {
+            Ok(ints) => ints,
+            Err(_) => continue,
+        };
+        
+        // Parse the Runestone data
+        let mut protocol_data = Vec::new();
         let mut i = 0;
         
-        loop {
-            if i >= bytes.len() {
-                return Err(anyhow!("Truncated varint"));
-            }
-            
-            let byte = bytes[i];
+        while i < integers.len() {
+            let tag = integers[i];
             i += 1;
             
-            result |= u128::from(byte & 0x7f) << shift;
-            
-            if byte & 0x80 == 0 {
+            // The edicts body takes the rest of the payload
+            if tag == tag::BODY {
                 break;
             }
-            
-            shift += 7;
-            
-            if shift > 127 {
-                return Err(anyhow!("Varint too large"));
+            if tag == tag::PROTOCOL && i < integers.len() {
+                protocol_data.push(integers[i]);
+                i += 1;
+            } else {
+                // Skip other tags and their values
+                if i < integers.len() {
+                    i += 1;
+                }
             }
         }
         
-        Ok((result, i))
-    }
-    
-    /// Decode all integers from a payload
-    pub fn decode_all(payload: &[u8]) -> Result<Vec<u128>> {
-        let mut integers = Vec::new();
-        let mut i = 0;
-        
-        while i < payload.len() {
-            let (integer, length) = decode(&payload[i..])?;
-            integers.push(integer);
-            i += length;
+        if !protocol_data.is_empty() {
+            return Some(protocol_data);
         }
-        
-        Ok(integers)
     }
+    
+    None
 }
 
 impl Runestone {
     /// Magic number for Runestone protocol
     pub const MAGIC_NUMBER: bdk::bitcoin::blockdata::opcodes::All = bdk::bitcoin::blockdata::opcodes::all::OP_PUSHNUM_13;
     
-    /// Create a new Runestone with the given protocol tag and message
-    pub fn new(protocol_tag: u128, message: &[u8]) -> Self {
-        let mut protocol = Vec::new();
-        protocol.push(protocol_tag);
-        
-        // Convert message bytes to u128 values
-        for byte in message {
-            protocol.push(*byte as u128);
-        }
-        
-        Self {
-            protocol: Some(protocol),
-        }
+    /// Start building a Runestone
+    pub fn builder() -> RunestoneBuilder {
+        RunestoneBuilder::default()
     }
     
     /// Create a new DIESEL token minting Runestone
+    ///
+    /// Protocol tag 1, message cellpack `[2, 0, 77]`, with the minted tokens
+    /// (and any refund) going to output 0.
     pub fn new_diesel() -> Self {
-        // Protocol tag: 1
-        // Message cellpack: [2, 0, 77]
-        Self::new(1, &[2, 0, 77])
+        Self::builder().add_protostone(ProtostoneSpec::diesel_mint()).build()
+    }
+    
+    /// Check that every pointer and refund targets one of `outputs`
+    ///
+    /// `outputs` are those of the transaction carrying the runestone; an
+    /// OP_RETURN output can't receive runes.
+    pub fn validate_outputs(&self, outputs: &[TxOut]) -> Result<()> {
+        self.protostones.iter().try_for_each(|spec| spec.validate_outputs(outputs))
+    }
+    
+    /// Encode the Runestone for a transaction with `outputs`, checking its
+    /// pointers and refunds first
+    pub fn encipher_for(&self, outputs: &[TxOut]) -> Result<bdk::bitcoin::ScriptBuf> {
+        self.validate_outputs(outputs)?;
+        self.encipher()
     }
     
     /// Encode the Runestone as a Bitcoin script
-    pub fn encipher(&self) -> bdk::bitcoin::ScriptBuf {
+    ///
+    /// The runestone pointer is that of the first protostone, as with
+    /// `ProtostoneSpec::encipher_all`.
+    pub fn encipher(&self) -> Result<bdk::bitcoin::ScriptBuf> {
+        ProtostoneSpec::encipher_all(&self.protostones)
+    }
+    
+    /// Extract a Runestone from a transaction if present
+    ///
+    /// Returns `None` if there is no runestone or if any protostone can't be
+    /// decoded as a spec, e.g. because its message isn't a cellpack.
+    pub fn extract(transaction: &Transaction) -> Option<Self> {
+        let protocol = extract_protocol(transaction)?;
+        let protostones = Protostone::decipher(&protocol).ok()?;
+        let protostones = protostones.iter()
+            .map(ProtostoneSpec::from_protostone)
+            .collect::<Result<Vec<_>>>()
+            .ok()?;
+        Some(Self { protostones })
+    }
+    
+    /// Get the protocol tag of the first protostone
+    pub fn protocol_tag(&self) -> Option<u128> {
+        self.protostones.first().map(|spec| spec.protocol_tag)
+    }
+    
+    /// Get the cellpack values of the first protostone
+    pub fn message_values(&self) -> Option<Vec<u128>> {
+        self.protostones.first().map(|spec| spec.cellpack.clone())
+    }
+    
+    /// Get the cellpack values of the first protostone as one byte each
+    ///
+    /// Only holds small values such as DIESEL's `[2, 0, 77]`; `None` if there
+    /// is no protostone or a value doesn't fit in a byte.
+    #[deprecated(note = "values above 255 don't fit; use `message_values`")]
+    pub fn message_bytes(&self) -> Option<Vec<u8>> {
+        self.message_values()?
+            .into_iter()
+            .map(|value| u8::try_from(value).ok())
+            .collect()
+    }
+    
+    /// Check if this Runestone carries a DIESEL mint protostone
+    pub fn is_diesel(&self) -> bool {
+        self.protostones.iter().any(|spec| {
+            spec.protocol_tag == 1 && spec.cellpack == [diesel_id::BLOCK, diesel_id::TX, 77]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::Edict;
+    use bdk::bitcoin::script::PushBytesBuf;
+    use bdk::bitcoin::TxIn;
+    
+    /// A protocol whose encoded payload is exactly `len` bytes (at least 3)
+    ///
+    /// The protocol tag takes two bytes, so each value below 128 encodes to
+    /// three bytes with its tag, and each value from 128 to 16383 to four.
+    fn protocol_of_len(len: usize) -> Vec<u128> {
+        let wide = len % 3;
+        let mut protocol = vec![7u128; (len - 4 * wide) / 3];
+        protocol.extend(std::iter::repeat(300).take(wide));
+        protocol
+    }
+    
+    fn tx_with_script(script_pubkey: bdk::bitcoin::ScriptBuf) -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: bdk::bitcoin::absolute::LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut { value: 0, script_pubkey }],
+        }
+    }
+    
+    /// The protocol field payload of `protocol`, each value after its tag
+    fn payload_of(protocol: &[u128]) -> Vec<u8> {
         let mut payload = Vec::new();
-        
-        // Encode protocol tag and message
-        if let Some(protostones) = &self.protocol {
-            for proto_u128 in protostones {
-                varint::encode_to_vec(tag::PROTOCOL, &mut payload);
-                varint::encode_to_vec(*proto_u128, &mut payload);
+        for value in protocol {
+            varint::encode_to_vec(tag::PROTOCOL, &mut payload);
+            varint::encode_to_vec(*value, &mut payload);
+        }
+        payload
+    }
+    
+    #[test]
+    fn test_encipher_large_runestones() {
+        for len in [1, 50, 200, 600] {
+            let spec = ProtostoneSpec { cellpack: vec![2, 0, 77, 300].repeat(len), ..ProtostoneSpec::diesel_mint() };
+            let script = Runestone::builder().add_protostone(spec.clone()).build().encipher().unwrap();
+            
+            // Pushes stay within the script element size limit
+            for instruction in script.instructions().skip(2) {
+                match instruction {
+                    Ok(Instruction::PushBytes(push)) => assert!(push.len() <= 520, "{} byte push", push.len()),
+                    other => panic!("expected a push, got {:?}", other),
+                }
             }
+            assert_eq!(Runestone::extract(&tx_with_script(script)).unwrap().protostones, vec![spec], "cellpack of {} values", 4 * len);
         }
+    }
+    
+    #[test]
+    fn test_encipher_writes_the_runestone_pointer() {
+        // Both encoders agree, down to the runestone pointer
+        let runestone = Runestone::new_diesel();
+        let script = runestone.encipher().unwrap();
+        assert_eq!(script, ProtostoneSpec::diesel_mint().encipher().unwrap());
         
-        // Create a script manually with OP_RETURN, magic number, and payload
-        let mut script_bytes = Vec::new();
+        match crate::runestone_enhanced::format_runestone(&tx_with_script(script)).unwrap() {
+            crate::runestone_enhanced::Decoded::Runestone { runes: Some(runes), .. } => assert_eq!(runes.pointer, Some(0)),
+            other => panic!("expected a runestone with a pointer, got {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_extract_concatenates_pushes_of_varying_sizes() {
+        let protocol = protocol_of_len(400);
+        let payload = payload_of(&protocol);
         
-        // Add OP_RETURN
-        script_bytes.push(0x6a); // OP_RETURN opcode
+        // A direct push, a PUSHDATA1 push and a PUSHDATA2 push
+        let mut builder = Builder::new()
+            .push_opcode(opcodes::all::OP_RETURN)
+            .push_opcode(Runestone::MAGIC_NUMBER);
+        for range in [0..10, 10..110, 110..400] {
+            builder = builder.push_slice(PushBytesBuf::try_from(payload[range].to_vec()).unwrap());
+        }
+        let tx = tx_with_script(builder.into_script());
         
-        // Add magic number (OP_PUSHNUM_13)
-        script_bytes.push(0x5d); // OP_PUSHNUM_13 opcode
+        assert_eq!(extract_protocol(&tx).unwrap(), protocol);
+        let decoded = crate::runestone_enhanced::decode_runestone(&tx).unwrap();
+        assert_eq!(decoded.protocol_data(), protocol);
+    }
+    
+    #[test]
+    fn test_encipher_round_trips_through_ordinals() {
+        // The DIESEL mint, cellpack [2, 0, 77]
+        let runestone = Runestone::new_diesel();
+        assert!(runestone.is_diesel());
+        let tx = tx_with_script(runestone.encipher().unwrap());
+        let protostones = crate::runestone_enhanced::format_runestone(&tx).unwrap().into_protostones().unwrap();
+        assert_eq!(protostones.len(), 1);
+        assert_eq!(protostones[0].protocol_tag, 1);
+        assert_eq!(protostones[0].message, vec![2, 0, 77]);
+        assert_eq!(protostones[0].pointer, Some(0));
+        assert_eq!(protostones[0].refund, Some(0));
+        assert_eq!(Runestone::extract(&tx).unwrap(), runestone);
         
-        // Add payload in chunks
-        for chunk in payload.chunks(MAX_SCRIPT_ELEMENT_SIZE) {
-            if chunk.len() <= 75 {
-                // Direct push for small chunks
-                script_bytes.push(chunk.len() as u8);
-                script_bytes.extend_from_slice(chunk);
-            } else if chunk.len() <= 255 {
-                // OP_PUSHDATA1 for medium chunks
-                script_bytes.push(0x4c); // OP_PUSHDATA1
-                script_bytes.push(chunk.len() as u8);
-                script_bytes.extend_from_slice(chunk);
-            } else {
-                // OP_PUSHDATA2 for larger chunks
-                script_bytes.push(0x4d); // OP_PUSHDATA2
-                script_bytes.push((chunk.len() & 0xff) as u8);
-                script_bytes.push((chunk.len() >> 8) as u8);
-                script_bytes.extend_from_slice(chunk);
-            }
-        }
+        // Raw messages that aren't cellpacks still encode
+        let raw = Protostone {
+            burn: None,
+            message: vec![0xff],
+            edicts: Vec::new(),
+            refund: None,
+            pointer: None,
+            from: None,
+            protocol_tag: 7,
+        };
+        let tx = tx_with_script(encipher_protostones(vec![raw], None).unwrap());
+        let protostones = crate::runestone_enhanced::format_runestone(&tx).unwrap().into_protostones().unwrap();
+        assert_eq!((protostones[0].protocol_tag, protostones[0].message.clone()), (7, vec![0xff]));
+    }
+    
+    #[test]
+    fn test_multiple_protostones_keep_their_order() {
+        let call = ProtostoneSpec {
+            protocol_tag: 1,
+            cellpack: vec![4, 100, 5, 300],
+            edicts: vec![Edict { id: (2, 1), amount: 1_000, output: 1 }],
+            pointer: Some(1),
+            refund: Some(2),
+            burn: None,
+        };
+        let other = ProtostoneSpec {
+            protocol_tag: 7,
+            cellpack: vec![9],
+            edicts: Vec::new(),
+            pointer: None,
+            refund: None,
+            burn: None,
+        };
+        let runestone = Runestone::builder()
+            .add_protostone(call.clone())
+            .add_protostone(ProtostoneSpec::diesel_mint())
+            .add_protostone(other.clone())
+            .build();
+        assert!(runestone.is_diesel());
+        assert_eq!(runestone.protocol_tag(), Some(1));
+        assert_eq!(runestone.message_values(), Some(vec![4, 100, 5, 300]));
+        let tx = tx_with_script(runestone.encipher().unwrap());
+        
+        // Internal decoder
+        let extracted = Runestone::extract(&tx).unwrap();
+        assert_eq!(extracted.protostones, vec![call, ProtostoneSpec::diesel_mint(), other]);
+        let decoded = crate::runestone_enhanced::decode_runestone(&tx).unwrap();
+        assert_eq!(decoded.protostones[0].protocol_tag, 1);
         
-        // Create a ScriptBuf from the bytes
-        bdk::bitcoin::ScriptBuf::from_bytes(script_bytes)
+        // Ordinals-based decoder
+        let protostones = crate::runestone_enhanced::format_runestone(&tx).unwrap().into_protostones().unwrap();
+        let summary: Vec<(u128, Vec<u8>)> = protostones.iter()
+            .map(|protostone| (protostone.protocol_tag, protostone.message.clone()))
+            .collect();
+        assert_eq!(summary, vec![
+            (1, encode_cellpack(&[4, 100, 5, 300])),
+            (1, vec![2, 0, 77]),
+            (7, vec![9]),
+        ]);
+        assert_eq!((protostones[0].pointer, protostones[0].refund), (Some(1), Some(2)));
+        let edicts: Vec<(u128, u128, u128, u128)> = protostones[0].edicts.iter()
+            .map(|edict| (edict.id.block, edict.id.tx, edict.amount, edict.output))
+            .collect();
+        assert_eq!(edicts, vec![(2, 1, 1_000, 1)]);
+        
+        // Without the mint it's no longer DIESEL
+        assert!(!Runestone::builder().add_protostone(ProtostoneSpec { cellpack: vec![2, 0, 78], ..ProtostoneSpec::diesel_mint() }).build().is_diesel());
+        assert!(Runestone::default().protocol_tag().is_none());
     }
     
-    /// Extract a Runestone from a transaction if present
-    pub fn extract(transaction: &Transaction) -> Option<Self> {
-        // Search transaction outputs for Runestone
-        for output in &transaction.output {
-            let mut instructions = output.script_pubkey.instructions();
-            
-            // Check for OP_RETURN
-            if instructions.next() != Some(Ok(Instruction::Op(opcodes::all::OP_RETURN))) {
-                continue;
-            }
-            
-            // Check for magic number
-            if instructions.next() != Some(Ok(Instruction::Op(Runestone::MAGIC_NUMBER))) {
-                continue;
-            }
-            
-            // Construct the payload by concatenating remaining data pushes
-            let mut payload = Vec::new();
-            
-            for result in instructions {
-                match result {
-                    Ok(Instruction::PushBytes(push)) => {
-                        payload.extend_from_slice(push.as_bytes());
-                    }
-                    Ok(Instruction::Op(_)) => {
-                        // Invalid opcode in Runestone payload
-                        return None;
-                    }
-                    Err(_) => {
-                        // Invalid script in Runestone payload
-                        return None;
-                    }
-                }
-            }
-            
-            // Decode the integers from the payload
-            let integers = match varint::decode_all(&payload) {
-                Ok(ints) => ints,
-                Err(_) => return None,
-            };
-            
-            // Parse the Runestone data
-            let mut protocol_data = Vec::new();
-            let mut i = 0;
-            
-            while i < integers.len() {
-                let tag = integers[i];
-                i += 1;
-                
-                // Tag 13 is the protocol tag
-                if tag == tag::PROTOCOL && i < integers.len() {
-                    protocol_data.push(integers[i]);
-                    i += 1;
-                } else {
-                    // Skip other tags and their values
-                    if i < integers.len() {
-                        i += 1;
-                    }
-                }
-            }
-            
-            if !protocol_data.is_empty() {
-                return Some(Self {
-                    protocol: Some(protocol_data),
-                });
-            }
-        }
+    #[test]
+    #[allow(deprecated)]
+    fn test_wide_cellpack_values_round_trip() {
+        let values = vec![4, 100, 3, (1u128 << 32) + 1, (1u128 << 64) + 5, u128::MAX];
+        let runestone = Runestone::builder()
+            .add_protostone(ProtostoneSpec { cellpack: values.clone(), ..ProtostoneSpec::diesel_mint() })
+            .build();
+        assert!(!runestone.is_diesel());
+        assert_eq!(runestone.message_values(), Some(values.clone()));
+        assert_eq!(runestone.message_bytes(), None);
+        assert_eq!(Runestone::new_diesel().message_bytes(), Some(vec![2, 0, 77]));
+        
+        let tx = tx_with_script(runestone.encipher().unwrap());
+        assert_eq!(Runestone::extract(&tx).unwrap().message_values(), Some(values.clone()));
+        let protostones = crate::runestone_enhanced::format_runestone(&tx).unwrap().into_protostones().unwrap();
+        assert_eq!(ProtostoneSpec::from_protostone(&protostones[0]).unwrap().cellpack, values);
         
-        None
+        // A last input of 0 is kept
+        let spec = ProtostoneSpec { cellpack: vec![2, 0, 77, 0], ..ProtostoneSpec::diesel_mint() };
+        assert_eq!(ProtostoneSpec::from_protostone(&spec.to_protostone()).unwrap(), spec);
+        let tx = tx_with_script(spec.encipher().unwrap());
+        let protostones = crate::runestone_enhanced::format_runestone(&tx).unwrap().into_protostones().unwrap();
+        assert_eq!(ProtostoneSpec::from_protostone(&protostones[0]).unwrap(), spec);
+        crate::transaction::verify_runestone(&tx, &[spec]).unwrap();
     }
     
-    /// Get the protocol tag (first element in protocol)
-    pub fn protocol_tag(&self) -> Option<u128> {
-        self.protocol.as_ref().and_then(|p| p.first().copied())
+    #[test]
+    fn test_protostone_vout() {
+        // Two real outputs and the runestone's virtual output come first
+        assert_eq!(protostone_vout(0, 2), 3);
+        assert_eq!(protostone_vout(2, 2), 5);
+        assert_eq!(protostone_vout(0, 0), 1);
     }
     
-    /// Get the message bytes (all elements after the first in protocol)
-    pub fn message_bytes(&self) -> Option<Vec<u8>> {
-        self.protocol.as_ref().map(|p| {
-            p.iter()
-                .skip(1)
-                .map(|&n| n as u8)
-                .collect()
-        })
+    #[test]
+    fn test_pointer_and_refund() {
+        let spec = |pointer, refund| ProtostoneSpec { pointer, refund, ..ProtostoneSpec::diesel_mint() };
+        let runestone = Runestone::builder().add_protostone(spec(Some(1), Some(0))).build();
+        let dust = TxOut { value: 546, script_pubkey: bdk::bitcoin::ScriptBuf::from_bytes(vec![0x51]) };
+        let mut tx = tx_with_script(runestone.encipher().unwrap());
+        tx.output.insert(0, dust.clone());
+        tx.output.insert(1, dust.clone());
+        
+        let protostones = crate::runestone_enhanced::format_runestone(&tx).unwrap().into_protostones().unwrap();
+        assert_eq!((protostones[0].pointer, protostones[0].refund), (Some(1), Some(0)));
+        assert!(runestone.validate_outputs(&tx.output).is_ok());
+        assert_eq!(runestone.encipher_for(&tx.output).unwrap(), tx.output[2].script_pubkey);
+        
+        // Pointing at the runestone itself or past the outputs, in any protostone
+        let at_runestone = Runestone::builder().add_protostone(spec(Some(2), None)).build();
+        assert!(at_runestone.encipher_for(&tx.output).unwrap_err().to_string().contains("OP_RETURN"));
+        let out_of_range = Runestone::builder()
+            .add_protostone(spec(Some(0), None))
+            .add_protostone(spec(Some(0), Some(3)))
+            .build();
+        assert!(out_of_range.validate_outputs(&tx.output).unwrap_err().to_string().contains("refund 3"));
+        assert!(Runestone::builder().add_protostone(spec(None, None)).build().validate_outputs(&[]).is_ok());
     }
     
-    /// Check if this is a DIESEL token minting Runestone
-    pub fn is_diesel(&self) -> bool {
-        if let Some(tag) = self.protocol_tag() {
-            if tag == 1 {
-                if let Some(message) = self.message_bytes() {
-                    return message == [2, 0, 77];
-                }
+    fn tx_with_outputs(scripts: Vec<bdk::bitcoin::ScriptBuf>) -> Transaction {
+        Transaction {
+            output: scripts.into_iter().map(|script_pubkey| TxOut { value: 0, script_pubkey }).collect(),
+            ..tx_with_script(bdk::bitcoin::ScriptBuf::new())
+        }
+    }
+    
+    #[test]
+    fn test_extract_pushdata_chunks() {
+        let protocol = protocol_of_len(600);
+        let payload = payload_of(&protocol);
+        
+        // Two PUSHDATA2 chunks around an empty push
+        let mut script = vec![0x6a, 0x5d];
+        for chunk in [&payload[..300], &[][..], &payload[300..]] {
+            if chunk.is_empty() {
+                script.push(0x00);
+            } else {
+                script.push(opcodes::all::OP_PUSHDATA2.to_u8());
+                script.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+                script.extend_from_slice(chunk);
             }
         }
-        false
+        let tx = tx_with_script(bdk::bitcoin::ScriptBuf::from_bytes(script.clone()));
+        assert_eq!(extract_protocol(&tx).unwrap(), protocol);
+        let decoded = crate::runestone_enhanced::decode_runestone(&tx).unwrap();
+        assert_eq!(decoded.protocol_data(), protocol);
+        assert!(!decoded.is_cenotaph());
+        
+        // The same payload in a single PUSHDATA4
+        let mut script = vec![0x6a, 0x5d, opcodes::all::OP_PUSHDATA4.to_u8()];
+        script.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        script.extend_from_slice(&payload);
+        let tx = tx_with_script(bdk::bitcoin::ScriptBuf::from_bytes(script));
+        assert_eq!(extract_protocol(&tx).unwrap(), protocol);
+    }
+    
+    #[test]
+    fn test_extract_skips_other_op_returns() {
+        let hello = Builder::new()
+            .push_opcode(opcodes::all::OP_RETURN)
+            .push_slice(b"hello")
+            .into_script();
+        let runestone = Runestone::new_diesel();
+        let tx = tx_with_outputs(vec![hello.clone(), runestone.encipher().unwrap()]);
+        
+        assert_eq!(Runestone::extract(&tx).unwrap(), runestone);
+        let decoded = crate::runestone_enhanced::decode_runestone(&tx).unwrap();
+        assert_eq!(decoded.output_index, 1);
+        assert_eq!(decoded.protostones[0].protocol_tag, 1);
+        let protostones = crate::runestone_enhanced::format_runestone(&tx).unwrap().into_protostones().unwrap();
+        assert_eq!(protostones[0].message, vec![2, 0, 77]);
+        
+        // A malformed candidate doesn't stop the scan
+        let malformed = bdk::bitcoin::ScriptBuf::from_bytes(vec![0x6a, 0x5d, opcodes::all::OP_VERIFY.to_u8()]);
+        let tx = tx_with_outputs(vec![hello, malformed, runestone.encipher().unwrap()]);
+        assert_eq!(Runestone::extract(&tx).unwrap(), runestone);
     }
 }
@@ -6,19 +6,35 @@
 //!
 //! The module provides two main functions:
 //! - `decode_runestone`: Manually extracts and decodes Runestone data from a transaction
+//!   into a `DecodedRunestone`, whose JSON form is versioned
 //! - `format_runestone`: Uses the ordinals crate to extract Runestones and convert them to Protostones
+//!
+//! Both also surface the standard Runes fields (etching, mint, edicts and
+//! pointer) as `DecodedRunes`, so plain Runes transactions decode too.
+//!
+//! `diff_decoders` runs both on the same transaction to catch them drifting apart.
 
 use anyhow::{anyhow, Context, Result};
-use bdk::bitcoin::Transaction;
+use bdk::bitcoin::{Transaction, Txid};
+use bdk::bitcoin::psbt::PartiallySignedTransaction;
 use bitcoin;
 use bdk::bitcoin::blockdata::script::Instruction;
 use bdk::bitcoin::blockdata::opcodes;
 use log::{debug, trace};
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use thiserror::Error;
 use ordinals::{Artifact, runestone::{Runestone}};
 use protorune_support::protostone::Protostone;
+use crate::alkane_id::AlkaneId;
+use crate::cellpack::{Cellpack, INITIALIZE_OPCODE, MINT_OPCODE};
+use crate::network::{script_type, NetworkParams};
+use crate::rpc::{decode_events, RpcClient};
+use crate::varint;
 use hex;
-use std::str::FromStr;
 use bdk::bitcoin::consensus::deserialize;
 
 /// Convert a BDK Transaction to a Bitcoin Transaction
@@ -27,54 +43,37 @@ use bdk::bitcoin::consensus::deserialize;
 /// Bitcoin library format. This is necessary when using functions from the
 /// ordinals crate, which expects Bitcoin library types.
 ///
+/// The inputs, their witnesses and the outputs are consensus-encoded with
+/// BDK and decoded with the Bitcoin library. They are converted separately
+/// because a whole transaction without inputs doesn't round-trip: its empty
+/// input list reads as a segwit marker.
+///
 /// # Arguments
 ///
 /// * `v` - The BDK Transaction to convert
 ///
 /// # Returns
 ///
-/// The equivalent Bitcoin Transaction
-pub fn from_bdk(v: bdk::bitcoin::Transaction) -> bitcoin::Transaction {
-    // Create a new Bitcoin transaction with the same properties
-    // We need to manually convert each field since they're from different crates
-    let mut inputs = Vec::new();
-    for input in &v.input {
-        // Convert txid to bytes and then to the other Bitcoin library's Txid
-        let txid_bytes = input.previous_output.txid.to_string();
-        let txid = bitcoin::Txid::from_str(&txid_bytes).unwrap();
-        
-        // Create the input with converted fields
-        inputs.push(bitcoin::TxIn {
-            previous_output: bitcoin::OutPoint {
-                txid,
-                vout: input.previous_output.vout,
-            },
-            script_sig: bitcoin::ScriptBuf::from_bytes(input.script_sig.as_bytes().to_vec()),
-            sequence: bitcoin::Sequence(input.sequence.0),
-            witness: {
-                let mut witness = bitcoin::Witness::new();
-                for item in &input.witness {
-                    witness.push(item.clone());
-                }
-                witness
-            },
-        });
-    }
+/// The equivalent Bitcoin Transaction, or an error if a part doesn't decode
+pub fn from_bdk(v: &bdk::bitcoin::Transaction) -> Result<bitcoin::Transaction> {
+    use bdk::bitcoin::consensus::encode::serialize;
+    use bitcoin::consensus::deserialize;
     
-    let mut outputs = Vec::new();
-    for output in &v.output {
-        outputs.push(bitcoin::TxOut {
-            value: bitcoin::Amount::from_sat(output.value),
-            script_pubkey: bitcoin::ScriptBuf::from_bytes(output.script_pubkey.as_bytes().to_vec()),
-        });
+    let mut input: Vec<bitcoin::TxIn> = deserialize(&serialize(&v.input))
+        .context("Failed to convert transaction inputs")?;
+    for (index, (converted, original)) in input.iter_mut().zip(&v.input).enumerate() {
+        converted.witness = deserialize(&serialize(&original.witness))
+            .with_context(|| format!("Failed to convert the witness of input {}", index))?;
     }
+    let output: Vec<bitcoin::TxOut> = deserialize(&serialize(&v.output))
+        .context("Failed to convert transaction outputs")?;
     
-    bitcoin::Transaction {
+    Ok(bitcoin::Transaction {
         version: bitcoin::transaction::Version(v.version),
         lock_time: bitcoin::absolute::LockTime::from_consensus(v.lock_time.to_consensus_u32()),
-        input: inputs,
-        output: outputs,
-    }
+        input,
+        output,
+    })
 }
 
 /// Magic number for Runestone protocol
@@ -116,10 +115,442 @@ pub mod protorune_operations {
     pub const JOIN: u8 = 5;
 }
 
-/// Operation types for DIESEL token operations
-pub mod diesel_operations {
-    /// Mint operation (message [2, 0, 77])
-    pub const MINT: [u8; 3] = [2, 0, 77];
+/// Version of the `DecodedRunestone` JSON representation
+///
+/// Bumped whenever a field is renamed, removed or changes type.
+pub const DECODED_RUNESTONE_VERSION: u32 = 1;
+
+/// A Runestone as decoded by `decode_runestone`
+///
+/// The JSON form is versioned by `version`. Values that may exceed a u64
+/// are numbers when they fit and decimal strings when they don't, except
+/// amounts, burns and cellpacks which are always strings.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DecodedRunestone {
+    /// Version of the representation, `DECODED_RUNESTONE_VERSION`
+    pub version: u32,
+    /// ID of the transaction carrying the Runestone
+    pub txid: String,
+    /// Index of the Runestone's OP_RETURN output
+    pub output_index: usize,
+    /// Standard Runes fields, if the Runestone has any and isn't a cenotaph
+    pub runes: Option<DecodedRunes>,
+    /// Protostones carried in the protocol field, in order
+    pub protostones: Vec<DecodedProtostone>,
+    /// Values of each tag, in the order the tags first appear
+    #[serde(with = "wire::wide_tags")]
+    pub all_tags: TagMap,
+    /// Why the Runestone is a cenotaph, if it is one
+    pub cenotaph: Option<CenotaphInfo>,
+    /// Integers of the payload, for debugging
+    #[serde(with = "wire::wide_seq")]
+    pub raw_integers: Vec<u128>,
+}
+
+impl DecodedRunestone {
+    /// Values of the protocol field (tag 16383)
+    pub fn protocol_data(&self) -> Vec<u128> {
+        self.all_tags.get(crate::runestone::tag::PROTOCOL).map(<[u128]>::to_vec).unwrap_or_default()
+    }
+    
+    /// Whether the Runestone is malformed, burning the runes it would move
+    pub fn is_cenotaph(&self) -> bool {
+        self.cenotaph.is_some()
+    }
+    
+    /// Fill in what each edict, pointer and refund pays
+    ///
+    /// `tx` is the transaction the Runestone was decoded from; addresses
+    /// are encoded with the prefixes of `params`.
+    pub fn resolve_outputs(&mut self, tx: &Transaction, params: &NetworkParams) {
+        let protostones = self.protostones.len();
+        let resolve = |index: u128| resolve_output(tx, index, protostones, params);
+        
+        if let Some(runes) = &mut self.runes {
+            for edict in &mut runes.edicts {
+                edict.destination = Some(resolve(edict.output));
+            }
+            runes.pointer_output = runes.pointer.map(|pointer| resolve(u128::from(pointer)));
+        }
+        for protostone in &mut self.protostones {
+            for edict in &mut protostone.edicts {
+                edict.destination = Some(resolve(edict.output));
+            }
+            protostone.pointer_output = protostone.pointer.map(|pointer| resolve(u128::from(pointer)));
+            protostone.refund_output = protostone.refund.map(|refund| resolve(u128::from(refund)));
+        }
+    }
+}
+
+impl From<DecodedRunestone> for Value {
+    fn from(decoded: DecodedRunestone) -> Self {
+        // Map keys are strings and every u128 goes through `wire`
+        serde_json::to_value(decoded).expect("DecodedRunestone serializes to JSON")
+    }
+}
+
+/// The fields of a Runestone, tag by tag
+///
+/// Tags are kept in the order they first appear in the payload, each with
+/// all of its values in order. The JSON form is an object keyed by the tag
+/// in decimal, which only keeps that order when written straight to text.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TagMap(pub Vec<(u128, Vec<u128>)>);
+
+impl TagMap {
+    /// Group the tag/value pairs of a payload's integers
+    ///
+    /// The edicts body isn't a tag/value list, so tag 0 is recorded with the
+    /// integer following it like any other. A trailing tag without a value
+    /// is dropped.
+    pub fn from_integers(integers: &[u128]) -> Self {
+        let mut tags = TagMap::default();
+        for pair in integers.chunks_exact(2) {
+            tags.push(pair[0], pair[1]);
+        }
+        tags
+    }
+    
+    /// Append a value to a tag, adding the tag if it is new
+    pub fn push(&mut self, tag: u128, value: u128) {
+        match self.0.iter_mut().find(|(existing, _)| *existing == tag) {
+            Some((_, values)) => values.push(value),
+            None => self.0.push((tag, vec![value])),
+        }
+    }
+    
+    /// Values of a tag, if it is present
+    pub fn get(&self, tag: u128) -> Option<&[u128]> {
+        self.0.iter().find(|(existing, _)| *existing == tag).map(|(_, values)| values.as_slice())
+    }
+    
+    /// Tags and their values, in order
+    pub fn iter(&self) -> impl Iterator<Item = (u128, &[u128])> {
+        self.0.iter().map(|(tag, values)| (*tag, values.as_slice()))
+    }
+    
+    /// Number of distinct tags
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    
+    /// Whether there are no tags
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    
+    /// Tags the runes protocol doesn't define, in order
+    pub fn unknown_tags(&self) -> Vec<u128> {
+        self.iter().map(|(tag, _)| tag).filter(|tag| TagKind::of(*tag) != TagKind::Known).collect()
+    }
+    
+    /// Undefined even tags, each of which makes the Runestone a cenotaph
+    pub fn unrecognized_even_tags(&self) -> Vec<u128> {
+        self.iter().map(|(tag, _)| tag).filter(|tag| TagKind::of(*tag) == TagKind::UnrecognizedEven).collect()
+    }
+}
+
+/// How the runes protocol treats a tag
+///
+/// Known tags are the even ones in `KNOWN_EVEN_TAGS` (body, flags, rune,
+/// premine, cap, amount, the mint heights and offsets, mint and pointer)
+/// and the odd ones in `KNOWN_ODD_TAGS` (divisibility, spacers, symbol,
+/// nop and the protocol field carrying protostones). Unknown odd tags are
+/// ignored; unknown even tags, including the reserved cenotaph tag 126,
+/// make a cenotaph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TagKind {
+    /// Defined by the protocol
+    Known,
+    /// Undefined and odd, safely ignored
+    UnknownOdd,
+    /// Undefined and even, makes a cenotaph
+    UnrecognizedEven,
+}
+
+impl TagKind {
+    /// Classify a tag
+    pub fn of(tag: u128) -> Self {
+        if KNOWN_EVEN_TAGS.contains(&tag) || KNOWN_ODD_TAGS.contains(&tag) {
+            TagKind::Known
+        } else if tag % 2 == 0 {
+            TagKind::UnrecognizedEven
+        } else {
+            TagKind::UnknownOdd
+        }
+    }
+}
+
+/// Flaws of a cenotaph
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CenotaphInfo {
+    /// Each flaw found, at least one
+    pub flaws: Vec<String>,
+}
+
+/// The standard Runes fields of a Runestone, as read by the ordinals crate
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedRunes {
+    /// Rune etched by the transaction
+    pub etching: Option<DecodedEtching>,
+    /// ID of the rune minted, as `block:tx`
+    pub mint: Option<String>,
+    /// Edicts transferring runes
+    pub edicts: Vec<DecodedEdict>,
+    /// Output receiving runes not assigned by edicts
+    pub pointer: Option<u32>,
+    /// What `pointer` pays, once resolved with `DecodedRunestone::resolve_outputs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pointer_output: Option<ResolvedOutput>,
+}
+
+impl DecodedRunes {
+    /// Whether none of the fields are set, as for a bare protostone carrier
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+impl From<&Runestone> for DecodedRunes {
+    fn from(runestone: &Runestone) -> Self {
+        Self {
+            etching: runestone.etching.as_ref().map(DecodedEtching::from),
+            mint: runestone.mint.map(|id| id.to_string()),
+            edicts: runestone.edicts.iter()
+                .map(|edict| DecodedEdict {
+                    block: u128::from(edict.id.block),
+                    tx: u128::from(edict.id.tx),
+                    amount: edict.amount,
+                    output: u128::from(edict.output),
+                    destination: None,
+                })
+                .collect(),
+            pointer: runestone.pointer,
+            pointer_output: None,
+        }
+    }
+}
+
+impl fmt::Display for DecodedRunes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(etching) = &self.etching {
+            writeln!(f, "Etching: {}", etching)?;
+        }
+        if let Some(mint) = &self.mint {
+            writeln!(f, "Mint: {}", mint)?;
+        }
+        for edict in &self.edicts {
+            writeln!(f, "Edict: {} of {}:{} to output {}", edict.amount, edict.block, edict.tx, edict.output)?;
+        }
+        if let Some(pointer) = self.pointer {
+            writeln!(f, "Pointer: output {}", pointer)?;
+        }
+        Ok(())
+    }
+}
+
+/// A rune etching
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedEtching {
+    /// Name with its spacers, or `None` if one is assigned on etching
+    pub rune: Option<String>,
+    /// Decimal places of amounts
+    pub divisibility: Option<u8>,
+    /// Currency symbol
+    pub symbol: Option<char>,
+    /// Amount allocated to the etcher
+    #[serde(with = "wire::decimal_opt")]
+    pub premine: Option<u128>,
+    /// Open mint terms, if the rune is mintable
+    pub terms: Option<DecodedTerms>,
+    /// Whether the etcher opts into future protocol changes
+    pub turbo: bool,
+}
+
+impl From<&ordinals::Etching> for DecodedEtching {
+    fn from(etching: &ordinals::Etching) -> Self {
+        Self {
+            rune: etching.rune.map(|rune| ordinals::SpacedRune { rune, spacers: etching.spacers.unwrap_or_default() }.to_string()),
+            divisibility: etching.divisibility,
+            symbol: etching.symbol,
+            premine: etching.premine,
+            terms: etching.terms.as_ref().map(|terms| DecodedTerms {
+                amount: terms.amount,
+                cap: terms.cap,
+                height: terms.height,
+                offset: terms.offset,
+            }),
+            turbo: etching.turbo,
+        }
+    }
+}
+
+impl fmt::Display for DecodedEtching {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.rune.as_deref().unwrap_or("(reserved name)"))?;
+        if let Some(symbol) = self.symbol {
+            write!(f, " {}", symbol)?;
+        }
+        write!(f, ", divisibility {}", self.divisibility.unwrap_or(0))?;
+        if let Some(premine) = self.premine {
+            write!(f, ", premine {}", premine)?;
+        }
+        if let Some(terms) = &self.terms {
+            write!(f, ", {}", terms)?;
+        }
+        if self.turbo {
+            write!(f, ", turbo")?;
+        }
+        Ok(())
+    }
+}
+
+/// Terms of an open mint
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedTerms {
+    /// Amount per mint
+    #[serde(with = "wire::decimal_opt")]
+    pub amount: Option<u128>,
+    /// Number of mints allowed
+    #[serde(with = "wire::decimal_opt")]
+    pub cap: Option<u128>,
+    /// Absolute heights the mint opens and closes at
+    pub height: (Option<u64>, Option<u64>),
+    /// Heights relative to the etching the mint opens and closes at
+    pub offset: (Option<u64>, Option<u64>),
+}
+
+impl fmt::Display for DecodedTerms {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bound = |value: Option<u64>| value.map_or_else(|| "-".to_string(), |value| value.to_string());
+        write!(f, "mint {} x {}", self.amount.unwrap_or(0), self.cap.unwrap_or(0))?;
+        if self.height != (None, None) {
+            write!(f, " at heights {}..{}", bound(self.height.0), bound(self.height.1))?;
+        }
+        if self.offset != (None, None) {
+            write!(f, " at offsets {}..{}", bound(self.offset.0), bound(self.offset.1))?;
+        }
+        Ok(())
+    }
+}
+
+/// A protostone in structured form
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DecodedProtostone {
+    /// Protocol the protostone is addressed to
+    #[serde(with = "wire::wide")]
+    pub protocol_tag: u128,
+    /// Message bytes, hex in JSON
+    #[serde(with = "wire::hex_bytes")]
+    pub message: Vec<u8>,
+    /// The message's varints, or `None` if it isn't a varint list
+    #[serde(with = "wire::decimal_seq_opt")]
+    pub cellpack: Option<Vec<u128>>,
+    /// Edicts transferring protorunes
+    pub edicts: Vec<DecodedEdict>,
+    /// Output receiving protorunes not assigned by edicts
+    pub pointer: Option<u32>,
+    /// Output receiving protorunes if the message fails
+    pub refund: Option<u32>,
+    /// What `pointer` pays, once resolved with `DecodedRunestone::resolve_outputs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pointer_output: Option<ResolvedOutput>,
+    /// What `refund` pays, once resolved with `DecodedRunestone::resolve_outputs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refund_output: Option<ResolvedOutput>,
+    /// Protocol tag the protorunes are burned to
+    #[serde(with = "wire::decimal_opt")]
+    pub burn: Option<u128>,
+    /// Protocol-specific reading of the message, loosely structured
+    pub operation: Value,
+}
+
+impl From<&Protostone> for DecodedProtostone {
+    fn from(protostone: &Protostone) -> Self {
+        Self {
+            protocol_tag: protostone.protocol_tag,
+            message: protostone.message.clone(),
+            cellpack: varint::decode_all(&protostone.message).ok(),
+            edicts: protostone.edicts.iter()
+                .map(|edict| DecodedEdict {
+                    block: edict.id.block,
+                    tx: edict.id.tx,
+                    amount: edict.amount,
+                    output: edict.output,
+                    destination: None,
+                })
+                .collect(),
+            pointer: protostone.pointer,
+            refund: protostone.refund,
+            pointer_output: None,
+            refund_output: None,
+            burn: protostone.burn,
+            operation: decode_protostone(protostone.protocol_tag, &protostone.message),
+        }
+    }
+}
+
+impl From<DecodedProtostone> for Value {
+    fn from(decoded: DecodedProtostone) -> Self {
+        serde_json::to_value(decoded).expect("DecodedProtostone serializes to JSON")
+    }
+}
+
+/// An edict of a protostone or of the Runestone itself
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedEdict {
+    /// Block component of the rune or protorune ID
+    #[serde(with = "wire::wide")]
+    pub block: u128,
+    /// Transaction component of the rune or protorune ID
+    #[serde(with = "wire::wide")]
+    pub tx: u128,
+    /// Amount transferred
+    #[serde(with = "wire::decimal")]
+    pub amount: u128,
+    /// Output receiving the amount
+    #[serde(with = "wire::wide")]
+    pub output: u128,
+    /// What `output` pays, once resolved with `DecodedRunestone::resolve_outputs`
+    #[serde(default, flatten, skip_serializing_if = "Option::is_none")]
+    pub destination: Option<ResolvedOutput>,
+}
+
+/// What an output index of a Runestone or protostone pays
+///
+/// Indices past the transaction's outputs are the protostones' virtual
+/// outputs, labeled `protostone #N` in place of an address.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolvedOutput {
+    /// Value of the output, `None` for anything but a real output
+    pub output_value_sats: Option<u64>,
+    /// Kind of script as named by `network::script_type`; `protostone` for a
+    /// virtual output, `all` for the index one past the last output (split
+    /// among every output) and `missing` for indices pointing nowhere
+    pub script_type: String,
+    /// Address of the output, `None` if its script has none
+    pub address: Option<String>,
+}
+
+/// Resolve output `index` of `tx`, which carries `protostones` protostones
+fn resolve_output(tx: &Transaction, index: u128, protostones: usize, params: &NetworkParams) -> ResolvedOutput {
+    let outputs = tx.output.len() as u128;
+    match usize::try_from(index).ok().and_then(|vout| tx.output.get(vout)) {
+        Some(output) => ResolvedOutput {
+            output_value_sats: Some(output.value),
+            script_type: script_type(&output.script_pubkey),
+            address: params.address_from_script(&output.script_pubkey),
+        },
+        None => {
+            let (kind, address) = match index - outputs {
+                0 => ("all", None),
+                // Protostone i is virtual output outputs + 1 + i
+                shadow if shadow <= protostones as u128 => ("protostone", Some(format!("protostone #{}", shadow - 1))),
+                _ => ("missing", None),
+            };
+            ResolvedOutput { output_value_sats: None, script_type: kind.to_string(), address }
+        },
+    }
 }
 
 /// Decode a Runestone from a transaction
@@ -128,14 +559,18 @@ pub mod diesel_operations {
 /// It searches for outputs with OP_RETURN followed by OP_PUSHNUM_13, then decodes
 /// the payload to extract protocol data and protostone information.
 ///
+/// Other OP_RETURN outputs are skipped. As in the ordinals crate, the first
+/// candidate is the Runestone, and if its payload is malformed it is decoded
+/// as a cenotaph rather than skipped.
+///
 /// # Arguments
 ///
 /// * `tx` - The transaction to decode
 ///
 /// # Returns
 ///
-/// A JSON object containing the decoded Runestone data, or an error if no valid
-/// Runestone was found in the transaction.
+/// The decoded Runestone, or a `DecodeError::NoRunestone` error if no
+/// Runestone was found in the transaction. `Value::from` gives its JSON form.
 ///
 /// # Example
 ///
@@ -144,10 +579,10 @@ pub mod diesel_operations {
 /// use deezel::runestone_enhanced::decode_runestone;
 ///
 /// let tx = // get transaction from somewhere
-/// let runestone_data = decode_runestone(&tx)?;
-/// println!("{}", serde_json::to_string_pretty(&runestone_data)?);
+/// let runestone = decode_runestone(&tx)?;
+/// println!("{}", serde_json::to_string_pretty(&runestone)?);
 /// ```
-pub fn decode_runestone(tx: &Transaction) -> Result<Value> {
+pub fn decode_runestone(tx: &Transaction) -> Result<DecodedRunestone> {
     debug!("Decoding Runestone from transaction {}", tx.txid());
     
     // Search transaction outputs for Runestone
@@ -167,50 +602,375 @@ pub fn decode_runestone(tx: &Transaction) -> Result<Value> {
         // Found a Runestone
         debug!("Found Runestone in output {}", vout);
         
-        // Extract payload from script
-        let payload = extract_payload_from_instructions(instructions)?;
+        // A malformed payload makes a cenotaph, decoded as if empty
+        let mut flaws = Vec::new();
+        let payload = extract_payload_from_instructions(instructions).unwrap_or_else(|e| {
+            flaws.push(e.to_string());
+            Vec::new()
+        });
         
         // Decode the integers from the payload
-        let integers = decode_integers(&payload)
-            .context("Failed to decode integers from Runestone payload")?;
-        
-        // Extract protocol data (tag 13)
-        let protocol_data = extract_protocol_data(&integers);
-        
-        // Create the base result
-        let mut result = json!({
-            "transaction_id": tx.txid().to_string(),
-            "output_index": vout,
-            "protocol_data": protocol_data,
+        let integers = varint::decode_all(&payload).unwrap_or_else(|e| {
+            flaws.push(format!("{:#}", e));
+            Vec::new()
         });
+        flaws.extend(message_flaws(&integers, tx.output.len()));
         
-        // Extract all tags and their values
-        let all_tags = extract_all_tags(&integers);
-        result["all_tags"] = all_tags;
+        // The ordinals crate has the final say, e.g. on flags, and reads
+        // the standard Runes fields
+        let mut runes = None;
+        if flaws.is_empty() {
+            match Runestone::decipher(&from_bdk(tx)?) {
+                Some(Artifact::Cenotaph(cenotaph)) => {
+                    flaws.push(cenotaph.flaw.map_or_else(|| "Cenotaph".to_string(), |flaw| flaw.to_string()));
+                },
+                Some(Artifact::Runestone(runestone)) => {
+                    runes = Some(DecodedRunes::from(&runestone)).filter(|runes| !runes.is_empty());
+                },
+                None => {},
+            }
+        }
         
-        // Process protocol data if available
-        if !protocol_data.is_empty() {
-            // Extract protocol tag and message bytes
-            let protocol_tag = protocol_data[0];
-            let message_bytes: Vec<u8> = protocol_data.iter().skip(1).map(|&n| n as u8).collect();
-            
-            result["protocol_tag"] = json!(protocol_tag);
-            result["message_bytes"] = json!(message_bytes);
+        // Every protostone of the protocol field (tag 16383)
+        let protostones = Protostone::decipher(&extract_protocol_data(&integers)).unwrap_or_default();
+        
+        return Ok(DecodedRunestone {
+            version: DECODED_RUNESTONE_VERSION,
+            txid: tx.txid().to_string(),
+            output_index: vout,
+            runes,
+            protostones: protostones.iter().map(DecodedProtostone::from).collect(),
+            all_tags: TagMap::from_integers(&integers),
+            // Cenotaphs burn the runes they would have moved
+            cenotaph: (!flaws.is_empty()).then_some(CenotaphInfo { flaws }),
+            raw_integers: integers,
+        });
+    }
+    
+    Err(DecodeError::NoRunestone.into())
+}
+
+/// Decode a Runestone with the outputs it names resolved
+///
+/// As `decode_runestone`, then `DecodedRunestone::resolve_outputs` against
+/// `tx` with the prefixes of `params`.
+pub fn decode_runestone_with_outputs(tx: &Transaction, params: &NetworkParams) -> Result<DecodedRunestone> {
+    let mut decoded = decode_runestone(tx)?;
+    decoded.resolve_outputs(tx, params);
+    Ok(decoded)
+}
+
+/// Magic bytes starting a serialized PSBT
+const PSBT_MAGIC: &[u8] = b"psbt\xff";
+
+/// Why a transaction given as hex or bytes couldn't be decoded
+///
+/// Returned inside the `anyhow::Error` of the `decode_from_*` functions and
+/// `decode_runestone`, so callers can tell bad input from a transaction that
+/// simply has no Runestone.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input isn't hex
+    #[error("Invalid transaction hex: {0}")]
+    InvalidHex(String),
+    /// The bytes aren't a transaction
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+    /// The bytes start with the PSBT magic but aren't a PSBT
+    #[error("Invalid PSBT: {0}")]
+    InvalidPsbt(String),
+    /// The transaction has no OP_RETURN OP_13 output
+    #[error("No Runestone found in transaction")]
+    NoRunestone,
+}
+
+/// Parse a transaction from hex
+///
+/// Surrounding whitespace and a `0x` prefix are ignored. The hex may be that
+/// of a PSBT, whose unsigned transaction is returned.
+pub fn transaction_from_hex(hex: &str) -> Result<Transaction, DecodeError> {
+    let trimmed = hex.trim();
+    let trimmed = trimmed.strip_prefix("0x").unwrap_or(trimmed);
+    let bytes = hex::decode(trimmed).map_err(|e| DecodeError::InvalidHex(e.to_string()))?;
+    raw_transaction(&bytes)
+}
+
+/// Parse a transaction from hex text or raw bytes, as read from a file
+///
+/// Input that is hex once trimmed goes through `transaction_from_hex`; any
+/// other input is a serialized transaction or PSBT.
+pub fn transaction_from_bytes(bytes: &[u8]) -> Result<Transaction, DecodeError> {
+    let trimmed = bytes.trim_ascii();
+    let trimmed = trimmed.strip_prefix(b"0x").unwrap_or(trimmed);
+    match std::str::from_utf8(trimmed) {
+        Ok(hex) if !hex.is_empty() && hex.bytes().all(|b| b.is_ascii_hexdigit()) => transaction_from_hex(hex),
+        _ => raw_transaction(bytes),
+    }
+}
+
+/// Parse a transaction from a file, as for `transaction_from_bytes`
+pub fn transaction_from_path(path: &Path) -> Result<Transaction> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(transaction_from_bytes(&bytes)?)
+}
+
+/// A serialized transaction, or the unsigned transaction of a serialized PSBT
+fn raw_transaction(bytes: &[u8]) -> Result<Transaction, DecodeError> {
+    if bytes.starts_with(PSBT_MAGIC) {
+        let psbt = PartiallySignedTransaction::deserialize(bytes)
+            .map_err(|e| DecodeError::InvalidPsbt(e.to_string()))?;
+        return Ok(psbt.unsigned_tx);
+    }
+    deserialize(bytes).map_err(|e| DecodeError::InvalidTransaction(e.to_string()))
+}
+
+/// Decode the Runestone of a transaction or PSBT given as hex
+///
+/// Errors carry a `DecodeError` telling invalid hex, an invalid transaction
+/// and a missing Runestone apart.
+pub fn decode_from_hex(hex: &str) -> Result<DecodedRunestone> {
+    decode_runestone(&transaction_from_hex(hex)?)
+}
+
+/// Decode the Runestone of a transaction or PSBT stored in a file
+///
+/// The file holds hex text or raw bytes; see `transaction_from_bytes`.
+pub fn decode_from_path(path: &Path) -> Result<DecodedRunestone> {
+    decode_runestone(&transaction_from_path(path)?)
+}
+
+/// Whether a transaction has a Runestone candidate, an OP_RETURN OP_13 output
+fn has_runestone(tx: &Transaction) -> bool {
+    tx.output.iter().any(|output| {
+        let mut instructions = output.script_pubkey.instructions();
+        instructions.next() == Some(Ok(Instruction::Op(opcodes::all::OP_RETURN)))
+            && instructions.next() == Some(Ok(Instruction::Op(opcodes::all::OP_PUSHNUM_13)))
+    })
+}
+
+/// Decode the Runestones of a block's transactions
+///
+/// Only transactions with a Runestone are returned, in block order, each
+/// with its own result so that one failing transaction doesn't hide the
+/// others.
+pub fn decode_block(txs: &[Transaction]) -> Vec<(Txid, Result<DecodedRunestone>)> {
+    txs.iter()
+        .filter(|tx| has_runestone(tx))
+        .map(|tx| (tx.txid(), decode_runestone(tx)))
+        .collect()
+}
+
+/// Fetch the block at `height` and decode its Runestones
+///
+/// Transactions come from `RpcClient::get_block_transactions`, which fetches
+/// them concurrently. Those that couldn't be fetched are returned with their
+/// error, since whether they carry a Runestone is unknown. Fails only if the
+/// block itself can't be fetched.
+pub async fn decode_block_at_height(rpc: &RpcClient, height: u64) -> Result<Vec<(Txid, Result<DecodedRunestone>)>> {
+    let txs = rpc.get_block_transactions(height).await?;
+    
+    let decoded: Vec<(Txid, Result<DecodedRunestone>)> = txs.into_iter()
+        .filter_map(|(txid, tx)| match tx {
+            Ok(tx) => has_runestone(&tx).then(|| (txid, decode_runestone(&tx))),
+            Err(e) => Some((txid, Err(e))),
+        })
+        .collect();
+    debug!("Decoded {} runestone transaction(s) in block {}", decoded.len(), height);
+    Ok(decoded)
+}
+
+/// Counts of what the Runestones of a block do
+///
+/// A Runestone may both mint and transfer, so it counts toward each.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct BlockSummary {
+    /// Transactions with a Runestone
+    pub runestones: usize,
+    /// Runestones etching a rune
+    pub etchings: usize,
+    /// Runestones minting a rune, or calling opcode 77 through a protostone
+    pub mints: usize,
+    /// Runestones with edicts, in the Runestone or a protostone
+    pub transfers: usize,
+    /// Runestones that are cenotaphs
+    pub cenotaphs: usize,
+    /// Transactions that couldn't be fetched or decoded
+    pub errors: usize,
+}
+
+impl BlockSummary {
+    /// Summarize the results of `decode_block` or `decode_block_at_height`
+    pub fn new(decoded: &[(Txid, Result<DecodedRunestone>)]) -> Self {
+        let mut summary = Self::default();
+        for (_, result) in decoded {
+            let runestone = match result {
+                Ok(runestone) => runestone,
+                Err(_) => {
+                    summary.errors += 1;
+                    continue;
+                },
+            };
             
-            // Decode protostone based on protocol tag
-            result["protostone"] = decode_protostone(protocol_tag, &message_bytes);
+            summary.runestones += 1;
+            if runestone.is_cenotaph() {
+                summary.cenotaphs += 1;
+                continue;
+            }
+            let runes = runestone.runes.as_ref();
+            if runes.is_some_and(|runes| runes.etching.is_some()) {
+                summary.etchings += 1;
+            }
+            let protostone_mint = runestone.protostones.iter()
+                .any(|protostone| protostone.cellpack.as_ref().and_then(|cellpack| cellpack.get(2)) == Some(&77));
+            if runes.is_some_and(|runes| runes.mint.is_some()) || protostone_mint {
+                summary.mints += 1;
+            }
+            let protostone_edicts = runestone.protostones.iter().any(|protostone| !protostone.edicts.is_empty());
+            if runes.is_some_and(|runes| !runes.edicts.is_empty()) || protostone_edicts {
+                summary.transfers += 1;
+            }
+        }
+        summary
+    }
+}
+
+impl fmt::Display for BlockSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} runestone(s): {} mint(s), {} transfer(s), {} etching(s), {} cenotaph(s)",
+            self.runestones, self.mints, self.transfers, self.etchings, self.cenotaphs)?;
+        if self.errors > 0 {
+            write!(f, ", {} error(s)", self.errors)?;
+        }
+        Ok(())
+    }
+}
+
+/// Decode a Runestone into the JSON object `decode_runestone` used to return
+///
+/// Kept for callers indexing into the untyped form: `transaction_id`,
+/// `protocol_data`, a boolean `cenotaph` with its `flaws`, and the first
+/// protostone's `protocol_tag`, `message_bytes` and decoded `protostone`.
+/// New code should use `decode_runestone`.
+pub fn decode_runestone_json(tx: &Transaction) -> Result<Value> {
+    let decoded = decode_runestone(tx)?;
+    let protocol_data: Vec<Value> = decoded.protocol_data().into_iter().map(u128_json).collect();
+    let flaws = decoded.cenotaph.as_ref().map(|cenotaph| cenotaph.flaws.clone()).unwrap_or_default();
+    let first = decoded.protostones.first().cloned();
+    
+    let mut result = Value::from(decoded);
+    if let Some(object) = result.as_object_mut() {
+        object.remove("version");
+        if let Some(txid) = object.remove("txid") {
+            object.insert("transaction_id".to_string(), txid);
+        }
+        object.insert("protocol_data".to_string(), json!(protocol_data));
+        object.insert("cenotaph".to_string(), json!(!flaws.is_empty()));
+        object.insert("flaws".to_string(), json!(flaws));
+        if let Some(protostone) = first {
+            object.insert("protocol_tag".to_string(), u128_json(protostone.protocol_tag));
+            object.insert("message_bytes".to_string(), json!(protostone.message));
+            object.insert("protostone".to_string(), protostone.operation);
+        }
+    }
+    
+    Ok(result)
+}
+
+/// A u128 as a JSON number if it fits in a u64, as a decimal string if not
+fn u128_json(value: u128) -> Value {
+    match u64::try_from(value) {
+        Ok(value) => json!(value),
+        Err(_) => json!(value.to_string()),
+    }
+}
+
+/// Structured form of a protostone
+///
+/// The JSON form of `DecodedProtostone`: edicts are `{block, tx, amount,
+/// output}` objects, amounts and the burn decimal strings, the message hex
+/// and `cellpack` its varints, or null if it isn't a varint list.
+pub fn protostone_json(protostone: &Protostone) -> Value {
+    Value::from(DecodedProtostone::from(protostone))
+}
+
+/// Even tags defined by the runes protocol
+///
+/// Body, flags, rune, premine, the mint terms, mint and pointer. Any other
+/// even tag makes a cenotaph; odd tags may be ignored.
+const KNOWN_EVEN_TAGS: [u128; 12] = [0, 2, 4, 6, 8, 10, 12, 14, 16, 18, 20, 22];
+
+/// Odd tags defined by the runes protocol
+///
+/// Divisibility, spacers, symbol, nop and the protocol field.
+const KNOWN_ODD_TAGS: [u128; 5] = [1, 3, 5, 127, crate::runestone::tag::PROTOCOL];
+
+/// Flaws of a runestone message that make it a cenotaph
+///
+/// Replicates the checks the ordinals crate makes on the decoded integers:
+/// fields come in tag/value pairs, even tags must be known, and the body
+/// after tag 0 must be whole edicts with valid rune IDs and outputs.
+fn message_flaws(integers: &[u128], output_count: usize) -> Vec<String> {
+    let mut flaws = Vec::new();
+    let mut i = 0;
+    
+    while i < integers.len() {
+        let tag = integers[i];
+        if tag == crate::runestone::tag::BODY {
+            flaws.extend(body_flaw(&integers[i + 1..], output_count));
+            break;
+        }
+        if i + 1 >= integers.len() {
+            flaws.push(format!("Tag {} has no value", tag));
+            break;
+        }
+        if TagKind::of(tag) == TagKind::UnrecognizedEven {
+            flaws.push(format!("Unrecognized even tag {}", tag));
+        }
+        i += 2;
+    }
+    
+    flaws
+}
+
+/// First flaw of the edicts body, if any
+///
+/// Edicts are `[block delta, tx, amount, output]`, the rune ID being delta
+/// encoded against the previous edict's.
+fn body_flaw(body: &[u128], output_count: usize) -> Option<String> {
+    let (mut block, mut tx) = (0u128, 0u128);
+    
+    for (index, edict) in body.chunks(4).enumerate() {
+        if edict.len() < 4 {
+            return Some(format!("{} trailing integers after the edicts", edict.len()));
         }
         
-        // Add raw integers for debugging
-        result["raw_integers"] = json!(integers);
+        let id = if edict[0] == 0 {
+            tx.checked_add(edict[1]).map(|tx| (block, tx))
+        } else {
+            block.checked_add(edict[0]).map(|block| (block, edict[1]))
+        };
+        match id {
+            Some((next_block, next_tx)) if next_block <= u128::from(u64::MAX) && next_tx <= u128::from(u32::MAX)
+                && !(next_block == 0 && next_tx > 0) => {
+                block = next_block;
+                tx = next_tx;
+            },
+            _ => return Some(format!("Edict {} has an invalid rune ID", index)),
+        }
         
-        return Ok(result);
+        // An output equal to the count splits the amount across all outputs
+        if edict[3] > output_count as u128 {
+            return Some(format!("Edict {} output {} is past the {} outputs", index, edict[3], output_count));
+        }
     }
     
-    Err(anyhow!("No Runestone found in transaction"))
+    None
 }
 
 /// Extract payload from script instructions
+///
+/// Pushes of any encoding count, direct, PUSHDATA1/2/4 or empty; any other
+/// opcode makes the payload invalid.
 fn extract_payload_from_instructions<'a, I>(instructions: I) -> Result<Vec<u8>>
 where
     I: Iterator<Item = std::result::Result<Instruction<'a>, bdk::bitcoin::blockdata::script::Error>>
@@ -235,7 +995,7 @@ where
     Ok(payload)
 }
 
-/// Extract protocol data (tag 13) from integers
+/// Extract protocol data (tag 16383) from integers
 fn extract_protocol_data(integers: &[u128]) -> Vec<u128> {
     let mut protocol_data = Vec::new();
     let mut i = 0;
@@ -244,8 +1004,7 @@ fn extract_protocol_data(integers: &[u128]) -> Vec<u128> {
         let tag = integers[i];
         i += 1;
         
-        // Tag 13 is the protocol tag
-        if tag == RUNESTONE_MAGIC_NUMBER as u128 && i < integers.len() {
+        if tag == crate::runestone::tag::PROTOCOL && i < integers.len() {
             protocol_data.push(integers[i]);
             i += 1;
         } else {
@@ -259,278 +1018,193 @@ fn extract_protocol_data(integers: &[u128]) -> Vec<u128> {
     protocol_data
 }
 
-/// Extract all tags and their values from integers
-fn extract_all_tags(integers: &[u128]) -> Value {
-    let mut all_tags = json!({});
-    let mut i = 0;
-    
-    while i < integers.len() {
-        if i + 1 < integers.len() {
-            let tag = integers[i];
-            let value = integers[i + 1];
-            
-            // Add to the all_tags object
-            if all_tags[tag.to_string()].is_null() {
-                all_tags[tag.to_string()] = json!([value]);
-            } else {
-                all_tags[tag.to_string()].as_array_mut().unwrap().push(json!(value));
-            }
-            
-            i += 2;
-        } else {
-            // Odd number of integers, skip the last one
-            i += 1;
-        }
-    }
-    
-    all_tags
-}
-
 /// Decode protostone based on protocol tag
+///
+/// Alkanes messages are read as a `Cellpack`, and those that aren't one are
+/// reported as raw bytes with the reason. Protorune operations and events
+/// have layouts of their own.
 fn decode_protostone(protocol_tag: u128, message_bytes: &[u8]) -> Value {
     match protocol_tag {
-        protocol_tags::DIESEL => decode_diesel_protostone(message_bytes),
-        protocol_tags::ALKANE => decode_alkane_protostone(message_bytes),
         protocol_tags::PROTORUNE => decode_protorune_protostone(message_bytes),
-        protocol_tags::ALKANE_STATE => decode_alkane_state_protostone(message_bytes),
         protocol_tags::ALKANE_EVENT => decode_alkane_event_protostone(message_bytes),
-        _ => json!({
-            "type": "Unknown",
-            "protocol_tag": protocol_tag,
-            "cellpack": message_bytes
-        })
+        _ => decode_cellpack_protostone(protocol_tag, message_bytes),
     }
 }
 
-/// Decode DIESEL protostone
-fn decode_diesel_protostone(message_bytes: &[u8]) -> Value {
-    // DIESEL token minting
-    if message_bytes == diesel_operations::MINT {
-        json!({
+/// Decode a protostone whose message is a cellpack
+///
+/// DIESEL mints are recognized by their target and opcode, `2:0` opcode 77,
+/// wherever the encoding puts them.
+fn decode_cellpack_protostone(protocol_tag: u128, message_bytes: &[u8]) -> Value {
+    let type_name = match protocol_tag {
+        protocol_tags::DIESEL | protocol_tags::ALKANE => "Alkane",
+        protocol_tags::ALKANE_STATE => "AlkaneState",
+        _ => "Unknown",
+    };
+    
+    let mut result = match Cellpack::decode(message_bytes) {
+        Ok(cellpack) if cellpack.is_diesel_mint() && protocol_tag == protocol_tags::DIESEL => json!({
             "type": "DIESEL",
             "operation": "mint",
-            "cellpack": {
-                "message_type": message_bytes[0],
-                "reserved": message_bytes[1],
-                "action": "M" // ASCII 77 = 'M' for 'Mint'
+            "cellpack": cellpack
+        }),
+        Ok(cellpack) => json!({
+            "type": type_name,
+            "operation": if cellpack.is_deploy() { "deploy" } else { "call" },
+            "cellpack": cellpack
+        }),
+        Err(e) => {
+            let mut result = json!({
+                "type": type_name,
+                "operation": "unknown",
+                "cellpack": message_bytes
+            });
+            if !message_bytes.is_empty() {
+                result["error"] = json!(e.to_string());
             }
-        })
-    } else {
-        json!({
-            "type": "DIESEL",
-            "operation": "unknown",
-            "cellpack": message_bytes
-        })
+            result
+        },
+    };
+    if type_name == "Unknown" {
+        result["protocol_tag"] = u128_json(protocol_tag);
     }
+    
+    result
 }
 
-/// Decode Alkane contract call protostone
-fn decode_alkane_protostone(message_bytes: &[u8]) -> Value {
+/// Decode Protorune token operation protostone
+///
+/// The message is a list of LEB128 values, the operation type first, and is
+/// reported as raw bytes with the reason if it isn't one.
+fn decode_protorune_protostone(message_bytes: &[u8]) -> Value {
     let mut result = json!({
-        "type": "Alkane",
-        "operation": "contract_call",
+        "type": "Protorune",
+        "operation": "token_operation",
         "cellpack": message_bytes
     });
     
-    // Try to decode the cellpack structure
-    if message_bytes.len() >= 2 {
-        let call_type = message_bytes[0];
-        let data = &message_bytes[1..];
-        
-        let call_type_name = match call_type {
-            1 => "deploy",
-            2 => "call",
-            3 => "upgrade",
-            _ => "unknown"
-        };
-        
-        result["cellpack"] = json!({
-            "call_type": call_type,
-            "call_type_name": call_type_name,
-            "data": data
-        });
-        
-        // For contract calls (type 2), try to decode function selector and arguments
-        if call_type == 2 && data.len() >= 4 {
-            let function_selector = &data[0..4];
-            let arguments = &data[4..];
-            
-            result["cellpack"]["function_selector"] = json!(hex::encode(function_selector));
-            result["cellpack"]["arguments"] = json!(hex::encode(arguments));
-        }
+    let values = match varint::decode_all(message_bytes) {
+        Ok(values) => values,
+        Err(e) => {
+            result["error"] = json!(e.to_string());
+            return result;
+        },
+    };
+    if values.len() < 2 {
+        return result;
     }
+    let operation_type = values[0];
+    let data = &values[1..];
+    let wide = |values: &[u128]| values.iter().copied().map(u128_json).collect::<Vec<_>>();
     
-    result
-}
-
-/// Decode Protorune token operation protostone
-fn decode_protorune_protostone(message_bytes: &[u8]) -> Value {
-    let mut result = json!({
-        "type": "Protorune",
-        "operation": "token_operation",
-        "cellpack": message_bytes
+    let operation = u8::try_from(operation_type).ok();
+    let operation_name = match operation {
+        Some(protorune_operations::MINT) => "mint",
+        Some(protorune_operations::TRANSFER) => "transfer",
+        Some(protorune_operations::BURN) => "burn",
+        Some(protorune_operations::SPLIT) => "split",
+        Some(protorune_operations::JOIN) => "join",
+        _ => "unknown"
+    };
+    
+    result["cellpack"] = json!({
+        "operation_type": u128_json(operation_type),
+        "operation_name": operation_name,
+        "data": wide(data)
     });
     
-    // Try to decode the cellpack structure
-    if message_bytes.len() >= 2 {
-        let operation_type = message_bytes[0];
-        let data = &message_bytes[1..];
-        
-        let operation_name = match operation_type {
-            protorune_operations::MINT => "mint",
-            protorune_operations::TRANSFER => "transfer",
-            protorune_operations::BURN => "burn",
-            protorune_operations::SPLIT => "split",
-            protorune_operations::JOIN => "join",
-            _ => "unknown"
-        };
-        
-        result["cellpack"] = json!({
-            "operation_type": operation_type,
-            "operation_name": operation_name,
-            "data": data
+    // For mint operations, try to decode token details
+    if operation == Some(protorune_operations::MINT) && data.len() >= 3 {
+        result["cellpack"]["token_details"] = json!({
+            "token_id": u128_json(data[0]),
+            "amount": u128_json(data[1]),
+            "metadata": wide(&data[2..])
         });
-        
-        // For mint operations, try to decode token details
-        if operation_type == protorune_operations::MINT && data.len() >= 3 {
-            let token_id = data[0];
-            let amount = data[1];
-            let metadata = &data[2..];
-            
-            result["cellpack"]["token_details"] = json!({
-                "token_id": token_id,
-                "amount": amount,
-                "metadata": metadata
-            });
-        }
-        
-        // For transfer operations, try to decode transfer details
-        if operation_type == protorune_operations::TRANSFER && data.len() >= 3 {
-            let token_id = data[0];
-            let amount = data[1];
-            let recipient = &data[2..];
-            
-            result["cellpack"]["transfer_details"] = json!({
-                "token_id": token_id,
-                "amount": amount,
-                "recipient": hex::encode(recipient)
-            });
+    }
+    
+    // For transfer operations, the recipient is the bytes after the token ID
+    // and amount
+    if operation == Some(protorune_operations::TRANSFER) && data.len() >= 3 {
+        let recipient = (0..3).try_fold(message_bytes, |rest, _| varint::decode(rest).map(|(_, len)| &rest[len..]));
+        result["cellpack"]["transfer_details"] = json!({
+            "token_id": u128_json(data[0]),
+            "amount": u128_json(data[1]),
+            "recipient": recipient.map(hex::encode).unwrap_or_default()
+        });
+    }
+    
+    // Split and join operations carry the rune ID and amounts
+    if operation == Some(protorune_operations::SPLIT) || operation == Some(protorune_operations::JOIN) {
+        if data.len() >= 3 {
+            let rune_id = format!("{}:{}", data[0], data[1]);
+            let amounts: Vec<String> = data[2..].iter().map(|amount| amount.to_string()).collect();
+            if operation == Some(protorune_operations::SPLIT) {
+                result["cellpack"]["split_details"] = json!({
+                    "rune_id": rune_id,
+                    "amounts": amounts
+                });
+            } else {
+                result["cellpack"]["join_details"] = json!({
+                    "rune_id": rune_id,
+                    "amount": amounts[0]
+                });
+            }
+        } else {
+            result["error"] = json!("Invalid split or join cellpack");
         }
     }
     
     result
 }
 
-/// Decode Alkane state operation protostone
-fn decode_alkane_state_protostone(message_bytes: &[u8]) -> Value {
-    json!({
-        "type": "AlkaneState",
-        "operation": "state_operation",
-        "cellpack": message_bytes
-    })
-}
-
 /// Decode Alkane event operation protostone
+///
+/// The message carries length-prefixed events (see `decode_events`); if it
+/// doesn't parse, the raw bytes are reported with the reason.
 fn decode_alkane_event_protostone(message_bytes: &[u8]) -> Value {
-    json!({
+    let mut result = json!({
         "type": "AlkaneEvent",
         "operation": "event_operation",
         "cellpack": message_bytes
-    })
-}
-
-/// Decode integers from a payload
-///
-/// This function decodes a sequence of variable-length integers from a byte payload.
-/// It processes the payload sequentially, extracting one integer at a time until
-/// the entire payload has been consumed.
-///
-/// # Arguments
-///
-/// * `payload` - The byte payload to decode
-///
-/// # Returns
-///
-/// A vector of decoded integers, or an error if the payload is invalid.
-///
-/// # Errors
-///
-/// Returns an error if:
-/// - The payload contains a truncated varint
-/// - A varint is too large (exceeds 128 bits)
-fn decode_integers(payload: &[u8]) -> Result<Vec<u128>> {
-    let mut integers = Vec::new();
-    let mut i = 0;
+    });
     
-    while i < payload.len() {
-        let (integer, length) = decode_varint(&payload[i..])
-            .context(format!("Failed to decode varint at position {}", i))?;
-        integers.push(integer);
-        i += length;
+    match decode_events(message_bytes) {
+        Ok(events) => result["events"] = json!(events),
+        Err(e) => result["error"] = json!(e.to_string()),
     }
     
-    Ok(integers)
+    result
 }
 
-/// Decode a variable-length integer
-///
-/// This function decodes a single variable-length integer from a byte slice using
-/// the LEB128 encoding format. Each byte uses 7 bits for the value and 1 bit to
-/// indicate if more bytes follow.
-///
-/// # Arguments
-///
-/// * `bytes` - The byte slice to decode from
-///
-/// # Returns
-///
-/// A tuple containing the decoded integer and the number of bytes consumed,
-/// or an error if the encoding is invalid.
-///
-/// # Errors
-///
-/// Returns an error if:
-/// - The byte slice is empty or truncated
-/// - The varint is too large (exceeds 128 bits)
-///
-/// # Algorithm
-///
-/// The LEB128 encoding uses the high bit (0x80) of each byte to indicate if more
-/// bytes follow (1) or if this is the last byte (0). The remaining 7 bits contribute
-/// to the value, with each successive byte adding 7 more bits of precision.
-fn decode_varint(bytes: &[u8]) -> Result<(u128, usize)> {
-    let mut result: u128 = 0;
-    let mut shift = 0;
-    let mut i = 0;
-    
-    loop {
-        if i >= bytes.len() {
-            return Err(anyhow!("Truncated varint"));
-        }
-        
-        let byte = bytes[i];
-        i += 1;
-        
-        result |= u128::from(byte & 0x7f) << shift;
-        
-        if byte & 0x80 == 0 {
-            break;
-        }
-        
-        shift += 7;
-        
-        if shift > 127 {
-            return Err(anyhow!("Varint too large"));
+/// A Runestone as deciphered by the ordinals crate
+#[derive(Debug)]
+pub enum Decoded {
+    /// A valid Runestone
+    Runestone {
+        /// Standard Runes fields, `None` if it has none
+        runes: Option<DecodedRunes>,
+        /// Protostones of the protocol field, empty if it has none
+        protostones: Vec<Protostone>,
+    },
+    /// A malformed Runestone, which burns its runes, and its flaws
+    Cenotaph(Vec<String>),
+}
+
+impl Decoded {
+    /// The Protostones, or an error listing the cenotaph's flaws
+    pub fn into_protostones(self) -> Result<Vec<Protostone>> {
+        match self {
+            Decoded::Runestone { protostones, .. } => Ok(protostones),
+            Decoded::Cenotaph(flaws) => Err(anyhow!("Runestone is a cenotaph: {}", flaws.join(", "))),
         }
     }
-    
-    Ok((result, i))
 }
 
 /// Format a Runestone from a transaction using the ordinals crate
 ///
 /// This function uses the ordinals crate to extract a Runestone from a transaction
-/// and convert it to a vector of Protostones.
+/// and convert it to a vector of Protostones. Plain Runes transactions, such as
+/// etchings and transfers, have their standard fields and no Protostones.
 ///
 /// # Arguments
 ///
@@ -538,7 +1212,8 @@ fn decode_varint(bytes: &[u8]) -> Result<(u128, usize)> {
 ///
 /// # Returns
 ///
-/// A vector of Protostones, or an error if no valid Runestone was found in the transaction.
+/// The Runes fields and Protostones, or the flaws of a malformed Runestone
+/// (a cenotaph), or an error if no Runestone was found in the transaction.
 ///
 /// # Example
 ///
@@ -547,16 +1222,16 @@ fn decode_varint(bytes: &[u8]) -> Result<(u128, usize)> {
 /// use deezel::runestone_enhanced::format_runestone;
 ///
 /// let tx = // get transaction from somewhere
-/// let protostones = format_runestone(&tx)?;
+/// let protostones = format_runestone(&tx)?.into_protostones()?;
 /// for protostone in protostones {
 ///     println!("{:?}", protostone);
 /// }
 /// ```
-pub fn format_runestone(tx: &Transaction) -> Result<Vec<Protostone>> {
+pub fn format_runestone(tx: &Transaction) -> Result<Decoded> {
     trace!("Formatting Runestone from transaction {}", tx.txid());
     
     // Convert BDK transaction to Bitcoin transaction
-    let bitcoin_tx = from_bdk(tx.clone());
+    let bitcoin_tx = from_bdk(tx)?;
     
     // Use the ordinals crate to decipher the Runestone
     let artifact = Runestone::decipher(&bitcoin_tx)
@@ -566,11 +1241,421 @@ pub fn format_runestone(tx: &Transaction) -> Result<Vec<Protostone>> {
     // Extract the Runestone from the artifact
     match artifact {
         Artifact::Runestone(ref runestone) => {
-            // Convert the Runestone to Protostones
-            Protostone::from_runestone(runestone)
-                .context("Failed to convert Runestone to Protostones")
+            // Convert the protocol field, if any, to Protostones
+            let protostones = match runestone.protocol {
+                Some(_) => Protostone::from_runestone(runestone)
+                    .context("Failed to convert Runestone to Protostones")?,
+                None => Vec::new(),
+            };
+            let runes = Some(DecodedRunes::from(runestone)).filter(|runes| !runes.is_empty());
+            Ok(Decoded::Runestone { runes, protostones })
+        },
+        Artifact::Cenotaph(cenotaph) => {
+            Ok(Decoded::Cenotaph(cenotaph.flaw.map(|flaw| flaw.to_string()).into_iter().collect()))
         },
-        _ => Err(anyhow!("Artifact is not a Runestone"))
+    }
+}
+
+/// Explain protostones in plain language
+///
+/// Each protostone gets a labeled, indented block: its protocol, what its
+/// cellpack does, each edict, its pointer and refund, and any burn. Outputs
+/// are shown with their address on `params.network`; indices past the
+/// transaction's outputs are the protostones' own virtual outputs.
+///
+/// # Example
+///
+/// ```ignore
+/// use deezel_cli::network::NetworkParams;
+/// use deezel_cli::runestone_enhanced::{explain, format_runestone};
+///
+/// let tx = // get transaction from somewhere
+/// let protostones = format_runestone(&tx)?.into_protostones()?;
+/// println!("{}", explain(&protostones, &tx, &NetworkParams::mainnet()));
+/// ```
+pub fn explain(protostones: &[Protostone], tx: &Transaction, params: &NetworkParams) -> String {
+    let mut lines = Vec::new();
+    for (index, protostone) in protostones.iter().enumerate() {
+        lines.push(format!("Protostone {}: {} (protocol {})", index, protocol_name(protostone.protocol_tag), protostone.protocol_tag));
+        lines.extend(explain_message(protostone.protocol_tag, &protostone.message).into_iter().map(|line| format!("  {}", line)));
+        for edict in &protostone.edicts {
+            lines.push(format!("  Edict: send {} {} to {}",
+                edict.amount, token_name(AlkaneId::from(edict.id)), describe_output(tx, edict.output, protostones.len(), params)));
+        }
+        if let Some(pointer) = protostone.pointer {
+            lines.push(format!("  Pointer: {}", describe_output(tx, u128::from(pointer), protostones.len(), params)));
+        }
+        if let Some(refund) = protostone.refund {
+            lines.push(format!("  Refund: {}", describe_output(tx, u128::from(refund), protostones.len(), params)));
+        }
+        if let Some(burn) = protostone.burn {
+            lines.push(format!("  Burn: runes burned to protocol {}", burn));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Name of the protocol a protostone is addressed to
+fn protocol_name(protocol_tag: u128) -> &'static str {
+    match protocol_tag {
+        protocol_tags::DIESEL => "DIESEL",
+        protocol_tags::ALKANE => "alkane",
+        protocol_tags::PROTORUNE => "protorune",
+        protocol_tags::ALKANE_STATE => "alkane state",
+        protocol_tags::ALKANE_EVENT => "alkane event",
+        _ => "unknown",
+    }
+}
+
+/// An alkane ID, named if it is DIESEL
+fn token_name(id: AlkaneId) -> String {
+    if id == AlkaneId::new(crate::runestone::diesel_id::BLOCK, crate::runestone::diesel_id::TX) {
+        format!("DIESEL ({})", id)
+    } else {
+        format!("alkane {}", id)
+    }
+}
+
+/// What a protostone's message does, as labeled lines
+///
+/// Cellpacks are `[block, tx, opcode, inputs...]`; targets with blocks 1,
+/// 3, 5 and 6 deploy contracts rather than call one.
+fn explain_message(protocol_tag: u128, message: &[u8]) -> Vec<String> {
+    if message.is_empty() {
+        return Vec::new();
+    }
+    if protocol_tag == protocol_tags::ALKANE_EVENT {
+        return match decode_events(message) {
+            Ok(events) => events.iter().map(|event| format!("Event: {}", event)).collect(),
+            Err(_) => vec![format!("Message: {}", hex::encode(message))],
+        };
+    }
+    
+    let cellpack = match Cellpack::decode(message) {
+        Ok(cellpack) if matches!(protocol_tag, protocol_tags::DIESEL | protocol_tags::ALKANE) => cellpack,
+        _ => return vec![format!("Message: {}", hex::encode(message))],
+    };
+    let target = match (cellpack.target.block, cellpack.target.tx) {
+        (1, _) => "Deploy: new contract".to_string(),
+        (3, tx) => format!("Deploy: contract at reserved ID 4:{}", tx),
+        (5, tx) => format!("Deploy: clone of 2:{}", tx),
+        (6, tx) => format!("Deploy: clone of 4:{}", tx),
+        (block, tx) => format!("Call: {}", token_name(AlkaneId::new(block, tx))),
+    };
+    let mut line = target;
+    if let Some(opcode) = cellpack.opcode() {
+        let name = match opcode {
+            INITIALIZE_OPCODE => " (initialize)",
+            MINT_OPCODE => " (mint)",
+            _ => "",
+        };
+        line.push_str(&format!(", opcode {}{}", opcode, name));
+    }
+    if !cellpack.arguments().is_empty() {
+        let inputs: Vec<String> = cellpack.arguments().iter().map(|input| input.to_string()).collect();
+        line.push_str(&format!(", inputs [{}]", inputs.join(", ")));
+    }
+    vec![line]
+}
+
+/// An output index with its address, or what it stands for if it has none
+fn describe_output(tx: &Transaction, index: u128, protostones: usize, params: &NetworkParams) -> String {
+    let output = resolve_output(tx, index, protostones, params);
+    match (output.script_type.as_str(), output.address) {
+        ("op_return", _) => format!("output {} (OP_RETURN)", index),
+        (_, Some(address)) => format!("output {} ({})", index, address),
+        _ => format!("output {}", index),
+    }
+}
+
+/// Fields on which the manual and ordinals decoders were compared
+///
+/// Values are the JSON form of each field; `mismatched` holds the manual
+/// value first.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct DecoderDiff {
+    /// Fields both decoders report with the same value
+    pub agreed: BTreeMap<String, Value>,
+    /// Fields both decoders report with different values
+    pub mismatched: BTreeMap<String, (Value, Value)>,
+    /// Fields only `decode_runestone` reports
+    pub only_manual: BTreeMap<String, Value>,
+    /// Fields only the ordinals crate reports
+    pub only_ordinals: BTreeMap<String, Value>,
+}
+
+impl DecoderDiff {
+    /// Whether the decoders agree on every field they both report
+    pub fn is_consistent(&self) -> bool {
+        self.mismatched.is_empty()
+    }
+}
+
+/// Decode a transaction with both decoders and compare the results
+///
+/// Compared are whether a runestone was found, its pointer, its protocol
+/// data, edicts and cenotaph status. A field only one decoder understands
+/// (the manual decoder knows nothing of edicts) is reported as present on
+/// one side rather than as a disagreement.
+pub fn diff_decoders(tx: &Transaction) -> DecoderDiff {
+    let manual = manual_fields(tx);
+    let mut ordinals = ordinals_fields(tx);
+    let mut diff = DecoderDiff::default();
+    
+    for (field, manual_value) in manual {
+        match ordinals.remove(&field) {
+            Some(ordinals_value) if ordinals_value == manual_value => {
+                diff.agreed.insert(field, manual_value);
+            },
+            Some(ordinals_value) => {
+                diff.mismatched.insert(field, (manual_value, ordinals_value));
+            },
+            None => {
+                diff.only_manual.insert(field, manual_value);
+            },
+        }
+    }
+    diff.only_ordinals = ordinals;
+    
+    diff
+}
+
+/// Comparable fields of the `decode_runestone` result
+fn manual_fields(tx: &Transaction) -> BTreeMap<String, Value> {
+    let mut fields = BTreeMap::new();
+    let decoded = match decode_runestone(tx) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            debug!("Manual decoder found no runestone: {}", e);
+            fields.insert("runestone".to_string(), json!(false));
+            return fields;
+        },
+    };
+    
+    fields.insert("runestone".to_string(), json!(true));
+    fields.insert("cenotaph".to_string(), json!(decoded.is_cenotaph()));
+    if let Some(pointer) = decoded.all_tags.get(crate::runestone::tag::POINTER).and_then(|values| values.first()) {
+        fields.insert("pointer".to_string(), u128_json(*pointer));
+    }
+    // Numbers are compared as strings, as protocol values may exceed u64
+    let protocol = decoded.protocol_data();
+    if !protocol.is_empty() {
+        let protocol: Vec<String> = protocol.iter().map(|value| value.to_string()).collect();
+        fields.insert("protocol".to_string(), json!(protocol));
+    }
+    fields
+}
+
+/// Comparable fields of the ordinals crate's artifact
+fn ordinals_fields(tx: &Transaction) -> BTreeMap<String, Value> {
+    let mut fields = BTreeMap::new();
+    let bitcoin_tx = match from_bdk(tx) {
+        Ok(bitcoin_tx) => bitcoin_tx,
+        Err(e) => {
+            fields.insert("error".to_string(), json!(format!("{:#}", e)));
+            return fields;
+        },
+    };
+    let runestone = match Runestone::decipher(&bitcoin_tx) {
+        Some(Artifact::Runestone(runestone)) => runestone,
+        Some(Artifact::Cenotaph(cenotaph)) => {
+            fields.insert("runestone".to_string(), json!(true));
+            fields.insert("cenotaph".to_string(), json!(true));
+            if let Some(flaw) = cenotaph.flaw {
+                fields.insert("flaw".to_string(), json!(flaw.to_string()));
+            }
+            return fields;
+        },
+        None => {
+            fields.insert("runestone".to_string(), json!(false));
+            return fields;
+        },
+    };
+    
+    fields.insert("runestone".to_string(), json!(true));
+    fields.insert("cenotaph".to_string(), json!(false));
+    if let Some(pointer) = runestone.pointer {
+        fields.insert("pointer".to_string(), json!(pointer));
+    }
+    if let Some(protocol) = &runestone.protocol {
+        let protocol: Vec<String> = protocol.iter().map(|value| value.to_string()).collect();
+        fields.insert("protocol".to_string(), json!(protocol));
+    }
+    if !runestone.edicts.is_empty() {
+        let edicts: Vec<Value> = runestone.edicts.iter()
+            .map(|edict| json!({
+                "id": format!("{}:{}", edict.id.block, edict.id.tx),
+                "amount": edict.amount.to_string(),
+                "output": edict.output,
+            }))
+            .collect();
+        fields.insert("edicts".to_string(), json!(edicts));
+    }
+    fields
+}
+
+/// Serde helpers for the JSON form of `DecodedRunestone`
+///
+/// `wide` values are numbers if they fit in a u64 and decimal strings if
+/// not, `decimal` values always strings. Both forms are read back.
+mod wire {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use serde_json::Value;
+    
+    /// A u128 written as a number when it fits in a u64
+    struct Wide(u128);
+    
+    /// A u128 always written as a decimal string
+    struct Decimal(u128);
+    
+    fn parse<E: Error>(value: &Value) -> Result<u128, E> {
+        let parsed = match value {
+            Value::Number(number) => number.as_u64().map(u128::from),
+            Value::String(text) => text.parse().ok(),
+            _ => None,
+        };
+        parsed.ok_or_else(|| E::custom(format!("Invalid u128: {}", value)))
+    }
+    
+    impl Serialize for Wide {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            super::u128_json(self.0).serialize(serializer)
+        }
+    }
+    
+    impl<'de> Deserialize<'de> for Wide {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            parse(&Value::deserialize(deserializer)?).map(Wide)
+        }
+    }
+    
+    impl Serialize for Decimal {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&self.0.to_string())
+        }
+    }
+    
+    impl<'de> Deserialize<'de> for Decimal {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            parse(&Value::deserialize(deserializer)?).map(Decimal)
+        }
+    }
+    
+    pub mod wide {
+        use super::*;
+        
+        pub fn serialize<S: Serializer>(value: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+            Wide(*value).serialize(serializer)
+        }
+        
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+            Wide::deserialize(deserializer).map(|wide| wide.0)
+        }
+    }
+    
+    pub mod wide_seq {
+        use super::*;
+        
+        pub fn serialize<S: Serializer>(values: &[u128], serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(values.iter().map(|value| Wide(*value)))
+        }
+        
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u128>, D::Error> {
+            Ok(Vec::<Wide>::deserialize(deserializer)?.into_iter().map(|wide| wide.0).collect())
+        }
+    }
+    
+    /// Tags as decimal string keys, their values `wide`
+    pub mod wide_tags {
+        use super::*;
+        use crate::runestone_enhanced::TagMap;
+        use serde::de::{MapAccess, Visitor};
+        use std::fmt;
+        
+        pub fn serialize<S: Serializer>(tags: &TagMap, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_map(tags.iter().map(|(tag, values)| {
+                (tag.to_string(), values.iter().map(|value| Wide(*value)).collect::<Vec<Wide>>())
+            }))
+        }
+        
+        /// Reads the object entry by entry, so tags keep the order they're written in
+        struct TagMapVisitor;
+        
+        impl<'de> Visitor<'de> for TagMapVisitor {
+            type Value = TagMap;
+            
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("an object of tags and their values")
+            }
+            
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<TagMap, A::Error> {
+                let mut tags = TagMap::default();
+                while let Some((tag, values)) = map.next_entry::<String, Vec<Wide>>()? {
+                    let tag = tag.parse().map_err(|_| A::Error::custom(format!("Invalid tag: {}", tag)))?;
+                    for value in values {
+                        tags.push(tag, value.0);
+                    }
+                }
+                Ok(tags)
+            }
+        }
+        
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<TagMap, D::Error> {
+            deserializer.deserialize_map(TagMapVisitor)
+        }
+    }
+    
+    pub mod decimal {
+        use super::*;
+        
+        pub fn serialize<S: Serializer>(value: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+            Decimal(*value).serialize(serializer)
+        }
+        
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+            Decimal::deserialize(deserializer).map(|decimal| decimal.0)
+        }
+    }
+    
+    pub mod decimal_opt {
+        use super::*;
+        
+        pub fn serialize<S: Serializer>(value: &Option<u128>, serializer: S) -> Result<S::Ok, S::Error> {
+            value.map(Decimal).serialize(serializer)
+        }
+        
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u128>, D::Error> {
+            Ok(Option::<Decimal>::deserialize(deserializer)?.map(|decimal| decimal.0))
+        }
+    }
+    
+    pub mod decimal_seq_opt {
+        use super::*;
+        
+        pub fn serialize<S: Serializer>(values: &Option<Vec<u128>>, serializer: S) -> Result<S::Ok, S::Error> {
+            values.as_ref()
+                .map(|values| values.iter().map(|value| Decimal(*value)).collect::<Vec<Decimal>>())
+                .serialize(serializer)
+        }
+        
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<u128>>, D::Error> {
+            Ok(Option::<Vec<Decimal>>::deserialize(deserializer)?
+                .map(|values| values.into_iter().map(|decimal| decimal.0).collect()))
+        }
+    }
+    
+    /// Bytes as a hex string
+    pub mod hex_bytes {
+        use super::*;
+        
+        pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&hex::encode(bytes))
+        }
+        
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+            hex::decode(String::deserialize(deserializer)?).map_err(D::Error::custom)
+        }
     }
 }
 
@@ -591,7 +1676,7 @@ mod tests {
         let bdk_tx: bdk::bitcoin::Transaction = deserialize(&tx_bytes).expect("Failed to deserialize transaction");
 
         // Try to format the Runestone
-        match format_runestone(&bdk_tx) {
+        match format_runestone(&bdk_tx).and_then(Decoded::into_protostones) {
             Ok(protostones) => {
                 println!("Successfully formatted {} protostones:", protostones.len());
                 for (i, protostone) in protostones.iter().enumerate() {
@@ -603,6 +1688,522 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_from_bdk() {
+        let tx_hex = "0200000000010141de32694c6aece390828c54475862396edfd46289bbd0f7b78f3e34ee80b7880300000000fdffffff024a010000000000002251200e5843aef2fa13444715b7002071678368e2ae5a6da415e0395448ad1cc9c2200000000000000000116a5d0eff7f818cec82d08bc0a882cdd215024830450221008c8de39854dfea97bfc0cac9f2d0843664b413eb6e135fd99896fb4b03b2e26402207003b3ec1950edd4593130ad934a2551ee4cb7249511a73263441ee6cc37b73a01210287698f1cd27599d8d32fdd5a29fa500d54d8bb2ef5355ca6753107539c47a9b500000000";
+        let tx: Transaction = deserialize(&hex::decode(tx_hex).unwrap()).unwrap();
+        let converted = from_bdk(&tx).unwrap();
+        assert_eq!(bitcoin::consensus::encode::serialize_hex(&converted), tx_hex);
+        assert_eq!(converted.compute_txid().to_string(), tx.txid().to_string());
+
+        // Without inputs the transaction encoding is ambiguous, the parts aren't
+        let mint = crate::runestone::Runestone::new_diesel().encipher().unwrap();
+        let no_inputs = Transaction { input: Vec::new(), ..tx_with_script(mint.into_bytes()) };
+        let converted = from_bdk(&no_inputs).unwrap();
+        assert!(converted.input.is_empty());
+        assert_eq!(converted.output.len(), 2);
+        assert_eq!(format_runestone(&no_inputs).unwrap().into_protostones().unwrap()[0].message, vec![2, 0, 77]);
+    }
+
+    fn tx_with_script(script: Vec<u8>) -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: bdk::bitcoin::absolute::LockTime::ZERO,
+            input: vec![bdk::bitcoin::TxIn::default()],
+            output: vec![
+                bdk::bitcoin::TxOut { value: 546, script_pubkey: bdk::bitcoin::ScriptBuf::new() },
+                bdk::bitcoin::TxOut { value: 0, script_pubkey: bdk::bitcoin::ScriptBuf::from_bytes(script) },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_diff_decoders() {
+        // Both see the pointer; only ordinals knows about edicts
+        let runestone = Runestone {
+            edicts: vec![ordinals::Edict { id: ordinals::RuneId { block: 2, tx: 1 }, amount: 500, output: 0 }],
+            pointer: Some(1),
+            ..Default::default()
+        };
+        let diff = diff_decoders(&tx_with_script(runestone.encipher().into_bytes()));
+        assert!(diff.is_consistent());
+        assert_eq!(diff.agreed["pointer"], json!(1));
+        assert_eq!(diff.agreed["cenotaph"], json!(false));
+        assert_eq!(diff.only_ordinals["edicts"][0]["amount"], "500");
+        assert!(diff.only_manual.is_empty());
+
+        // No runestone at all
+        let diff = diff_decoders(&tx_with_script(vec![0x6a, 0x01, 0x00]));
+        assert!(diff.is_consistent());
+        assert_eq!(diff.agreed["runestone"], json!(false));
+
+        // An opcode in the payload makes a cenotaph to both decoders
+        let diff = diff_decoders(&tx_with_script(vec![0x6a, 0x5d, 0x69]));
+        assert!(diff.is_consistent());
+        assert_eq!(diff.agreed["runestone"], json!(true));
+        assert_eq!(diff.agreed["cenotaph"], json!(true));
+    }
+
+    #[test]
+    fn test_structured_protostones() {
+        // A mainnet mint of alkane 2:19 (opcode 77), tokens and refund to output 0
+        let tx_hex = "0200000000010141de32694c6aece390828c54475862396edfd46289bbd0f7b78f3e34ee80b7880300000000fdffffff024a010000000000002251200e5843aef2fa13444715b7002071678368e2ae5a6da415e0395448ad1cc9c2200000000000000000116a5d0eff7f818cec82d08bc0a882cdd215024830450221008c8de39854dfea97bfc0cac9f2d0843664b413eb6e135fd99896fb4b03b2e26402207003b3ec1950edd4593130ad934a2551ee4cb7249511a73263441ee6cc37b73a01210287698f1cd27599d8d32fdd5a29fa500d54d8bb2ef5355ca6753107539c47a9b500000000";
+        let tx: Transaction = deserialize(&hex::decode(tx_hex).unwrap()).unwrap();
+        let decoded = decode_runestone(&tx).unwrap();
+        let mint = &decoded.protostones[0];
+        assert_eq!(decoded.protostones.len(), 1);
+        assert_eq!((mint.protocol_tag, mint.message.clone(), mint.cellpack.clone()), (1, vec![2, 19, 77], Some(vec![2, 19, 77])));
+        assert_eq!((mint.pointer, mint.refund, mint.burn), (Some(0), Some(0), None));
+        assert!(mint.edicts.is_empty());
+        let ordinals: Vec<DecodedProtostone> = format_runestone(&tx).unwrap().into_protostones().unwrap().iter().map(DecodedProtostone::from).collect();
+        assert_eq!(decoded.protostones, ordinals);
+
+        // Edicts and burns, with amounts past what JSON numbers hold
+        let transfer = crate::transaction::ProtostoneSpec {
+            protocol_tag: 1,
+            cellpack: Vec::new(),
+            edicts: vec![
+                crate::transaction::Edict { id: (2, 0), amount: u128::MAX, output: 1 },
+                crate::transaction::Edict { id: (840_000, 7), amount: 25, output: 0 },
+            ],
+            pointer: Some(1),
+            refund: None,
+            burn: Some(1),
+        };
+        let tx = tx_with_script(transfer.encipher().unwrap().into_bytes());
+        let decoded = decode_runestone(&tx).unwrap();
+        assert_eq!(decoded.protostones[0].edicts, vec![
+            DecodedEdict { block: 2, tx: 0, amount: u128::MAX, output: 1, destination: None },
+            DecodedEdict { block: 840_000, tx: 7, amount: 25, output: 0, destination: None },
+        ]);
+        let json = protostone_json(&format_runestone(&tx).unwrap().into_protostones().unwrap()[0]);
+        assert_eq!(json["edicts"], json!([
+            { "block": 2, "tx": 0, "amount": u128::MAX.to_string(), "output": 1 },
+            { "block": 840_000, "tx": 7, "amount": "25", "output": 0 },
+        ]));
+        assert_eq!((&json["message"], &json["cellpack"], &json["pointer"], &json["refund"], &json["burn"]),
+            (&json!(""), &json!([]), &json!(1), &Value::Null, &json!("1")));
+        let ordinals: Vec<DecodedProtostone> = format_runestone(&tx).unwrap().into_protostones().unwrap().iter().map(DecodedProtostone::from).collect();
+        assert_eq!(decoded.protostones, ordinals);
+        assert_eq!(u128_json(u128::from(u64::MAX) + 1), json!("18446744073709551616"));
+    }
+
+    #[test]
+    fn test_decoded_runestone_wire_format() {
+        // Version 1 of the JSON form, for the mainnet 2:19 mint
+        let tx_hex = "0200000000010141de32694c6aece390828c54475862396edfd46289bbd0f7b78f3e34ee80b7880300000000fdffffff024a010000000000002251200e5843aef2fa13444715b7002071678368e2ae5a6da415e0395448ad1cc9c2200000000000000000116a5d0eff7f818cec82d08bc0a882cdd215024830450221008c8de39854dfea97bfc0cac9f2d0843664b413eb6e135fd99896fb4b03b2e26402207003b3ec1950edd4593130ad934a2551ee4cb7249511a73263441ee6cc37b73a01210287698f1cd27599d8d32fdd5a29fa500d54d8bb2ef5355ca6753107539c47a9b500000000";
+        let tx: Transaction = deserialize(&hex::decode(tx_hex).unwrap()).unwrap();
+        let txid = tx.txid().to_string();
+        let decoded = decode_runestone(&tx).unwrap();
+        let operation = json!({ "type": "Alkane", "operation": "call", "cellpack": { "target": "2:19", "inputs": ["77"] } });
+        let protostone = json!({
+            "protocol_tag": 1,
+            "message": "02134d",
+            "cellpack": ["2", "19", "77"],
+            "edicts": [],
+            "pointer": 0,
+            "refund": 0,
+            "burn": null,
+            "operation": operation.clone(),
+        });
+        assert_eq!(Value::from(decoded.clone()), json!({
+            "version": 1,
+            "txid": txid.clone(),
+            "output_index": 1,
+            "runes": null,
+            "protostones": [protostone.clone()],
+            "all_tags": { "16383": ["3270949155949127755564545"] },
+            "cenotaph": null,
+            "raw_integers": [16383, "3270949155949127755564545"],
+        }));
+        assert_eq!(serde_json::from_value::<DecodedRunestone>(Value::from(decoded.clone())).unwrap(), decoded);
+
+        // The untyped form `decode_runestone` used to return
+        assert_eq!(decode_runestone_json(&tx).unwrap(), json!({
+            "transaction_id": txid,
+            "output_index": 1,
+            "runes": null,
+            "protostones": [protostone],
+            "all_tags": { "16383": ["3270949155949127755564545"] },
+            "cenotaph": false,
+            "flaws": [],
+            "raw_integers": [16383, "3270949155949127755564545"],
+            "protocol_data": ["3270949155949127755564545"],
+            "protocol_tag": 1,
+            "message_bytes": [2, 19, 77],
+            "protostone": operation,
+        }));
+
+        // Cenotaphs carry their flaws
+        let decoded = decode_runestone(&tx_with_script(runestone_script(&[24, 1]))).unwrap();
+        assert_eq!(Value::from(decoded.clone())["cenotaph"], json!({ "flaws": ["Unrecognized even tag 24"] }));
+        assert_eq!(serde_json::from_value::<DecodedRunestone>(Value::from(decoded.clone())).unwrap(), decoded);
+        assert!(serde_json::from_value::<DecodedRunestone>(json!({ "version": 1 })).is_err());
+    }
+
+    #[test]
+    fn test_plain_runes() {
+        // An etching with the terms of UNCOMMON•GOODS, block 1 of runes
+        let etching = Runestone {
+            etching: Some(ordinals::Etching {
+                rune: Some("UNCOMMONGOODS".parse().unwrap()),
+                spacers: Some(1 << 7),
+                divisibility: Some(0),
+                symbol: Some('⧉'),
+                terms: Some(ordinals::Terms {
+                    amount: Some(1),
+                    cap: Some(u128::MAX),
+                    height: (Some(840_000), Some(1_050_000)),
+                    offset: (None, None),
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let tx = tx_with_script(etching.encipher().into_bytes());
+        let decoded = decode_runestone(&tx).unwrap();
+        assert!(decoded.protostones.is_empty());
+        assert_eq!(decoded.cenotaph, None);
+        let runes = decoded.runes.clone().unwrap();
+        assert_eq!(Value::from(decoded)["runes"], json!({
+            "etching": {
+                "rune": "UNCOMMON•GOODS",
+                "divisibility": 0,
+                "symbol": "⧉",
+                "premine": null,
+                "terms": { "amount": "1", "cap": u128::MAX.to_string(), "height": [840_000, 1_050_000], "offset": [null, null] },
+                "turbo": false,
+            },
+            "mint": null,
+            "edicts": [],
+            "pointer": null,
+        }));
+        assert_eq!(runes.to_string(), format!("Etching: UNCOMMON•GOODS ⧉, divisibility 0, mint 1 x {} at heights 840000..1050000\n", u128::MAX));
+        match format_runestone(&tx).unwrap() {
+            Decoded::Runestone { runes: ordinals, protostones } => {
+                assert_eq!(ordinals, Some(runes));
+                assert!(protostones.is_empty());
+            },
+            Decoded::Cenotaph(flaws) => panic!("unexpected cenotaph: {:?}", flaws),
+        }
+
+        // A transfer minting runes and moving some, with the rest to output 0
+        let transfer = Runestone {
+            edicts: vec![ordinals::Edict { id: ordinals::RuneId { block: 840_000, tx: 1 }, amount: 1_000, output: 0 }],
+            mint: Some(ordinals::RuneId { block: 840_000, tx: 1 }),
+            pointer: Some(0),
+            ..Default::default()
+        };
+        let tx = tx_with_script(transfer.encipher().into_bytes());
+        let runes = decode_runestone(&tx).unwrap().runes.unwrap();
+        assert_eq!(runes, DecodedRunes {
+            etching: None,
+            mint: Some("840000:1".to_string()),
+            edicts: vec![DecodedEdict { block: 840_000, tx: 1, amount: 1_000, output: 0, destination: None }],
+            pointer: Some(0),
+            pointer_output: None,
+        });
+        assert_eq!(runes.to_string(), "Mint: 840000:1\nEdict: 1000 of 840000:1 to output 0\nPointer: output 0\n");
+        assert!(format_runestone(&tx).unwrap().into_protostones().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_explain() {
+        // The mainnet 2:19 mint, tokens and refund to its taproot output
+        let tx_hex = "0200000000010141de32694c6aece390828c54475862396edfd46289bbd0f7b78f3e34ee80b7880300000000fdffffff024a010000000000002251200e5843aef2fa13444715b7002071678368e2ae5a6da415e0395448ad1cc9c2200000000000000000116a5d0eff7f818cec82d08bc0a882cdd215024830450221008c8de39854dfea97bfc0cac9f2d0843664b413eb6e135fd99896fb4b03b2e26402207003b3ec1950edd4593130ad934a2551ee4cb7249511a73263441ee6cc37b73a01210287698f1cd27599d8d32fdd5a29fa500d54d8bb2ef5355ca6753107539c47a9b500000000";
+        let tx: Transaction = deserialize(&hex::decode(tx_hex).unwrap()).unwrap();
+        let protostones = format_runestone(&tx).unwrap().into_protostones().unwrap();
+        assert_eq!(explain(&protostones, &tx, &NetworkParams::mainnet()), [
+            "Protostone 0: DIESEL (protocol 1)",
+            "  Call: alkane 2:19, opcode 77 (mint)",
+            "  Pointer: output 0 (bc1ppevy8thjlgf5g3c4kuqzqut8sd5w9tj6dkjptcpe23y268xfcgsq0y5x2n)",
+            "  Refund: output 0 (bc1ppevy8thjlgf5g3c4kuqzqut8sd5w9tj6dkjptcpe23y268xfcgsq0y5x2n)",
+        ].join("\n"));
+
+        // Edicts to an address, to the OP_RETURN and to the protostone itself
+        let transfer = crate::transaction::ProtostoneSpec {
+            protocol_tag: 1,
+            cellpack: vec![2, 0, 77],
+            edicts: vec![
+                crate::transaction::Edict { id: (2, 0), amount: 1_500, output: 0 },
+                crate::transaction::Edict { id: (2, 1), amount: 25, output: 1 },
+                crate::transaction::Edict { id: (2, 1), amount: 5, output: 3 },
+            ],
+            pointer: Some(0),
+            refund: None,
+            burn: Some(1),
+        };
+        let mut tx = tx_with_script(transfer.encipher().unwrap().into_bytes());
+        tx.output[0].script_pubkey = bdk::bitcoin::ScriptBuf::new_v0_p2wpkh(&bdk::bitcoin::hashes::Hash::from_byte_array([0x11; 20]));
+        let protostones = format_runestone(&tx).unwrap().into_protostones().unwrap();
+        assert_eq!(explain(&protostones, &tx, &NetworkParams::testnet()), [
+            "Protostone 0: DIESEL (protocol 1)",
+            "  Call: DIESEL (2:0), opcode 77 (mint)",
+            "  Edict: send 1500 DIESEL (2:0) to output 0 (tb1qzyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3apj6d3)",
+            "  Edict: send 25 alkane 2:1 to output 1 (OP_RETURN)",
+            "  Edict: send 5 alkane 2:1 to output 3 (protostone #0)",
+            "  Pointer: output 0 (tb1qzyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3apj6d3)",
+            "  Burn: runes burned to protocol 1",
+        ].join("\n"));
+
+        // Deployments and messages that aren't cellpacks
+        assert_eq!(explain_message(1, &[1, 0, 0, 7]), vec!["Deploy: new contract, opcode 0 (initialize), inputs [7]"]);
+        assert_eq!(explain_message(1, &[3, 9]), vec!["Deploy: contract at reserved ID 4:9"]);
+        assert_eq!(explain_message(3, &[0xab]), vec!["Message: ab"]);
+        assert!(explain(&[], &tx, &NetworkParams::testnet()).is_empty());
+    }
+
+    /// A runestone script carrying `payload` in a single push
+    fn runestone_script(payload: &[u8]) -> Vec<u8> {
+        [vec![0x6a, 0x5d, payload.len() as u8], payload.to_vec()].concat()
+    }
+
+    #[test]
+    fn test_tag_map() {
+        let tags = TagMap::from_integers(&[16383, 5, 22, 1, 16383, 7, 24, 1, 9, 2, 3]);
+        assert_eq!(tags, TagMap(vec![(16383, vec![5, 7]), (22, vec![1]), (24, vec![1]), (9, vec![2])]));
+        assert_eq!(tags.get(16383), Some(&[5, 7][..]));
+        assert_eq!(tags.get(4), None);
+        assert_eq!(tags.unknown_tags(), vec![24, 9]);
+        assert_eq!(tags.unrecognized_even_tags(), vec![24]);
+        assert_eq!((TagKind::of(1), TagKind::of(127), TagKind::of(126), TagKind::of(25)),
+            (TagKind::Known, TagKind::Known, TagKind::UnrecognizedEven, TagKind::UnknownOdd));
+
+        // Written as text, tags keep the order they appear in, both ways
+        let decoded = decode_runestone(&tx_with_script(runestone_script(&[9, 2, 22, 1, 9, 3]))).unwrap();
+        assert_eq!(decoded.all_tags, TagMap(vec![(9, vec![2, 3]), (22, vec![1])]));
+        let text = serde_json::to_string(&decoded).unwrap();
+        assert!(text.contains(r#""all_tags":{"9":[2,3],"22":[1]}"#));
+        assert_eq!(serde_json::from_str::<DecodedRunestone>(&text).unwrap().all_tags, decoded.all_tags);
+    }
+
+    #[test]
+    fn test_decode_block() {
+        let plain = tx_with_script(vec![0x6a, 0x04, 0xde, 0xad, 0xbe, 0xef]);
+        let mint = tx_with_script(crate::runestone::Runestone::new_diesel().encipher().unwrap().into_bytes());
+        let transfer = tx_with_script(Runestone {
+            edicts: vec![ordinals::Edict { id: ordinals::RuneId { block: 840_000, tx: 1 }, amount: 5, output: 0 }],
+            ..Default::default()
+        }.encipher().into_bytes());
+        let cenotaph = tx_with_script(runestone_script(&[24, 1]));
+
+        // Transactions without a Runestone are left out
+        let decoded = decode_block(&[plain.clone(), mint.clone(), transfer.clone(), cenotaph.clone()]);
+        let txids: Vec<Txid> = decoded.iter().map(|(txid, _)| *txid).collect();
+        assert_eq!(txids, vec![mint.txid(), transfer.txid(), cenotaph.txid()]);
+        assert_eq!(BlockSummary::new(&decoded), BlockSummary {
+            runestones: 3,
+            etchings: 0,
+            mints: 1,
+            transfers: 1,
+            cenotaphs: 1,
+            errors: 0,
+        });
+
+        // A failed transaction is counted without hiding the others
+        let mut decoded = decoded;
+        decoded.insert(0, (plain.txid(), Err(anyhow!("Failed to fetch transaction"))));
+        let summary = BlockSummary::new(&decoded);
+        assert_eq!((summary.runestones, summary.mints, summary.errors), (3, 1, 1));
+        assert_eq!(summary.to_string(), "3 runestone(s): 1 mint(s), 1 transfer(s), 0 etching(s), 1 cenotaph(s), 1 error(s)");
+        assert!(decode_block(&[plain]).is_empty());
+    }
+
+    #[test]
+    fn test_decode_from_hex_and_path() {
+        let tx = tx_with_script(crate::runestone::Runestone::new_diesel().encipher().unwrap().into_bytes());
+        let tx_hex = bdk::bitcoin::consensus::encode::serialize_hex(&tx);
+        let txid = tx.txid().to_string();
+
+        // Surrounding whitespace and a 0x prefix are ignored
+        assert_eq!(decode_from_hex(&format!("\n  0x{}\t\n", tx_hex)).unwrap().txid, txid);
+
+        // A PSBT decodes through its unsigned transaction, as hex or raw bytes
+        let psbt = PartiallySignedTransaction::from_unsigned_tx(tx.clone()).unwrap().serialize();
+        assert_eq!(decode_from_hex(&hex::encode(&psbt)).unwrap().txid, txid);
+        let path = std::env::temp_dir().join(format!("deezel_decode_{}.psbt", std::process::id()));
+        std::fs::write(&path, &psbt).unwrap();
+        assert_eq!(decode_from_path(&path).unwrap().txid, txid);
+        std::fs::write(&path, format!("{}\n", tx_hex)).unwrap();
+        assert_eq!(decode_from_path(&path).unwrap().txid, txid);
+        std::fs::remove_file(&path).unwrap();
+        assert!(decode_from_path(&path).is_err());
+
+        // Each kind of bad input has its own error
+        let error_of = |input: &str| decode_from_hex(input).unwrap_err().downcast::<DecodeError>().unwrap();
+        assert!(matches!(error_of("not hex"), DecodeError::InvalidHex(_)));
+        assert!(matches!(error_of("abc"), DecodeError::InvalidHex(_)));
+        assert!(matches!(error_of("deadbeef"), DecodeError::InvalidTransaction(_)));
+        assert!(matches!(error_of(&hex::encode(&psbt[..10])), DecodeError::InvalidPsbt(_)));
+        let plain = tx_with_script(vec![0x6a, 0x01, 0x00]);
+        assert_eq!(error_of(&bdk::bitcoin::consensus::encode::serialize_hex(&plain)), DecodeError::NoRunestone);
+        assert!(matches!(transaction_from_bytes(&[0xff, 0xfe]), Err(DecodeError::InvalidTransaction(_))));
+    }
+
+    #[test]
+    fn test_cenotaph_flaws() {
+        let flaws_of = |payload: &[u8]| {
+            let decoded = decode_runestone(&tx_with_script(runestone_script(payload))).unwrap();
+            decoded.cenotaph.unwrap_or_else(|| panic!("payload {:?} isn't a cenotaph", payload)).flaws
+        };
+
+        // Tag 24 isn't defined, and even tags can't be ignored
+        assert_eq!(flaws_of(&[24, 1, 22, 0]), vec!["Unrecognized even tag 24"]);
+        // The last varint has its continuation bit set
+        assert!(flaws_of(&[22, 0x80])[0].ends_with("Truncated varint"));
+        // 19 bytes with more than 128 bits set
+        let mut oversized = vec![0xff; 18];
+        oversized.push(0x04);
+        assert!(flaws_of(&oversized)[0].ends_with("Varint too large"));
+        // Half an edict after the body tag
+        assert_eq!(flaws_of(&[0, 2, 1]), vec!["2 trailing integers after the edicts"]);
+        // A tag without its value
+        assert_eq!(flaws_of(&[22, 0, 23]), vec!["Tag 23 has no value"]);
+        // An edict paying an output the transaction doesn't have
+        assert_eq!(flaws_of(&[0, 2, 1, 100, 3]), vec!["Edict 0 output 3 is past the 2 outputs"]);
+
+        // The ordinals decoder agrees, with its own wording
+        match format_runestone(&tx_with_script(runestone_script(&[24, 1]))).unwrap() {
+            Decoded::Cenotaph(flaws) => assert_eq!(flaws.len(), 1),
+            Decoded::Runestone { .. } => panic!("expected a cenotaph"),
+        }
+        assert!(format_runestone(&tx_with_script(runestone_script(&[0, 2, 1]))).unwrap().into_protostones().is_err());
+
+        // Well-formed runestones aren't cenotaphs
+        let decoded = decode_runestone(&tx_with_script(runestone_script(&[22, 1, 0, 2, 1, 100, 2]))).unwrap();
+        assert_eq!(decoded.cenotaph, None);
+    }
+
+    #[test]
+    fn test_resolve_outputs() {
+        // A taproot and a segwit output ahead of the runestone
+        let transfer = crate::transaction::ProtostoneSpec {
+            protocol_tag: 1,
+            cellpack: vec![2, 0, 77],
+            edicts: vec![
+                crate::transaction::Edict { id: (2, 0), amount: 10, output: 0 },
+                crate::transaction::Edict { id: (2, 0), amount: 20, output: 2 },
+                crate::transaction::Edict { id: (2, 0), amount: 30, output: 4 },
+            ],
+            pointer: Some(0),
+            refund: Some(1),
+            burn: None,
+        };
+        let mut tx = tx_with_script(transfer.encipher().unwrap().into_bytes());
+        tx.output[0].script_pubkey = bdk::bitcoin::ScriptBuf::from_bytes([vec![0x51, 0x20], vec![0x22; 32]].concat());
+        tx.output.insert(1, bdk::bitcoin::TxOut {
+            value: 10_000,
+            script_pubkey: bdk::bitcoin::ScriptBuf::new_v0_p2wpkh(&bdk::bitcoin::hashes::Hash::from_byte_array([0x11; 20])),
+        });
+        
+        let decoded = decode_runestone_with_outputs(&tx, &NetworkParams::testnet()).unwrap();
+        let json = Value::from(decoded.protostones[0].clone());
+        let taproot = json!({ "output_value_sats": 546, "script_type": "p2tr", "address": "tb1pyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3q7fyw6k" });
+        assert_eq!(json["edicts"], json!([
+            { "block": 2, "tx": 0, "amount": "10", "output": 0, "output_value_sats": 546, "script_type": "p2tr", "address": "tb1pyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3q7fyw6k" },
+            { "block": 2, "tx": 0, "amount": "20", "output": 2, "output_value_sats": 0, "script_type": "op_return", "address": null },
+            { "block": 2, "tx": 0, "amount": "30", "output": 4, "output_value_sats": null, "script_type": "protostone", "address": "protostone #0" },
+        ]));
+        assert_eq!(json["pointer_output"], taproot);
+        assert_eq!(json["refund_output"], json!({ "output_value_sats": 10_000, "script_type": "p2wpkh", "address": "tb1qzyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3apj6d3" }));
+        
+        // The resolved form reads back, and a plain decode leaves it out
+        assert_eq!(serde_json::from_value::<DecodedProtostone>(json).unwrap(), decoded.protostones[0]);
+        let plain = Value::from(decode_runestone(&tx).unwrap().protostones[0].clone());
+        assert!(plain.get("pointer_output").is_none() && plain["edicts"][0].get("address").is_none());
+    }
+
+    #[test]
+    fn test_decode_cellpack_protostone() {
+        let decoded = decode_protostone(protocol_tags::DIESEL, &[2, 0, 77]);
+        assert_eq!(decoded, json!({ "type": "DIESEL", "operation": "mint", "cellpack": { "target": "2:0", "inputs": ["77"] } }));
+
+        // A contract call with arguments, one too wide for a single byte
+        let message = crate::runestone::encode_cellpack(&[2, 19, 5, 1_000, 3]);
+        let decoded = decode_protostone(protocol_tags::ALKANE, &message);
+        assert_eq!(decoded["operation"], "call");
+        assert_eq!(decoded["cellpack"], json!({ "target": "2:19", "inputs": ["5", "1000", "3"] }));
+        assert_eq!(decode_protostone(protocol_tags::DIESEL, &[1, 0, 0])["operation"], "deploy");
+
+        // A trailing byte that starts a varint it doesn't finish
+        let decoded = decode_protostone(protocol_tags::DIESEL, &[2, 0, 77, 0x80]);
+        assert_eq!(decoded["operation"], "unknown");
+        assert_eq!(decoded["cellpack"], json!([2, 0, 77, 0x80]));
+        assert!(decoded["error"].as_str().unwrap().contains("Truncated varint"));
+        assert_eq!(decode_protostone(99, &[2, 0, 77])["protocol_tag"], 99);
+    }
+
+    #[test]
+    fn test_decode_event_protostone() {
+        let message = [vec![4], b"Mint".to_vec(), vec![1, 0x0a], vec![4], b"Burn".to_vec(), vec![0]].concat();
+        let decoded = decode_protostone(protocol_tags::ALKANE_EVENT, &message);
+        assert_eq!(decoded["events"], json!([{ "topic": "4d696e74", "data": "0a" }, { "topic": "4275726e", "data": "" }]));
+
+        let decoded = decode_protostone(protocol_tags::ALKANE_EVENT, &[9, 1]);
+        assert!(decoded["events"].is_null());
+        assert_eq!(decoded["cellpack"], json!([9, 1]));
+        assert!(decoded["error"].as_str().unwrap().contains("Truncated"));
+    }
+
+    #[test]
+    fn test_decode_protorune_protostone() {
+        use crate::runestone::encode_cellpack;
+
+        // Values of 128 and up take several bytes each
+        let decoded = decode_protostone(protocol_tags::PROTORUNE, &encode_cellpack(&[1, 300, 1_000_000, 7, 200]));
+        assert_eq!(decoded["cellpack"]["operation_name"], "mint");
+        assert_eq!(decoded["cellpack"]["data"], json!([300, 1_000_000, 7, 200]));
+        assert_eq!(decoded["cellpack"]["token_details"], json!({ "token_id": 300, "amount": 1_000_000, "metadata": [7, 200] }));
+
+        let mut message = encode_cellpack(&[2, 130, 1u128 << 70]);
+        message.extend_from_slice(&[0x01, 0x7f]);
+        let decoded = decode_protostone(protocol_tags::PROTORUNE, &message);
+        assert_eq!(decoded["cellpack"]["transfer_details"], json!({
+            "token_id": 130,
+            "amount": (1u128 << 70).to_string(),
+            "recipient": "017f"
+        }));
+
+        // Operation types are values too
+        let decoded = decode_protostone(protocol_tags::PROTORUNE, &encode_cellpack(&[257, 1]));
+        assert_eq!(decoded["cellpack"]["operation_type"], 257);
+        assert_eq!(decoded["cellpack"]["operation_name"], "unknown");
+
+        // Bytes that aren't LEB128 values are reported as they are
+        let decoded = decode_protostone(protocol_tags::PROTORUNE, &[1, 0x80]);
+        assert_eq!(decoded["cellpack"], json!([1, 0x80]));
+        assert!(decoded["error"].as_str().unwrap().contains("Truncated varint"));
+    }
+
+    #[test]
+    fn test_split_and_join_round_trip() {
+        use crate::transaction::ProtostoneSpec;
+
+        let split = ProtostoneSpec::split((2, 1), 1_000, &[300, 200, 500]).unwrap();
+        let tx = tx_with_script(split.encipher().unwrap().into_bytes());
+        let decoded = decode_runestone(&tx).unwrap();
+        assert_eq!(decoded.protostones[0].protocol_tag, protocol_tags::PROTORUNE);
+        assert_eq!(decoded.protostones[0].operation["cellpack"]["operation_name"], "split");
+        assert_eq!(decoded.protostones[0].operation["cellpack"]["split_details"], json!({ "rune_id": "2:1", "amounts": ["300", "200", "500"] }));
+        let protostones = format_runestone(&tx).unwrap().into_protostones().unwrap();
+        let edicts: Vec<(u128, u128)> = protostones[0].edicts.iter().map(|edict| (edict.amount, edict.output)).collect();
+        assert_eq!(edicts, vec![(300, 0), (200, 1), (500, 2)]);
+
+        let join = ProtostoneSpec::join((2, 1), &[u128::from(u64::MAX), 5], 0).unwrap();
+        let tx = tx_with_script(join.encipher().unwrap().into_bytes());
+        let decoded = decode_runestone(&tx).unwrap();
+        assert_eq!(decoded.protostones[0].operation["cellpack"]["operation_name"], "join");
+        assert_eq!(decoded.protostones[0].operation["cellpack"]["join_details"], json!({ "rune_id": "2:1", "amount": "18446744073709551620" }));
+
+        // Amounts must account for exactly what the inputs hold
+        assert!(ProtostoneSpec::split((2, 1), 1_000, &[300, 200]).unwrap_err().to_string().contains("add up to 500"));
+        assert!(ProtostoneSpec::split((2, 1), 1_000, &[1_000, 0]).is_err());
+        assert!(ProtostoneSpec::split((2, 1), 1_000, &[1_000]).is_err());
+        assert!(ProtostoneSpec::split((2, 1), 0, &[u128::MAX, 1]).unwrap_err().to_string().contains("overflow"));
+        assert!(ProtostoneSpec::join((2, 1), &[1_000], 0).is_err());
+        assert!(ProtostoneSpec::join((2, 1), &[u128::MAX, 1], 0).is_err());
+    }
 }
 
 // 
\ No newline at end of file
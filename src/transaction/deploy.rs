@@ -0,0 +1,296 @@
+//! Commit/reveal deployment of alkanes contracts
+//!
+//! A contract's wasm is far too large for an OP_RETURN, so it is carried in
+//! a taproot script-path witness instead. The commit transaction pays to a
+//! taproot output whose only leaf is an envelope script holding the gzipped
+//! wasm; the reveal spends that output, exposing the envelope, and carries
+//! the protostone with the deployment cellpack.
+
+use anyhow::{anyhow, Context, Result};
+use bdk::bitcoin::opcodes::all::{OP_CHECKSIG, OP_ENDIF, OP_IF};
+use bdk::bitcoin::opcodes::OP_FALSE;
+use bdk::bitcoin::script::{Builder, Instruction, PushBytes, PushBytesBuf};
+use bdk::bitcoin::secp256k1::{KeyPair, Message, Secp256k1, SecretKey, XOnlyPublicKey};
+use bdk::bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+use bdk::bitcoin::taproot::{self, LeafVersion, TapLeafHash, TaprootBuilder};
+use bdk::bitcoin::{OutPoint, ScriptBuf, Transaction, TxIn, TxOut, Witness};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{debug, info};
+use rand::Rng;
+use std::io::Write;
+
+use super::{
+    MintArtifact, ProtostoneOptions, ProtostoneSpec, TransactionConstructor,
+    MAX_STANDARD_TX_WEIGHT, PROTOCOL_TAG, SCHNORR_SIGNATURE_SIZE,
+};
+
+/// Tag opening the envelope, identifying its payload as contract bytecode
+const ENVELOPE_TAG: [u8; 3] = *b"BIN";
+
+/// Largest data push allowed in a tapscript
+pub const MAX_PUSH_SIZE: usize = 520;
+
+/// Magic bytes opening a gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Gzip contract wasm, leaving already compressed input untouched
+pub fn compress_wasm(wasm: &[u8]) -> Result<Vec<u8>> {
+    if wasm.starts_with(&GZIP_MAGIC) {
+        return Ok(wasm.to_vec());
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(wasm).context("Failed to compress contract wasm")?;
+    encoder.finish().context("Failed to compress contract wasm")
+}
+
+/// Build the envelope script revealing `payload`
+///
+/// The script is `<key> OP_CHECKSIG OP_FALSE OP_IF "BIN" <> <chunks...>
+/// OP_ENDIF`: the `OP_FALSE OP_IF` block is never executed, so the payload
+/// pushes (at most `MAX_PUSH_SIZE` bytes each) cost nothing but weight.
+pub fn envelope_script(key: XOnlyPublicKey, payload: &[u8]) -> Result<ScriptBuf> {
+    let mut builder = Builder::new()
+        .push_slice(key.serialize())
+        .push_opcode(OP_CHECKSIG)
+        .push_opcode(OP_FALSE)
+        .push_opcode(OP_IF)
+        .push_slice(ENVELOPE_TAG)
+        .push_slice(PushBytesBuf::new());
+    for chunk in payload.chunks(MAX_PUSH_SIZE) {
+        builder = builder.push_slice(<&PushBytes>::try_from(chunk)?);
+    }
+    Ok(builder.push_opcode(OP_ENDIF).into_script())
+}
+
+/// Reassemble the payload of the envelope revealed by a script-path witness
+///
+/// Returns `None` if the witness doesn't reveal a `BIN` envelope.
+pub fn envelope_payload(witness: &Witness) -> Option<Vec<u8>> {
+    let script = witness.tapscript()?;
+    let mut instructions = script.instructions()
+        .skip_while(|instruction| !matches!(instruction, Ok(Instruction::Op(OP_IF))))
+        .skip(1);
+
+    match instructions.next()? {
+        Ok(Instruction::PushBytes(tag)) if tag.as_bytes() == ENVELOPE_TAG => {},
+        _ => return None,
+    }
+    match instructions.next()? {
+        Ok(Instruction::PushBytes(separator)) if separator.is_empty() => {},
+        _ => return None,
+    }
+
+    let mut payload = Vec::new();
+    for instruction in instructions {
+        match instruction.ok()? {
+            Instruction::PushBytes(chunk) => payload.extend_from_slice(chunk.as_bytes()),
+            Instruction::Op(OP_ENDIF) => return Some(payload),
+            Instruction::Op(_) => return None,
+        }
+    }
+    None
+}
+
+impl TransactionConstructor {
+    /// Create signed commit and reveal transactions deploying a contract
+    ///
+    /// The wasm is gzipped (unless it already is) and committed to in a
+    /// taproot output with a single envelope leaf, locked to an ephemeral
+    /// key. The commit output pays for the reveal, whose outputs are
+    /// `[recipient of the new alkane, OP_RETURN runestone]`; the protostone
+    /// carries `init_cellpack` (e.g. `[1, 0]` to deploy to the next free
+    /// ID) and points at output 0. The commit must be broadcast first.
+    ///
+    /// Both transactions are checked against the fee limits. Fails if the
+    /// wallet is watch-only, since the reveal's txid depends on the signed
+    /// commit.
+    pub async fn create_deploy_transactions(&self, wasm: &[u8], init_cellpack: Vec<u128>, options: &ProtostoneOptions) -> Result<(Transaction, Transaction)> {
+        if wasm.is_empty() {
+            return Err(anyhow!("Contract wasm is empty"));
+        }
+        let payload = compress_wasm(wasm)?;
+        info!("Creating commit/reveal deployment of {} byte contract ({} bytes compressed)", wasm.len(), payload.len());
+
+        let secp = Secp256k1::new();
+        let secret_key = SecretKey::from_slice(&rand::thread_rng().gen::<[u8; 32]>())
+            .context("Failed to generate reveal key")?;
+        let keypair = KeyPair::from_secret_key(&secp, &secret_key);
+        let (key, _) = keypair.x_only_public_key();
+
+        let script = envelope_script(key, &payload)?;
+        let spend_info = TaprootBuilder::new()
+            .add_leaf(0, script.clone())?
+            .finalize(&secp, key)
+            .map_err(|_| anyhow!("Failed to build the envelope taproot tree"))?;
+        let control_block = spend_info.control_block(&(script.clone(), LeafVersion::TapScript))
+            .ok_or_else(|| anyhow!("Envelope leaf missing from its taproot tree"))?;
+
+        let spec = ProtostoneSpec {
+            protocol_tag: u128::from(PROTOCOL_TAG),
+            cellpack: init_cellpack,
+            edicts: Vec::new(),
+            pointer: Some(0),
+            refund: Some(0),
+            burn: None,
+        };
+        let fee_rate = self.resolve_fee_rate().await?;
+
+        // Size the reveal with a placeholder signature to price its witness
+        let mut reveal = Transaction {
+            version: 2,
+            lock_time: bdk::bitcoin::absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: self.sequence(),
+                witness: Witness::from_slice(&[vec![0u8; SCHNORR_SIGNATURE_SIZE], script.to_bytes(), control_block.serialize()]),
+            }],
+            output: self.protostone_outputs(&spec, options).await?,
+        };
+        let weight = reveal.weight().to_wu();
+        if weight > MAX_STANDARD_TX_WEIGHT {
+            return Err(anyhow!(
+                "Reveal transaction weight {} exceeds the standard limit of {}; the compressed contract is {} bytes",
+                weight, MAX_STANDARD_TX_WEIGHT, payload.len()
+            ));
+        }
+        let reveal_fee = (reveal.vsize() as f64 * fee_rate).ceil() as u64;
+        self.check_fee(reveal_fee)?;
+
+        let commit_output = TxOut {
+            value: reveal.output.iter().map(|output| output.value).sum::<u64>() + reveal_fee,
+            script_pubkey: ScriptBuf::new_v1_p2tr_tweaked(spend_info.output_key()),
+        };
+        let funded = self.fund_outputs(&[], vec![commit_output.clone()], fee_rate).await?;
+        let commit = match self.sign_funded(funded).await? {
+            MintArtifact::Signed(tx) => tx,
+            MintArtifact::Psbt(_) => return Err(anyhow!("Wallet is watch-only and cannot sign a commit/reveal deployment")),
+        };
+
+        reveal.input[0].previous_output = OutPoint { txid: commit.txid(), vout: 0 };
+        let leaf_hash = TapLeafHash::from_script(&script, LeafVersion::TapScript);
+        let sighash = SighashCache::new(&reveal)
+            .taproot_script_spend_signature_hash(0, &Prevouts::All(&[commit_output]), leaf_hash, TapSighashType::Default)
+            .context("Failed to compute reveal sighash")?;
+        let signature = taproot::Signature {
+            sig: secp.sign_schnorr_no_aux_rand(&Message::from_slice(sighash.as_ref())?, &keypair),
+            hash_ty: TapSighashType::Default,
+        };
+        reveal.input[0].witness = Witness::from_slice(&[signature.to_vec(), script.to_bytes(), control_block.serialize()]);
+
+        debug!("Commit transaction: {:?}", commit);
+        debug!("Reveal transaction: {:?}", reveal);
+        Ok((commit, reveal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::RpcClient;
+    use crate::runestone_enhanced::format_runestone;
+    use crate::transaction::TransactionConfig;
+    use crate::wallet::{WalletConfig, WalletManager};
+    use bdk::bitcoin::consensus::encode::serialize;
+    use bdk::bitcoin::Network;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use std::sync::Arc;
+
+    /// Deterministic, poorly compressible test bytes
+    fn test_wasm(len: usize) -> Vec<u8> {
+        let mut state: u32 = 0x2545_f491;
+        let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+        while wasm.len() < len {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            wasm.push(state as u8);
+        }
+        wasm
+    }
+
+    fn gunzip(payload: &[u8]) -> Vec<u8> {
+        let mut wasm = Vec::new();
+        GzDecoder::new(payload).read_to_end(&mut wasm).unwrap();
+        wasm
+    }
+
+    #[test]
+    fn test_envelope_round_trip() {
+        let secp = Secp256k1::new();
+        let keypair = KeyPair::from_secret_key(&secp, &SecretKey::from_slice(&[7u8; 32]).unwrap());
+        let payload = compress_wasm(&test_wasm(2_000)).unwrap();
+        assert_eq!(compress_wasm(&payload).unwrap(), payload, "gzipped input is left untouched");
+
+        let script = envelope_script(keypair.x_only_public_key().0, &payload).unwrap();
+        let pushes: Vec<usize> = script.instructions()
+            .filter_map(|instruction| match instruction.unwrap() {
+                Instruction::PushBytes(bytes) => Some(bytes.len()),
+                Instruction::Op(_) => None,
+            })
+            .collect();
+        assert!(pushes.iter().all(|&len| len <= MAX_PUSH_SIZE));
+        assert!(pushes.len() > 4, "payload is split over several pushes");
+
+        let witness = Witness::from_slice(&[vec![0u8; 64], script.to_bytes(), vec![0xc0; 33]]);
+        assert_eq!(gunzip(&envelope_payload(&witness).unwrap()), test_wasm(2_000));
+        assert_eq!(envelope_payload(&Witness::from_slice(&[vec![0u8; 64]])), None);
+    }
+
+    #[tokio::test]
+    async fn test_create_deploy_transactions() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let (descriptor, change_descriptor) = crate::wallet::TEST_SIGNING_DESCRIPTORS;
+        let wallet_manager = Arc::new(WalletManager::from_descriptors(WalletConfig {
+            wallet_path: "test_wallet.dat".to_string(),
+            network: Network::Testnet,
+            bitcoin_rpc_url: server.url.clone(),
+            metashrew_rpc_url: server.url.clone(),
+            esplora_url: None,
+        }, descriptor, Some(change_descriptor)).await.unwrap());
+        server.set("esplora_address::utxo", serde_json::json!([{ "txid": "11".repeat(32), "vout": 0, "value": 100_000 }]));
+        server.set("ord_output", serde_json::json!({ "inscriptions": [], "runes": {} }));
+        server.set("alkanes_protorunesbyoutpoint", serde_json::json!([]));
+        server.set("btc_getblockcount", serde_json::json!(800_000));
+        let constructor = TransactionConstructor::new(
+            Arc::clone(&wallet_manager),
+            Arc::new(RpcClient::new(server.config())),
+            TransactionConfig { fee_rate: 2.0, ..TransactionConfig::default() },
+        );
+
+        let wasm = test_wasm(3_000);
+        let (commit, reveal) = constructor.create_deploy_transactions(&wasm, vec![1, 0], &ProtostoneOptions::default()).await.unwrap();
+
+        // The reveal spends the commit's taproot output and pays the rest as fee
+        assert_eq!(reveal.input.len(), 1);
+        assert_eq!(reveal.input[0].previous_output, OutPoint { txid: commit.txid(), vout: 0 });
+        assert!(commit.output[0].script_pubkey.is_v1_p2tr());
+        let reveal_fee = commit.output[0].value - reveal.output.iter().map(|output| output.value).sum::<u64>();
+        assert!(reveal_fee as f64 >= reveal.vsize() as f64 * 2.0);
+
+        // Its witness reassembles to the original bytes
+        assert_eq!(reveal.input[0].witness.len(), 3);
+        assert_eq!(gunzip(&envelope_payload(&reveal.input[0].witness).unwrap()), wasm);
+
+        // The protostone carries the deployment cellpack and points at output 0
+        assert_eq!(reveal.output.len(), 2);
+        let decoded = format_runestone(&reveal).unwrap().into_protostones().unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].pointer, Some(0));
+        assert_eq!(crate::varint::decode_all(&decoded[0].message).unwrap(), vec![1, 0]);
+
+        // The commit's wallet inputs are validly signed
+        let utxos = wallet_manager.list_utxos().await.unwrap();
+        let serialized = serialize(&commit);
+        for (index, input) in commit.input.iter().enumerate() {
+            let prevout = &utxos.iter().find(|utxo| utxo.outpoint == input.previous_output).unwrap().txout;
+            bitcoinconsensus::verify(prevout.script_pubkey.as_bytes(), prevout.value, &serialized, index)
+                .unwrap_or_else(|e| panic!("commit input {} failed verification: {:?}", index, e));
+        }
+
+        assert!(constructor.create_deploy_transactions(&[], vec![1, 0], &ProtostoneOptions::default()).await.is_err());
+    }
+}
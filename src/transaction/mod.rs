@@ -6,25 +6,107 @@
 //! - Output consolidation
 //! - Transaction signing and verification
 
-use anyhow::{Context, Result};
-use bdk::bitcoin::{Address, Network, ScriptBuf, Transaction, TxOut};
+use anyhow::{anyhow, Context, Result};
+use bdk::bitcoin::{Address, Network, OutPoint, Script, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness};
 use bdk::bitcoin::consensus::encode::serialize;
-use log::{debug, info};
+use bdk::bitcoin::psbt::PartiallySignedTransaction;
+use bdk::bitcoin::sighash::TapSighashType;
+use bdk::bitcoin::absolute::LockTime;
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::sync::Arc;
 use std::str::FromStr;
+use std::time::Duration;
+use thiserror::Error;
 
-use crate::rpc::RpcClient;
-use crate::wallet::WalletManager;
-use crate::runestone::Runestone;
+use crate::alkane_id::AlkaneId;
+use crate::cellpack::Cellpack;
+use crate::monitor::BlockMonitor;
+use crate::monitor::backoff::Backoff;
+use crate::rpc::{value_to_u128, RpcClient};
+use crate::wallet::{OrdCheck, Utxo, WalletManager, HALVING_INTERVAL, INITIAL_SUBSIDY};
+use crate::runestone::{check_output, encipher_protostones, encode_cellpack};
+use crate::runestone_enhanced::{format_runestone, protocol_tags, protorune_operations, Decoded};
+use protorune_support::balance_sheet::ProtoruneRuneId;
+use protorune_support::protostone::{Protostone, ProtostoneEdict};
 
-/// Dust output value in satoshis
-const DUST_OUTPUT_VALUE: u64 = 546;
+pub mod deploy;
+
+/// Fee rate in satoshis per vbyte standardness dust limits are computed
+/// at, Bitcoin Core's minimum relay fee rate
+const DUST_RELAY_FEE_RATE: f64 = 1.0;
 
 /// Protocol tag for DIESEL token minting
 const PROTOCOL_TAG: u8 = 1;
 
-/// Message cellpack for DIESEL token minting
-const MESSAGE_CELLPACK: [u8; 3] = [2, 0, 77];
+/// Cellpack of a DIESEL mint: contract `2:0`, opcode 77
+pub const DIESEL_MINT_CELLPACK: [u128; 3] = [2, 0, 77];
+
+/// Approximate block capacity in virtual bytes
+const BLOCK_VSIZE: u64 = 1_000_000;
+
+/// Minimum relay fee rate in satoshis per vbyte
+const MIN_FEE_RATE: f64 = 1.0;
+
+/// Worst-case size of a DER-encoded ECDSA signature plus sighash byte
+const ECDSA_SIGNATURE_SIZE: usize = 73;
+
+/// Size of a Schnorr signature plus a non-default sighash byte
+const SCHNORR_SIGNATURE_SIZE: usize = 65;
+
+/// Size of a compressed public key
+const COMPRESSED_PUBKEY_SIZE: usize = 33;
+
+/// Maximum passes adjusting change before the fee must have settled
+const MAX_FEE_ITERATIONS: usize = 4;
+
+/// Maximum weight of a standard transaction
+const MAX_STANDARD_TX_WEIGHT: u64 = 400_000;
+
+/// Largest OP_RETURN script relayed by default (Core's `-datacarriersize`)
+const MAX_OP_RETURN_RELAY: usize = 83;
+
+/// One in this many anti-fee-sniping locktimes is moved back, as Core does
+const LOCKTIME_BACKOFF_ODDS: u32 = 10;
+
+/// Largest random back-off of an anti-fee-sniping locktime, in blocks
+const MAX_LOCKTIME_BACKOFF: u64 = 100;
+
+/// Cellpack target that deploys a contract to the next free alkane ID
+const DEPLOY_CELLPACK_TARGET: [u128; 2] = [1, 0];
+
+/// Default highest fee rate a transaction may pay, in satoshis per vbyte
+const DEFAULT_MAX_FEE_RATE: f64 = 500.0;
+
+/// Default highest fee a transaction may pay, in satoshis
+const DEFAULT_MAX_ABSOLUTE_FEE: u64 = 50_000;
+
+/// Default time between checks that a broadcast transaction is still pending
+const DEFAULT_RESUBMIT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Default time a broadcast transaction is tracked before giving up
+const DEFAULT_BROADCAST_TIMEOUT: Duration = Duration::from_secs(24 * 3600);
+
+/// Default number of times a broadcast failing in transport is retried
+const DEFAULT_BROADCAST_RETRIES: u32 = 3;
+
+/// Default delay before the first broadcast retry, doubling after each
+const DEFAULT_BROADCAST_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest delay between broadcast retries
+const MAX_BROADCAST_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Factor the fee rate of a mint is raised by when the node finds it too low
+const FEE_BUMP_FACTOR: f64 = 1.5;
+
+/// Most fee bumps of a mint before giving up
+const MAX_FEE_BUMPS: usize = 5;
+
+/// Raw DIESEL units in one whole DIESEL
+const DIESEL_UNIT: f64 = 100_000_000.0;
 
 /// Transaction constructor configuration
 pub struct TransactionConfig {
@@ -36,8 +118,186 @@ pub struct TransactionConfig {
     pub max_inputs: usize,
     /// Maximum number of outputs to include in a transaction
     pub max_outputs: usize,
+    /// Target confirmation in this many blocks using the mempool fee histogram
+    /// instead of the fixed `fee_rate`
+    pub target_blocks: Option<u16>,
+    /// Outputs that must never be spent
+    pub frozen_outpoints: Vec<OutPoint>,
+    /// How fee-paying inputs are chosen among the wallet's UTXOs
+    pub coin_selection: CoinSelection,
+    /// Signal replaceability (BIP 125) on every input
+    pub rbf: bool,
+    /// Set the locktime to the current tip height to discourage fee sniping
+    pub anti_fee_sniping: bool,
+    /// Locktime of every transaction, overriding `anti_fee_sniping`
+    pub lock_time: Option<LockTime>,
+    /// Sequence of every input, overriding the one chosen by `rbf`
+    pub sequence: Option<Sequence>,
+    /// How the value of dust outputs (and the change cut-off) is chosen
+    pub dust_policy: DustPolicy,
+    /// Relay policy transactions are checked against before signing
+    pub standardness: StandardnessPolicy,
+    /// Sign transactions that break `standardness` instead of failing
+    pub allow_nonstandard: bool,
+    /// Spend UTXOs carrying inscriptions or rare sats, skipping the ord check
+    pub allow_ordinal_spend: bool,
+    /// What to do when ord can't report on a candidate UTXO
+    pub ord_unavailable: OrdUnavailable,
+    /// DIESEL mints per transaction, one protostone and dust output each
+    pub mint_count: u32,
+    /// Where the dust outputs of mints (receiving the DIESEL) are paid
+    pub dust_recipient: DustRecipient,
+    /// Highest fee rate in satoshis per vbyte a transaction may pay
+    /// (unlimited if `None`)
+    pub max_fee_rate: Option<f64>,
+    /// Highest fee in satoshis a transaction may pay (unlimited if `None`)
+    pub max_absolute_fee: Option<u64>,
+    /// Time between checks that a transaction tracked by
+    /// `broadcast_and_track` is still pending
+    pub resubmit_interval: Duration,
+    /// Time `broadcast_and_track` waits for confirmation before failing
+    pub broadcast_timeout: Duration,
+    /// Times a broadcast failing in transport is retried
+    pub broadcast_retries: u32,
+    /// Delay before the first broadcast retry, doubling after each
+    pub broadcast_retry_delay: Duration,
+    /// Rebuild a mint at a higher fee rate (up to `max_fee_rate`) when the
+    /// node rejects its fee as too low
+    pub bump_fee_on_rejection: bool,
+    /// Sign without decoding the runestone and checking it against the
+    /// protostones it was built from, for runestones `verify_runestone`
+    /// can't follow
+    pub skip_runestone_verification: bool,
+}
+
+/// Recipient of the dust outputs receiving minted DIESEL
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DustRecipient {
+    /// A new change address of the wallet for every mint
+    NewInternal,
+    /// A new receive address of the wallet for every mint
+    NewExternal,
+    /// Always the same address, e.g. a single taproot address for a bot
+    Fixed(Address),
+}
+
+impl DustRecipient {
+    /// A fixed recipient, checked to be an address on `network`
+    pub fn fixed(address: &str, network: Network) -> Result<Self> {
+        let address = Address::from_str(address)
+            .with_context(|| format!("Invalid dust address {}", address))?
+            .require_network(network)
+            .with_context(|| format!("Dust address {} is not a {} address", address, network))?;
+        Ok(DustRecipient::Fixed(address))
+    }
+}
+
+/// Handling of candidate UTXOs that ord can't report on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrdUnavailable {
+    /// Fail the transaction
+    Abort,
+    /// Spend the UTXO anyway, with a warning
+    Proceed,
+}
+
+/// Relay policy limits checked by `validate_standardness`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StandardnessPolicy {
+    /// Largest OP_RETURN output script, in bytes
+    pub max_op_return_size: usize,
+    /// Most OP_RETURN outputs in one transaction
+    pub max_op_returns: usize,
+}
+
+impl Default for StandardnessPolicy {
+    fn default() -> Self {
+        Self {
+            max_op_return_size: MAX_OP_RETURN_RELAY,
+            max_op_returns: 1,
+        }
+    }
+}
+
+/// A way in which a transaction breaks relay policy
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum StandardnessViolation {
+    /// An OP_RETURN script exceeds the relay limit
+    #[error("OP_RETURN output {vout} is {size} bytes, more than the limit of {max_size}")]
+    OpReturnTooLarge {
+        /// Index of the output
+        vout: usize,
+        /// Size of its script in bytes
+        size: usize,
+        /// Configured limit
+        max_size: usize,
+    },
+    /// More OP_RETURN outputs than the policy allows
+    #[error("{count} OP_RETURN outputs, more than the maximum of {max_count}")]
+    TooManyOpReturns {
+        /// Number of OP_RETURN outputs
+        count: usize,
+        /// Configured maximum
+        max_count: usize,
+    },
+    /// An output is worth less than the dust limit of its script
+    #[error("Output {vout} of {value} sats is below its dust limit of {dust_limit} sats")]
+    DustOutput {
+        /// Index of the output
+        vout: usize,
+        /// Value of the output
+        value: u64,
+        /// Dust limit of its script type
+        dust_limit: u64,
+    },
+    /// The signed transaction would exceed the standard size
+    #[error("Transaction is {vsize} vbytes, more than the maximum of {max_vsize}")]
+    TooLarge {
+        /// Estimated virtual size once signed
+        vsize: u64,
+        /// Largest standard virtual size
+        max_vsize: u64,
+    },
+    /// The inputs don't exceed the outputs
+    #[error("Transaction pays no fee")]
+    ZeroFeeRate,
+}
+
+/// Choice of the smallest value given to an output
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DustPolicy {
+    /// The standardness dust limit of each output's script type
+    Standard,
+    /// The same value for every output, whatever its script
+    Fixed(u64),
+}
+
+/// Strategy choosing the fee-paying inputs of a transaction
+///
+/// Every strategy is deterministic: ties are broken by outpoint, so the
+/// same UTXO set always yields the same inputs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CoinSelection {
+    /// Largest UTXOs first, keeping the input count and fee low
+    #[default]
+    LargestFirst,
+    /// Longest-confirmed UTXOs first, unconfirmed ones last
+    OldestFirst,
+    /// A set of UTXOs paying the outputs and fee without change, falling
+    /// back to `LargestFirst` when there is none
+    BranchAndBound,
+    /// Sweep every UTXO below `CONSOLIDATION_THRESHOLD` that pays for its
+    /// own input, then add the largest as needed. Cleans up the small
+    /// change left behind by repeated mints.
+    ConsolidateDust,
 }
 
+/// UTXOs worth less than this many satoshis are swept by `ConsolidateDust`
+pub const CONSOLIDATION_THRESHOLD: u64 = 10_000;
+
+/// Most steps the branch and bound search takes before giving up
+const BNB_MAX_TRIES: usize = 100_000;
+
 impl Default for TransactionConfig {
     fn default() -> Self {
         Self {
@@ -45,8 +305,720 @@ impl Default for TransactionConfig {
             fee_rate: 1.0,        // 1 sat/vbyte
             max_inputs: 100,      // Maximum 100 inputs
             max_outputs: 20,      // Maximum 20 outputs
+            target_blocks: None,  // Use the fixed fee rate
+            frozen_outpoints: Vec::new(),
+            coin_selection: CoinSelection::LargestFirst,
+            rbf: true,
+            anti_fee_sniping: true,
+            lock_time: None,
+            sequence: None,
+            dust_policy: DustPolicy::Standard,
+            standardness: StandardnessPolicy::default(),
+            allow_nonstandard: false,
+            allow_ordinal_spend: false,
+            ord_unavailable: OrdUnavailable::Abort,
+            mint_count: 1,
+            dust_recipient: DustRecipient::NewExternal,
+            max_fee_rate: Some(DEFAULT_MAX_FEE_RATE),
+            max_absolute_fee: Some(DEFAULT_MAX_ABSOLUTE_FEE),
+            resubmit_interval: DEFAULT_RESUBMIT_INTERVAL,
+            broadcast_timeout: DEFAULT_BROADCAST_TIMEOUT,
+            broadcast_retries: DEFAULT_BROADCAST_RETRIES,
+            broadcast_retry_delay: DEFAULT_BROADCAST_RETRY_DELAY,
+            bump_fee_on_rejection: false,
+            skip_runestone_verification: false,
+        }
+    }
+}
+
+/// Errors raised while constructing transactions
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum TransactionError {
+    /// The spendable wallet UTXOs don't cover the outputs and fee
+    #[error("Insufficient funds: need {needed} sats, have {available} sats")]
+    InsufficientFunds {
+        /// Satoshis needed for the outputs and fee
+        needed: u64,
+        /// Satoshis available in spendable UTXOs
+        available: u64,
+    },
+    /// The wallet holds less of a protorune than a transfer sends
+    #[error("Insufficient {block}:{tx} balance: need {needed}, have {available}")]
+    InsufficientTokenBalance {
+        /// Block component of the rune ID
+        block: u128,
+        /// Transaction component of the rune ID
+        tx: u128,
+        /// Raw amount to send
+        needed: u128,
+        /// Raw amount held in spendable UTXOs
+        available: u128,
+    },
+    /// The wallet could fund the outputs, but only with more than `max_inputs` inputs
+    #[error("Funding needs more than {max_inputs} inputs: {selected} sats selected, {needed} sats needed")]
+    TooManyInputsRequired {
+        /// Configured input limit
+        max_inputs: usize,
+        /// Satoshis needed for the outputs and fee
+        needed: u64,
+        /// Satoshis covered by the largest `max_inputs` inputs
+        selected: u64,
+    },
+    /// The outputs and change don't fit in `max_outputs`
+    #[error("Transaction needs {count} outputs, more than the maximum of {max_outputs}")]
+    TooManyOutputs {
+        /// Outputs including the change output
+        count: usize,
+        /// Configured output limit
+        max_outputs: usize,
+    },
+    /// More mint protostones than fit in a standard OP_RETURN
+    #[error("{count} mints don't fit in one OP_RETURN, the maximum is {max_count}")]
+    TooManyMints {
+        /// Requested mints per transaction
+        count: u32,
+        /// Most mints whose runestone stays within the OP_RETURN limit
+        max_count: u32,
+    },
+    /// The fee rate is above `max_fee_rate`
+    #[error("Fee rate of {fee_rate} sat/vB exceeds the maximum of {max_fee_rate} sat/vB")]
+    FeeRateTooHigh {
+        /// Fee rate the transaction would pay
+        fee_rate: f64,
+        /// Configured maximum
+        max_fee_rate: f64,
+    },
+    /// The fee is above `max_absolute_fee`
+    #[error("Fee of {fee} sats exceeds the maximum of {max_fee} sats")]
+    FeeTooHigh {
+        /// Fee the transaction would pay
+        fee: u64,
+        /// Configured maximum
+        max_fee: u64,
+    },
+    /// The transaction breaks relay policy and `allow_nonstandard` is off
+    #[error("Transaction is non-standard: {}", .violations.iter().map(|v| v.to_string()).collect::<Vec<_>>().join("; "))]
+    Nonstandard {
+        /// Every rule the transaction breaks
+        violations: Vec<StandardnessViolation>,
+    },
+    /// A non-zero locktime with only final input sequences, which disable it
+    #[error("Locktime {lock_time} has no effect: every input sequence is final (0xffffffff)")]
+    LockTimeDisabled {
+        /// Consensus value of the locktime
+        lock_time: u32,
+    },
+    /// The runestone of a built transaction is a cenotaph
+    #[error("Runestone is a cenotaph: {}", .flaws.join(", "))]
+    RunestoneCenotaph {
+        /// Flaws reported by the decoder
+        flaws: Vec<String>,
+    },
+    /// The runestone of a built transaction doesn't decode to the
+    /// protostones it was built from
+    #[error("Runestone doesn't match its protostones:\n{diff}")]
+    RunestoneMismatch {
+        /// The differing protostones, expected (`-`) then decoded (`+`)
+        diff: String,
+    },
+    /// A step of `TransactionConstructor::mint` failed
+    #[error("Mint failed at the {stage} stage: {message}")]
+    MintFailed {
+        /// Stage that failed
+        stage: MintStage,
+        /// Error of the failed stage
+        message: String,
+    },
+}
+
+/// Why a node refused to broadcast a transaction
+///
+/// Classified from the error message, which bitcoind and esplora (relaying
+/// bitcoind's reject reason) word the same way.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BroadcastError {
+    /// The node already has the transaction; `send_transaction` treats this
+    /// as success
+    #[error("Transaction already known: {0}")]
+    AlreadyKnown(String),
+    /// The fee is below the node's minimum relay or mempool fee
+    #[error("Fee too low: {0}")]
+    InsufficientFee(String),
+    /// An input is unknown to the node, already spent or in conflict with a
+    /// mempool transaction
+    #[error("Missing or spent inputs: {0}")]
+    MissingInputs(String),
+    /// The request never got an answer from the node
+    #[error("Transport error: {0}")]
+    Transport(String),
+    /// Any other rejection
+    #[error("Transaction rejected: {0}")]
+    Rejected(String),
+}
+
+impl BroadcastError {
+    /// Classify a broadcast error message
+    pub fn classify(message: &str) -> Self {
+        let lower = message.to_ascii_lowercase();
+        let any = |patterns: &[&str]| patterns.iter().any(|pattern| lower.contains(pattern));
+        let message = message.to_string();
+        
+        if any(&["txn-already-in-mempool", "txn-already-known", "already in block chain", "outputs already in utxo set"]) {
+            BroadcastError::AlreadyKnown(message)
+        } else if any(&["min relay fee not met", "mempool min fee not met", "insufficient fee", "min-fee-not-met"]) {
+            BroadcastError::InsufficientFee(message)
+        } else if any(&["missing-inputs", "missing inputs", "missingorspent", "txn-mempool-conflict"]) {
+            BroadcastError::MissingInputs(message)
+        } else if any(&["failed to send rpc request", "rpc request failed with status", "failed to parse rpc response"]) {
+            BroadcastError::Transport(message)
+        } else {
+            BroadcastError::Rejected(message)
+        }
+    }
+}
+
+/// Whether a status lookup failed because the node doesn't know the
+/// transaction, as opposed to the lookup itself failing
+fn is_unknown_transaction(error: &anyhow::Error) -> bool {
+    let message = format!("{:#}", error).to_ascii_lowercase();
+    ["transaction not found", "no such mempool or blockchain transaction"].iter()
+        .any(|pattern| message.contains(pattern))
+}
+
+/// Steps of `TransactionConstructor::mint`, in order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MintStage {
+    /// Building and funding the transaction
+    Construct,
+    /// Signing it with the wallet
+    Sign,
+    /// Sending it to the network
+    Broadcast,
+}
+
+impl fmt::Display for MintStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MintStage::Construct => write!(f, "construct"),
+            MintStage::Sign => write!(f, "sign"),
+            MintStage::Broadcast => write!(f, "broadcast"),
+        }
+    }
+}
+
+/// Outcome of a broadcast mint as far as the trace shows
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MintStatus {
+    /// Broadcast, but not traceable yet (usually unconfirmed)
+    Pending,
+    /// The trace shows the mint executed
+    Minted,
+    /// The trace shows the mint reverted
+    Reverted,
+}
+
+/// Record of a single mint, from construction to the trace
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MintResult {
+    /// Transaction ID reported by the node
+    pub txid: String,
+    /// Fee paid in satoshis
+    pub fee_sats: u64,
+    /// Estimated virtual size of the signed transaction
+    pub vsize: u64,
+    /// Fee rate used, in satoshis per vbyte
+    pub fee_rate: f64,
+    /// Confirmation target the fee rate was chosen for, if any
+    pub block_target: Option<u16>,
+    /// When the transaction was broadcast
+    pub broadcast_at: DateTime<Utc>,
+    /// Trace of the mint protostone, if it could already be traced
+    pub trace: Option<Vec<TraceEvent>>,
+    /// Outcome of the mint
+    pub status: MintStatus,
+}
+
+/// Outcome of a DIESEL mint protostone, read from its trace
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MintTrace {
+    /// Whether the mint executed without reverting
+    pub success: bool,
+    /// Raw DIESEL the mint transferred out, 0 if it reverted
+    pub minted: u128,
+    /// Revert data of the mint, if it reverted
+    pub revert_reason: Option<String>,
+}
+
+/// How `TransactionConstructor::escalate` speeds up an unconfirmed mint
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EscalationStrategy {
+    /// Replace the mint (BIP 125) at `new_fee_rate`, paying the extra fee
+    /// out of its change
+    RbfBump {
+        /// Fee rate of the replacement in satoshis per vbyte
+        new_fee_rate: f64,
+    },
+    /// Spend the mint's change in a child that brings the fee rate of both
+    /// to `package_fee_rate`
+    Cpfp {
+        /// Fee rate of mint and child together in satoshis per vbyte
+        package_fee_rate: f64,
+    },
+}
+
+/// Predicted outcome of a DIESEL mint, from `alkanes_simulate`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimulationOutcome {
+    /// Whether the mint would execute without reverting
+    pub success: bool,
+    /// DIESEL the mint would pay out (0 once the block's cap is reached)
+    pub expected_amount: u128,
+    /// Mint protostones expected to pay out
+    #[serde(default)]
+    pub payable_mints: u32,
+    /// Fuel consumed by the execution
+    pub gas_used: u64,
+    /// Error reported by the execution, if any
+    pub error: Option<String>,
+}
+
+/// Cost breakdown of a DIESEL minting transaction
+#[derive(Clone, Debug, PartialEq)]
+pub struct MintQuote {
+    /// Requested fee rate in satoshis per vbyte
+    pub fee_rate: f64,
+    /// Estimated virtual size of the signed transaction
+    pub vsize: u64,
+    /// Fee rate actually paid, fee divided by vsize
+    pub effective_fee_rate: f64,
+    /// Total value of the selected inputs (0 if the wallet can't fund the mint)
+    pub total_input: u64,
+    /// Value of the dust output receiving the minted DIESEL
+    pub dust: u64,
+    /// Transaction fee in satoshis
+    pub fee: u64,
+    /// Value returned to the wallet as change
+    pub change: u64,
+    /// Confirmed wallet balance in satoshis
+    pub confirmed_balance: u64,
+}
+
+impl MintQuote {
+    /// Check whether the confirmed balance covers the dust output and fee
+    pub fn is_affordable(&self) -> bool {
+        self.confirmed_balance >= self.dust + self.fee
+    }
+}
+
+/// Market price of DIESEL, as supplied by the user
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct DieselPrice {
+    /// Satoshis paid for one whole DIESEL (10^8 raw units)
+    pub sats_per_diesel: f64,
+}
+
+/// Expected value of a mint against its cost
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ProfitabilityReport {
+    /// Raw DIESEL the mint is expected to pay out
+    pub expected_amount: u128,
+    /// Whether `expected_amount` comes from a simulation rather than the
+    /// halving schedule
+    pub simulated: bool,
+    /// Price the payout is valued at, if one was given
+    pub price: Option<DieselPrice>,
+    /// Value of the payout in satoshis, if a price was given
+    pub expected_value_sats: Option<u64>,
+    /// Fee of the mint in satoshis
+    pub cost_sats: u64,
+    /// Expected value minus cost, if a price was given
+    pub expected_profit_sats: Option<i64>,
+    /// Whether the mint is expected to pay off; without a price, whether it
+    /// pays out anything at all
+    pub profitable: bool,
+}
+
+impl ProfitabilityReport {
+    /// Value `expected_amount` at `price` against a fee of `cost_sats`
+    pub fn new(expected_amount: u128, simulated: bool, cost_sats: u64, price: Option<DieselPrice>) -> Self {
+        let expected_value_sats = price.map(|price| (expected_amount as f64 / DIESEL_UNIT * price.sats_per_diesel).floor() as u64);
+        let expected_profit_sats = expected_value_sats.map(|value| value as i64 - cost_sats as i64);
+        Self {
+            expected_amount,
+            simulated,
+            price,
+            expected_value_sats,
+            cost_sats,
+            expected_profit_sats,
+            profitable: match expected_profit_sats {
+                Some(profit) => profit > 0,
+                None => expected_amount > 0,
+            },
+        }
+    }
+}
+
+/// A transaction built but not broadcast, as reported by `dry_run`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DryRun {
+    /// Transaction ID
+    pub txid: String,
+    /// Whether the wallet signed the transaction
+    pub signed: bool,
+    /// Serialized transaction in hex, without witnesses if unsigned
+    pub raw_hex: String,
+    /// Virtual size, estimated for unsigned transactions
+    pub vsize: u64,
+    /// Fee in satoshis
+    pub fee: u64,
+    /// Fee rate in satoshis per vbyte
+    pub fee_rate: f64,
+    /// Spent outputs, in input order
+    pub inputs: Vec<DryRunInput>,
+    /// Created outputs
+    pub outputs: Vec<DryRunOutput>,
+    /// Protostones decoded from the runestone, if any
+    pub protostones: Vec<ProtostoneSummary>,
+}
+
+/// An input of a `DryRun`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DryRunInput {
+    /// Spent outpoint as `txid:vout`
+    pub outpoint: String,
+    /// Address of the spent output, if it has one
+    pub address: Option<String>,
+    /// Value of the spent output in satoshis
+    pub value: u64,
+}
+
+/// An output of a `DryRun`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DryRunOutput {
+    /// Output index
+    pub vout: usize,
+    /// Receiving address, if the script has one
+    pub address: Option<String>,
+    /// Value in satoshis
+    pub value: u64,
+    /// Whether this is an OP_RETURN output
+    pub op_return: bool,
+}
+
+/// A decoded protostone of a `DryRun`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ProtostoneSummary {
+    /// Protocol tag (1 for alkanes)
+    pub protocol_tag: u128,
+    /// Cellpack decoded from the message (empty if it isn't one)
+    pub cellpack: Vec<u128>,
+    /// Edicts as (rune ID, amount, output)
+    pub edicts: Vec<(AlkaneId, u128, u128)>,
+    /// Output receiving unallocated runes
+    pub pointer: Option<u32>,
+    /// Output receiving runes if the call reverts
+    pub refund: Option<u32>,
+}
+
+impl From<&Protostone> for ProtostoneSummary {
+    fn from(protostone: &Protostone) -> Self {
+        Self {
+            protocol_tag: protostone.protocol_tag,
+            cellpack: crate::varint::decode_all(&protostone.message).unwrap_or_default(),
+            edicts: protostone.edicts.iter()
+                .map(|edict| (AlkaneId::from(edict.id), edict.amount, edict.output))
+                .collect(),
+            pointer: protostone.pointer,
+            refund: protostone.refund,
+        }
+    }
+}
+
+impl fmt::Display for ProtostoneSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let edicts: Vec<String> = self.edicts.iter()
+            .map(|(id, amount, output)| format!("{} {} -> {}", amount, id, output))
+            .collect();
+        write!(f, "tag {}, cellpack {:?}, edicts [{}], pointer {:?}, refund {:?}",
+            self.protocol_tag, self.cellpack, edicts.join(", "), self.pointer, self.refund)
+    }
+}
+
+impl fmt::Display for DryRun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let address = |address: &Option<String>| address.clone().unwrap_or_else(|| "-".to_string());
+        writeln!(f, "Transaction:   {} ({})", self.txid, if self.signed { "signed" } else { "unsigned" })?;
+        writeln!(f, "Size:          {} vB", self.vsize)?;
+        writeln!(f, "Fee:           {} sats ({:.2} sat/vB)", self.fee, self.fee_rate)?;
+        writeln!(f, "Inputs:")?;
+        for input in &self.inputs {
+            writeln!(f, "  {} {} {} sats", input.outpoint, address(&input.address), input.value)?;
+        }
+        writeln!(f, "Outputs:")?;
+        for output in &self.outputs {
+            let destination = if output.op_return { "OP_RETURN".to_string() } else { address(&output.address) };
+            writeln!(f, "  {}: {} {} sats", output.vout, destination, output.value)?;
+        }
+        for (index, protostone) in self.protostones.iter().enumerate() {
+            writeln!(f, "Protostone {}: {}", index, protostone)?;
+        }
+        write!(f, "Raw:           {}", self.raw_hex)
+    }
+}
+
+/// The result of building a transaction with the wallet
+#[derive(Clone, Debug, PartialEq)]
+pub enum MintArtifact {
+    /// A fully signed transaction, ready to broadcast
+    Signed(Transaction),
+    /// An unsigned PSBT, returned when the wallet is watch-only so an
+    /// external signer can take over
+    Psbt(PartiallySignedTransaction),
+}
+
+impl MintArtifact {
+    /// Get the transaction, signed or not
+    pub fn transaction(&self) -> &Transaction {
+        match self {
+            MintArtifact::Signed(tx) => tx,
+            MintArtifact::Psbt(psbt) => &psbt.unsigned_tx,
+        }
+    }
+}
+
+/// A protorune edict: `amount` of rune `id` to output `output`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Edict {
+    /// Rune ID as `(block, tx)`
+    pub id: (u128, u128),
+    /// Raw amount to transfer
+    pub amount: u128,
+    /// Index of the receiving output
+    pub output: u32,
+}
+
+/// A protostone to be carried in a transaction's OP_RETURN
+///
+/// The cellpack is the target contract's `block` and `tx` followed by its
+/// inputs; an empty cellpack yields a protostone without a message, as used
+/// for plain transfers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProtostoneSpec {
+    /// Protocol tag of the protostone (1 for alkanes)
+    pub protocol_tag: u128,
+    /// Cellpack values, encoded as the protostone message
+    pub cellpack: Vec<u128>,
+    /// Edicts applied before the message executes
+    pub edicts: Vec<Edict>,
+    /// Output receiving unallocated runes, also used as the runestone pointer
+    pub pointer: Option<u32>,
+    /// Output receiving the runes if the message reverts
+    pub refund: Option<u32>,
+    /// Protocol tag the protostone burns runes under, if any
+    pub burn: Option<u128>,
+}
+
+impl ProtostoneSpec {
+    /// The DIESEL mint, with the minted tokens going to output 0
+    pub fn diesel_mint() -> Self {
+        Self::diesel_mint_to(0)
+    }
+    
+    /// The DIESEL mint, with the minted tokens going to `output`
+    pub fn diesel_mint_to(output: u32) -> Self {
+        Self {
+            protocol_tag: u128::from(PROTOCOL_TAG),
+            cellpack: DIESEL_MINT_CELLPACK.to_vec(),
+            edicts: Vec::new(),
+            pointer: Some(output),
+            refund: Some(output),
+            burn: None,
+        }
+    }
+    
+    /// A transfer of rune `rune_id`, without a message
+    ///
+    /// `edicts` are `(amount, output)` pairs; unallocated tokens go to output
+    /// `pointer`, which is also the refund.
+    pub fn transfer(rune_id: (u128, u128), edicts: &[(u128, u32)], pointer: u32) -> Self {
+        Self {
+            protocol_tag: u128::from(PROTOCOL_TAG),
+            cellpack: Vec::new(),
+            edicts: edicts.iter()
+                .map(|(amount, output)| Edict { id: rune_id, amount: *amount, output: *output })
+                .collect(),
+            pointer: Some(pointer),
+            refund: Some(pointer),
+            burn: None,
+        }
+    }
+    
+    /// Split `input_amount` of `rune_id` into `amounts`, amount `i` going
+    /// to output `i`
+    ///
+    /// The protorune cellpack is `[SPLIT, block, tx, amounts...]`. The
+    /// amounts must be positive and add up to `input_amount` exactly, so no
+    /// runes are left for the pointer.
+    pub fn split(rune_id: (u128, u128), input_amount: u128, amounts: &[u128]) -> Result<Self> {
+        if amounts.len() < 2 {
+            return Err(anyhow!("A split needs at least two output amounts"));
+        }
+        if amounts.contains(&0) {
+            return Err(anyhow!("Split amounts must be positive"));
+        }
+        let total = checked_sum(amounts).ok_or_else(|| anyhow!("Split amounts overflow"))?;
+        if total != input_amount {
+            return Err(anyhow!("Split amounts add up to {}, but the inputs hold {}", total, input_amount));
+        }
+        
+        let mut cellpack = vec![u128::from(protorune_operations::SPLIT), rune_id.0, rune_id.1];
+        cellpack.extend_from_slice(amounts);
+        Ok(Self {
+            protocol_tag: protocol_tags::PROTORUNE,
+            cellpack,
+            edicts: amounts.iter().enumerate()
+                .map(|(output, amount)| Edict { id: rune_id, amount: *amount, output: output as u32 })
+                .collect(),
+            pointer: Some(0),
+            refund: Some(0),
+            burn: None,
+        })
+    }
+    
+    /// Join the `input_amounts` of `rune_id` held by several inputs into
+    /// `output`
+    ///
+    /// The protorune cellpack is `[JOIN, block, tx, total]`, and a single
+    /// edict moves the total to `output`.
+    pub fn join(rune_id: (u128, u128), input_amounts: &[u128], output: u32) -> Result<Self> {
+        if input_amounts.len() < 2 {
+            return Err(anyhow!("A join needs at least two inputs"));
+        }
+        let total = checked_sum(input_amounts).ok_or_else(|| anyhow!("Join amounts overflow"))?;
+        if total == 0 {
+            return Err(anyhow!("Nothing to join"));
+        }
+        
+        Ok(Self {
+            protocol_tag: protocol_tags::PROTORUNE,
+            cellpack: vec![u128::from(protorune_operations::JOIN), rune_id.0, rune_id.1, total],
+            edicts: vec![Edict { id: rune_id, amount: total, output }],
+            pointer: Some(output),
+            refund: Some(output),
+            burn: None,
+        })
+    }
+    
+    /// Encode the spec as an OP_RETURN runestone script
+    pub fn encipher(&self) -> Result<ScriptBuf> {
+        Self::encipher_all(std::slice::from_ref(self))
+    }
+    
+    /// Check that the pointer and refund target existing, non-OP_RETURN
+    /// outputs among `outputs`
+    pub fn validate_outputs(&self, outputs: &[TxOut]) -> Result<()> {
+        if let Some(pointer) = self.pointer {
+            check_output(outputs, pointer, "pointer")?;
+        }
+        if let Some(refund) = self.refund {
+            check_output(outputs, refund, "refund")?;
+        }
+        Ok(())
+    }
+    
+    /// Encode the spec for a transaction with `outputs`, checking its
+    /// pointer and refund first
+    pub fn encipher_for(&self, outputs: &[TxOut]) -> Result<ScriptBuf> {
+        self.validate_outputs(outputs)?;
+        self.encipher()
+    }
+    
+    /// Convert the spec to the protostone it encodes as
+    pub fn to_protostone(&self) -> Protostone {
+        Protostone {
+            burn: self.burn,
+            message: encode_cellpack(&self.cellpack),
+            edicts: self.edicts.iter()
+                .map(|edict| ProtostoneEdict {
+                    id: ProtoruneRuneId { block: edict.id.0, tx: edict.id.1 },
+                    amount: edict.amount,
+                    output: u128::from(edict.output),
+                })
+                .collect(),
+            refund: self.refund,
+            pointer: self.pointer,
+            from: None,
+            protocol_tag: self.protocol_tag,
         }
     }
+    
+    /// Recover the spec from a decoded protostone
+    ///
+    /// The cellpack is the message's values as decoded, trailing zeros
+    /// included; `verify_runestone` is what tolerates zero padding.
+    ///
+    /// Fails if the message isn't a cellpack or an edict output doesn't fit
+    /// an output index.
+    pub fn from_protostone(protostone: &Protostone) -> Result<Self> {
+        let cellpack = crate::varint::decode_all(&protostone.message)
+            .context("Protostone message isn't a cellpack")?;
+        let edicts = protostone.edicts.iter()
+            .map(|edict| Ok(Edict {
+                id: (edict.id.block, edict.id.tx),
+                amount: edict.amount,
+                output: u32::try_from(edict.output)
+                    .map_err(|_| anyhow!("Edict output {} out of range", edict.output))?,
+            }))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            protocol_tag: protostone.protocol_tag,
+            cellpack,
+            edicts,
+            pointer: protostone.pointer,
+            refund: protostone.refund,
+            burn: protostone.burn,
+        })
+    }
+    
+    /// Encode several specs as the protostones of one runestone script
+    ///
+    /// The runestone pointer is that of the first spec.
+    pub fn encipher_all(specs: &[ProtostoneSpec]) -> Result<ScriptBuf> {
+        let protostones = specs.iter().map(ProtostoneSpec::to_protostone).collect();
+        encipher_protostones(protostones, specs.first().and_then(|spec| spec.pointer))
+    }
+}
+
+/// Where `create_protostone_transaction` sends its first output
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProtostoneOptions {
+    /// Recipient address (a new wallet address if not specified)
+    pub to: Option<String>,
+    /// Output value in satoshis (dust if not specified)
+    pub value: Option<u64>,
+}
+
+/// A single event of an alkanes execution trace
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TraceEvent {
+    /// Event kind, e.g. "enter", "create" or "return"
+    pub event: String,
+    /// Event payload
+    #[serde(default)]
+    pub data: serde_json::Value,
+}
+
+/// An unsigned transaction funded from wallet UTXOs
+struct FundedTransaction {
+    /// The funded transaction, without witnesses
+    tx: Transaction,
+    /// Outputs spent by each input, in input order
+    prevouts: Vec<TxOut>,
+    /// Total value of the inputs
+    total_input: u64,
+    /// Fee paid, total input minus total output
+    fee: u64,
 }
 
 /// Transaction constructor for creating DIESEL token minting transactions
@@ -57,6 +1029,8 @@ pub struct TransactionConstructor {
     rpc_client: Arc<RpcClient>,
     /// Transaction configuration
     config: TransactionConfig,
+    /// Monitor that broadcast mints are registered with, if any
+    monitor: Option<Arc<BlockMonitor>>,
 }
 
 impl TransactionConstructor {
@@ -70,158 +1044,3768 @@ impl TransactionConstructor {
             wallet_manager,
             rpc_client,
             config,
+            monitor: None,
         }
     }
     
-    /// Create a DIESEL token minting transaction
-    pub async fn create_minting_transaction(&self) -> Result<Transaction> {
-        info!("Creating DIESEL token minting transaction");
+    /// Register transactions broadcast by `mint` with `monitor`
+    pub fn with_monitor(mut self, monitor: Arc<BlockMonitor>) -> Self {
+        self.monitor = Some(monitor);
+        self
+    }
+    
+    /// Construct, sign and broadcast a DIESEL mint in one call
+    ///
+    /// The broadcast transaction is watched by the monitor, if one was set
+    /// with `with_monitor`, and traced right away. A trace is usually only
+    /// available once the mint confirms, so a failed trace leaves the result
+    /// `Pending` rather than failing the mint. Errors are
+    /// `TransactionError::MintFailed` naming the stage that failed.
+    ///
+    /// A mint rejected for missing inputs is rebuilt once after a wallet
+    /// sync. With `bump_fee_on_rejection`, one rejected for its fee is
+    /// rebuilt at a higher fee rate until it is accepted or would exceed
+    /// `max_fee_rate`.
+    pub async fn mint(&self) -> Result<MintResult> {
+        let failed = |stage: MintStage| move |e: anyhow::Error| TransactionError::MintFailed {
+            stage,
+            message: format!("{:#}", e),
+        };
         
-        // Get a new address for the dust output
-        let dust_address = self.wallet_manager.get_address().await?;
-        let address = Address::from_str(&dust_address)
-            .context("Failed to parse dust address")?;
-        let dust_script = address.assume_checked().script_pubkey();
+        let mut fee_rate = self.resolve_fee_rate().await.map_err(failed(MintStage::Construct))?;
+        let mut fee_bumps = 0;
+        let mut resynced = false;
+        let (tx, txid, fee_sats, vsize) = loop {
+            let funded = self.fund_mint(fee_rate).await.map_err(failed(MintStage::Construct))?;
+            let fee_sats = funded.fee;
+            let vsize = estimate_signed_vsize(&funded.tx, &funded.prevouts);
+            
+            let tx = match self.sign_funded(funded).await.map_err(failed(MintStage::Sign))? {
+                MintArtifact::Signed(tx) => tx,
+                MintArtifact::Psbt(_) => return Err(failed(MintStage::Sign)(anyhow!("wallet is watch-only")).into()),
+            };
+            
+            let e = match self.send_transaction(&tx).await {
+                Ok(txid) => break (tx, txid, fee_sats, vsize),
+                Err(e) => e,
+            };
+            match e.downcast_ref::<BroadcastError>() {
+                Some(BroadcastError::InsufficientFee(_)) if self.config.bump_fee_on_rejection && fee_bumps < MAX_FEE_BUMPS => {
+                    let bumped = fee_rate * FEE_BUMP_FACTOR;
+                    if let Err(limit) = self.check_fee_rate(bumped) {
+                        return Err(failed(MintStage::Broadcast)(e.context(limit.to_string())).into());
+                    }
+                    warn!("Mint {} rejected for its fee, rebuilding at {:.2} sat/vB", tx.txid(), bumped);
+                    fee_rate = bumped;
+                    fee_bumps += 1;
+                },
+                Some(BroadcastError::MissingInputs(_)) if !resynced => {
+                    warn!("Mint {} spends missing inputs, syncing the wallet and reselecting", tx.txid());
+                    self.wallet_manager.sync().await.map_err(failed(MintStage::Construct))?;
+                    resynced = true;
+                },
+                _ => return Err(failed(MintStage::Broadcast)(e).into()),
+            }
+        };
+        let broadcast_at = Utc::now();
+        if let Some(monitor) = &self.monitor {
+            monitor.watch_transaction(&txid).await;
+        }
         
-        // Create Runestone with Protostone for DIESEL token minting
-        let runestone = Runestone::new_diesel();
-        let runestone_script = runestone.encipher();
+        let traces = match self.trace_protostones(&tx).await {
+            Ok(traces) => Some(traces),
+            Err(e) => {
+                debug!("Mint {} is not traceable yet: {}", txid, e);
+                None
+            },
+        };
+        // With several mints, the transaction minted if any of them did
+        let reverted = |events: &[TraceEvent]| events.iter().any(|event| event.event == "return" && event.data["status"] == "revert");
+        let status = match &traces {
+            None => MintStatus::Pending,
+            Some(traces) if traces.iter().all(|events| reverted(events)) => MintStatus::Reverted,
+            Some(_) => MintStatus::Minted,
+        };
+        let trace = traces.map(|traces| traces.concat());
         
-        // TODO: Implement actual UTXO selection and transaction construction
-        // This is a placeholder implementation
+        info!("Mint {} broadcast, status {:?}", txid, status);
+        Ok(MintResult {
+            txid,
+            fee_sats,
+            vsize,
+            fee_rate,
+            block_target: self.config.target_blocks,
+            broadcast_at,
+            trace,
+            status,
+        })
+    }
+    
+    /// Simulate a DIESEL mint at the indexer's current height
+    ///
+    /// Tells before any fee is paid whether a mint broadcast now would pay
+    /// out, e.g. not when the per-block cap is already reached. The
+    /// simulation runs against the state before the transaction, so with
+    /// `mint_count` above one every mint is expected to pay like the first,
+    /// and none once the cap is reached.
+    pub async fn simulate_mint(&self) -> Result<SimulationOutcome> {
+        let [block, tx, opcode] = DIESEL_MINT_CELLPACK;
+        let result = self.rpc_client.simulate(&AlkaneId::new(block, tx), &[opcode.to_string()]).await
+            .context("Failed to simulate the mint")?;
+        let mut outcome = parse_simulation_outcome(&result)?;
+        if outcome.payable_mints > 0 {
+            let count = self.config.mint_count.max(1);
+            outcome.payable_mints = count;
+            outcome.expected_amount = outcome.expected_amount.saturating_mul(u128::from(count));
+        }
         
-        // 1. Get spendable UTXOs
-        // In a real implementation, we would:
-        // - Get regular BTC outputs via esplora
-        // - Check ordinal safety via ord_address
-        // - Get DIESEL balance via alkanes_protorunesbyaddress
-        
-        // 2. Select UTXOs for spending
-        // In a real implementation, we would select UTXOs based on:
-        // - Regular BTC outputs for fees
-        // - DIESEL outputs for consolidation
-        
-        // Create transaction with:
-        // - Dust output (546 sats)
-        // - OP_RETURN output with Runestone
-        let tx = Transaction {
-            version: 2,
-            lock_time: bdk::bitcoin::absolute::LockTime::ZERO,
-            input: vec![],
-            output: vec![
-                // Dust output
-                TxOut {
-                    value: DUST_OUTPUT_VALUE,
-                    script_pubkey: dust_script,
-                },
-                // OP_RETURN output with Runestone
-                TxOut {
-                    value: 0,
-                    script_pubkey: runestone_script,
-                },
-            ],
+        debug!("Mint simulation: {:?}", outcome);
+        Ok(outcome)
+    }
+    
+    /// Estimate whether a mint quoted by `quote` pays for itself
+    ///
+    /// The payout comes from `simulate_mint` when the simulation succeeds,
+    /// otherwise from the DIESEL emission at the next block, which assumes
+    /// no other mints share it and is therefore an upper bound.
+    pub async fn estimate_profitability(&self, quote: &MintQuote, price: Option<DieselPrice>) -> Result<ProfitabilityReport> {
+        let (expected_amount, simulated) = match self.simulate_mint().await {
+            Ok(outcome) => (outcome.expected_amount, true),
+            Err(e) => {
+                debug!("Falling back to the halving schedule: {:#}", e);
+                let height = self.rpc_client.get_block_count().await
+                    .context("Failed to get the height for the mint reward")?;
+                let reward = diesel_block_reward(height + 1);
+                (reward.saturating_mul(u128::from(self.config.mint_count.max(1))), false)
+            },
         };
         
-        info!("DIESEL token minting transaction created successfully");
-        debug!("Transaction: {:?}", tx);
-        Ok(tx)
+        let report = ProfitabilityReport::new(expected_amount, simulated, quote.fee, price);
+        debug!("Mint profitability: {:?}", report);
+        Ok(report)
     }
     
-    /// Broadcast a transaction to the network
-    pub async fn broadcast_transaction(&self, tx: &Transaction) -> Result<String> {
-        info!("Broadcasting transaction");
+    /// Speed up a mint that is still unconfirmed
+    ///
+    /// An RBF bump re-signs the mint with the same inputs and outputs, only
+    /// taking the extra fee from the change output (dropping it if what is
+    /// left would be dust), so the runestone stays byte-for-byte the same.
+    /// The replacement pays strictly more than the original, as BIP 125
+    /// requires. A CPFP child spends the change to a new change address.
+    ///
+    /// Returns the broadcast replacement or child, which is watched by the
+    /// monitor if one was set.
+    pub async fn escalate(&self, mint: &MintResult, strategy: EscalationStrategy) -> Result<MintResult> {
+        let tx = self.rpc_client.get_transaction(&mint.txid).await?;
+        let prevouts = self.fetch_prevouts(&tx).await?;
         
-        // Serialize transaction to hex
-        let _tx_hex = hex::encode(serialize(tx));
+        let (funded, fee_rate) = match strategy {
+            EscalationStrategy::RbfBump { new_fee_rate } => (self.rbf_replacement(&tx, &prevouts, new_fee_rate).await?, new_fee_rate),
+            EscalationStrategy::Cpfp { package_fee_rate } => (self.cpfp_child(&tx, &prevouts, package_fee_rate).await?, package_fee_rate),
+        };
+        let fee_sats = funded.fee;
+        let vsize = estimate_signed_vsize(&funded.tx, &funded.prevouts);
         
-        // Get the transaction ID before broadcasting
-        let txid = tx.txid().to_string();
+        let signed = match self.sign_funded(funded).await? {
+            MintArtifact::Signed(tx) => tx,
+            MintArtifact::Psbt(_) => return Err(anyhow!("Wallet is watch-only, cannot escalate mint {}", mint.txid)),
+        };
+        let txid = self.send_transaction(&signed).await?;
+        if let Some(monitor) = &self.monitor {
+            monitor.watch_transaction(&txid).await;
+        }
         
-        // In a real implementation, we would:
-        // - Send the transaction to the Bitcoin network via RPC
-        // - Start monitoring for confirmations
+        info!("Escalated mint {} with {:?}: {} sats in {}", mint.txid, strategy, fee_sats, txid);
+        Ok(MintResult {
+            txid,
+            fee_sats,
+            vsize,
+            fee_rate,
+            block_target: None,
+            broadcast_at: Utc::now(),
+            trace: None,
+            status: MintStatus::Pending,
+        })
+    }
+    
+    /// Fetch the outputs spent by each input of `tx`, in input order
+    async fn fetch_prevouts(&self, tx: &Transaction) -> Result<Vec<TxOut>> {
+        self.rpc_client.get_prevouts(tx).await
+    }
+    
+    /// Index of the wallet change output of a mint, if it has one
+    ///
+    /// Change is the last output, after the runestone, paying to the wallet.
+    async fn change_output(&self, tx: &Transaction) -> Result<Option<usize>> {
+        let runestone = tx.output.iter().position(|output| output.script_pubkey.is_op_return());
+        let last = tx.output.len().checked_sub(1);
+        match (runestone, last) {
+            (Some(runestone), Some(last)) if last > runestone => {
+                let is_mine = self.wallet_manager.is_mine(&tx.output[last].script_pubkey).await?;
+                Ok(is_mine.then_some(last))
+            },
+            _ => Ok(None),
+        }
+    }
+    
+    /// Build an unsigned replacement of `tx` at `fee_rate`
+    async fn rbf_replacement(&self, tx: &Transaction, prevouts: &[TxOut], fee_rate: f64) -> Result<FundedTransaction> {
+        let txid = tx.txid();
+        if !tx.input.iter().any(|input| input.sequence.is_rbf()) {
+            return Err(anyhow!("Mint {} does not signal replaceability, use CPFP instead", txid));
+        }
+        let total_input: u64 = prevouts.iter().map(|prevout| prevout.value).sum();
+        let old_fee = total_input.checked_sub(tx.output.iter().map(|output| output.value).sum())
+            .ok_or_else(|| anyhow!("Mint {} spends more than its inputs", txid))?;
+        let change = self.change_output(tx).await?
+            .ok_or_else(|| anyhow!("Mint {} has no change to pay a higher fee from, use CPFP instead", txid))?;
         
-        // For now, just log the transaction ID
-        info!("Transaction broadcast successfully: {}", txid);
+        let mut replacement = tx.clone();
+        for input in &mut replacement.input {
+            input.script_sig = ScriptBuf::new();
+            input.witness = Witness::new();
+        }
         
-        // Trace the transaction to verify DIESEL token minting
-        self.trace_transaction(&txid).await?;
+        // BIP 125: the replacement must also pay for its own relay
+        let required_fee = |tx: &Transaction| {
+            let vsize = estimate_signed_vsize(tx, prevouts);
+            fee_for(tx, prevouts, fee_rate).max(old_fee + (vsize as f64 * MIN_FEE_RATE).ceil() as u64)
+        };
+        let fee = required_fee(&replacement);
+        let change_value = replacement.output[change].value;
+        let change_dust = self.dust_limit(&replacement.output[change].script_pubkey);
         
-        Ok(txid)
+        let fee = match (old_fee + change_value).checked_sub(fee) {
+            Some(remaining) if remaining >= change_dust => {
+                replacement.output[change].value = remaining;
+                fee
+            },
+            _ => {
+                replacement.output.remove(change);
+                if old_fee + change_value < required_fee(&replacement) {
+                    return Err(TransactionError::InsufficientFunds {
+                        needed: required_fee(&replacement),
+                        available: old_fee + change_value,
+                    }.into());
+                }
+                old_fee + change_value
+            },
+        };
+        
+        Ok(FundedTransaction {
+            tx: replacement,
+            prevouts: prevouts.to_vec(),
+            total_input,
+            fee,
+        })
     }
     
-    /// Trace a transaction to verify DIESEL token minting
-    pub async fn trace_transaction(&self, txid: &str) -> Result<()> {
-        // For DIESEL token minting, the vout for tracing is tx.output.len() + 1
-        // This is because the Runestone protocol uses a 1-based index for outputs
-        // and the OP_RETURN output is typically the last output in the transaction
-        let vout = 2; // Dust output (index 0) + OP_RETURN output (index 1) + 1
+    /// Build an unsigned child of `tx` spending its change at
+    /// `package_fee_rate` for both
+    async fn cpfp_child(&self, tx: &Transaction, prevouts: &[TxOut], package_fee_rate: f64) -> Result<FundedTransaction> {
+        let txid = tx.txid();
+        let parent_fee = prevouts.iter().map(|prevout| prevout.value).sum::<u64>()
+            .checked_sub(tx.output.iter().map(|output| output.value).sum())
+            .ok_or_else(|| anyhow!("Mint {} spends more than its inputs", txid))?;
+        let vout = self.change_output(tx).await?
+            .ok_or_else(|| anyhow!("Mint {} has no change output for a child to spend", txid))?;
+        let change = tx.output[vout].clone();
         
-        info!("Tracing transaction: {} vout: {}", txid, vout);
+        let destination = Address::from_str(&self.wallet_manager.get_change_address().await?)
+            .context("Failed to parse change address")?
+            .assume_checked()
+            .script_pubkey();
+        let mut child = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint { txid, vout: vout as u32 },
+                sequence: self.sequence(),
+                ..Default::default()
+            }],
+            output: vec![TxOut { value: 0, script_pubkey: destination }],
+        };
+        let child_prevouts = vec![change.clone()];
         
-        // Reverse txid bytes for trace calls
-        // In a real implementation, we would reverse the bytes
-        // For now, just use the txid as-is
-        let reversed_txid = txid.to_string();
+        let child_vsize = estimate_signed_vsize(&child, &child_prevouts);
+        let package_vsize = tx.vsize() as u64 + child_vsize;
+        let fee = ((package_vsize as f64 * package_fee_rate).ceil() as u64)
+            .saturating_sub(parent_fee)
+            .max((child_vsize as f64 * MIN_FEE_RATE).ceil() as u64);
+        let dust = self.dust_limit(&child.output[0].script_pubkey);
+        child.output[0].value = change.value.checked_sub(fee)
+            .filter(|value| *value >= dust)
+            .ok_or(TransactionError::InsufficientFunds { needed: fee + dust, available: change.value })?;
         
-        // Call alkanes_trace with reversed txid and appropriate vout
-        let trace_result = self.rpc_client.trace_transaction(&reversed_txid, vout).await?;
+        Ok(FundedTransaction {
+            tx: child,
+            prevouts: child_prevouts,
+            total_input: change.value,
+            fee,
+        })
+    }
+    
+    /// Create a funded and signed DIESEL token minting transaction
+    ///
+    /// Inputs are plain-sats wallet UTXOs covering the dust outputs, one per
+    /// `mint_count`, and the fee at the configured fee rate. If the wallet is
+    /// watch-only, the unsigned PSBT is returned instead.
+    pub async fn create_minting_transaction(&self) -> Result<MintArtifact> {
+        let fee_rate = self.resolve_fee_rate().await?;
+        let funded = self.fund_mint(fee_rate).await?;
+        self.sign_funded(funded).await
+    }
+    
+    /// Create a DIESEL token minting transaction and its cost breakdown
+    pub async fn create_minting_transaction_with_quote(&self) -> Result<(MintArtifact, MintQuote)> {
+        info!("Creating DIESEL token minting transaction");
         
-        info!("Transaction traced successfully");
-        debug!("Trace result: {:?}", trace_result);
+        let fee_rate = self.resolve_fee_rate().await?;
+        let funded = self.fund_mint(fee_rate).await?;
+        let quote = self.quote_funded(&funded, fee_rate).await?;
+        let artifact = self.sign_funded(funded).await?;
         
-        Ok(())
+        info!("DIESEL token minting transaction created successfully");
+        debug!("Transaction: {:?}", artifact);
+        Ok((artifact, quote))
+    }
+    
+    /// Build and fund the outputs of a DIESEL mint, checking its runestone
+    async fn fund_mint(&self, fee_rate: f64) -> Result<FundedTransaction> {
+        let outputs = self.minting_outputs().await?;
+        let funded = self.fund_outputs(&[], outputs, fee_rate).await?;
+        self.verify_runestone(&funded.tx, &self.minting_specs())?;
+        Ok(funded)
+    }
+    
+    /// Check the runestone of `tx` against `expected` with
+    /// `verify_runestone`, unless `skip_runestone_verification` is set
+    fn verify_runestone(&self, tx: &Transaction, expected: &[ProtostoneSpec]) -> Result<()> {
+        if self.config.skip_runestone_verification {
+            return Ok(());
+        }
+        verify_runestone(tx, expected)
     }
     
-    /// Create a Runestone with Protostone
-    fn create_runestone(&self) -> Result<bdk::bitcoin::ScriptBuf> {
-        // TODO: Implement actual Runestone creation
-        // This is a placeholder implementation
+    /// Sign a funded transaction with the wallet
+    ///
+    /// Transactions paying more than `max_fee_rate` or `max_absolute_fee`
+    /// are refused, as are those breaking relay policy unless
+    /// `allow_nonstandard` is set.
+    async fn sign_funded(&self, mut funded: FundedTransaction) -> Result<MintArtifact> {
+        let vsize = estimate_signed_vsize(&funded.tx, &funded.prevouts);
+        self.check_fee_rate(funded.fee as f64 / vsize as f64)?;
+        self.check_fee(funded.fee)?;
         
-        // In a real implementation, we would:
-        // - Create an OP_RETURN output
-        // - Include protocol tag (1)
-        // - Include message cellpack [2, 0, 77]
+        if let Err(violations) = validate_standardness(&funded.tx, &funded.prevouts, &self.config.standardness) {
+            let error = TransactionError::Nonstandard { violations };
+            if !self.config.allow_nonstandard {
+                return Err(error.into());
+            }
+            warn!("Signing anyway: {}", error);
+        }
         
-        // For now, return a placeholder script
-        Ok(bdk::bitcoin::ScriptBuf::new())
+        // Fetched only now so the locktime isn't stale by the time we sign
+        funded.tx.lock_time = self.lock_time().await?;
+        let mut psbt = PartiallySignedTransaction::from_unsigned_tx(funded.tx)
+            .context("Failed to create PSBT")?;
+        for (input, prevout) in psbt.inputs.iter_mut().zip(funded.prevouts) {
+            // Taproot inputs are key-spent; the wallet adds their internal key
+            if prevout.script_pubkey.is_v1_p2tr() {
+                input.sighash_type = Some(TapSighashType::Default.into());
+            }
+            input.witness_utxo = Some(prevout);
+        }
+        
+        if self.wallet_manager.sign_psbt(&mut psbt).await? {
+            Ok(MintArtifact::Signed(psbt.extract_tx()))
+        } else {
+            Ok(MintArtifact::Psbt(psbt))
+        }
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::rpc::{RpcClient, RpcConfig};
-    use crate::wallet::{WalletManager, WalletConfig};
-    use bdk::bitcoin::Network;
     
-    #[tokio::test]
-    async fn test_transaction_constructor_creation() {
-        // Create wallet manager
-        let wallet_config = WalletConfig {
-            wallet_path: "test_wallet.dat".to_string(),
+    /// Quote the cost of a DIESEL minting transaction without signing it
+    ///
+    /// The mint is funded exactly as `create_minting_transaction` would. If
+    /// the wallet can't cover it, the quote assumes a single P2WPKH input and
+    /// reports the shortfall instead of failing.
+    pub async fn quote_minting_transaction(&self) -> Result<MintQuote> {
+        let outputs = self.minting_outputs().await?;
+        let fee_rate = self.resolve_fee_rate().await?;
+        
+        let funded = match self.fund_outputs(&[], outputs.clone(), fee_rate).await {
+            Ok(funded) => funded,
+            Err(e) if matches!(e.downcast_ref::<TransactionError>(), Some(TransactionError::InsufficientFunds { .. })) => {
+                debug!("Wallet cannot fund the mint, quoting a single input: {}", e);
+                let tx = Transaction {
+                    version: 2,
+                    lock_time: bdk::bitcoin::absolute::LockTime::ZERO,
+                    input: vec![TxIn::default()],
+                    output: outputs,
+                };
+                let fee = fee_for(&tx, &[], fee_rate);
+                FundedTransaction { tx, prevouts: Vec::new(), total_input: 0, fee }
+            },
+            Err(e) => return Err(e),
+        };
+        
+        self.quote_funded(&funded, fee_rate).await
+    }
+    
+    /// Summarize the cost of a funded minting transaction
+    async fn quote_funded(&self, funded: &FundedTransaction, fee_rate: f64) -> Result<MintQuote> {
+        let confirmed_balance = self.wallet_manager.get_balance().await?.confirmed;
+        let vsize = estimate_signed_vsize(&funded.tx, &funded.prevouts);
+        let runestone = funded.tx.output.iter().position(|output| output.script_pubkey.is_op_return())
+            .unwrap_or(1);
+        
+        Ok(MintQuote {
+            fee_rate,
+            vsize,
+            effective_fee_rate: funded.fee as f64 / vsize as f64,
+            total_input: funded.total_input,
+            dust: funded.tx.output.iter().take(runestone).map(|output| output.value).sum(),
+            fee: funded.fee,
+            // Change, if any, follows the dust and OP_RETURN outputs
+            change: funded.tx.output.iter().skip(runestone + 1).map(|output| output.value).sum(),
+            confirmed_balance,
+        })
+    }
+    
+    /// Add inputs (and change) paying for `outputs` at `fee_rate`
+    ///
+    /// The `required` inputs are spent first, in order. Further candidates
+    /// are wallet UTXOs carrying nothing but sats and not frozen, in the
+    /// order of the configured `CoinSelection`, up to `max_inputs` inputs in
+    /// total. Change goes to a new change address when it is at least the
+    /// dust value, otherwise it is left to the fee. The transaction is
+    /// returned unsigned.
+    ///
+    /// Room for the change output counts against `max_outputs`. If the
+    /// wallet could only pay with more than `max_inputs` inputs, this fails
+    /// with `TooManyInputsRequired` rather than `InsufficientFunds`.
+    async fn fund_outputs(&self, required: &[Utxo], outputs: Vec<TxOut>, fee_rate: f64) -> Result<FundedTransaction> {
+        self.fund_outputs_excluding(required, outputs, fee_rate, &[]).await
+    }
+    
+    /// `fund_outputs`, never adding the `excluded` outpoints as candidates
+    ///
+    /// Used when building a chain of transactions, whose earlier members
+    /// spend wallet UTXOs the wallet still lists as unspent.
+    async fn fund_outputs_excluding(&self, required: &[Utxo], outputs: Vec<TxOut>, fee_rate: f64, excluded: &[OutPoint]) -> Result<FundedTransaction> {
+        if outputs.len() + 1 > self.config.max_outputs {
+            return Err(TransactionError::TooManyOutputs {
+                count: outputs.len() + 1,
+                max_outputs: self.config.max_outputs,
+            }.into());
+        }
+        let max_inputs = self.config.max_inputs;
+        
+        let utxos = self.wallet_manager.list_utxos().await?;
+        let mut candidates: Vec<Utxo> = self.wallet_manager.filter_plain(utxos, self.ord_check()).await?
+            .into_iter()
+            .filter(|utxo| !self.config.frozen_outpoints.contains(&utxo.outpoint) && !excluded.contains(&utxo.outpoint))
+            .collect();
+        order_candidates(&mut candidates, self.config.coin_selection);
+        let spendable: u64 = required.iter().chain(&candidates).map(|utxo| utxo.txout.value).sum();
+        
+        let change_address = Address::from_str(&self.wallet_manager.get_change_address().await?)
+            .context("Failed to parse change address")?;
+        let payment: u64 = outputs.iter().map(|output| output.value).sum();
+        let payment_outputs = outputs.len();
+        
+        // Size the fee as if there is change; the change output is dropped
+        // again if it would be dust
+        let change_script = change_address.assume_checked().script_pubkey();
+        let change_dust = self.dust_limit(&change_script);
+        
+        // Inputs spent whatever the payment: the required ones and, when
+        // consolidating, the small UTXOs worth more than their input's fee
+        let mut forced = required.to_vec();
+        match self.config.coin_selection {
+            CoinSelection::BranchAndBound => {
+                let change = TxOut { value: 0, script_pubkey: change_script.clone() };
+                let change_cost = change_dust + (serialize(&change).len() as f64 * fee_rate).ceil() as u64;
+                if let Some(funded) = self.fund_without_change(required, &candidates, &outputs, fee_rate, change_cost) {
+                    debug!("Funded {} sats of outputs without change from {} input(s)", payment, funded.tx.input.len());
+                    return Ok(funded);
+                }
+            },
+            CoinSelection::ConsolidateDust => {
+                let room = max_inputs.saturating_sub(required.len());
+                let (mut sweep, mut rest) = (Vec::new(), Vec::new());
+                for utxo in candidates {
+                    let value = utxo.txout.value;
+                    if sweep.len() < room && value < CONSOLIDATION_THRESHOLD && value > input_fee(&utxo.txout, fee_rate) {
+                        sweep.push(utxo);
+                    } else {
+                        rest.push(utxo);
+                    }
+                }
+                debug!("Consolidating {} small UTXO(s)", sweep.len());
+                forced.extend(sweep);
+                rest.sort_by(largest_first);
+                candidates = rest;
+            },
+            CoinSelection::LargestFirst | CoinSelection::OldestFirst => {},
+        }
+        let candidates: Vec<&Utxo> = forced.iter().chain(&candidates).collect();
+        let mut tx = Transaction {
+            version: 2,
+            lock_time: bdk::bitcoin::absolute::LockTime::ZERO,
+            input: Vec::new(),
+            output: outputs,
+        };
+        tx.output.push(TxOut {
+            value: 0,
+            script_pubkey: change_script,
+        });
+        
+        let mut prevouts = Vec::new();
+        let mut total = 0u64;
+        for (index, utxo) in candidates.into_iter().take(max_inputs.max(forced.len())).enumerate() {
+            tx.input.push(TxIn {
+                previous_output: utxo.outpoint,
+                script_sig: ScriptBuf::new(),
+                sequence: self.sequence(),
+                witness: Witness::new(),
+            });
+            prevouts.push(utxo.txout.clone());
+            total += utxo.txout.value;
+            if index + 1 < forced.len() {
+                continue;
+            }
+            // The required inputs alone exceed the limit
+            if index + 1 > max_inputs {
+                break;
+            }
+            
+            let fee = fee_for(&tx, &prevouts, fee_rate);
+            if total >= payment + fee {
+                let change = total - payment - fee;
+                if change >= change_dust {
+                    if let Some(output) = tx.output.last_mut() {
+                        output.value = change;
+                    }
+                } else {
+                    tx.output.pop();
+                }
+                
+                let fee = settle_fee(&mut tx, &prevouts, total, payment_outputs, change_dust, fee_rate)?;
+                debug!("Funded {} sats of outputs with {} input(s) totalling {} sats, fee {} sats", payment, tx.input.len(), total, fee);
+                return Ok(FundedTransaction { tx, prevouts, total_input: total, fee });
+            }
+        }
+        
+        // Report what even the largest allowed selection would need
+        if tx.input.is_empty() {
+            tx.input.push(TxIn::default());
+        }
+        let needed = payment + fee_for(&tx, &prevouts, fee_rate);
+        if spendable >= needed && tx.input.len() >= max_inputs {
+            return Err(TransactionError::TooManyInputsRequired { max_inputs, needed, selected: total }.into());
+        }
+        Err(TransactionError::InsufficientFunds {
+            needed,
+            available: total,
+        }.into())
+    }
+    
+    /// Pay `outputs` from `required` and candidates chosen by branch and bound
+    ///
+    /// Inputs are valued net of the fee they add, and a set is accepted if
+    /// what it pays beyond the outputs and fee is less than `change_cost`,
+    /// the dust value plus the fee of a change output; the excess goes to
+    /// the fee. `None` if there is no such set.
+    fn fund_without_change(&self, required: &[Utxo], candidates: &[Utxo], outputs: &[TxOut], fee_rate: f64, change_cost: u64) -> Option<FundedTransaction> {
+        let input = |utxo: &Utxo| TxIn {
+            previous_output: utxo.outpoint,
+            script_sig: ScriptBuf::new(),
+            sequence: self.sequence(),
+            witness: Witness::new(),
+        };
+        let mut tx = Transaction {
+            version: 2,
+            lock_time: bdk::bitcoin::absolute::LockTime::ZERO,
+            input: required.iter().map(input).collect(),
+            output: outputs.to_vec(),
+        };
+        let mut prevouts: Vec<TxOut> = required.iter().map(|utxo| utxo.txout.clone()).collect();
+        let required_total: u64 = required.iter().map(|utxo| utxo.txout.value).sum();
+        let payment: u64 = outputs.iter().map(|output| output.value).sum();
+        let target = (payment + fee_for(&tx, &prevouts, fee_rate)).checked_sub(required_total)?;
+        
+        let values: Vec<u64> = candidates.iter()
+            .map(|utxo| utxo.txout.value.saturating_sub(input_fee(&utxo.txout, fee_rate)))
+            .collect();
+        let chosen = branch_and_bound(&values, target, change_cost, self.config.max_inputs.saturating_sub(required.len()))?;
+        for index in chosen {
+            tx.input.push(input(&candidates[index]));
+            prevouts.push(candidates[index].txout.clone());
+        }
+        
+        let total: u64 = prevouts.iter().map(|prevout| prevout.value).sum();
+        let fee = settle_fee(&mut tx, &prevouts, total, outputs.len(), 0, fee_rate).ok()?;
+        Some(FundedTransaction { tx, prevouts, total_input: total, fee })
+    }
+    
+    /// Build the outputs of a DIESEL minting transaction
+    ///
+    /// Outputs are `[dust to the dust recipient, OP_RETURN runestone]`.
+    /// With `mint_count` above one there is a dust output per mint, each
+    /// the pointer of its own protostone, ahead of the OP_RETURN.
+    async fn minting_outputs(&self) -> Result<Vec<TxOut>> {
+        let count = self.config.mint_count;
+        if count <= 1 {
+            let script_pubkey = self.dust_recipient_script().await?;
+            let mut outputs = vec![TxOut { value: self.dust_limit(&script_pubkey), script_pubkey }];
+            // The minted DIESEL goes to the dust output just added
+            let runestone = ProtostoneSpec::diesel_mint_to(0).encipher_for(&outputs)?;
+            outputs.push(TxOut { value: 0, script_pubkey: runestone });
+            return Ok(outputs);
+        }
+        
+        let max_count = max_mint_count(self.config.standardness.max_op_return_size);
+        if count > max_count && !self.config.allow_nonstandard {
+            return Err(TransactionError::TooManyMints { count, max_count }.into());
+        }
+        
+        let mut outputs = Vec::with_capacity(count as usize + 1);
+        for _ in 0..count {
+            let script_pubkey = self.dust_recipient_script().await?;
+            outputs.push(TxOut { value: self.dust_limit(&script_pubkey), script_pubkey });
+        }
+        let specs = self.minting_specs();
+        for spec in &specs {
+            spec.validate_outputs(&outputs)?;
+        }
+        outputs.push(TxOut { value: 0, script_pubkey: ProtostoneSpec::encipher_all(&specs)? });
+        Ok(outputs)
+    }
+    
+    /// Protostones of a DIESEL minting transaction, mint `i` paying output `i`
+    fn minting_specs(&self) -> Vec<ProtostoneSpec> {
+        (0..self.config.mint_count.max(1)).map(ProtostoneSpec::diesel_mint_to).collect()
+    }
+    
+    /// Script of the next mint dust output, per `dust_recipient`
+    async fn dust_recipient_script(&self) -> Result<ScriptBuf> {
+        let address = match &self.config.dust_recipient {
+            DustRecipient::NewExternal => self.wallet_manager.get_address().await?,
+            DustRecipient::NewInternal => self.wallet_manager.get_change_address().await?,
+            DustRecipient::Fixed(address) => {
+                if !address.is_valid_for_network(self.config.network) {
+                    return Err(anyhow!("Dust address {} is not a {} address", address, self.config.network));
+                }
+                return Ok(address.script_pubkey());
+            },
+        };
+        Ok(Address::from_str(&address)
+            .context("Failed to parse output address")?
+            .assume_checked()
+            .script_pubkey())
+    }
+    
+    /// Create a transaction executing the protostone described by `spec`
+    ///
+    /// Outputs are `[options.value to the recipient, OP_RETURN runestone,
+    /// change]`, so output 0 is where the spec's pointer and edicts usually
+    /// point. Like minting, a watch-only wallet gets an unsigned PSBT back.
+    pub async fn create_protostone_transaction(&self, spec: &ProtostoneSpec, options: &ProtostoneOptions) -> Result<MintArtifact> {
+        info!("Creating protostone transaction for cellpack {:?}", spec.cellpack);
+        
+        let outputs = self.protostone_outputs(spec, options).await?;
+        let fee_rate = self.resolve_fee_rate().await?;
+        let funded = self.fund_outputs(&[], outputs, fee_rate).await?;
+        self.verify_runestone(&funded.tx, std::slice::from_ref(spec))?;
+        let artifact = self.sign_funded(funded).await?;
+        
+        debug!("Protostone transaction: {:?}", artifact);
+        Ok(artifact)
+    }
+    
+    /// Build the `[recipient, OP_RETURN runestone]` outputs of a protostone
+    async fn protostone_outputs(&self, spec: &ProtostoneSpec, options: &ProtostoneOptions) -> Result<Vec<TxOut>> {
+        let recipient = match &options.to {
+            Some(to) => Address::from_str(to)
+                .with_context(|| format!("Invalid recipient address {}", to))?
+                .require_network(self.config.network)
+                .with_context(|| format!("Recipient {} is not a {} address", to, self.config.network))?,
+            None => Address::from_str(&self.wallet_manager.get_address().await?)
+                .context("Failed to parse output address")?
+                .assume_checked(),
+        };
+        let script_pubkey = recipient.script_pubkey();
+        let dust = self.dust_limit(&script_pubkey);
+        let value = options.value.unwrap_or(dust);
+        if value < dust {
+            return Err(anyhow!("Output value {} is below the dust limit of {}", value, dust));
+        }
+        
+        Ok(vec![
+            TxOut {
+                value,
+                script_pubkey,
+            },
+            // OP_RETURN output with Runestone
+            TxOut {
+                value: 0,
+                script_pubkey: spec.encipher()?,
+            },
+        ])
+    }
+    
+    /// Create a signed transaction deploying an alkanes contract
+    ///
+    /// The deploy cellpack `[1, 0]` is followed by the fuel limit, if any, as
+    /// the first input, and the wasm bytes are appended to the protostone
+    /// message. The new alkane is assigned to the dust output at index 0.
+    ///
+    /// Any real contract makes the OP_RETURN larger than relay policy allows,
+    /// so signing is refused unless `allow_nonstandard` is set.
+    pub async fn create_deploy_transaction(&self, wasm: &[u8], fuel_limit: Option<u64>) -> Result<Transaction> {
+        if wasm.is_empty() {
+            return Err(anyhow!("Contract wasm is empty"));
+        }
+        info!("Creating deploy transaction for {} byte contract", wasm.len());
+        
+        let mut cellpack = DEPLOY_CELLPACK_TARGET.to_vec();
+        cellpack.extend(fuel_limit.map(u128::from));
+        let mut message = encode_cellpack(&cellpack);
+        message.extend_from_slice(wasm);
+        
+        let tx = self.create_message_transaction(&message).await?;
+        debug!("Deploy transaction: {:?}", tx);
+        Ok(tx)
+    }
+    
+    /// Create a transaction sending `amount` of protorune `rune_id` to `to`
+    ///
+    /// UTXOs holding only this rune are spent largest first until they cover
+    /// `amount`, plus plain UTXOs for the fee. Outputs are `[dust to the
+    /// recipient, dust token change (if any), OP_RETURN runestone, change]`;
+    /// the protostone's edicts assign `amount` to the recipient and the rest
+    /// to the token change output, which is also the pointer for anything
+    /// unallocated. Fails with `InsufficientTokenBalance` before funding if
+    /// the indexer reports too little of the rune across the wallet's
+    /// addresses, or if too little of it sits in spendable UTXOs.
+    pub async fn create_transfer_transaction(&self, rune_id: (u128, u128), amount: u128, to: &str) -> Result<MintArtifact> {
+        info!("Creating transfer of {} {}:{} to {}", amount, rune_id.0, rune_id.1, to);
+        if amount == 0 {
+            return Err(anyhow!("Transfer amount must be positive"));
+        }
+        let recipient = Address::from_str(to)
+            .with_context(|| format!("Invalid recipient address {}", to))?
+            .require_network(self.config.network)
+            .with_context(|| format!("Recipient {} is not a {} address", to, self.config.network))?;
+        
+        let (token_inputs, selected) = self.select_token_inputs(rune_id, amount).await?;
+        let token_change = selected - amount;
+        
+        let mut outputs = vec![TxOut {
+            value: self.dust_limit(&recipient.script_pubkey()),
+            script_pubkey: recipient.script_pubkey(),
+        }];
+        let mut edicts = vec![(amount, 0)];
+        if token_change > 0 {
+            let change_address = Address::from_str(&self.wallet_manager.get_change_address().await?)
+                .context("Failed to parse token change address")?;
+            let change_script = change_address.assume_checked().script_pubkey();
+            outputs.push(TxOut {
+                value: self.dust_limit(&change_script),
+                script_pubkey: change_script,
+            });
+            edicts.push((token_change, 1));
+        }
+        let spec = ProtostoneSpec::transfer(rune_id, &edicts, (outputs.len() - 1) as u32);
+        outputs.push(TxOut {
+            value: 0,
+            script_pubkey: spec.encipher()?,
+        });
+        
+        let fee_rate = self.resolve_fee_rate().await?;
+        let funded = self.fund_outputs(&token_inputs, outputs, fee_rate).await?;
+        self.verify_runestone(&funded.tx, &[spec])?;
+        let artifact = self.sign_funded(funded).await?;
+        
+        debug!("Transfer transaction: {:?}", artifact);
+        Ok(artifact)
+    }
+    
+    /// Create a transaction provably burning protorunes
+    ///
+    /// The protostone's edict sends `amount` to the OP_RETURN output, which
+    /// can't be spent, and its `burn` field marks the alkanes protocol as the
+    /// one burned under. Runes left on the selected UTXOs go back to a wallet
+    /// dust output through the pointer; when nothing is left the only other
+    /// output is BTC change.
+    pub async fn create_burn_transaction(&self, rune_id: (u128, u128), amount: u128) -> Result<MintArtifact> {
+        info!("Creating burn of {} {}:{}", amount, rune_id.0, rune_id.1);
+        if amount == 0 {
+            return Err(anyhow!("Burn amount must be positive"));
+        }
+        
+        let (token_inputs, selected) = self.select_token_inputs(rune_id, amount).await?;
+        let remainder = selected - amount;
+        
+        let mut outputs = Vec::new();
+        if remainder > 0 {
+            let change_address = Address::from_str(&self.wallet_manager.get_change_address().await?)
+                .context("Failed to parse token change address")?;
+            let change_script = change_address.assume_checked().script_pubkey();
+            outputs.push(TxOut {
+                value: self.dust_limit(&change_script),
+                script_pubkey: change_script,
+            });
+        }
+        let runestone_index = outputs.len() as u32;
+        let pointer = if remainder > 0 { Some(0) } else { None };
+        let spec = ProtostoneSpec {
+            protocol_tag: u128::from(PROTOCOL_TAG),
+            cellpack: Vec::new(),
+            edicts: vec![Edict { id: rune_id, amount, output: runestone_index }],
+            pointer,
+            refund: pointer,
+            burn: Some(u128::from(PROTOCOL_TAG)),
+        };
+        outputs.push(TxOut {
+            value: 0,
+            script_pubkey: spec.encipher()?,
+        });
+        
+        let fee_rate = self.resolve_fee_rate().await?;
+        let funded = self.fund_outputs(&token_inputs, outputs, fee_rate).await?;
+        let artifact = self.sign_funded(funded).await?;
+        
+        debug!("Burn transaction: {:?}", artifact);
+        Ok(artifact)
+    }
+    
+    /// Create a transaction splitting protorune `rune_id` into `amounts`
+    ///
+    /// Outputs are a wallet dust output per amount, then one for whatever the
+    /// selected UTXOs hold beyond the amounts, then the OP_RETURN runestone;
+    /// the split protostone assigns each amount to its output.
+    pub async fn create_split_transaction(&self, rune_id: (u128, u128), amounts: &[u128]) -> Result<MintArtifact> {
+        info!("Creating split of {}:{} into {} outputs", rune_id.0, rune_id.1, amounts.len());
+        let requested = checked_sum(amounts).ok_or_else(|| anyhow!("Split amounts overflow"))?;
+        let (token_inputs, selected) = self.select_token_inputs(rune_id, requested).await?;
+        let mut split_amounts = amounts.to_vec();
+        if selected > requested {
+            split_amounts.push(selected - requested);
+        }
+        let spec = ProtostoneSpec::split(rune_id, selected, &split_amounts)?;
+        
+        let mut outputs = Vec::new();
+        for _ in &split_amounts {
+            let address = Address::from_str(&self.wallet_manager.get_address().await?)
+                .context("Failed to parse split output address")?;
+            let script_pubkey = address.assume_checked().script_pubkey();
+            outputs.push(TxOut {
+                value: self.dust_limit(&script_pubkey),
+                script_pubkey,
+            });
+        }
+        outputs.push(TxOut {
+            value: 0,
+            script_pubkey: spec.encipher_for(&outputs)?,
+        });
+        
+        let fee_rate = self.resolve_fee_rate().await?;
+        let funded = self.fund_outputs(&token_inputs, outputs, fee_rate).await?;
+        let artifact = self.sign_funded(funded).await?;
+        
+        debug!("Split transaction: {:?}", artifact);
+        Ok(artifact)
+    }
+    
+    /// Create a transaction joining every wallet UTXO holding protorune
+    /// `rune_id` into one output
+    ///
+    /// Outputs are `[wallet dust output, OP_RETURN runestone, change]`; the
+    /// join protostone moves the total held to output 0. Frozen outpoints
+    /// are left out, and at least two UTXOs must remain.
+    pub async fn create_join_transaction(&self, rune_id: (u128, u128)) -> Result<MintArtifact> {
+        info!("Creating join of {}:{}", rune_id.0, rune_id.1);
+        let mut holdings = self.wallet_manager.protorune_utxos(rune_id.0, rune_id.1, self.ord_check()).await?;
+        holdings.retain(|(utxo, _)| !self.config.frozen_outpoints.contains(&utxo.outpoint));
+        if holdings.len() < 2 {
+            return Err(anyhow!("Joining {}:{} needs at least two UTXOs holding it, found {}", rune_id.0, rune_id.1, holdings.len()));
+        }
+        let amounts: Vec<u128> = holdings.iter().map(|(_, held)| *held).collect();
+        let spec = ProtostoneSpec::join(rune_id, &amounts, 0)?;
+        
+        let address = Address::from_str(&self.wallet_manager.get_address().await?)
+            .context("Failed to parse join output address")?;
+        let script_pubkey = address.assume_checked().script_pubkey();
+        let mut outputs = vec![TxOut {
+            value: self.dust_limit(&script_pubkey),
+            script_pubkey,
+        }];
+        outputs.push(TxOut {
+            value: 0,
+            script_pubkey: spec.encipher_for(&outputs)?,
+        });
+        
+        let token_inputs: Vec<Utxo> = holdings.into_iter().map(|(utxo, _)| utxo).collect();
+        let fee_rate = self.resolve_fee_rate().await?;
+        let funded = self.fund_outputs(&token_inputs, outputs, fee_rate).await?;
+        let artifact = self.sign_funded(funded).await?;
+        
+        debug!("Join transaction: {:?}", artifact);
+        Ok(artifact)
+    }
+    
+    /// Select wallet UTXOs holding at least `amount` of a protorune
+    ///
+    /// The outputs holding the rune alone are found with `protorune_utxos`
+    /// under the configured ordinal check and taken largest first; frozen
+    /// ones are skipped. Returns the inputs and the total amount of the rune
+    /// they hold.
+    async fn select_token_inputs(&self, rune_id: (u128, u128), amount: u128) -> Result<(Vec<Utxo>, u128)> {
+        let held = self.wallet_manager.protorune_balance(rune_id.0, rune_id.1).await?;
+        if held < amount {
+            return Err(TransactionError::InsufficientTokenBalance {
+                block: rune_id.0,
+                tx: rune_id.1,
+                needed: amount,
+                available: held,
+            }.into());
+        }
+        
+        let mut holdings = self.wallet_manager.protorune_utxos(rune_id.0, rune_id.1, self.ord_check()).await?;
+        holdings.retain(|(utxo, _)| !self.config.frozen_outpoints.contains(&utxo.outpoint));
+        holdings.sort_by(|a, b| b.1.cmp(&a.1));
+        let available = holdings.iter().fold(0u128, |total, (_, held)| total.saturating_add(*held));
+        if available < amount {
+            return Err(TransactionError::InsufficientTokenBalance {
+                block: rune_id.0,
+                tx: rune_id.1,
+                needed: amount,
+                available,
+            }.into());
+        }
+        
+        let mut token_inputs = Vec::new();
+        let mut selected = 0u128;
+        for (utxo, held) in holdings {
+            if selected >= amount {
+                break;
+            }
+            selected = selected.checked_add(held)
+                .ok_or_else(|| anyhow!("Amount of {}:{} overflows", rune_id.0, rune_id.1))?;
+            token_inputs.push(utxo);
+        }
+        Ok((token_inputs, selected))
+    }
+    
+    /// Create a transaction executing an alkanes contract with protorunes
+    ///
+    /// The protostone's cellpack is `[target.0, target.1, inputs...]` and its
+    /// edicts move each `incoming` amount to the protostone itself, so the
+    /// contract receives them. Unspent runes, anything the contract returns
+    /// and refunds on revert go to output 0, the `options` recipient. Without
+    /// `incoming` this is a plain contract call.
+    pub async fn create_execute_transaction(
+        &self,
+        target: (u128, u128),
+        inputs: Vec<u128>,
+        incoming: Vec<((u128, u128), u128)>,
+        options: &ProtostoneOptions,
+    ) -> Result<MintArtifact> {
+        info!("Creating execute transaction for contract {}:{}", target.0, target.1);
+        
+        // Amounts of the same rune are moved by a single edict
+        let mut amounts: Vec<((u128, u128), u128)> = Vec::new();
+        for (rune_id, amount) in incoming {
+            if amount == 0 {
+                return Err(anyhow!("Amount of {}:{} sent to the contract must be positive", rune_id.0, rune_id.1));
+            }
+            match amounts.iter_mut().find(|(id, _)| *id == rune_id) {
+                Some((_, total)) => *total = total.checked_add(amount).ok_or_else(|| anyhow!("Amount of {}:{} overflows", rune_id.0, rune_id.1))?,
+                None => amounts.push((rune_id, amount)),
+            }
+        }
+        
+        // Token UTXOs hold a single rune, so selections never overlap
+        let mut token_inputs = Vec::new();
+        for (rune_id, amount) in &amounts {
+            let (selected, _) = self.select_token_inputs(*rune_id, *amount).await?;
+            token_inputs.extend(selected);
+        }
+        
+        let mut cellpack = vec![target.0, target.1];
+        cellpack.extend(inputs);
+        let spec_for = |shadow_vout: usize| ProtostoneSpec {
+            protocol_tag: u128::from(PROTOCOL_TAG),
+            cellpack: cellpack.clone(),
+            edicts: amounts.iter()
+                .map(|(id, amount)| Edict { id: *id, amount: *amount, output: shadow_vout as u32 })
+                .collect(),
+            pointer: Some(0),
+            refund: Some(0),
+            burn: None,
+        };
+        
+        // The shadow vout depends on whether funding adds a change output;
+        // assume it does and re-encode if not
+        let mut outputs = self.protostone_outputs(&spec_for(0), options).await?;
+        let runestone_index = outputs.len() - 1;
+        outputs[runestone_index].script_pubkey = spec_for(crate::runestone::protostone_vout(0, outputs.len() + 1)).encipher()?;
+        
+        let fee_rate = self.resolve_fee_rate().await?;
+        let mut funded = self.fund_outputs(&token_inputs, outputs, fee_rate).await?;
+        let shadow_vout = crate::runestone::protostone_vout(0, funded.tx.output.len());
+        let spec = spec_for(shadow_vout);
+        let script = spec.encipher()?;
+        let runestone = &mut funded.tx.output[runestone_index];
+        if script.len() != runestone.script_pubkey.len() {
+            return Err(anyhow!("Re-encoding the protostone for shadow vout {} changed its size", shadow_vout));
+        }
+        runestone.script_pubkey = script;
+        self.verify_runestone(&funded.tx, &[spec])?;
+        let artifact = self.sign_funded(funded).await?;
+        
+        debug!("Execute transaction: {:?}", artifact);
+        Ok(artifact)
+    }
+    
+    /// Fund and sign a transaction carrying a single protostone message
+    ///
+    /// Outputs are `[dust wallet output, OP_RETURN runestone, change]`. The
+    /// message is chunked into the OP_RETURN by `encipher_protostones`. Fee
+    /// limits and relay policy apply as with every other transaction, and a
+    /// watch-only wallet is refused since the caller needs a signed
+    /// transaction.
+    async fn create_message_transaction(&self, message: &[u8]) -> Result<Transaction> {
+        // The message needn't be a cellpack, so it's carried as is
+        let runestone_script = encipher_protostones(vec![Protostone {
+            burn: None,
+            message: message.to_vec(),
+            edicts: Vec::new(),
+            refund: None,
+            pointer: None,
+            from: None,
+            protocol_tag: u128::from(PROTOCOL_TAG),
+        }], None)?;
+        
+        // Output bytes weigh 4 WU each, reject early rather than after funding
+        if runestone_script.len() as u64 * 4 >= MAX_STANDARD_TX_WEIGHT {
+            return Err(anyhow!(
+                "Payload is too large: {} byte runestone exceeds the standard transaction weight",
+                runestone_script.len()
+            ));
+        }
+        
+        let address = Address::from_str(&self.wallet_manager.get_address().await?)
+            .context("Failed to parse output address")?;
+        let script_pubkey = address.assume_checked().script_pubkey();
+        let outputs = vec![
+            TxOut {
+                value: self.dust_limit(&script_pubkey),
+                script_pubkey,
+            },
+            TxOut {
+                value: 0,
+                script_pubkey: runestone_script,
+            },
+        ];
+        
+        let fee_rate = self.resolve_fee_rate().await?;
+        let funded = self.fund_outputs(&[], outputs, fee_rate).await?;
+        match self.sign_funded(funded).await? {
+            MintArtifact::Signed(tx) => Ok(tx),
+            MintArtifact::Psbt(_) => Err(anyhow!("Wallet could not sign every input (is it watch-only?)")),
+        }
+    }
+    
+    /// Trace the protostone of a confirmed transaction
+    pub async fn trace_protostone(&self, tx: &Transaction) -> Result<serde_json::Value> {
+        let outpoint = trace_outpoint(tx, 0)?;
+        let txid = outpoint.txid.to_string();
+        
+        self.rpc_client.trace_transaction(&txid, outpoint.vout as usize).await
+            .with_context(|| format!("Failed to trace transaction {}", txid))
+    }
+    
+    /// Trace a confirmed deploy transaction and return the created alkane ID
+    pub async fn trace_deployment(&self, tx: &Transaction) -> Result<(u128, u128)> {
+        let trace = self.trace_protostone(tx).await?;
+        parse_deployment_trace(&trace)
+    }
+    
+    /// Input sequence for new transactions, `sequence` if set
+    ///
+    /// Non-replaceable inputs still enable the locktime so anti-fee-sniping
+    /// locktimes are enforced.
+    fn sequence(&self) -> Sequence {
+        match self.config.sequence {
+            Some(sequence) => sequence,
+            None if self.config.rbf => Sequence::ENABLE_RBF_NO_LOCKTIME,
+            None => Sequence::ENABLE_LOCKTIME_NO_RBF,
+        }
+    }
+    
+    /// Locktime for new transactions
+    ///
+    /// An explicit `lock_time` wins; otherwise it is the current tip height
+    /// when `anti_fee_sniping` is enabled, and zero when not. A non-zero
+    /// locktime is refused when the input sequence is final, since consensus
+    /// would ignore it.
+    async fn lock_time(&self) -> Result<LockTime> {
+        let lock_time = match self.config.lock_time {
+            Some(lock_time) => lock_time,
+            None if !self.config.anti_fee_sniping => LockTime::ZERO,
+            // Anti-fee-sniping is best effort, so a final sequence just skips it
+            None if self.sequence() == Sequence::MAX => LockTime::ZERO,
+            None => {
+                let tip = self.rpc_client.get_block_count().await
+                    .context("Failed to get tip height for locktime")?;
+                let height = anti_fee_sniping_height(tip, &mut rand::thread_rng());
+                LockTime::from_height(height).context("Tip height is not a valid locktime")?
+            },
+        };
+        check_lock_time(lock_time, self.sequence())?;
+        Ok(lock_time)
+    }
+    
+    /// How candidate UTXOs are screened for inscriptions and rare sats
+    fn ord_check(&self) -> OrdCheck {
+        match (self.config.allow_ordinal_spend, self.config.ord_unavailable) {
+            (true, _) => OrdCheck::Skip,
+            (false, OrdUnavailable::Abort) => OrdCheck::Strict,
+            (false, OrdUnavailable::Proceed) => OrdCheck::BestEffort,
+        }
+    }
+    
+    /// Smallest value of an output paying to `script` under the dust policy
+    fn dust_limit(&self, script: &Script) -> u64 {
+        match self.config.dust_policy {
+            DustPolicy::Standard => dust_limit_for(script),
+            DustPolicy::Fixed(value) => value,
+        }
+    }
+    
+    /// Determine the fee rate to use for new transactions
+    ///
+    /// When `target_blocks` is configured the rate is derived from the current
+    /// mempool fee histogram, otherwise the fixed `fee_rate` is used.
+    pub async fn resolve_fee_rate(&self) -> Result<f64> {
+        let target_blocks = match self.config.target_blocks {
+            Some(target_blocks) => target_blocks,
+            None => {
+                self.check_fee_rate(self.config.fee_rate)?;
+                return Ok(self.config.fee_rate);
+            },
+        };
+        
+        let histogram = self.rpc_client.get_mempool_fee_histogram().await?;
+        let fee_rate = fee_rate_for_target(&histogram, target_blocks);
+        info!("Selected fee rate {} sat/vB to confirm within {} block(s)", fee_rate, target_blocks);
+        self.check_fee_rate(fee_rate)?;
+        Ok(fee_rate)
+    }
+    
+    /// Reject fee rates above `max_fee_rate`
+    fn check_fee_rate(&self, fee_rate: f64) -> Result<()> {
+        match self.config.max_fee_rate {
+            Some(max_fee_rate) if fee_rate > max_fee_rate => {
+                Err(TransactionError::FeeRateTooHigh { fee_rate, max_fee_rate }.into())
+            },
+            _ => Ok(()),
+        }
+    }
+    
+    /// Reject fees above `max_absolute_fee`
+    fn check_fee(&self, fee: u64) -> Result<()> {
+        match self.config.max_absolute_fee {
+            Some(max_fee) if fee > max_fee => Err(TransactionError::FeeTooHigh { fee, max_fee }.into()),
+            _ => Ok(()),
+        }
+    }
+    
+    /// Create signed transactions sweeping the wallet's plain UTXOs into one
+    ///
+    /// Each transaction has a single output to a new change address. When
+    /// there are more UTXOs than `max_inputs`, the work is split into a chain
+    /// of transactions, each spending the previous one's output along with
+    /// the next batch, so they must be broadcast in order.
+    pub async fn create_consolidation_transactions(&self) -> Result<Vec<Transaction>> {
+        let max_inputs = self.config.max_inputs;
+        if max_inputs < 2 {
+            return Err(anyhow!("Consolidation needs max_inputs of at least 2, got {}", max_inputs));
+        }
+        
+        let utxos = self.wallet_manager.list_utxos().await?;
+        let mut pending: Vec<Utxo> = self.wallet_manager.filter_plain(utxos, self.ord_check()).await?
+            .into_iter()
+            .filter(|utxo| !self.config.frozen_outpoints.contains(&utxo.outpoint))
+            .collect();
+        if pending.len() < 2 {
+            return Err(anyhow!("Nothing to consolidate: {} spendable UTXO(s)", pending.len()));
+        }
+        pending.sort_by(|a, b| b.txout.value.cmp(&a.txout.value));
+        info!("Consolidating {} UTXO(s) with at most {} input(s) per transaction", pending.len(), max_inputs);
+        
+        let destination = Address::from_str(&self.wallet_manager.get_change_address().await?)
+            .context("Failed to parse consolidation address")?
+            .assume_checked()
+            .script_pubkey();
+        let fee_rate = self.resolve_fee_rate().await?;
+        
+        let mut transactions: Vec<Transaction> = Vec::new();
+        let mut pending = pending.into_iter().peekable();
+        let mut carried: Option<Utxo> = None;
+        while pending.peek().is_some() {
+            let room = max_inputs - usize::from(carried.is_some());
+            let batch: Vec<Utxo> = carried.take().into_iter().chain(pending.by_ref().take(room)).collect();
+            
+            let mut tx = Transaction {
+                version: 2,
+                lock_time: bdk::bitcoin::absolute::LockTime::ZERO,
+                input: batch.iter().map(|utxo| TxIn {
+                    previous_output: utxo.outpoint,
+                    script_sig: ScriptBuf::new(),
+                    sequence: self.sequence(),
+                    witness: Witness::new(),
+                }).collect(),
+                output: vec![TxOut { value: 0, script_pubkey: destination.clone() }],
+            };
+            let prevouts: Vec<TxOut> = batch.iter().map(|utxo| utxo.txout.clone()).collect();
+            let total: u64 = prevouts.iter().map(|prevout| prevout.value).sum();
+            let fee = fee_for(&tx, &prevouts, fee_rate);
+            if total < fee + self.dust_limit(&destination) {
+                return Err(TransactionError::InsufficientFunds {
+                    needed: fee + self.dust_limit(&destination),
+                    available: total,
+                }.into());
+            }
+            tx.output[0].value = total - fee;
+            
+            let signed = match self.sign_funded(FundedTransaction { tx, prevouts, total_input: total, fee }).await? {
+                MintArtifact::Signed(tx) => tx,
+                MintArtifact::Psbt(_) => return Err(anyhow!("Wallet is watch-only and cannot sign a consolidation chain")),
+            };
+            carried = Some(Utxo {
+                outpoint: OutPoint { txid: signed.txid(), vout: 0 },
+                txout: signed.output[0].clone(),
+                height: None,
+            });
+            debug!("Consolidation transaction {} spends {} input(s), fee {} sats", signed.txid(), signed.input.len(), fee);
+            transactions.push(signed);
+        }
+        
+        Ok(transactions)
+    }
+    
+    /// Create signed transactions gathering every wallet output of a protorune
+    ///
+    /// Each transaction sends its tokens to a single dust output through the
+    /// runestone pointer, so no edicts are needed; outputs also holding other
+    /// runes are left alone. When there are more outputs than fit in
+    /// `max_inputs` (keeping room for a fee input), the work is split into a
+    /// chain of transactions, each spending the previous one's token output
+    /// and BTC change along with the next batch, so they must be broadcast in
+    /// order. Only the last transaction's token output remains.
+    pub async fn create_token_sweep_transaction(&self, rune_id: (u128, u128)) -> Result<Vec<Transaction>> {
+        let mut holdings = self.wallet_manager.protorune_utxos(rune_id.0, rune_id.1, self.ord_check()).await?;
+        holdings.retain(|(utxo, _)| !self.config.frozen_outpoints.contains(&utxo.outpoint));
+        if holdings.len() < 2 {
+            return Err(anyhow!("Nothing to sweep: {} output(s) hold {}:{}", holdings.len(), rune_id.0, rune_id.1));
+        }
+        info!("Sweeping {} output(s) of {}:{} with at most {} input(s) per transaction", holdings.len(), rune_id.0, rune_id.1, self.config.max_inputs);
+        
+        let destination = Address::from_str(&self.wallet_manager.get_change_address().await?)
+            .context("Failed to parse sweep address")?
+            .assume_checked()
+            .script_pubkey();
+        let runestone = transfer_runestone(rune_id, &[], 0)?;
+        let fee_rate = self.resolve_fee_rate().await?;
+        
+        let mut transactions: Vec<Transaction> = Vec::new();
+        let mut pending = holdings.into_iter().map(|(utxo, _)| utxo).peekable();
+        let mut carried: Vec<Utxo> = Vec::new();
+        let mut spent: Vec<OutPoint> = Vec::new();
+        while pending.peek().is_some() {
+            // Leave room for one fee-paying input
+            let room = self.config.max_inputs.saturating_sub(carried.len() + 1);
+            if room == 0 {
+                return Err(anyhow!("Sweeping needs max_inputs of at least {}, got {}", carried.len() + 2, self.config.max_inputs));
+            }
+            let required: Vec<Utxo> = carried.drain(..).chain(pending.by_ref().take(room)).collect();
+            let outputs = vec![
+                TxOut {
+                    value: self.dust_limit(&destination),
+                    script_pubkey: destination.clone(),
+                },
+                TxOut {
+                    value: 0,
+                    script_pubkey: runestone.clone(),
+                },
+            ];
+            
+            let funded = self.fund_outputs_excluding(&required, outputs, fee_rate, &spent).await?;
+            let fee = funded.fee;
+            spent.extend(funded.tx.input.iter().map(|input| input.previous_output));
+            let signed = match self.sign_funded(funded).await? {
+                MintArtifact::Signed(tx) => tx,
+                MintArtifact::Psbt(_) => return Err(anyhow!("Wallet is watch-only and cannot sign a sweep chain")),
+            };
+            
+            // The token output, then the BTC change if there is any
+            for vout in [0, 2] {
+                if let Some(txout) = signed.output.get(vout) {
+                    carried.push(Utxo {
+                        outpoint: OutPoint { txid: signed.txid(), vout: vout as u32 },
+                        txout: txout.clone(),
+                        height: None,
+                    });
+                }
+            }
+            debug!("Sweep transaction {} spends {} input(s), fee {} sats", signed.txid(), signed.input.len(), fee);
+            transactions.push(signed);
+        }
+        
+        Ok(transactions)
+    }
+    
+    /// Select fee-paying inputs covering at least `target` satoshis
+    ///
+    /// Candidates are the wallet's UTXOs after ordinal-safety filtering, so
+    /// outputs carrying inscriptions or foreign runes are never spent. At
+    /// most `max_inputs` UTXOs are selected, in the order of the configured
+    /// `CoinSelection`; `BranchAndBound` looks for UTXOs adding up to exactly
+    /// `target`, as fees aren't known here.
+    pub async fn select_utxos(&self, target: u64) -> Result<Vec<Utxo>> {
+        let utxos = self.wallet_manager.list_utxos().await?;
+        let mut candidates = self.wallet_manager.filter_ordinal_safe(utxos, self.ord_check()).await?;
+        
+        order_candidates(&mut candidates, self.config.coin_selection);
+        let spendable: u64 = candidates.iter().map(|utxo| utxo.txout.value).sum();
+        
+        if self.config.coin_selection == CoinSelection::BranchAndBound {
+            let values: Vec<u64> = candidates.iter().map(|utxo| utxo.txout.value).collect();
+            if let Some(chosen) = branch_and_bound(&values, target, 0, self.config.max_inputs) {
+                debug!("Selected {} input(s) totalling exactly {} sats", chosen.len(), target);
+                return Ok(chosen.into_iter().map(|index| candidates[index].clone()).collect());
+            }
+        }
+        
+        let mut selected = Vec::new();
+        let mut total = 0u64;
+        for utxo in candidates.into_iter().take(self.config.max_inputs) {
+            if total >= target {
+                break;
+            }
+            total += utxo.txout.value;
+            selected.push(utxo);
+        }
+        
+        if total < target {
+            if spendable >= target {
+                return Err(TransactionError::TooManyInputsRequired {
+                    max_inputs: self.config.max_inputs,
+                    needed: target,
+                    selected: total,
+                }.into());
+            }
+            return Err(TransactionError::InsufficientFunds { needed: target, available: total }.into());
+        }
+        
+        debug!("Selected {} input(s) totalling {} sats", selected.len(), total);
+        Ok(selected)
+    }
+    
+    /// Summarize a built transaction instead of broadcasting it
+    ///
+    /// Reports the raw hex, the inputs and outputs with their addresses and
+    /// values, the fee and the decoded protostones. Nothing is sent: this is
+    /// the dry-run counterpart of `send_transaction`.
+    pub async fn dry_run(&self, artifact: &MintArtifact) -> Result<DryRun> {
+        let tx = artifact.transaction();
+        let prevouts = match artifact {
+            MintArtifact::Psbt(psbt) if psbt.inputs.iter().all(|input| input.witness_utxo.is_some()) => {
+                psbt.inputs.iter().filter_map(|input| input.witness_utxo.clone()).collect()
+            },
+            _ => {
+                let utxos = self.wallet_manager.list_utxos().await?;
+                let wallet_prevouts: Option<Vec<TxOut>> = tx.input.iter()
+                    .map(|input| utxos.iter().find(|utxo| utxo.outpoint == input.previous_output).map(|utxo| utxo.txout.clone()))
+                    .collect();
+                match wallet_prevouts {
+                    Some(prevouts) => prevouts,
+                    None => self.fetch_prevouts(tx).await?,
+                }
+            },
+        };
+        
+        let signed = matches!(artifact, MintArtifact::Signed(_));
+        let vsize = if signed { tx.vsize() as u64 } else { estimate_signed_vsize(tx, &prevouts) };
+        let total_input: u64 = prevouts.iter().map(|prevout| prevout.value).sum();
+        let total_output: u64 = tx.output.iter().map(|output| output.value).sum();
+        let fee = total_input.saturating_sub(total_output);
+        let address = |script: &Script| Address::from_script(script, self.config.network).ok().map(|address| address.to_string());
+        let protostones = match format_runestone(tx).and_then(Decoded::into_protostones) {
+            Ok(protostones) => protostones.iter().map(ProtostoneSummary::from).collect(),
+            Err(e) => {
+                debug!("No protostones to summarize: {}", e);
+                Vec::new()
+            },
+        };
+        
+        Ok(DryRun {
+            txid: tx.txid().to_string(),
+            signed,
+            raw_hex: hex::encode(serialize(tx)),
+            vsize,
+            fee,
+            fee_rate: fee as f64 / vsize as f64,
+            inputs: tx.input.iter().zip(&prevouts)
+                .map(|(input, prevout)| DryRunInput {
+                    outpoint: input.previous_output.to_string(),
+                    address: address(&prevout.script_pubkey),
+                    value: prevout.value,
+                })
+                .collect(),
+            outputs: tx.output.iter().enumerate()
+                .map(|(vout, output)| DryRunOutput {
+                    vout,
+                    address: address(&output.script_pubkey),
+                    value: output.value,
+                    op_return: output.script_pubkey.is_op_return(),
+                })
+                .collect(),
+            protostones,
+        })
+    }
+    
+    /// Send a signed transaction to the network
+    ///
+    /// Failures are classified as a `BroadcastError`. A transaction the node
+    /// already has counts as sent, and transport failures are retried up to
+    /// `broadcast_retries` times with exponential backoff.
+    pub async fn send_transaction(&self, tx: &Transaction) -> Result<String> {
+        let tx_hex = hex::encode(serialize(tx));
+        let mut backoff = Backoff::new(self.config.broadcast_retry_delay, MAX_BROADCAST_RETRY_DELAY);
+        let mut retries = 0;
+        loop {
+            let error = match self.rpc_client.send_raw_transaction(&tx_hex).await {
+                Ok(txid) => {
+                    info!("Transaction broadcast successfully: {}", txid);
+                    return Ok(txid);
+                },
+                Err(e) => BroadcastError::classify(&format!("{:#}", e)),
+            };
+            
+            match error {
+                BroadcastError::AlreadyKnown(_) => {
+                    info!("Transaction {} is already known to the node", tx.txid());
+                    return Ok(tx.txid().to_string());
+                },
+                BroadcastError::Transport(_) if retries < self.config.broadcast_retries => {
+                    retries += 1;
+                    let delay = backoff.next_delay();
+                    warn!("Broadcast of {} failed ({}), retry {} in {:?}", tx.txid(), error, retries, delay);
+                    tokio::time::sleep(delay).await;
+                },
+                error => return Err(error.into()),
+            }
+        }
+    }
+    
+    /// Send a signed transaction and, if `resubmit` is set, keep it in the
+    /// mempool until it confirms
+    ///
+    /// Every `resubmit_interval` the transaction's status is checked; when
+    /// the node reports it unknown (e.g. it was evicted for paying too
+    /// little) the same transaction is sent again. Failed resubmissions are
+    /// logged and retried at the next check, and failed status checks are
+    /// retried with exponential backoff without resubmitting. Fails once
+    /// `broadcast_timeout` elapses without a confirmation, having checked
+    /// the status at least once. Without `resubmit` this is
+    /// `send_transaction`.
+    pub async fn broadcast_and_track(&self, tx: &Transaction, resubmit: bool) -> Result<String> {
+        let txid = self.send_transaction(tx).await?;
+        if !resubmit {
+            return Ok(txid);
+        }
+        
+        let deadline = tokio::time::Instant::now() + self.config.broadcast_timeout;
+        let mut backoff = Backoff::new(self.config.resubmit_interval, self.config.broadcast_timeout);
+        let mut wait = self.config.resubmit_interval;
+        let mut resubmissions = 0;
+        loop {
+            // Never sleep past the deadline, so there is always a last check
+            tokio::time::sleep(wait.min(deadline.saturating_duration_since(tokio::time::Instant::now()))).await;
+            wait = self.config.resubmit_interval;
+            
+            match self.rpc_client.get_tx_status(&txid).await {
+                Ok(status) if status.confirmed => {
+                    info!("Transaction {} confirmed at height {:?}", txid, status.block_height);
+                    return Ok(txid);
+                },
+                Ok(_) => {
+                    debug!("Transaction {} still pending", txid);
+                    backoff.reset();
+                },
+                Err(e) if is_unknown_transaction(&e) => {
+                    backoff.reset();
+                    resubmissions += 1;
+                    warn!("Transaction {} is missing from the mempool ({}), resubmitting (attempt {})", txid, e, resubmissions);
+                    if let Err(e) = self.send_transaction(tx).await {
+                        warn!("Resubmission of {} failed: {}", txid, e);
+                    }
+                },
+                Err(e) => {
+                    wait = backoff.next_delay();
+                    warn!("Failed to check the status of {} ({:#}), retrying in {:?}", txid, e, wait);
+                },
+            }
+            
+            if tokio::time::Instant::now() >= deadline {
+                return Err(anyhow!(
+                    "Transaction {} not confirmed within {}s ({} resubmission(s))",
+                    txid, self.config.broadcast_timeout.as_secs(), resubmissions
+                ));
+            }
+        }
+    }
+    
+    /// Broadcast a transaction to the network
+    pub async fn broadcast_transaction(&self, tx: &Transaction) -> Result<String> {
+        info!("Broadcasting transaction");
+        
+        // Serialize transaction to hex
+        let _tx_hex = hex::encode(serialize(tx));
+        
+        // Get the transaction ID before broadcasting
+        let txid = tx.txid().to_string();
+        
+        // In a real implementation, we would:
+        // - Send the transaction to the Bitcoin network via RPC
+        // - Start monitoring for confirmations
+        
+        // For now, just log the transaction ID
+        info!("Transaction broadcast successfully: {}", txid);
+        
+        // Trace the transaction to verify DIESEL token minting
+        self.trace_transaction(tx, 0).await?;
+        
+        Ok(txid)
+    }
+    
+    /// Trace every protostone of a transaction, in runestone order
+    pub async fn trace_protostones(&self, tx: &Transaction) -> Result<Vec<Vec<TraceEvent>>> {
+        let count = format_runestone(tx)?.into_protostones()?.len();
+        let mut traces = Vec::with_capacity(count);
+        for protostone_index in 0..count {
+            traces.push(self.trace_transaction(tx, protostone_index).await?);
+        }
+        Ok(traces)
+    }
+    
+    /// Trace one protostone of a transaction and parse its events
+    ///
+    /// `protostone_index` counts protostones in runestone order, the DIESEL
+    /// mint being protostone 0.
+    pub async fn trace_transaction(&self, tx: &Transaction, protostone_index: usize) -> Result<Vec<TraceEvent>> {
+        let outpoint = trace_outpoint(tx, protostone_index)?;
+        let txid = outpoint.txid.to_string();
+        info!("Tracing transaction: {} vout: {}", txid, outpoint.vout);
+        
+        let trace = self.rpc_client.trace_transaction(&txid, outpoint.vout as usize).await
+            .with_context(|| format!("Failed to trace transaction {}", txid))?;
+        let events = parse_trace_events(&trace)?;
+        
+        info!("Transaction traced successfully");
+        debug!("Trace events: {:?}", events);
+        Ok(events)
+    }
+}
+
+/// Extract the created alkane ID from a deploy trace
+///
+/// A reverted execution is reported as an error carrying the revert data so
+/// contract authors can see why the deployment failed.
+pub fn parse_deployment_trace(trace: &serde_json::Value) -> Result<(u128, u128)> {
+    let events = trace.as_array().context("Invalid trace response")?;
+    let mut created = None;
+    
+    for event in events {
+        let data = &event["data"];
+        match event["event"].as_str() {
+            Some("create") => {
+                let block = data.get("block").and_then(value_to_u128);
+                let tx = data.get("tx").and_then(value_to_u128);
+                if let (Some(block), Some(tx)) = (block, tx) {
+                    created = Some((block, tx));
+                }
+            },
+            Some("return") if data["status"] == "revert" => {
+                let reason = data["response"]["data"].as_str().unwrap_or("no revert data");
+                return Err(anyhow!("Contract deployment reverted: {}", reason));
+            },
+            _ => {},
+        }
+    }
+    
+    created.ok_or_else(|| anyhow!("Trace did not report a created alkane"))
+}
+
+/// Build the OP_RETURN runestone of a protorune transfer
+///
+/// `edicts` are `(amount, output)` pairs of rune `rune_id`; unallocated
+/// tokens go to output `pointer`, at both the runestone and protostone level.
+pub fn transfer_runestone(rune_id: (u128, u128), edicts: &[(u128, u32)], pointer: u32) -> Result<ScriptBuf> {
+    ProtostoneSpec::transfer(rune_id, edicts, pointer).encipher()
+}
+
+/// Check that the runestone of `tx` decodes to the protostones of `expected`
+///
+/// Fails with `RunestoneCenotaph` if the runestone is malformed, and with
+/// `RunestoneMismatch` listing every protostone whose tag, cellpack, edicts,
+/// pointer or refund differ, or that is missing or extra. Edicts are
+/// compared regardless of order, and zero padding after a decoded cellpack
+/// is ignored.
+pub fn verify_runestone(tx: &Transaction, expected: &[ProtostoneSpec]) -> Result<()> {
+    let protostones = match format_runestone(tx)? {
+        Decoded::Runestone { protostones, .. } => protostones,
+        Decoded::Cenotaph(flaws) => return Err(TransactionError::RunestoneCenotaph { flaws }.into()),
+    };
+    let expected: Vec<ProtostoneSummary> = expected.iter()
+        .map(|spec| ProtostoneSummary::from(&spec.to_protostone()))
+        .collect();
+    let decoded: Vec<ProtostoneSummary> = protostones.iter().map(ProtostoneSummary::from).collect();
+    
+    let describe = |summary: Option<&ProtostoneSummary>| summary.map_or_else(|| "(none)".to_string(), |s| s.to_string());
+    let mut diff = Vec::new();
+    for index in 0..expected.len().max(decoded.len()) {
+        let (expected, decoded) = (expected.get(index), decoded.get(index));
+        let same = match (expected, decoded) {
+            (Some(expected), Some(decoded)) => summaries_match(expected, decoded),
+            _ => false,
+        };
+        if !same {
+            diff.push(format!("protostone {}:\n- {}\n+ {}", index, describe(expected), describe(decoded)));
+        }
+    }
+    if diff.is_empty() {
+        Ok(())
+    } else {
+        Err(TransactionError::RunestoneMismatch { diff: diff.join("\n") }.into())
+    }
+}
+
+/// Whether a decoded protostone is the one expected
+fn summaries_match(expected: &ProtostoneSummary, decoded: &ProtostoneSummary) -> bool {
+    // The message is packed into u128s, so decoding can leave zero bytes,
+    // each a zero value, after the cellpack
+    let cellpack = decoded.cellpack.starts_with(&expected.cellpack)
+        && decoded.cellpack[expected.cellpack.len()..].iter().all(|value| *value == 0);
+    let sorted = |summary: &ProtostoneSummary| {
+        let mut edicts = summary.edicts.clone();
+        edicts.sort();
+        edicts
+    };
+    cellpack
+        && expected.protocol_tag == decoded.protocol_tag
+        && sorted(expected) == sorted(decoded)
+        && expected.pointer == decoded.pointer
+        && expected.refund == decoded.refund
+}
+
+/// Runestone script with `count` DIESEL mints, mint `i` paying output `i`
+pub fn diesel_mint_runestone(count: u32) -> Result<ScriptBuf> {
+    let specs: Vec<ProtostoneSpec> = (0..count).map(ProtostoneSpec::diesel_mint_to).collect();
+    ProtostoneSpec::encipher_all(&specs)
+}
+
+/// Most DIESEL mints whose runestone fits in `max_op_return_size` bytes
+pub fn max_mint_count(max_op_return_size: usize) -> u32 {
+    let fits = |count: u32| diesel_mint_runestone(count).map_or(false, |script| script.len() <= max_op_return_size);
+    // Every protostone takes at least a byte, which bounds the search
+    let mut count = 0;
+    while (count as usize) < max_op_return_size && fits(count + 1) {
+        count += 1;
+    }
+    count
+}
+
+/// Raw DIESEL emitted in the block at `height`
+///
+/// DIESEL follows Bitcoin's subsidy schedule: 50 DIESEL per block, halving
+/// every 210,000 blocks.
+pub fn diesel_block_reward(height: u64) -> u128 {
+    let halvings = height / HALVING_INTERVAL;
+    if halvings >= 64 {
+        return 0;
+    }
+    u128::from(INITIAL_SUBSIDY >> halvings)
+}
+
+/// Sum of `amounts`, or `None` on overflow
+fn checked_sum(amounts: &[u128]) -> Option<u128> {
+    amounts.iter().try_fold(0u128, |total, amount| total.checked_add(*amount))
+}
+
+/// Shadow vout of the protostone at `protostone_index`
+///
+/// Protostones are numbered after the real outputs and the virtual output
+/// implied by the OP_RETURN.
+pub fn protostone_vout(tx: &Transaction, protostone_index: usize) -> usize {
+    crate::runestone::protostone_vout(protostone_index, tx.output.len())
+}
+
+/// Outpoint to trace for the protostone at `protostone_index`
+///
+/// Protostones execute at their shadow vout (see `protostone_vout`), not at
+/// the OP_RETURN output or their pointer, so that is the vout traced. Fails
+/// if the transaction has no runestone, if the runestone is a cenotaph, or
+/// if it has no protostone at `protostone_index`.
+pub fn trace_outpoint(tx: &Transaction, protostone_index: usize) -> Result<OutPoint> {
+    let protostones = format_runestone(tx)
+        .context("Transaction has no runestone to trace")?
+        .into_protostones()?;
+    if protostone_index >= protostones.len() {
+        return Err(anyhow!(
+            "Transaction has {} protostone(s), there is no protostone {}",
+            protostones.len(),
+            protostone_index
+        ));
+    }
+    
+    Ok(OutPoint {
+        txid: tx.txid(),
+        vout: protostone_vout(tx, protostone_index) as u32,
+    })
+}
+
+/// Indices of the protostones of `tx` that mint DIESEL, in runestone order
+///
+/// Fails if the transaction has no runestone or it is a cenotaph.
+pub fn diesel_mint_protostones(tx: &Transaction) -> Result<Vec<usize>> {
+    let protostones = format_runestone(tx)
+        .context("Transaction has no runestone")?
+        .into_protostones()?;
+    Ok(protostones.iter()
+        .enumerate()
+        .filter(|(_, protostone)| protostone.protocol_tag == u128::from(PROTOCOL_TAG)
+            && Cellpack::decode(&protostone.message).is_ok_and(|cellpack| cellpack.is_diesel_mint()))
+        .map(|(index, _)| index)
+        .collect())
+}
+
+/// Read the outcome of a DIESEL mint from its trace events
+///
+/// The last `return` event is that of the mint call itself, nested calls
+/// returning first. Its `response.alkanes` give the DIESEL minted, and a
+/// `revert` status its revert data. Fails if the trace has no `return`
+/// event, as when the mint hasn't been indexed yet.
+pub fn parse_mint_trace(events: &[TraceEvent]) -> Result<MintTrace> {
+    let data = &events.iter()
+        .rev()
+        .find(|event| event.event == "return")
+        .ok_or_else(|| anyhow!("Trace has no return event, the mint may not be indexed yet"))?
+        .data;
+    
+    if data["status"] == "revert" {
+        let reason = data["response"]["data"].as_str().unwrap_or("no revert data");
+        return Ok(MintTrace { success: false, minted: 0, revert_reason: Some(reason.to_string()) });
+    }
+    
+    let [block, tx, _] = DIESEL_MINT_CELLPACK;
+    let minted = data["response"]["alkanes"].as_array()
+        .map(|transfers| transfers.iter()
+            .filter(|transfer| value_to_u128(&transfer["id"]["block"]) == Some(block) && value_to_u128(&transfer["id"]["tx"]) == Some(tx))
+            .filter_map(|transfer| value_to_u128(&transfer["value"]))
+            .fold(0u128, u128::saturating_add))
+        .unwrap_or(0);
+    Ok(MintTrace { success: true, minted, revert_reason: None })
+}
+
+/// Parse an `alkanes_trace` result into its events
+pub fn parse_trace_events(trace: &serde_json::Value) -> Result<Vec<TraceEvent>> {
+    serde_json::from_value(trace.clone()).context("Invalid trace result")
+}
+
+/// Parse an `alkanes_simulate` result of a DIESEL mint
+///
+/// The expected amount is the DIESEL the execution transfers out. A
+/// non-zero status or an execution error marks the mint as failing, in
+/// which case nothing is expected to be minted.
+pub fn parse_simulation_outcome(result: &serde_json::Value) -> Result<SimulationOutcome> {
+    let execution = result.get("execution").context("Invalid simulation result: missing execution")?;
+    let status = result.get("status").and_then(|status| status.as_u64()).unwrap_or(0);
+    let error = execution.get("error").or_else(|| result.get("error"))
+        .and_then(|error| error.as_str())
+        .filter(|error| !error.is_empty())
+        .map(|error| error.to_string())
+        .or_else(|| (status != 0).then(|| format!("execution failed with status {}", status)));
+    let success = error.is_none();
+    
+    let [block, tx, _] = DIESEL_MINT_CELLPACK;
+    let expected_amount = match execution["alkanes"].as_array() {
+        Some(transfers) if success => transfers.iter()
+            .filter(|transfer| value_to_u128(&transfer["id"]["block"]) == Some(block) && value_to_u128(&transfer["id"]["tx"]) == Some(tx))
+            .filter_map(|transfer| value_to_u128(&transfer["value"]))
+            .sum(),
+        _ => 0,
+    };
+    let gas_used = result.get("gasUsed").or_else(|| result.get("gas_used"))
+        .and_then(|gas| gas.as_u64())
+        .unwrap_or(0);
+    
+    Ok(SimulationOutcome { success, expected_amount, payable_mints: u32::from(expected_amount > 0), gas_used, error })
+}
+
+/// Standardness dust limit of an output paying to `script`
+///
+/// `dust_value_for` at the minimum relay fee rate, as in Bitcoin Core: 546
+/// sats for P2PKH, 294 for P2WPKH and 330 for P2TR or P2WSH. OP_RETURN
+/// outputs can't be spent and have no limit.
+pub fn dust_limit_for(script: &Script) -> u64 {
+    dust_value_for(script, DUST_RELAY_FEE_RATE)
+}
+
+/// Dust threshold of an output paying to `script` at `fee_rate` sat/vB
+///
+/// An output is dust when it is worth less than three times what it costs
+/// at `fee_rate`, counting the output itself and a typical input spending
+/// it, so the threshold follows the script type: smaller for witness
+/// programs, whose signatures are discounted, and larger for long scripts
+/// such as bare multisig. OP_RETURN outputs have no threshold.
+pub fn dust_value_for(script: &Script, fee_rate: f64) -> u64 {
+    if script.is_op_return() {
+        return 0;
+    }
+    let output_size = serialize(&TxOut { value: 0, script_pubkey: script.to_owned() }).len() as u64;
+    // Outpoint, script length, sequence and a signature + pubkey, the
+    // latter discounted for witness programs
+    let spend_size = if script.is_witness_program() {
+        32 + 4 + 1 + 107 / 4 + 4
+    } else {
+        32 + 4 + 1 + 107 + 4
+    };
+    (3.0 * (output_size + spend_size) as f64 * fee_rate).ceil() as u64
+}
+
+/// Make sure the fee of a funded transaction covers its signed vsize
+///
+/// The fee is measured against the transaction with placeholder witnesses
+/// and any shortfall is taken from the change output following the first
+/// `payment_outputs` outputs, dropping it once it would be below
+/// `change_dust`. Returns the settled fee.
+fn settle_fee(tx: &mut Transaction, prevouts: &[TxOut], total: u64, payment_outputs: usize, change_dust: u64, fee_rate: f64) -> Result<u64> {
+    for _ in 0..MAX_FEE_ITERATIONS {
+        let output_total: u64 = tx.output.iter().map(|output| output.value).sum();
+        let fee = total - output_total;
+        let required = fee_for(tx, prevouts, fee_rate);
+        if fee >= required {
+            return Ok(fee);
+        }
+        
+        let shortfall = required - fee;
+        let has_change = tx.output.len() > payment_outputs;
+        match tx.output.last_mut() {
+            Some(change) if has_change && change.value >= shortfall + change_dust => change.value -= shortfall,
+            _ if has_change => {
+                tx.output.pop();
+            },
+            _ => {
+                let payment: u64 = tx.output.iter().map(|output| output.value).sum();
+                return Err(TransactionError::InsufficientFunds { needed: payment + required, available: total }.into());
+            },
+        }
+    }
+    
+    Err(anyhow!("Fee did not settle after {} iterations", MAX_FEE_ITERATIONS))
+}
+
+/// Order fee-paying candidates for `strategy`
+///
+/// `ConsolidateDust` puts the smallest first; `BranchAndBound` orders as
+/// `LargestFirst`, its fallback. Ties are broken by outpoint.
+pub fn order_candidates(candidates: &mut [Utxo], strategy: CoinSelection) {
+    match strategy {
+        CoinSelection::LargestFirst | CoinSelection::BranchAndBound => candidates.sort_by(largest_first),
+        CoinSelection::OldestFirst => candidates.sort_by(|a, b| {
+            let height = |utxo: &Utxo| utxo.height.unwrap_or(u32::MAX);
+            height(a).cmp(&height(b)).then_with(|| largest_first(a, b))
+        }),
+        CoinSelection::ConsolidateDust => candidates.sort_by(|a, b| {
+            a.txout.value.cmp(&b.txout.value).then_with(|| a.outpoint.cmp(&b.outpoint))
+        }),
+    }
+}
+
+/// Larger values first, then by outpoint
+fn largest_first(a: &Utxo, b: &Utxo) -> std::cmp::Ordering {
+    b.txout.value.cmp(&a.txout.value).then_with(|| a.outpoint.cmp(&b.outpoint))
+}
+
+/// Fee an input spending `prevout` adds at `fee_rate`
+fn input_fee(prevout: &TxOut, fee_rate: f64) -> u64 {
+    // Outpoint, empty script_sig and sequence, then the discounted witness
+    let weight = 41 * 4 + placeholder_witness(Some(prevout)).serialized_len();
+    (weight as f64 / 4.0 * fee_rate).ceil() as u64
+}
+
+/// Indices of `values` summing to between `target` and `target + tolerance`
+///
+/// A depth-first search over the values, largest first, trying each with
+/// and then without it, using at most `max_count` values. The match paying
+/// the least over `target` is kept; the search stops at an exact match or
+/// after `BNB_MAX_TRIES` steps. Indices are returned in ascending order.
+pub fn branch_and_bound(values: &[u64], target: u64, tolerance: u64, max_count: usize) -> Option<Vec<usize>> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[b].cmp(&values[a]).then(a.cmp(&b)));
+    
+    // What the values from each position on add up to, for pruning
+    let mut remaining = vec![0u64; order.len() + 1];
+    for position in (0..order.len()).rev() {
+        remaining[position] = remaining[position + 1].saturating_add(values[order[position]]);
+    }
+    
+    let mut search = BranchAndBound {
+        values,
+        order: &order,
+        remaining: &remaining,
+        target,
+        limit: target.saturating_add(tolerance),
+        max_count,
+        tries: 0,
+        best: None,
+    };
+    search.explore(0, 0, &mut Vec::new());
+    search.best.map(|(_, mut indices)| {
+        indices.sort_unstable();
+        indices
+    })
+}
+
+/// State of a `branch_and_bound` search
+struct BranchAndBound<'a> {
+    values: &'a [u64],
+    order: &'a [usize],
+    remaining: &'a [u64],
+    target: u64,
+    limit: u64,
+    max_count: usize,
+    tries: usize,
+    /// Excess over the target and indices of the best match so far
+    best: Option<(u64, Vec<usize>)>,
+}
+
+impl BranchAndBound<'_> {
+    fn explore(&mut self, position: usize, sum: u64, chosen: &mut Vec<usize>) {
+        if self.tries >= BNB_MAX_TRIES || matches!(self.best, Some((0, _))) {
+            return;
+        }
+        self.tries += 1;
+        
+        // Adding values only raises the sum
+        if sum >= self.target {
+            let excess = sum - self.target;
+            if sum <= self.limit && self.best.as_ref().map_or(true, |(best, _)| excess < *best) {
+                self.best = Some((excess, chosen.clone()));
+            }
+            return;
+        }
+        if position == self.order.len() || chosen.len() == self.max_count
+            || sum.saturating_add(self.remaining[position]) < self.target {
+            return;
+        }
+        
+        let index = self.order[position];
+        chosen.push(index);
+        self.explore(position + 1, sum.saturating_add(self.values[index]), chosen);
+        chosen.pop();
+        self.explore(position + 1, sum, chosen);
+    }
+}
+
+/// Check a transaction against relay policy before it is signed
+///
+/// `prevouts` are the outputs spent by each input, in input order. The size
+/// is estimated as if signed, so unsigned transactions can be checked. All
+/// violations are reported, not just the first.
+pub fn validate_standardness(tx: &Transaction, prevouts: &[TxOut], policy: &StandardnessPolicy) -> Result<(), Vec<StandardnessViolation>> {
+    let mut violations = Vec::new();
+    
+    let mut op_returns = 0;
+    for (vout, output) in tx.output.iter().enumerate() {
+        if output.script_pubkey.is_op_return() {
+            op_returns += 1;
+            let size = output.script_pubkey.len();
+            if size > policy.max_op_return_size {
+                violations.push(StandardnessViolation::OpReturnTooLarge { vout, size, max_size: policy.max_op_return_size });
+            }
+            continue;
+        }
+        
+        let dust_limit = dust_limit_for(&output.script_pubkey);
+        if output.value < dust_limit {
+            violations.push(StandardnessViolation::DustOutput { vout, value: output.value, dust_limit });
+        }
+    }
+    if op_returns > policy.max_op_returns {
+        violations.push(StandardnessViolation::TooManyOpReturns { count: op_returns, max_count: policy.max_op_returns });
+    }
+    
+    let vsize = estimate_signed_vsize(tx, prevouts);
+    let max_vsize = MAX_STANDARD_TX_WEIGHT / 4;
+    if vsize > max_vsize {
+        violations.push(StandardnessViolation::TooLarge { vsize, max_vsize });
+    }
+    
+    let input_value: u64 = prevouts.iter().map(|prevout| prevout.value).sum();
+    let output_value: u64 = tx.output.iter().map(|output| output.value).sum();
+    if input_value <= output_value {
+        violations.push(StandardnessViolation::ZeroFeeRate);
+    }
+    
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+/// Check that `lock_time` is enforced with inputs of sequence `sequence`
+///
+/// Consensus ignores the locktime of a transaction whose inputs all have a
+/// final sequence, so a non-zero locktime needs a non-final one.
+pub fn check_lock_time(lock_time: LockTime, sequence: Sequence) -> Result<(), TransactionError> {
+    if lock_time != LockTime::ZERO && sequence == Sequence::MAX {
+        return Err(TransactionError::LockTimeDisabled { lock_time: lock_time.to_consensus_u32() });
+    }
+    Ok(())
+}
+
+/// Pick an anti-fee-sniping locktime height for the given tip
+///
+/// Like Bitcoin Core, one time in ten the height is moved back by up to 100
+/// blocks so that delayed transactions don't stand out.
+pub fn anti_fee_sniping_height(tip: u64, rng: &mut impl Rng) -> u32 {
+    let mut height = tip;
+    if rng.gen_range(0..LOCKTIME_BACKOFF_ODDS) == 0 {
+        height = height.saturating_sub(rng.gen_range(0..MAX_LOCKTIME_BACKOFF));
+    }
+    u32::try_from(height).unwrap_or(u32::MAX)
+}
+
+/// Fee in satoshis for a transaction at `fee_rate`, once signed
+fn fee_for(tx: &Transaction, prevouts: &[TxOut], fee_rate: f64) -> u64 {
+    (estimate_signed_vsize(tx, prevouts) as f64 * fee_rate).ceil() as u64
+}
+
+/// Worst-case witness of a signed input spending `prevout`
+///
+/// Taproot outputs are assumed to be spent through the key path; anything
+/// else is treated as P2WPKH, the wallet's own output type.
+fn placeholder_witness(prevout: Option<&TxOut>) -> Witness {
+    match prevout {
+        Some(prevout) if prevout.script_pubkey.is_v1_p2tr() => {
+            Witness::from_slice(&[vec![0u8; SCHNORR_SIGNATURE_SIZE]])
+        },
+        _ => Witness::from_slice(&[vec![0u8; ECDSA_SIGNATURE_SIZE], vec![0u8; COMPRESSED_PUBKEY_SIZE]]),
+    }
+}
+
+/// Estimate the vsize of a transaction once its inputs are signed
+///
+/// Unsigned inputs get a worst-case witness for the output they spend
+/// (`prevouts` in input order, P2WPKH when unknown) and the resulting
+/// transaction is measured.
+pub fn estimate_signed_vsize(tx: &Transaction, prevouts: &[TxOut]) -> u64 {
+    let mut signed = tx.clone();
+    for (index, input) in signed.input.iter_mut().enumerate() {
+        if input.witness.is_empty() {
+            input.witness = placeholder_witness(prevouts.get(index));
+        }
+    }
+    signed.vsize() as u64
+}
+
+/// Pick a fee rate that lands within `target_blocks` blocks
+///
+/// Walks the histogram from the highest fee rate down, accumulating vsize
+/// until `target_blocks` blocks worth of space is filled. A transaction paying
+/// slightly more than the bucket at that point should be mined in time. An
+/// empty or shallow mempool yields the minimum relay fee rate.
+pub fn fee_rate_for_target(histogram: &[(f64, u64)], target_blocks: u16) -> f64 {
+    let capacity = BLOCK_VSIZE * u64::from(target_blocks.max(1));
+    let mut cumulative = 0u64;
+    
+    for &(fee_rate, vsize) in histogram {
+        cumulative += vsize;
+        if cumulative >= capacity {
+            // Outbid the bucket that would be at the edge of the target block
+            return (fee_rate + 1.0).max(MIN_FEE_RATE);
+        }
+    }
+    
+    MIN_FEE_RATE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::{RpcClient, RpcConfig};
+    use crate::runestone::Runestone;
+    use crate::wallet::{WalletManager, WalletConfig};
+    use bdk::bitcoin::Network;
+    use std::collections::HashMap;
+    
+    /// Dust limit of the test wallets' P2WPKH outputs
+    const P2WPKH_DUST: u64 = 294;
+    
+    #[tokio::test]
+    async fn test_transaction_constructor_creation() {
+        // Create wallet manager
+        let wallet_config = WalletConfig {
+            wallet_path: "test_wallet.dat".to_string(),
             network: Network::Testnet,
             bitcoin_rpc_url: "http://localhost:18332".to_string(),
             metashrew_rpc_url: "http://localhost:8080".to_string(),
+            esplora_url: None,
+        };
+        let wallet_manager = WalletManager::new(wallet_config).await.unwrap();
+        
+        // Create RPC client
+        let rpc_config = RpcConfig {
+            bitcoin_rpc_url: "http://localhost:18332".to_string(),
+            metashrew_rpc_url: "http://localhost:8080".to_string(),
+            ..Default::default()
+        };
+        let rpc_client = RpcClient::new(rpc_config);
+        
+        // Create transaction constructor
+        let config = TransactionConfig::default();
+        let constructor = TransactionConstructor::new(
+            Arc::new(wallet_manager),
+            Arc::new(rpc_client),
+            config,
+        );
+        
+        // Verify constructor was created successfully
+        assert_eq!(constructor.config.network, Network::Testnet);
+    }
+    
+    #[test]
+    fn test_parse_deployment_trace() {
+        let trace = serde_json::json!([
+            { "event": "create", "data": { "block": "0x2", "tx": "0x1f" } },
+            { "event": "return", "data": { "status": "success", "response": { "data": "0x" } } },
+        ]);
+        assert_eq!(parse_deployment_trace(&trace).unwrap(), (2, 31));
+        
+        let reverted = serde_json::json!([
+            { "event": "return", "data": { "status": "revert", "response": { "data": "0x08c379a0" } } },
+        ]);
+        let err = parse_deployment_trace(&reverted).unwrap_err();
+        assert!(err.to_string().contains("0x08c379a0"));
+        
+        assert!(parse_deployment_trace(&serde_json::json!([])).is_err());
+    }
+    
+    #[test]
+    fn test_parse_simulation_outcome() {
+        let minted = serde_json::json!({
+            "status": 0,
+            "gasUsed": 1200,
+            "execution": {
+                "alkanes": [
+                    { "id": { "block": "0x2", "tx": "0x0" }, "value": "0x12a05f200" },
+                    { "id": { "block": "0x2", "tx": "0x1" }, "value": "0x5" },
+                ],
+                "error": null,
+            },
+        });
+        assert_eq!(parse_simulation_outcome(&minted).unwrap(), SimulationOutcome {
+            success: true,
+            expected_amount: 5_000_000_000,
+            payable_mints: 1,
+            gas_used: 1200,
+            error: None,
+        });
+        
+        // Cap reached: the mint executes but pays nothing
+        let capped = serde_json::json!({ "status": 0, "gasUsed": 800, "execution": { "alkanes": [] } });
+        let outcome = parse_simulation_outcome(&capped).unwrap();
+        assert!(outcome.success);
+        assert_eq!(outcome.expected_amount, 0);
+        
+        let reverted = serde_json::json!({
+            "status": 1,
+            "gasUsed": 300,
+            "execution": { "alkanes": [{ "id": { "block": 2, "tx": 0 }, "value": 1 }], "error": "mint cap reached" },
+        });
+        let outcome = parse_simulation_outcome(&reverted).unwrap();
+        assert!(!outcome.success);
+        assert_eq!(outcome.expected_amount, 0);
+        assert_eq!(outcome.error.as_deref(), Some("mint cap reached"));
+        
+        assert!(parse_simulation_outcome(&serde_json::json!({ "status": 0 })).is_err());
+    }
+    
+    #[test]
+    fn test_parse_mint_trace() {
+        let events = |trace: serde_json::Value| parse_trace_events(&trace).unwrap();
+        let minted = events(serde_json::json!([
+            { "event": "invoke", "data": { "type": "call", "context": {} } },
+            { "event": "return", "data": { "status": "success", "response": { "alkanes": [], "data": "0x" } } },
+            { "event": "return", "data": { "status": "success", "response": {
+                "alkanes": [{ "id": { "block": "0x2", "tx": "0x0" }, "value": "0x12a05f200" }, { "id": { "block": "0x2", "tx": "0x1" }, "value": "0x5" }],
+                "data": "0x",
+            } } },
+        ]));
+        assert_eq!(parse_mint_trace(&minted).unwrap(), MintTrace { success: true, minted: 5_000_000_000, revert_reason: None });
+        
+        let reverted = events(serde_json::json!([
+            { "event": "return", "data": { "status": "revert", "response": { "data": "0x08c379a0" } } },
+        ]));
+        assert_eq!(parse_mint_trace(&reverted).unwrap(), MintTrace {
+            success: false,
+            minted: 0,
+            revert_reason: Some("0x08c379a0".to_string()),
+        });
+        
+        assert!(parse_mint_trace(&[]).is_err());
+        
+        // Only DIESEL mint protostones are verified
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![
+                TxOut { value: 546, script_pubkey: ScriptBuf::new() },
+                TxOut { value: 0, script_pubkey: ProtostoneSpec::encipher_all(&[
+                    ProtostoneSpec { cellpack: vec![2, 7, 99], ..ProtostoneSpec::diesel_mint() },
+                    ProtostoneSpec::diesel_mint(),
+                ]).unwrap() },
+            ],
+        };
+        assert_eq!(diesel_mint_protostones(&tx).unwrap(), vec![1]);
+    }
+    
+    #[tokio::test]
+    async fn test_trace_transaction_vouts() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = funded_constructor(&server, TransactionConfig::default()).await;
+        // Dust, a runestone with two protostones, then more outputs
+        let tx_with_outputs = |count: u64| {
+            let mut output: Vec<TxOut> = (0..count).map(|i| TxOut { value: 546 + i, script_pubkey: ScriptBuf::new() }).collect();
+            output[1] = TxOut { value: 0, script_pubkey: diesel_mint_runestone(2).unwrap() };
+            Transaction {
+                version: 2,
+                lock_time: bdk::bitcoin::absolute::LockTime::ZERO,
+                input: vec![TxIn::default()],
+                output,
+            }
+        };
+        
+        // Dust + OP_RETURN, dust + OP_RETURN + change, and a second protostone
+        for (tx, protostone_index, vout) in [
+            (tx_with_outputs(2), 0, 3),
+            (tx_with_outputs(3), 0, 4),
+            (tx_with_outputs(2), 1, 4),
+        ] {
+            assert_eq!(protostone_vout(&tx, protostone_index), vout);
+            assert_eq!(trace_outpoint(&tx, protostone_index).unwrap(), OutPoint { txid: tx.txid(), vout: vout as u32 });
+            
+            // The trace is only answered for the txid in internal byte order
+            let internal_txid = hex::encode(serialize(&tx.txid()));
+            server.set_with_params("alkanes_trace", serde_json::json!([internal_txid, vout]), serde_json::json!([
+                { "event": "enter", "data": { "vout": vout } },
+                { "event": "return", "data": { "status": "success" } },
+            ]));
+            
+            let events = constructor.trace_transaction(&tx, protostone_index).await.unwrap();
+            assert_eq!(events.len(), 2);
+            assert_eq!(events[0].event, "enter");
+            assert_eq!(events[0].data["vout"], vout);
+        }
+        
+        // Past the last protostone, or without a runestone at all
+        let tx = tx_with_outputs(2);
+        assert!(trace_outpoint(&tx, 2).unwrap_err().to_string().contains("no protostone 2"));
+        let plain = Transaction { output: vec![tx.output[0].clone()], ..tx };
+        assert!(trace_outpoint(&plain, 0).unwrap_err().to_string().contains("no runestone"));
+        assert!(constructor.trace_transaction(&plain, 0).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_create_deploy_transaction_rejects_empty_wasm() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let wallet_manager = WalletManager::new(WalletConfig {
+            wallet_path: "test_wallet.dat".to_string(),
+            network: Network::Testnet,
+            bitcoin_rpc_url: server.url.clone(),
+            metashrew_rpc_url: server.url.clone(),
+            esplora_url: None,
+        }).await.unwrap();
+        let constructor = TransactionConstructor::new(
+            Arc::new(wallet_manager),
+            Arc::new(RpcClient::new(server.config())),
+            TransactionConfig::default(),
+        );
+        
+        assert!(constructor.create_deploy_transaction(&[], None).await.is_err());
+        
+        let oversized = vec![0xffu8; 100_000];
+        let err = constructor.create_deploy_transaction(&oversized, None).await.unwrap_err();
+        assert!(err.to_string().contains("too large"));
+        assert!(server.methods().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_quote_minting_transaction_unfunded() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let wallet_manager = WalletManager::new(WalletConfig {
+            wallet_path: "test_wallet.dat".to_string(),
+            network: Network::Testnet,
+            bitcoin_rpc_url: server.url.clone(),
+            metashrew_rpc_url: server.url.clone(),
+            esplora_url: None,
+        }).await.unwrap();
+        let constructor = TransactionConstructor::new(
+            Arc::new(wallet_manager),
+            Arc::new(RpcClient::new(server.config())),
+            TransactionConfig { fee_rate: 10.0, ..TransactionConfig::default() },
+        );
+        
+        // The empty wallet can't fund the mint, so a single input is assumed
+        server.set("esplora_address::utxo", serde_json::json!([]));
+        let quote = constructor.quote_minting_transaction().await.unwrap();
+        assert_eq!(quote.total_input, 0);
+        assert_eq!(quote.change, 0);
+        assert_eq!(quote.dust, P2WPKH_DUST);
+        assert_eq!(quote.fee, quote.vsize * 10);
+        assert_eq!(quote.effective_fee_rate, 10.0);
+        assert!(!quote.is_affordable());
+    }
+    
+    async fn funded_constructor(server: &crate::rpc::mock::MockRpcServer, config: TransactionConfig) -> TransactionConstructor {
+        let wallet_manager = WalletManager::new(WalletConfig {
+            wallet_path: "test_wallet.dat".to_string(),
+            network: Network::Testnet,
+            bitcoin_rpc_url: server.url.clone(),
+            metashrew_rpc_url: server.url.clone(),
+            esplora_url: None,
+        }).await.unwrap();
+        
+        let inscribed = format!("{}:0", "33".repeat(32));
+        server.set("esplora_address::utxo", serde_json::json!([
+            { "txid": "11".repeat(32), "vout": 0, "value": 20_000 },
+            { "txid": "22".repeat(32), "vout": 1, "value": 5_000 },
+            { "txid": "33".repeat(32), "vout": 0, "value": 1_000_000 },
+        ]));
+        server.set("ord_output", serde_json::json!({ "inscriptions": [], "runes": {} }));
+        server.set_with_params("ord_output", serde_json::json!([inscribed]), serde_json::json!({ "inscriptions": ["i0"], "runes": {} }));
+        server.set("alkanes_protorunesbyoutpoint", serde_json::json!([]));
+        server.set("btc_getblockcount", serde_json::json!(800_000));
+        
+        TransactionConstructor::new(Arc::new(wallet_manager), Arc::new(RpcClient::new(server.config())), config)
+    }
+    
+    /// Constructor over a wallet that can sign, holding a single 20,000 sat
+    /// UTXO at `11..11:0`
+    async fn signing_constructor(server: &crate::rpc::mock::MockRpcServer, config: TransactionConfig) -> TransactionConstructor {
+        let (descriptor, change_descriptor) = crate::wallet::TEST_SIGNING_DESCRIPTORS;
+        let wallet_manager = WalletManager::from_descriptors(WalletConfig {
+            wallet_path: "test_wallet.dat".to_string(),
+            network: Network::Testnet,
+            bitcoin_rpc_url: server.url.clone(),
+            metashrew_rpc_url: server.url.clone(),
+            esplora_url: None,
+        }, descriptor, Some(change_descriptor)).await.unwrap();
+        
+        server.set("esplora_address::utxo", serde_json::json!([{ "txid": "11".repeat(32), "vout": 0, "value": 20_000 }]));
+        server.set("ord_output", serde_json::json!({ "inscriptions": [], "runes": {} }));
+        server.set("alkanes_protorunesbyoutpoint", serde_json::json!([]));
+        server.set("btc_getblockcount", serde_json::json!(800_000));
+        
+        TransactionConstructor::new(Arc::new(wallet_manager), Arc::new(RpcClient::new(server.config())), config)
+    }
+    
+    #[test]
+    fn test_branch_and_bound() {
+        let values = [50_000, 30_000, 12_000, 2_000, 1_500];
+        assert_eq!(branch_and_bound(&values, 42_000, 0, 10), Some(vec![1, 2]));
+        assert_eq!(branch_and_bound(&values, 3_500, 0, 10), Some(vec![3, 4]));
+        // The match paying the least over the target wins
+        assert_eq!(branch_and_bound(&values, 13_000, 1_000, 10), Some(vec![2, 4]));
+        assert_eq!(branch_and_bound(&values, 13_000, 400, 10), None);
+        assert_eq!(branch_and_bound(&values, 43_500, 0, 2), None);
+        assert_eq!(branch_and_bound(&values, 43_500, 0, 3), Some(vec![1, 2, 4]));
+        assert_eq!(branch_and_bound(&values, 200_000, 1_000_000, 10), None);
+    }
+    
+    #[tokio::test]
+    async fn test_coin_selection_strategies() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let utxos = serde_json::json!([
+            { "txid": "aa".repeat(32), "vout": 0, "value": 50_000, "status": { "confirmed": true, "block_height": 100 } },
+            { "txid": "bb".repeat(32), "vout": 0, "value": 30_000, "status": { "confirmed": true, "block_height": 50 } },
+            { "txid": "cc".repeat(32), "vout": 0, "value": 2_000, "status": { "confirmed": true, "block_height": 200 } },
+            { "txid": "dd".repeat(32), "vout": 0, "value": 1_500, "status": { "confirmed": false } },
+            { "txid": "ee".repeat(32), "vout": 0, "value": 12_000, "status": { "confirmed": true, "block_height": 150 } },
+            { "txid": "ff".repeat(32), "vout": 0, "value": 600, "status": { "confirmed": true, "block_height": 120 } },
+        ]);
+        let txids = |utxos: &[Utxo]| -> Vec<String> {
+            utxos.iter().map(|utxo| utxo.outpoint.txid.to_string()[..2].to_string()).collect()
+        };
+        let inputs = |tx: &Transaction| -> Vec<String> {
+            tx.input.iter().map(|input| input.previous_output.txid.to_string()[..2].to_string()).collect()
+        };
+        
+        for (coin_selection, selected, minted) in [
+            (CoinSelection::LargestFirst, vec!["aa"], vec!["aa"]),
+            (CoinSelection::OldestFirst, vec!["bb", "aa"], vec!["bb"]),
+            // Exactly 42,000 sats; the mint spends the UTXO closest to its cost
+            (CoinSelection::BranchAndBound, vec!["bb", "ee"], vec!["ff"]),
+            // Smallest first; the mint sweeps everything under the threshold
+            (CoinSelection::ConsolidateDust, vec!["ff", "dd", "cc", "ee", "bb"], vec!["ff", "dd", "cc"]),
+        ] {
+            let constructor = funded_constructor(&server, TransactionConfig { coin_selection, ..TransactionConfig::default() }).await;
+            server.set("esplora_address::utxo", utxos.clone());
+            
+            assert_eq!(txids(&constructor.select_utxos(42_000).await.unwrap()), selected, "{:?}", coin_selection);
+            let tx = constructor.create_minting_transaction().await.unwrap().transaction().clone();
+            assert_eq!(inputs(&tx), minted, "{:?}", coin_selection);
+            if coin_selection == CoinSelection::BranchAndBound {
+                // No change output after the dust and the runestone
+                assert_eq!(tx.output.len(), 2);
+            }
+        }
+        
+        let mut candidates = vec![
+            Utxo { outpoint: OutPoint { txid: bdk::bitcoin::Txid::from_str(&"02".repeat(32)).unwrap(), vout: 0 }, txout: TxOut { value: 5, script_pubkey: ScriptBuf::new() }, height: Some(7) },
+            Utxo { outpoint: OutPoint { txid: bdk::bitcoin::Txid::from_str(&"01".repeat(32)).unwrap(), vout: 0 }, txout: TxOut { value: 5, script_pubkey: ScriptBuf::new() }, height: Some(7) },
+        ];
+        // Ties are broken by outpoint
+        for strategy in [CoinSelection::LargestFirst, CoinSelection::OldestFirst, CoinSelection::ConsolidateDust] {
+            order_candidates(&mut candidates, strategy);
+            assert_eq!(txids(&candidates), vec!["01", "02"]);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_multiple_mints_in_one_transaction() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let max_count = max_mint_count(MAX_OP_RETURN_RELAY);
+        assert!(max_count >= 2);
+        assert!(diesel_mint_runestone(max_count + 1).unwrap().len() > MAX_OP_RETURN_RELAY);
+        
+        let constructor = funded_constructor(&server, TransactionConfig { mint_count: 2, ..TransactionConfig::default() }).await;
+        let tx = constructor.create_minting_transaction().await.unwrap().transaction().clone();
+        
+        // Two dust outputs, the runestone, then change
+        assert_eq!(tx.output.len(), 4);
+        assert!(tx.output[2].script_pubkey.is_op_return());
+        let protostones = format_runestone(&tx).unwrap().into_protostones().unwrap();
+        assert_eq!(protostones.len(), 2);
+        for (index, protostone) in protostones.iter().enumerate() {
+            assert_eq!(protostone.pointer, Some(index as u32));
+            assert_eq!(protostone.refund, Some(index as u32));
+        }
+        
+        // Every shadow vout is traced
+        let internal_txid = hex::encode(serialize(&tx.txid()));
+        for (index, status) in [(0, "success"), (1, "revert")] {
+            server.set_with_params("alkanes_trace", serde_json::json!([internal_txid, protostone_vout(&tx, index)]),
+                serde_json::json!([{ "event": "return", "data": { "status": status } }]));
+        }
+        let traces = constructor.trace_protostones(&tx).await.unwrap();
+        assert_eq!(traces[1][0].data["status"], "revert");
+        
+        let quote = constructor.quote_minting_transaction().await.unwrap();
+        assert_eq!(quote.dust, 2 * P2WPKH_DUST);
+        
+        let constructor = funded_constructor(&server, TransactionConfig { mint_count: max_count + 1, ..TransactionConfig::default() }).await;
+        let err = constructor.create_minting_transaction().await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<TransactionError>(), Some(TransactionError::TooManyMints { .. })));
+        
+        // Each mint is expected to pay while the cap isn't reached
+        let constructor = funded_constructor(&server, TransactionConfig { mint_count: 3, ..TransactionConfig::default() }).await;
+        server.set("alkanes_simulate", serde_json::json!({ "status": 0, "execution": { "alkanes": [{ "id": { "block": 2, "tx": 0 }, "value": 100 }] } }));
+        let outcome = constructor.simulate_mint().await.unwrap();
+        assert_eq!((outcome.payable_mints, outcome.expected_amount), (3, 300));
+        server.set("alkanes_simulate", serde_json::json!({ "status": 0, "execution": { "alkanes": [] } }));
+        let outcome = constructor.simulate_mint().await.unwrap();
+        assert_eq!((outcome.payable_mints, outcome.expected_amount), (0, 0));
+    }
+    
+    #[tokio::test]
+    async fn test_create_minting_transaction_is_funded() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = funded_constructor(&server, TransactionConfig { fee_rate: 2.0, ..TransactionConfig::default() }).await;
+        
+        let artifact = constructor.create_minting_transaction().await.unwrap();
+        // The test wallet is watch-only
+        assert!(matches!(artifact, MintArtifact::Psbt(_)));
+        let tx = artifact.transaction();
+        
+        // The inscribed output is never spent; the largest plain one suffices
+        assert_eq!(tx.input.len(), 1);
+        assert_eq!(tx.input[0].previous_output.txid.to_string(), "11".repeat(32));
+        assert_eq!(tx.output.len(), 3);
+        assert_eq!(tx.output[0].value, P2WPKH_DUST);
+        
+        let output_total: u64 = tx.output.iter().map(|output| output.value).sum();
+        assert_eq!(20_000 - output_total, (estimate_signed_vsize(tx, &[]) as f64 * 2.0).ceil() as u64);
+        
+        // The minted DIESEL and any refund go to the dust output
+        let protostones = format_runestone(tx).unwrap().into_protostones().unwrap();
+        assert_eq!((protostones[0].pointer, protostones[0].refund), (Some(0), Some(0)));
+    }
+    
+    #[test]
+    fn test_protostone_spec_validates_outputs() {
+        let dust = TxOut { value: P2WPKH_DUST, script_pubkey: ScriptBuf::from_bytes([vec![0x00, 0x14], vec![0; 20]].concat()) };
+        let runestone = TxOut { value: 0, script_pubkey: ProtostoneSpec::diesel_mint().encipher().unwrap() };
+        let outputs = vec![dust.clone(), runestone, dust];
+        
+        assert!(ProtostoneSpec::diesel_mint_to(2).validate_outputs(&outputs).is_ok());
+        assert!(ProtostoneSpec::diesel_mint_to(1).validate_outputs(&outputs).is_err());
+        assert!(ProtostoneSpec::diesel_mint_to(3).encipher_for(&outputs).is_err());
+        let refund_only = ProtostoneSpec { pointer: None, ..ProtostoneSpec::diesel_mint_to(5) };
+        assert!(refund_only.validate_outputs(&outputs).unwrap_err().to_string().contains("refund 5"));
+        
+        // The encoding carries the same pointer and refund
+        let script = ProtostoneSpec { refund: Some(2), ..ProtostoneSpec::diesel_mint() }.encipher_for(&outputs).unwrap();
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: Vec::new(),
+            output: vec![outputs[0].clone(), TxOut { value: 0, script_pubkey: script }, outputs[2].clone()],
         };
-        let wallet_manager = WalletManager::new(wallet_config).await.unwrap();
+        let protostones = format_runestone(&tx).unwrap().into_protostones().unwrap();
+        assert_eq!((protostones[0].pointer, protostones[0].refund), (Some(0), Some(2)));
+    }
+    
+    #[tokio::test]
+    async fn test_rare_sats_are_never_spent() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = funded_constructor(&server, TransactionConfig::default()).await;
+        // The largest UTXO holds the first sat of block 1 rather than an inscription
+        let rare = format!("{}:0", "33".repeat(32));
+        server.set_with_params("ord_output", serde_json::json!([rare]), serde_json::json!({
+            "inscriptions": [],
+            "runes": {},
+            "sat_ranges": [[4_999_999_000u64, 5_000_999_000u64]],
+        }));
+        let options = ProtostoneOptions { value: Some(22_000), ..ProtostoneOptions::default() };
         
-        // Create RPC client
-        let rpc_config = RpcConfig {
-            bitcoin_rpc_url: "http://localhost:18332".to_string(),
-            metashrew_rpc_url: "http://localhost:8080".to_string(),
+        let artifact = constructor.create_protostone_transaction(&ProtostoneSpec::diesel_mint(), &options).await.unwrap();
+        let mut inputs: Vec<String> = artifact.transaction().input.iter()
+            .map(|input| input.previous_output.to_string())
+            .collect();
+        inputs.sort();
+        assert_eq!(inputs, vec![format!("{}:0", "11".repeat(32)), format!("{}:1", "22".repeat(32))]);
+        
+        let constructor = funded_constructor(&server, TransactionConfig {
+            allow_ordinal_spend: true,
+            ..TransactionConfig::default()
+        }).await;
+        let artifact = constructor.create_protostone_transaction(&ProtostoneSpec::diesel_mint(), &options).await.unwrap();
+        assert_eq!(artifact.transaction().input.len(), 1);
+        assert_eq!(artifact.transaction().input[0].previous_output.to_string(), rare);
+    }
+    
+    #[tokio::test]
+    async fn test_create_minting_transaction_folds_dust_change() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = funded_constructor(&server, TransactionConfig { fee_rate: 2.0, ..TransactionConfig::default() }).await;
+        server.set("esplora_address::utxo", serde_json::json!([{ "txid": "22".repeat(32), "vout": 1, "value": 800 }]));
+        
+        let artifact = constructor.create_minting_transaction().await.unwrap();
+        let tx = artifact.transaction();
+        
+        // 800 sats leave less than dust after the fee, so there is no change
+        assert_eq!(tx.output.len(), 2);
+        assert_eq!(tx.output[0].value, P2WPKH_DUST);
+    }
+    
+    #[test]
+    fn test_dust_limit_for() {
+        let script = |bytes: Vec<u8>| ScriptBuf::from_bytes(bytes);
+        let p2wpkh = script([vec![0x00, 0x14], vec![0; 20]].concat());
+        let p2tr = script([vec![0x51, 0x20], vec![0; 32]].concat());
+        let p2wsh = script([vec![0x00, 0x20], vec![0; 32]].concat());
+        let p2pkh = script([vec![0x76, 0xa9, 0x14], vec![0; 20], vec![0x88, 0xac]].concat());
+        let p2sh = script([vec![0xa9, 0x14], vec![0; 20], vec![0x87]].concat());
+        
+        assert_eq!(dust_limit_for(&p2wpkh), P2WPKH_DUST);
+        assert_eq!(dust_limit_for(&p2tr), 330);
+        assert_eq!(dust_limit_for(&p2wsh), 330);
+        assert_eq!(dust_limit_for(&p2pkh), 546);
+        assert_eq!(dust_limit_for(&p2sh), 540);
+        assert_eq!(dust_limit_for(&Runestone::new_diesel().encipher().unwrap()), 0);
+        
+        // The thresholds scale with the fee rate, P2TR staying above P2WPKH
+        // for its longer program
+        assert_eq!(dust_value_for(&p2wpkh, 1.0), P2WPKH_DUST);
+        assert_eq!(dust_value_for(&p2wpkh, 10.0), 2_940);
+        assert_eq!(dust_value_for(&p2tr, 10.0), 3_300);
+        assert_eq!(dust_value_for(&p2tr, 0.5), 165);
+        assert_eq!(dust_value_for(&p2wpkh, 0.1), 30);
+        
+        // Bare 1-of-2 multisig, with no witness discount
+        let multisig = script([vec![0x51, 0x21], vec![2; 33], vec![0x21], vec![3; 33], vec![0x52, 0xae]].concat());
+        assert_eq!(dust_limit_for(&multisig), 3 * (8 + 1 + 71 + 148));
+        assert!(dust_limit_for(&multisig) > dust_limit_for(&p2pkh));
+    }
+    
+    #[test]
+    fn test_validate_standardness() {
+        let p2wpkh = ScriptBuf::from_bytes([vec![0x00, 0x14], vec![0; 20]].concat());
+        let op_return = |size: usize| ScriptBuf::from_bytes([vec![0x6a, 0x4c, (size - 3) as u8], vec![0; size - 3]].concat());
+        let output = |value, script_pubkey: &ScriptBuf| TxOut { value, script_pubkey: script_pubkey.clone() };
+        let tx = |outputs: Vec<TxOut>| Transaction {
+            version: 2,
+            lock_time: bdk::bitcoin::absolute::LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: outputs,
         };
-        let rpc_client = RpcClient::new(rpc_config);
+        let prevouts = [output(10_000, &p2wpkh)];
+        let policy = StandardnessPolicy::default();
+        let violations = |tx: &Transaction, prevouts: &[TxOut]| validate_standardness(tx, prevouts, &policy).unwrap_err();
         
-        // Create transaction constructor
-        let config = TransactionConfig::default();
-        let constructor = TransactionConstructor::new(
-            Arc::new(wallet_manager),
-            Arc::new(rpc_client),
-            config,
+        let standard = tx(vec![output(P2WPKH_DUST, &p2wpkh), output(0, &op_return(83)), output(9_000, &p2wpkh)]);
+        assert_eq!(validate_standardness(&standard, &prevouts, &policy), Ok(()));
+        
+        assert_eq!(violations(&tx(vec![output(0, &op_return(84))]), &prevouts), vec![
+            StandardnessViolation::OpReturnTooLarge { vout: 0, size: 84, max_size: 83 },
+        ]);
+        assert_eq!(violations(&tx(vec![output(0, &op_return(10)), output(0, &op_return(10))]), &prevouts), vec![
+            StandardnessViolation::TooManyOpReturns { count: 2, max_count: 1 },
+        ]);
+        assert_eq!(violations(&tx(vec![output(P2WPKH_DUST - 1, &p2wpkh)]), &prevouts), vec![
+            StandardnessViolation::DustOutput { vout: 0, value: P2WPKH_DUST - 1, dust_limit: P2WPKH_DUST },
+        ]);
+        assert_eq!(violations(&tx(vec![output(10_000, &p2wpkh)]), &prevouts), vec![StandardnessViolation::ZeroFeeRate]);
+        
+        let mut oversized = tx(vec![output(1_000, &p2wpkh); 3_500]);
+        oversized.input = vec![TxIn::default(); 10];
+        let many_prevouts = vec![output(1_000_000, &p2wpkh); 10];
+        assert!(matches!(
+            violations(&oversized, &many_prevouts)[..],
+            [StandardnessViolation::TooLarge { max_vsize: 100_000, .. }]
+        ));
+        
+        // A looser policy accepts a bigger OP_RETURN and several of them
+        let loose = StandardnessPolicy { max_op_return_size: 100, max_op_returns: 2 };
+        let two_op_returns = tx(vec![output(0, &op_return(100)), output(0, &op_return(10)), output(9_000, &p2wpkh)]);
+        assert_eq!(validate_standardness(&two_op_returns, &prevouts, &loose), Ok(()));
+    }
+    
+    #[tokio::test]
+    async fn test_nonstandard_transactions_are_not_signed() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let config = |allow_nonstandard| TransactionConfig {
+            dust_policy: DustPolicy::Fixed(100),
+            allow_nonstandard,
+            ..TransactionConfig::default()
+        };
+        
+        let constructor = funded_constructor(&server, config(false)).await;
+        let err = constructor.create_minting_transaction().await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TransactionError>(),
+            Some(TransactionError::Nonstandard { violations }) if matches!(violations[..], [StandardnessViolation::DustOutput { vout: 0, value: 100, .. }])
+        ));
+        
+        let constructor = funded_constructor(&server, config(true)).await;
+        let artifact = constructor.create_minting_transaction().await.unwrap();
+        assert_eq!(artifact.transaction().output[0].value, 100);
+    }
+    
+    #[tokio::test]
+    async fn test_dust_policy() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = funded_constructor(&server, TransactionConfig::default()).await;
+        // A taproot recipient gets the smaller P2TR dust, not the wallet's P2WPKH one
+        let taproot = Address::p2tr(
+            &bdk::bitcoin::secp256k1::Secp256k1::verification_only(),
+            bdk::bitcoin::secp256k1::XOnlyPublicKey::from_str("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798").unwrap(),
+            None,
+            Network::Testnet,
+        ).to_string();
+        let options = ProtostoneOptions { to: Some(taproot), ..ProtostoneOptions::default() };
+        let artifact = constructor.create_protostone_transaction(&ProtostoneSpec::diesel_mint(), &options).await.unwrap();
+        assert_eq!(artifact.transaction().output[0].value, 330);
+        
+        let constructor = funded_constructor(&server, TransactionConfig {
+            dust_policy: DustPolicy::Fixed(1_000),
+            ..TransactionConfig::default()
+        }).await;
+        let artifact = constructor.create_minting_transaction().await.unwrap();
+        assert_eq!(artifact.transaction().output[0].value, 1_000);
+    }
+    
+    #[tokio::test]
+    async fn test_create_minting_transaction_is_signed() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = signing_constructor(&server, TransactionConfig { fee_rate: 2.0, ..TransactionConfig::default() }).await;
+        
+        let tx = match constructor.create_minting_transaction().await.unwrap() {
+            MintArtifact::Signed(tx) => tx,
+            other => panic!("expected a signed transaction, got {:?}", other),
+        };
+        
+        // Every input must satisfy the script of the wallet output it spends
+        let utxos = constructor.wallet_manager.list_utxos().await.unwrap();
+        let serialized = serialize(&tx);
+        for (index, input) in tx.input.iter().enumerate() {
+            let prevout = &utxos.iter().find(|utxo| utxo.outpoint == input.previous_output).unwrap().txout;
+            bitcoinconsensus::verify(prevout.script_pubkey.as_bytes(), prevout.value, &serialized, index)
+                .unwrap_or_else(|e| panic!("input {} failed verification: {:?}", index, e));
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_create_consolidation_transactions_splits_at_max_inputs() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = signing_constructor(&server, TransactionConfig { max_inputs: 100, ..TransactionConfig::default() }).await;
+        let utxos: Vec<serde_json::Value> = (0..250)
+            .map(|vout| serde_json::json!({ "txid": "55".repeat(32), "vout": vout, "value": 10_000 }))
+            .collect();
+        server.set("esplora_address::utxo", serde_json::Value::Array(utxos));
+        
+        let transactions = constructor.create_consolidation_transactions().await.unwrap();
+        let input_counts: Vec<usize> = transactions.iter().map(|tx| tx.input.len()).collect();
+        assert_eq!(input_counts, vec![100, 100, 52]);
+        
+        let mut prevouts: HashMap<OutPoint, TxOut> = constructor.wallet_manager.list_utxos().await.unwrap()
+            .into_iter()
+            .map(|utxo| (utxo.outpoint, utxo.txout))
+            .collect();
+        for (position, tx) in transactions.iter().enumerate() {
+            assert_eq!(tx.output.len(), 1);
+            // Each transaction spends the output of the one before it
+            if position > 0 {
+                assert_eq!(tx.input[0].previous_output, OutPoint { txid: transactions[position - 1].txid(), vout: 0 });
+            }
+            
+            let serialized = serialize(tx);
+            let mut total = 0;
+            for (index, input) in tx.input.iter().enumerate() {
+                let prevout = prevouts.remove(&input.previous_output).expect("input spends an unknown or spent output");
+                bitcoinconsensus::verify(prevout.script_pubkey.as_bytes(), prevout.value, &serialized, index)
+                    .unwrap_or_else(|e| panic!("input {} of transaction {} failed verification: {:?}", index, position, e));
+                total += prevout.value;
+            }
+            assert!(tx.output[0].value < total);
+            prevouts.insert(OutPoint { txid: tx.txid(), vout: 0 }, tx.output[0].clone());
+        }
+        // Only the final output is left
+        assert_eq!(prevouts.len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_create_token_sweep_transaction_chains_batches() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = signing_constructor(&server, TransactionConfig { max_inputs: 4, ..TransactionConfig::default() }).await;
+        let mut utxos: Vec<serde_json::Value> = (0..5)
+            .map(|vout| serde_json::json!({ "txid": "44".repeat(32), "vout": vout, "value": 546 }))
+            .collect();
+        utxos.push(serde_json::json!({ "txid": "11".repeat(32), "vout": 0, "value": 20_000 }));
+        server.set("esplora_address::utxo", serde_json::Value::Array(utxos));
+        for vout in 0..5 {
+            server.set_with_params("alkanes_protorunesbyoutpoint", serde_json::json!(["44".repeat(32), vout]),
+                serde_json::json!([{ "rune": { "id": { "block": "2", "tx": "0" } }, "balance": "1000" }]));
+        }
+        // Another rune's output is left alone
+        server.set_with_params("alkanes_protorunesbyoutpoint", serde_json::json!(["44".repeat(32), 4]),
+            serde_json::json!([{ "rune": { "id": { "block": "2", "tx": "1" } }, "balance": "1" }]));
+        
+        let transactions = constructor.create_token_sweep_transaction((2, 0)).await.unwrap();
+        // Three token inputs and the fee input, then the carried outputs and one more
+        let input_counts: Vec<usize> = transactions.iter().map(|tx| tx.input.len()).collect();
+        assert_eq!(input_counts, vec![4, 3]);
+        
+        let mut prevouts: HashMap<OutPoint, TxOut> = constructor.wallet_manager.list_utxos().await.unwrap()
+            .into_iter()
+            .map(|utxo| (utxo.outpoint, utxo.txout))
+            .collect();
+        for (position, tx) in transactions.iter().enumerate() {
+            assert_eq!(tx.output[0].value, P2WPKH_DUST);
+            assert!(tx.output[1].script_pubkey.is_op_return());
+            let protostones = crate::runestone_enhanced::format_runestone(tx).unwrap().into_protostones().unwrap();
+            assert!(protostones[0].edicts.is_empty());
+            assert_eq!(protostones[0].pointer, Some(0));
+            if position > 0 {
+                let previous = transactions[position - 1].txid();
+                assert_eq!(tx.input[0].previous_output, OutPoint { txid: previous, vout: 0 });
+                assert_eq!(tx.input[1].previous_output, OutPoint { txid: previous, vout: 2 });
+            }
+            
+            let serialized = serialize(tx);
+            for (index, input) in tx.input.iter().enumerate() {
+                let prevout = prevouts.remove(&input.previous_output).expect("input spends an unknown or spent output");
+                bitcoinconsensus::verify(prevout.script_pubkey.as_bytes(), prevout.value, &serialized, index)
+                    .unwrap_or_else(|e| panic!("input {} of transaction {} failed verification: {:?}", index, position, e));
+            }
+            for (vout, output) in tx.output.iter().enumerate() {
+                prevouts.insert(OutPoint { txid: tx.txid(), vout: vout as u32 }, output.clone());
+            }
+        }
+        
+        // Only the other rune's output is left unswept
+        let swept: Vec<OutPoint> = transactions.iter().flat_map(|tx| tx.input.iter().map(|input| input.previous_output)).collect();
+        assert!(!swept.contains(&OutPoint { txid: bdk::bitcoin::Txid::from_str(&"44".repeat(32)).unwrap(), vout: 4 }));
+    }
+    
+    #[tokio::test]
+    async fn test_fund_outputs_respects_limits() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let output = TxOut { value: 22_000, script_pubkey: ScriptBuf::from_bytes(vec![0; 22]) };
+        
+        // 20,000 + 5,000 sats cover the output, but not with a single input
+        let constructor = funded_constructor(&server, TransactionConfig { max_inputs: 1, ..TransactionConfig::default() }).await;
+        let err = constructor.fund_outputs(&[], vec![output.clone()], 1.0).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TransactionError>(),
+            Some(TransactionError::TooManyInputsRequired { max_inputs: 1, selected: 20_000, .. })
+        ));
+        
+        let constructor = funded_constructor(&server, TransactionConfig { max_inputs: 2, ..TransactionConfig::default() }).await;
+        let funded = constructor.fund_outputs(&[], vec![output.clone()], 1.0).await.unwrap();
+        assert_eq!(funded.tx.input.len(), 2);
+        
+        // The change output needs room too
+        let constructor = funded_constructor(&server, TransactionConfig { max_outputs: 2, ..TransactionConfig::default() }).await;
+        let err = constructor.fund_outputs(&[], vec![output.clone(), output], 1.0).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TransactionError>(),
+            Some(TransactionError::TooManyOutputs { count: 3, max_outputs: 2 })
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_mint_runs_stages_in_order() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = signing_constructor(&server, TransactionConfig { fee_rate: 2.0, ..TransactionConfig::default() }).await;
+        server.set("btc_sendrawtransaction", serde_json::json!("ab".repeat(32)));
+        let monitor = Arc::new(BlockMonitor::new(Arc::clone(&constructor.rpc_client), crate::monitor::BlockMonitorConfig::default()));
+        let constructor = constructor.with_monitor(Arc::clone(&monitor));
+        
+        // Not indexed yet, so there is no trace
+        let result = constructor.mint().await.unwrap();
+        assert_eq!(result.txid, "ab".repeat(32));
+        assert_eq!(result.status, MintStatus::Pending);
+        assert_eq!(result.trace, None);
+        assert_eq!(result.fee_rate, 2.0);
+        assert!(result.fee_sats >= result.vsize * 2);
+        assert!(!monitor.watch_transaction(&result.txid).await, "the mint should already be watched");
+        
+        // Construct, sign (which fetches the locktime height), broadcast, trace
+        let methods = server.methods();
+        let first = |method: &str| methods.iter().position(|m| m == method).unwrap();
+        assert!(first("esplora_address::utxo") < first("btc_getblockcount"));
+        assert!(first("btc_getblockcount") < first("btc_sendrawtransaction"));
+        assert!(first("btc_sendrawtransaction") < first("alkanes_trace"));
+        
+        server.set("alkanes_trace", serde_json::json!([{ "event": "return", "data": { "status": "success" } }]));
+        let result = constructor.mint().await.unwrap();
+        assert_eq!(result.status, MintStatus::Minted);
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["status"], "minted");
+        assert_eq!(serde_json::from_value::<MintResult>(json).unwrap(), result);
+        
+        server.set("alkanes_trace", serde_json::json!([{ "event": "return", "data": { "status": "revert" } }]));
+        assert_eq!(constructor.mint().await.unwrap().status, MintStatus::Reverted);
+        
+        // Failures name their stage
+        let stage = |err: anyhow::Error| match err.downcast_ref::<TransactionError>() {
+            Some(TransactionError::MintFailed { stage, .. }) => *stage,
+            other => panic!("unexpected error {:?}", other),
+        };
+        server.unset("btc_sendrawtransaction");
+        assert_eq!(stage(constructor.mint().await.unwrap_err()), MintStage::Broadcast);
+        server.unset("btc_getblockcount");
+        assert_eq!(stage(constructor.mint().await.unwrap_err()), MintStage::Sign);
+        server.set("esplora_address::utxo", serde_json::json!([]));
+        assert_eq!(stage(constructor.mint().await.unwrap_err()), MintStage::Construct);
+        
+        // A watch-only wallet can't get past signing
+        let watch_only = funded_constructor(&server, TransactionConfig::default()).await;
+        assert_eq!(stage(watch_only.mint().await.unwrap_err()), MintStage::Sign);
+    }
+    
+    #[tokio::test]
+    async fn test_escalate_keeps_runestone_and_raises_fee() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = signing_constructor(&server, TransactionConfig { fee_rate: 2.0, ..TransactionConfig::default() }).await;
+        let to_hex = |tx: &Transaction| serde_json::json!(hex::encode(serialize(tx)));
+        
+        // A real funding transaction, so the prevout can be looked up
+        let address = Address::from_str(&constructor.wallet_manager.get_address_at(0).await.unwrap()).unwrap().assume_checked();
+        let funding = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut { value: 20_000, script_pubkey: address.script_pubkey() }],
+        };
+        server.set("esplora_address::utxo", serde_json::json!([{ "txid": funding.txid().to_string(), "vout": 0, "value": 20_000 }]));
+        server.set("btc_sendrawtransaction", serde_json::json!("ab".repeat(32)));
+        server.set_with_params("esplora_tx::hex", serde_json::json!([funding.txid().to_string()]), to_hex(&funding));
+        
+        let mint_tx = match constructor.create_minting_transaction().await.unwrap() {
+            MintArtifact::Signed(tx) => tx,
+            MintArtifact::Psbt(_) => panic!("expected a signed mint"),
+        };
+        server.set_with_params("esplora_tx::hex", serde_json::json!([mint_tx.txid().to_string()]), to_hex(&mint_tx));
+        let mint_fee = 20_000 - mint_tx.output.iter().map(|output| output.value).sum::<u64>();
+        let mint = MintResult {
+            txid: mint_tx.txid().to_string(),
+            fee_sats: mint_fee,
+            vsize: mint_tx.vsize() as u64,
+            fee_rate: 2.0,
+            block_target: None,
+            broadcast_at: Utc::now(),
+            trace: None,
+            status: MintStatus::Pending,
+        };
+        let last_broadcast = || {
+            let (_, params) = server.calls().into_iter().filter(|(method, _)| method == "btc_sendrawtransaction").last().unwrap();
+            let bytes = hex::decode(params[0].as_str().unwrap()).unwrap();
+            bdk::bitcoin::consensus::deserialize::<Transaction>(&bytes).unwrap()
+        };
+        
+        // The replacement spends the same input and keeps the runestone intact
+        let bumped = constructor.escalate(&mint, EscalationStrategy::RbfBump { new_fee_rate: 5.0 }).await.unwrap();
+        let replacement = last_broadcast();
+        assert_eq!(replacement.input[0].previous_output, mint_tx.input[0].previous_output);
+        assert_eq!(replacement.output[..2], mint_tx.output[..2]);
+        let replacement_fee = 20_000 - replacement.output.iter().map(|output| output.value).sum::<u64>();
+        assert_eq!(bumped.fee_sats, replacement_fee);
+        assert!(replacement_fee > mint_fee);
+        assert!(replacement_fee >= (replacement.vsize() as f64 * 5.0) as u64);
+        
+        // Even at the same fee rate, the replacement pays strictly more
+        constructor.escalate(&mint, EscalationStrategy::RbfBump { new_fee_rate: 2.0 }).await.unwrap();
+        let replacement = last_broadcast();
+        assert_eq!(replacement.output[1], mint_tx.output[1]);
+        assert!(20_000 - replacement.output.iter().map(|output| output.value).sum::<u64>() > mint_fee);
+        
+        // The child spends the change and lifts the package to the target rate
+        let child_result = constructor.escalate(&mint, EscalationStrategy::Cpfp { package_fee_rate: 10.0 }).await.unwrap();
+        let child = last_broadcast();
+        assert_eq!(child.input[0].previous_output, OutPoint { txid: mint_tx.txid(), vout: 2 });
+        let package_vsize = mint_tx.vsize() + child.vsize();
+        assert!((mint_fee + child_result.fee_sats) as f64 >= package_vsize as f64 * 10.0);
+        assert_eq!(child.output[0].value, mint_tx.output[2].value - child_result.fee_sats);
+        
+        // A child can't pay more than the change holds
+        assert!(constructor.escalate(&mint, EscalationStrategy::Cpfp { package_fee_rate: 1_000.0 }).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_minting_sequence_and_locktime() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        
+        for (rbf, anti_fee_sniping) in [(true, true), (true, false), (false, true), (false, false)] {
+            let constructor = funded_constructor(&server, TransactionConfig {
+                rbf,
+                anti_fee_sniping,
+                ..TransactionConfig::default()
+            }).await;
+            let artifact = constructor.create_minting_transaction().await.unwrap();
+            let tx = artifact.transaction();
+            
+            let expected_sequence = if rbf { 0xFFFFFFFD } else { 0xFFFFFFFE };
+            assert!(tx.input.iter().all(|input| input.sequence.0 == expected_sequence), "rbf={}", rbf);
+            let lock_time = tx.lock_time.to_consensus_u32();
+            if anti_fee_sniping {
+                assert!((799_901..=800_000).contains(&lock_time), "locktime {}", lock_time);
+            } else {
+                assert_eq!(lock_time, 0);
+            }
+        }
+        
+        // Explicit values override rbf and anti_fee_sniping
+        let constructor = funded_constructor(&server, TransactionConfig {
+            lock_time: Some(LockTime::from_height(700_000).unwrap()),
+            sequence: Some(Sequence(0xFFFFFFF0)),
+            ..TransactionConfig::default()
+        }).await;
+        let tx = constructor.create_minting_transaction().await.unwrap().transaction().clone();
+        assert_eq!(tx.lock_time.to_consensus_u32(), 700_000);
+        assert!(tx.input.iter().all(|input| input.sequence == Sequence(0xFFFFFFF0)));
+        
+        // A final sequence disables the locktime: anti-fee-sniping is
+        // skipped, an explicit locktime is refused
+        let constructor = funded_constructor(&server, TransactionConfig {
+            sequence: Some(Sequence::MAX),
+            ..TransactionConfig::default()
+        }).await;
+        let tx = constructor.create_minting_transaction().await.unwrap().transaction().clone();
+        assert_eq!(tx.lock_time, LockTime::ZERO);
+        assert!(tx.input.iter().all(|input| input.sequence == Sequence::MAX));
+        
+        let constructor = funded_constructor(&server, TransactionConfig {
+            lock_time: Some(LockTime::from_height(700_000).unwrap()),
+            sequence: Some(Sequence::MAX),
+            ..TransactionConfig::default()
+        }).await;
+        let error = constructor.create_minting_transaction().await.unwrap_err();
+        assert_eq!(error.downcast_ref::<TransactionError>(), Some(&TransactionError::LockTimeDisabled { lock_time: 700_000 }));
+    }
+    
+    #[test]
+    fn test_anti_fee_sniping_height() {
+        let mut rng = rand::thread_rng();
+        let heights: Vec<u32> = (0..1_000).map(|_| anti_fee_sniping_height(800_000, &mut rng)).collect();
+        assert!(heights.iter().all(|height| (799_901..=800_000).contains(height)));
+        assert!(heights.iter().filter(|height| **height == 800_000).count() >= 800);
+        // Back-off never goes below genesis
+        assert!((0..1_000).all(|_| anti_fee_sniping_height(5, &mut rng) <= 5));
+    }
+    
+    #[tokio::test]
+    async fn test_create_transfer_transaction() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = funded_constructor(&server, TransactionConfig { fee_rate: 2.0, ..TransactionConfig::default() }).await;
+        let diesel_outpoint = serde_json::json!(["44".repeat(32), 2]);
+        server.set("esplora_address::utxo", serde_json::json!([
+            { "txid": "11".repeat(32), "vout": 0, "value": 20_000 },
+            { "txid": "44".repeat(32), "vout": 2, "value": 546 },
+        ]));
+        server.set_with_params("alkanes_protorunesbyoutpoint", diesel_outpoint,
+            serde_json::json!([{ "rune": { "id": { "block": "2", "tx": "0" } }, "balance": "1000" }]));
+        let holder = constructor.wallet_manager.get_address_at(0).await.unwrap();
+        server.set("alkanes_protorunesbyaddress", serde_json::json!([]));
+        server.set_with_params("alkanes_protorunesbyaddress", serde_json::json!([holder]),
+            serde_json::json!([{ "rune": { "id": { "block": "2", "tx": "0" } }, "balance": "1200" }]));
+        let recipient = constructor.wallet_manager.get_address_at(7).await.unwrap();
+        
+        // More than the indexer reports for the wallet fails before looking at UTXOs
+        let err = constructor.create_transfer_transaction((2, 0), 1201, &recipient).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TransactionError>(),
+            Some(TransactionError::InsufficientTokenBalance { needed: 1201, available: 1200, .. })
+        ));
+        assert!(!server.methods().iter().any(|m| m == "alkanes_protorunesbyoutpoint"));
+        
+        // 200 of it is on outputs that can't be spent on their own
+        let err = constructor.create_transfer_transaction((2, 0), 1001, &recipient).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TransactionError>(),
+            Some(TransactionError::InsufficientTokenBalance { needed: 1001, available: 1000, .. })
+        ));
+        assert!(!server.methods().iter().any(|m| m == "btc_getblockcount"));
+        
+        let artifact = constructor.create_transfer_transaction((2, 0), 300, &recipient).await.unwrap();
+        let tx = artifact.transaction();
+        
+        // The DIESEL input comes first, the plain input pays the fee
+        assert_eq!(tx.input.len(), 2);
+        assert_eq!(tx.input[0].previous_output.txid.to_string(), "44".repeat(32));
+        // Recipient, token change, OP_RETURN, BTC change
+        assert_eq!(tx.output.len(), 4);
+        assert_eq!(tx.output[0].script_pubkey, Address::from_str(&recipient).unwrap().assume_checked().script_pubkey());
+        assert_eq!(tx.output[1].value, P2WPKH_DUST);
+        assert!(tx.output[2].script_pubkey.is_op_return());
+        
+        let protostones = crate::runestone_enhanced::format_runestone(tx).unwrap().into_protostones().unwrap();
+        assert_eq!(protostones.len(), 1);
+        let edicts: Vec<(u128, u128, u128)> = protostones[0].edicts.iter()
+            .map(|edict| (edict.id.block, edict.amount, edict.output))
+            .collect();
+        assert_eq!(edicts, vec![(2, 300, 0), (2, 700, 1)]);
+        assert_eq!(protostones[0].pointer, Some(1));
+    }
+    
+    #[tokio::test]
+    async fn test_create_burn_transaction() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = funded_constructor(&server, TransactionConfig { fee_rate: 2.0, ..TransactionConfig::default() }).await;
+        server.set("esplora_address::utxo", serde_json::json!([
+            { "txid": "11".repeat(32), "vout": 0, "value": 20_000 },
+            { "txid": "44".repeat(32), "vout": 2, "value": 546 },
+        ]));
+        server.set_with_params("alkanes_protorunesbyoutpoint", serde_json::json!(["44".repeat(32), 2]),
+            serde_json::json!([{ "rune": { "id": { "block": "2", "tx": "0" } }, "balance": "1000" }]));
+        let holder = constructor.wallet_manager.get_address_at(0).await.unwrap();
+        server.set("alkanes_protorunesbyaddress", serde_json::json!([]));
+        server.set_with_params("alkanes_protorunesbyaddress", serde_json::json!([holder]),
+            serde_json::json!([{ "rune": { "id": { "block": "2", "tx": "0" } }, "balance": "1000" }]));
+        
+        let err = constructor.create_burn_transaction((2, 0), 1001).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TransactionError>(),
+            Some(TransactionError::InsufficientTokenBalance { needed: 1001, available: 1000, .. })
+        ));
+        
+        // A partial burn returns the rest to a wallet output
+        let artifact = constructor.create_burn_transaction((2, 0), 400).await.unwrap();
+        let tx = artifact.transaction();
+        assert_eq!(tx.input[0].previous_output.txid.to_string(), "44".repeat(32));
+        assert_eq!(tx.output[0].value, P2WPKH_DUST);
+        assert!(tx.output[1].script_pubkey.is_op_return());
+        let protostones = crate::runestone_enhanced::format_runestone(tx).unwrap().into_protostones().unwrap();
+        assert_eq!(protostones[0].burn, Some(1));
+        let edicts: Vec<(u128, u128, u128)> = protostones[0].edicts.iter()
+            .map(|edict| (edict.id.block, edict.amount, edict.output))
+            .collect();
+        assert_eq!(edicts, vec![(2, 400, 1)]);
+        assert_eq!(protostones[0].pointer, Some(0));
+        
+        // Burning the whole UTXO leaves no dust output behind
+        let artifact = constructor.create_burn_transaction((2, 0), 1000).await.unwrap();
+        let tx = artifact.transaction();
+        assert!(tx.output[0].script_pubkey.is_op_return());
+        let protostones = crate::runestone_enhanced::format_runestone(tx).unwrap().into_protostones().unwrap();
+        assert_eq!(protostones[0].edicts[0].amount, 1000);
+        assert_eq!(protostones[0].edicts[0].output, 0);
+        assert_eq!(protostones[0].pointer, None);
+    }
+    
+    #[tokio::test]
+    async fn test_create_split_and_join_transactions() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = funded_constructor(&server, TransactionConfig { fee_rate: 2.0, ..TransactionConfig::default() }).await;
+        server.set("esplora_address::utxo", serde_json::json!([
+            { "txid": "11".repeat(32), "vout": 0, "value": 20_000 },
+            { "txid": "44".repeat(32), "vout": 2, "value": 546 },
+            { "txid": "55".repeat(32), "vout": 1, "value": 546 },
+        ]));
+        server.set_with_params("alkanes_protorunesbyoutpoint", serde_json::json!(["44".repeat(32), 2]),
+            serde_json::json!([{ "rune": { "id": { "block": "2", "tx": "0" } }, "balance": "1000" }]));
+        server.set_with_params("alkanes_protorunesbyoutpoint", serde_json::json!(["55".repeat(32), 1]),
+            serde_json::json!([{ "rune": { "id": { "block": "2", "tx": "0" } }, "balance": "250" }]));
+        let holder = constructor.wallet_manager.get_address_at(0).await.unwrap();
+        server.set("alkanes_protorunesbyaddress", serde_json::json!([]));
+        server.set_with_params("alkanes_protorunesbyaddress", serde_json::json!([holder]),
+            serde_json::json!([{ "rune": { "id": { "block": "2", "tx": "0" } }, "balance": "1250" }]));
+        
+        // Splitting 700 out of the 1000 UTXO leaves its remaining 300 in a third output
+        let artifact = constructor.create_split_transaction((2, 0), &[400, 300]).await.unwrap();
+        let tx = artifact.transaction();
+        assert_eq!(tx.input[0].previous_output.txid.to_string(), "44".repeat(32));
+        assert!(tx.output[..3].iter().all(|output| output.value == P2WPKH_DUST));
+        assert!(tx.output[3].script_pubkey.is_op_return());
+        let protostones = crate::runestone_enhanced::format_runestone(tx).unwrap().into_protostones().unwrap();
+        let edicts: Vec<(u128, u128)> = protostones[0].edicts.iter().map(|edict| (edict.amount, edict.output)).collect();
+        assert_eq!(edicts, vec![(400, 0), (300, 1), (300, 2)]);
+        assert_eq!(protostones[0].message, encode_cellpack(&[4, 2, 0, 400, 300, 300]));
+        
+        // Joining spends both UTXOs holding the rune into output 0
+        let artifact = constructor.create_join_transaction((2, 0)).await.unwrap();
+        let tx = artifact.transaction();
+        let spent: Vec<String> = tx.input.iter().map(|input| input.previous_output.txid.to_string()).collect();
+        assert!(spent.contains(&"44".repeat(32)) && spent.contains(&"55".repeat(32)));
+        assert!(tx.output[1].script_pubkey.is_op_return());
+        let decoded = crate::runestone_enhanced::decode_runestone(tx).unwrap();
+        assert_eq!(decoded.protostones[0].operation["cellpack"]["join_details"], serde_json::json!({ "rune_id": "2:0", "amount": "1250" }));
+        
+        // Nothing to join with a single holding
+        let frozen = OutPoint::from_str(&format!("{}:1", "55".repeat(32))).unwrap();
+        let constructor = funded_constructor(&server, TransactionConfig { fee_rate: 2.0, frozen_outpoints: vec![frozen], ..TransactionConfig::default() }).await;
+        assert!(constructor.create_join_transaction((2, 0)).await.unwrap_err().to_string().contains("at least two"));
+    }
+    
+    #[tokio::test]
+    async fn test_create_execute_transaction() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = funded_constructor(&server, TransactionConfig { fee_rate: 2.0, ..TransactionConfig::default() }).await;
+        server.set("esplora_address::utxo", serde_json::json!([
+            { "txid": "11".repeat(32), "vout": 0, "value": 20_000 },
+            { "txid": "44".repeat(32), "vout": 2, "value": 546 },
+        ]));
+        server.set_with_params("alkanes_protorunesbyoutpoint", serde_json::json!(["44".repeat(32), 2]),
+            serde_json::json!([{ "rune": { "id": { "block": "2", "tx": "0" } }, "balance": "1000" }]));
+        let holder = constructor.wallet_manager.get_address_at(0).await.unwrap();
+        server.set("alkanes_protorunesbyaddress", serde_json::json!([]));
+        server.set_with_params("alkanes_protorunesbyaddress", serde_json::json!([holder]),
+            serde_json::json!([{ "rune": { "id": { "block": "2", "tx": "0" } }, "balance": "1000" }]));
+        
+        let err = constructor.create_execute_transaction((2, 1), vec![77], vec![((2, 0), 1001)], &ProtostoneOptions::default())
+            .await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TransactionError>(),
+            Some(TransactionError::InsufficientTokenBalance { needed: 1001, available: 1000, .. })
+        ));
+        
+        // Two amounts of the same rune become one edict
+        let incoming = vec![((2, 0), 600), ((2, 0), 100)];
+        let artifact = constructor.create_execute_transaction((2, 1), vec![77, 5], incoming, &ProtostoneOptions::default())
+            .await.unwrap();
+        let tx = artifact.transaction();
+        assert_eq!(tx.input[0].previous_output.txid.to_string(), "44".repeat(32));
+        assert!(tx.output[1].script_pubkey.is_op_return());
+        
+        let protostones = crate::runestone_enhanced::format_runestone(tx).unwrap().into_protostones().unwrap();
+        assert_eq!(protostones.len(), 1);
+        let edicts: Vec<(u128, u128, u128)> = protostones[0].edicts.iter()
+            .map(|edict| (edict.id.block, edict.amount, edict.output))
+            .collect();
+        assert_eq!(edicts, vec![(2, 700, protostone_vout(tx, 0) as u128)]);
+        assert_eq!(protostones[0].pointer, Some(0));
+        assert_eq!(protostones[0].refund, Some(0));
+        assert_eq!(crate::varint::decode_all(&protostones[0].message).unwrap(), vec![2, 1, 77, 5]);
+    }
+    
+    #[tokio::test]
+    async fn test_create_call_transaction() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = funded_constructor(&server, TransactionConfig { fee_rate: 2.0, ..TransactionConfig::default() }).await;
+        
+        // `alkanes call 2:1 300 5 --value-sat 10000`
+        let options = ProtostoneOptions { value: Some(10_000), ..ProtostoneOptions::default() };
+        let artifact = constructor.create_execute_transaction((2, 1), vec![300, 5], Vec::new(), &options).await.unwrap();
+        let tx = artifact.transaction();
+        
+        // The value sits on a wallet output, which also receives what the call returns
+        assert_eq!(tx.output.len(), 3);
+        assert_eq!(tx.output[0].value, 10_000);
+        assert!(constructor.wallet_manager.is_mine(&tx.output[0].script_pubkey).await.unwrap());
+        assert!(tx.output[1].script_pubkey.is_op_return());
+        assert!(tx.input.iter().all(|input| input.previous_output.txid.to_string() != "33".repeat(32)));
+        
+        let protostones = crate::runestone_enhanced::format_runestone(tx).unwrap().into_protostones().unwrap();
+        assert_eq!(protostones.len(), 1);
+        assert!(protostones[0].edicts.is_empty());
+        assert_eq!(protostones[0].pointer, Some(0));
+        assert_eq!(protostones[0].refund, Some(0));
+        assert_eq!(crate::varint::decode_all(&protostones[0].message).unwrap(), vec![2, 1, 300, 5]);
+    }
+    
+    #[tokio::test]
+    async fn test_create_protostone_transaction() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = funded_constructor(&server, TransactionConfig { fee_rate: 2.0, ..TransactionConfig::default() }).await;
+        let recipient = constructor.wallet_manager.get_address_at(7).await.unwrap();
+        let spec = ProtostoneSpec {
+            protocol_tag: 1,
+            cellpack: vec![4, 100, 3, u128::from(u64::MAX)],
+            edicts: vec![Edict { id: (2, 0), amount: 10, output: 0 }],
+            pointer: Some(0),
+            refund: Some(0),
+            burn: None,
+        };
+        let options = ProtostoneOptions { to: Some(recipient.clone()), value: Some(1_000) };
+        
+        let artifact = constructor.create_protostone_transaction(&spec, &options).await.unwrap();
+        let tx = artifact.transaction();
+        assert_eq!(tx.output[0].value, 1_000);
+        assert_eq!(tx.output[0].script_pubkey, Address::from_str(&recipient).unwrap().assume_checked().script_pubkey());
+        assert!(tx.output[1].script_pubkey.is_op_return());
+        
+        let protostones = crate::runestone_enhanced::format_runestone(tx).unwrap().into_protostones().unwrap();
+        assert_eq!(protostones.len(), 1);
+        assert_eq!(protostones[0].protocol_tag, 1);
+        // Decoding may leave padding from the u128 packing after the message
+        assert!(protostones[0].message.starts_with(&encode_cellpack(&spec.cellpack)));
+        assert_eq!(protostones[0].edicts.len(), 1);
+        assert_eq!((protostones[0].edicts[0].amount, protostones[0].edicts[0].output), (10, 0));
+        assert_eq!(protostones[0].pointer, Some(0));
+        assert_eq!(protostones[0].refund, Some(0));
+        
+        // The mint is the DIESEL spec
+        let mint = constructor.create_minting_transaction().await.unwrap();
+        let protostones = crate::runestone_enhanced::format_runestone(mint.transaction()).unwrap().into_protostones().unwrap();
+        assert!(protostones[0].message.starts_with(&encode_cellpack(&DIESEL_MINT_CELLPACK)));
+        assert_eq!(protostones[0].pointer, Some(0));
+        
+        // Sub-dust outputs are rejected
+        let options = ProtostoneOptions { value: Some(1), ..ProtostoneOptions::default() };
+        assert!(constructor.create_protostone_transaction(&spec, &options).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_verify_runestone() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = funded_constructor(&server, TransactionConfig { mint_count: 2, ..TransactionConfig::default() }).await;
+        let specs = constructor.minting_specs();
+        let tx = constructor.create_minting_transaction().await.unwrap().transaction().clone();
+        verify_runestone(&tx, &specs).unwrap();
+        
+        let corrupt = |script: ScriptBuf| {
+            let mut tx = tx.clone();
+            tx.output[2].script_pubkey = script;
+            tx
+        };
+        
+        // The second mint calls opcode 78 instead
+        let mut wrong_opcode = specs.clone();
+        wrong_opcode[1].cellpack = vec![2, 0, 78];
+        let err = constructor.verify_runestone(&corrupt(ProtostoneSpec::encipher_all(&wrong_opcode).unwrap()), &specs).unwrap_err();
+        let message = err.to_string();
+        assert!(matches!(err.downcast_ref::<TransactionError>(), Some(TransactionError::RunestoneMismatch { .. })));
+        assert!(message.contains("protostone 1:\n- tag 1, cellpack [2, 0, 77]"), "{}", message);
+        assert!(message.contains("\n+ tag 1, cellpack [2, 0, 78"), "{}", message);
+        assert!(!message.contains("protostone 0:"), "{}", message);
+        
+        // The second mint is missing
+        let err = constructor.verify_runestone(&corrupt(ProtostoneSpec::encipher_all(&specs[..1]).unwrap()), &specs).unwrap_err();
+        assert_eq!(err.downcast_ref::<TransactionError>(), Some(&TransactionError::RunestoneMismatch {
+            diff: "protostone 1:\n- tag 1, cellpack [2, 0, 77], edicts [], pointer Some(1), refund Some(1)\n+ (none)".to_string(),
+        }));
+        
+        // OP_RETURN OP_13 with a truncated varint as payload
+        let cenotaph = corrupt(ScriptBuf::from_bytes(vec![0x6a, 0x5d, 0x01, 0x80]));
+        let err = constructor.verify_runestone(&cenotaph, &specs).unwrap_err();
+        assert!(matches!(err.downcast_ref::<TransactionError>(), Some(TransactionError::RunestoneCenotaph { .. })));
+        
+        // Skipping the check lets anything through
+        let constructor = funded_constructor(&server, TransactionConfig {
+            skip_runestone_verification: true,
+            ..TransactionConfig::default()
+        }).await;
+        constructor.verify_runestone(&cenotaph, &specs).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_fee_limits() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = funded_constructor(&server, TransactionConfig { fee_rate: 1000.0, ..TransactionConfig::default() }).await;
+        let err = constructor.create_minting_transaction().await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<TransactionError>(),
+            Some(TransactionError::FeeRateTooHigh { max_fee_rate, .. }) if *max_fee_rate == DEFAULT_MAX_FEE_RATE
+        ));
+        
+        // Within the rate limit, but above the absolute one
+        let constructor = funded_constructor(&server, TransactionConfig {
+            fee_rate: 50.0,
+            max_absolute_fee: Some(5_000),
+            ..TransactionConfig::default()
+        }).await;
+        let err = constructor.create_minting_transaction().await.unwrap_err();
+        match err.downcast_ref::<TransactionError>() {
+            Some(TransactionError::FeeTooHigh { fee, max_fee: 5_000 }) => assert!(*fee > 5_000),
+            other => panic!("unexpected error {:?}", other),
+        }
+        assert!(err.to_string().contains("exceeds the maximum of 5000 sats"));
+        
+        // Both limits can be lifted
+        let constructor = funded_constructor(&server, TransactionConfig {
+            fee_rate: 50.0,
+            max_fee_rate: None,
+            max_absolute_fee: None,
+            ..TransactionConfig::default()
+        }).await;
+        assert!(constructor.create_minting_transaction().await.is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_broadcast_and_track_resubmits_evicted_transactions() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = funded_constructor(&server, TransactionConfig {
+            resubmit_interval: Duration::from_millis(10),
+            broadcast_timeout: Duration::from_secs(10),
+            ..TransactionConfig::default()
+        }).await;
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut { value: 1_000, script_pubkey: ScriptBuf::new() }],
+        };
+        let txid = tx.txid().to_string();
+        server.set("btc_sendrawtransaction", serde_json::json!(txid));
+        
+        // Unknown to the node until it has been resubmitted twice
+        server.set_error("esplora_tx::status", -5, "Transaction not found");
+        let constructor = Arc::new(constructor);
+        let tracking = tokio::spawn({
+            let constructor = Arc::clone(&constructor);
+            let tx = tx.clone();
+            async move { constructor.broadcast_and_track(&tx, true).await }
+        });
+        let sends = || server.methods().iter().filter(|method| *method == "btc_sendrawtransaction").count();
+        while sends() < 3 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        server.set("esplora_tx::status", serde_json::json!({ "confirmed": true, "block_height": 800_001 }));
+        assert_eq!(tracking.await.unwrap().unwrap(), txid);
+        
+        // Every submission is the same transaction
+        let hex = hex::encode(serialize(&tx));
+        assert!(server.calls().iter()
+            .filter(|(method, _)| method == "btc_sendrawtransaction")
+            .all(|(_, params)| params[0] == hex));
+        
+        // Without resubmission nothing is tracked
+        let before = server.calls().len();
+        assert_eq!(constructor.broadcast_and_track(&tx, false).await.unwrap(), txid);
+        assert_eq!(server.methods()[before..], ["btc_sendrawtransaction".to_string()]);
+        
+        // A transaction that never confirms times out
+        server.set("esplora_tx::status", serde_json::json!({ "confirmed": false }));
+        let impatient = funded_constructor(&server, TransactionConfig {
+            resubmit_interval: Duration::from_millis(10),
+            broadcast_timeout: Duration::from_millis(50),
+            ..TransactionConfig::default()
+        }).await;
+        assert!(impatient.broadcast_and_track(&tx, true).await.is_err());
+        
+        // Failed status checks are retried, but never resubmit
+        server.set_error("esplora_tx::status", -32603, "Internal error");
+        let checks = || server.methods().iter().filter(|method| *method == "esplora_tx::status").count();
+        let (sent, checked) = (sends(), checks());
+        assert!(impatient.broadcast_and_track(&tx, true).await.is_err());
+        assert_eq!(sends(), sent + 1);
+        assert!(checks() >= checked + 2);
+        
+        // The status is checked before giving up, however long the interval
+        server.set("esplora_tx::status", serde_json::json!({ "confirmed": true, "block_height": 800_001 }));
+        let slow = funded_constructor(&server, TransactionConfig {
+            resubmit_interval: Duration::from_secs(3600),
+            broadcast_timeout: Duration::from_millis(50),
+            ..TransactionConfig::default()
+        }).await;
+        assert_eq!(slow.broadcast_and_track(&tx, true).await.unwrap(), txid);
+    }
+    
+    #[tokio::test]
+    async fn test_dry_run_does_not_broadcast() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = funded_constructor(&server, TransactionConfig { fee_rate: 2.0, ..TransactionConfig::default() }).await;
+        server.set("btc_sendrawtransaction", serde_json::json!("00".repeat(32)));
+        
+        let artifact = constructor.create_minting_transaction().await.unwrap();
+        let dry_run = constructor.dry_run(&artifact).await.unwrap();
+        let tx = artifact.transaction();
+        
+        assert_eq!(dry_run.txid, tx.txid().to_string());
+        assert!(!dry_run.signed);
+        assert_eq!(dry_run.raw_hex, hex::encode(serialize(tx)));
+        assert_eq!(dry_run.inputs.len(), tx.input.len());
+        assert_eq!(dry_run.inputs[0].value, 20_000);
+        assert!(dry_run.inputs.iter().all(|input| input.address.is_some()));
+        let total_input: u64 = dry_run.inputs.iter().map(|input| input.value).sum();
+        let total_output: u64 = dry_run.outputs.iter().map(|output| output.value).sum();
+        assert_eq!(dry_run.fee, total_input - total_output);
+        assert!(dry_run.outputs[1].op_return);
+        assert_eq!(dry_run.outputs[1].address, None);
+        assert_eq!(dry_run.protostones.len(), 1);
+        assert_eq!(dry_run.protostones[0].cellpack, DIESEL_MINT_CELLPACK.to_vec());
+        assert!(dry_run.to_string().contains(&dry_run.txid));
+        
+        assert!(!server.methods().iter().any(|method| method == "btc_sendrawtransaction"));
+    }
+    
+    #[test]
+    fn test_broadcast_error_classification() {
+        // Reject reasons as bitcoind reports them and as esplora relays them
+        let cases = [
+            ("RPC error: txn-already-in-mempool (code: -27)", "already"),
+            ("sendrawtransaction RPC error: {\"code\":-27,\"message\":\"txn-already-known\"}", "already"),
+            ("RPC error: Transaction outputs already in utxo set (code: -27)", "already"),
+            ("RPC error: min relay fee not met, 110 < 141 (code: -26)", "fee"),
+            ("sendrawtransaction RPC error: {\"code\":-26,\"message\":\"mempool min fee not met, 150 < 1000\"}", "fee"),
+            ("RPC error: insufficient fee, rejecting replacement (code: -26)", "fee"),
+            ("RPC error: bad-txns-inputs-missingorspent (code: -25)", "inputs"),
+            ("sendrawtransaction RPC error: {\"code\":-25,\"message\":\"missing-inputs\"}", "inputs"),
+            ("RPC error: txn-mempool-conflict (code: -26)", "inputs"),
+            ("Failed to send RPC request: error sending request", "transport"),
+            ("RPC request failed with status: 502 Bad Gateway", "transport"),
+            ("RPC error: scriptpubkey (code: -26)", "rejected"),
+        ];
+        for (message, expected) in cases {
+            let kind = match BroadcastError::classify(message) {
+                BroadcastError::AlreadyKnown(_) => "already",
+                BroadcastError::InsufficientFee(_) => "fee",
+                BroadcastError::MissingInputs(_) => "inputs",
+                BroadcastError::Transport(_) => "transport",
+                BroadcastError::Rejected(_) => "rejected",
+            };
+            assert_eq!(kind, expected, "{}", message);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_send_transaction_classifies_node_errors() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = funded_constructor(&server, TransactionConfig::default()).await;
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut { value: 1_000, script_pubkey: ScriptBuf::new() }],
+        };
+        let send_error = |message: &str| {
+            server.set_error("btc_sendrawtransaction", -26, message);
+            let constructor = &constructor;
+            let tx = &tx;
+            async move { constructor.send_transaction(tx).await }
+        };
+        
+        // Already known counts as sent
+        assert_eq!(send_error("txn-already-in-mempool").await.unwrap(), tx.txid().to_string());
+        assert_eq!(send_error("Transaction already in block chain").await.unwrap(), tx.txid().to_string());
+        
+        let classify = |result: Result<String>| result.unwrap_err().downcast::<BroadcastError>().unwrap();
+        assert!(matches!(classify(send_error("min relay fee not met, 110 < 141").await), BroadcastError::InsufficientFee(_)));
+        assert!(matches!(classify(send_error("bad-txns-inputs-missingorspent").await), BroadcastError::MissingInputs(_)));
+        assert!(matches!(classify(send_error("dust").await), BroadcastError::Rejected(_)));
+        
+        // Transport failures are retried before giving up
+        let unreachable = TransactionConstructor::new(
+            Arc::clone(&constructor.wallet_manager),
+            Arc::new(RpcClient::new(RpcConfig {
+                bitcoin_rpc_url: "http://127.0.0.1:1/".to_string(),
+                ..server.config()
+            })),
+            TransactionConfig { broadcast_retries: 2, broadcast_retry_delay: Duration::from_millis(1), ..TransactionConfig::default() },
         );
+        assert!(matches!(classify(unreachable.send_transaction(&tx).await), BroadcastError::Transport(_)));
+    }
+    
+    #[tokio::test]
+    async fn test_mint_recovers_from_broadcast_rejections() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        server.set("metashrew_height", serde_json::json!(800_001));
+        let constructor = |bump_fee_on_rejection| signing_constructor(&server, TransactionConfig {
+            fee_rate: 2.0,
+            max_fee_rate: Some(5.0),
+            bump_fee_on_rejection,
+            ..TransactionConfig::default()
+        });
+        let sent_fee_rates = || -> Vec<f64> {
+            server.calls().into_iter()
+                .filter(|(method, _)| method == "btc_sendrawtransaction")
+                .map(|(_, params)| {
+                    let tx: Transaction = bdk::bitcoin::consensus::deserialize(&hex::decode(params[0].as_str().unwrap()).unwrap()).unwrap();
+                    let output: u64 = tx.output.iter().map(|output| output.value).sum();
+                    (20_000 - output) as f64 / tx.vsize() as f64
+                })
+                .collect()
+        };
         
-        // Verify constructor was created successfully
-        assert_eq!(constructor.config.network, Network::Testnet);
+        // Fee rejections are final unless bumping is enabled
+        server.set_error("btc_sendrawtransaction", -26, "min relay fee not met, 300 < 1000");
+        assert!(constructor(false).await.mint().await.is_err());
+        assert_eq!(sent_fee_rates().len(), 1);
+        
+        // 2 -> 3 -> 4.5 sat/vB, then 6.75 would exceed the limit
+        assert!(constructor(true).await.mint().await.is_err());
+        let rates = sent_fee_rates();
+        assert_eq!(rates.len(), 4);
+        assert!(rates[1] < rates[2] && rates[2] < rates[3], "{:?}", rates);
+        assert!(rates[3] <= 5.0);
+        
+        // Missing inputs trigger one wallet sync and rebuild
+        server.set_error("btc_sendrawtransaction", -25, "bad-txns-inputs-missingorspent");
+        let rebuilding = constructor(false).await;
+        let before = server.methods().len();
+        assert!(rebuilding.mint().await.is_err());
+        let methods = server.methods()[before..].to_vec();
+        assert_eq!(methods.iter().filter(|method| *method == "btc_sendrawtransaction").count(), 2);
+        assert!(methods.contains(&"metashrew_height".to_string()));
+    }
+    
+    #[tokio::test]
+    async fn test_dust_recipient() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let secp = bdk::bitcoin::secp256k1::Secp256k1::new();
+        let key = bdk::bitcoin::secp256k1::KeyPair::from_seckey_slice(&secp, &[1u8; 32]).unwrap().x_only_public_key().0;
+        let taproot = |network| Address::p2tr(&secp, key, None, network).to_string();
+        
+        // The wallet's own receive and change addresses
+        let external = funded_constructor(&server, TransactionConfig::default()).await;
+        let tx = external.create_minting_transaction().await.unwrap().transaction().clone();
+        let index = external.wallet_manager.get_last_revealed_index().await.unwrap();
+        let receive = Address::from_str(&external.wallet_manager.get_address_at(index).await.unwrap()).unwrap().assume_checked();
+        assert_eq!(tx.output[0].script_pubkey, receive.script_pubkey());
+        
+        let internal = funded_constructor(&server, TransactionConfig {
+            dust_recipient: DustRecipient::NewInternal,
+            ..TransactionConfig::default()
+        }).await;
+        let tx = internal.create_minting_transaction().await.unwrap().transaction().clone();
+        assert!(internal.wallet_manager.is_mine(&tx.output[0].script_pubkey).await.unwrap());
+        let revealed = internal.wallet_manager.get_last_revealed_index().await.unwrap_or(0);
+        for index in 0..=revealed {
+            let receive = Address::from_str(&internal.wallet_manager.get_address_at(index).await.unwrap()).unwrap().assume_checked();
+            assert_ne!(tx.output[0].script_pubkey, receive.script_pubkey());
+        }
+        
+        // A fixed address gets every dust output, each its protostone's pointer
+        let fixed = DustRecipient::fixed(&taproot(Network::Testnet), Network::Testnet).unwrap();
+        let fixed_script = match &fixed {
+            DustRecipient::Fixed(address) => address.script_pubkey(),
+            other => panic!("expected a fixed recipient, got {:?}", other),
+        };
+        let constructor = funded_constructor(&server, TransactionConfig {
+            dust_recipient: fixed.clone(),
+            mint_count: 2,
+            ..TransactionConfig::default()
+        }).await;
+        let tx = constructor.create_minting_transaction().await.unwrap().transaction().clone();
+        assert_eq!(tx.output[0].script_pubkey, fixed_script);
+        assert_eq!(tx.output[1].script_pubkey, fixed_script);
+        assert_eq!(tx.output[0].value, dust_limit_for(&fixed_script));
+        let pointers: Vec<Option<u32>> = format_runestone(&tx).unwrap().into_protostones().unwrap().iter().map(|protostone| protostone.pointer).collect();
+        assert_eq!(pointers, vec![Some(0), Some(1)]);
+        
+        // Addresses on another network are rejected, up front or when minting
+        assert!(DustRecipient::fixed(&taproot(Network::Bitcoin), Network::Testnet).is_err());
+        assert!(DustRecipient::fixed("not-an-address", Network::Testnet).is_err());
+        let mainnet = Address::from_str(&taproot(Network::Bitcoin)).unwrap().assume_checked();
+        let mismatched = funded_constructor(&server, TransactionConfig {
+            dust_recipient: DustRecipient::Fixed(mainnet),
+            ..TransactionConfig::default()
+        }).await;
+        assert!(mismatched.create_minting_transaction().await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_create_minting_transaction_insufficient_funds() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let frozen = OutPoint { txid: bdk::bitcoin::Txid::from_str(&"11".repeat(32)).unwrap(), vout: 0 };
+        let constructor = funded_constructor(&server, TransactionConfig {
+            fee_rate: 200.0,
+            frozen_outpoints: vec![frozen],
+            ..TransactionConfig::default()
+        }).await;
+        
+        let err = constructor.create_minting_transaction().await.unwrap_err();
+        match err.downcast_ref::<TransactionError>() {
+            Some(TransactionError::InsufficientFunds { needed, available }) => {
+                assert_eq!(*available, 5_000);
+                assert!(*needed > *available);
+            },
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+    
+    #[test]
+    fn test_diesel_block_reward() {
+        assert_eq!(diesel_block_reward(0), 5_000_000_000);
+        assert_eq!(diesel_block_reward(209_999), 5_000_000_000);
+        assert_eq!(diesel_block_reward(210_000), 2_500_000_000);
+        assert_eq!(diesel_block_reward(630_000), 625_000_000);
+        assert_eq!(diesel_block_reward(840_000), 312_500_000);
+        assert_eq!(diesel_block_reward(1_049_999), 312_500_000);
+        assert_eq!(diesel_block_reward(1_050_000), 156_250_000);
+        // The last non-zero epoch pays a single unit
+        assert_eq!(diesel_block_reward(32 * 210_000), 1);
+        assert_eq!(diesel_block_reward(33 * 210_000), 0);
+        assert_eq!(diesel_block_reward(u64::MAX), 0);
+    }
+    
+    #[test]
+    fn test_profitability_report() {
+        let price = DieselPrice { sats_per_diesel: 1_000.0 };
+        // 3.125 DIESEL at 1,000 sats each against a 2,000 sat fee
+        let report = ProfitabilityReport::new(312_500_000, false, 2_000, Some(price));
+        assert_eq!(report.expected_value_sats, Some(3_125));
+        assert_eq!(report.expected_profit_sats, Some(1_125));
+        assert!(report.profitable);
+        
+        let report = ProfitabilityReport::new(312_500_000, true, 4_000, Some(price));
+        assert_eq!(report.expected_profit_sats, Some(-875));
+        assert!(!report.profitable);
+        
+        // Without a price only a payout can be checked
+        assert!(ProfitabilityReport::new(1, true, 4_000, None).profitable);
+        assert!(!ProfitabilityReport::new(0, true, 4_000, None).profitable);
+    }
+    
+    #[tokio::test]
+    async fn test_estimate_profitability_falls_back_to_halving_schedule() {
+        let server = crate::rpc::mock::MockRpcServer::start().await;
+        let constructor = funded_constructor(&server, TransactionConfig { mint_count: 2, ..TransactionConfig::default() }).await;
+        server.set("btc_getblockcount", serde_json::json!(839_999));
+        let quote = constructor.quote_minting_transaction().await.unwrap();
+        
+        // No simulation available, so block 840,000's reward for both mints
+        let report = constructor.estimate_profitability(&quote, None).await.unwrap();
+        assert!(!report.simulated);
+        assert_eq!(report.expected_amount, 625_000_000);
+        assert_eq!(report.cost_sats, quote.fee);
+    }
+    
+    #[test]
+    fn test_estimate_signed_vsize() {
+        let tx = Transaction {
+            version: 2,
+            lock_time: bdk::bitcoin::absolute::LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut { value: 546, script_pubkey: ScriptBuf::from_bytes(vec![0; 22]) }],
+        };
+        // 1-in 1-out P2WPKH spend
+        assert_eq!(estimate_signed_vsize(&tx, &[]), 110);
+    }
+    
+    #[test]
+    fn test_estimate_signed_vsize_matches_signed_fixture() {
+        use bdk::bitcoin::key::TapTweak;
+        use bdk::bitcoin::secp256k1::{KeyPair, Message, Secp256k1, SecretKey};
+        use bdk::bitcoin::sighash::{EcdsaSighashType, Prevouts, SighashCache, TapSighashType};
+        use bdk::bitcoin::PublicKey;
+        
+        let secp = Secp256k1::new();
+        let secret = SecretKey::from_slice(&[0x11; 32]).unwrap();
+        let public = PublicKey::new(secret.public_key(&secp));
+        let keypair = KeyPair::from_secret_key(&secp, &secret);
+        let (internal_key, _) = keypair.x_only_public_key();
+        
+        let wpkh_script = ScriptBuf::new_v0_p2wpkh(&public.wpubkey_hash().unwrap());
+        let prevouts = vec![
+            TxOut { value: 50_000, script_pubkey: wpkh_script.clone() },
+            TxOut { value: 50_000, script_pubkey: ScriptBuf::new_v1_p2tr(&secp, internal_key, None) },
+        ];
+        let txid = bdk::bitcoin::Txid::from_str(&"44".repeat(32)).unwrap();
+        let tx = Transaction {
+            version: 2,
+            lock_time: bdk::bitcoin::absolute::LockTime::ZERO,
+            input: (0..2).map(|vout| TxIn {
+                previous_output: OutPoint { txid, vout },
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            }).collect(),
+            output: vec![
+                TxOut { value: 546, script_pubkey: wpkh_script.clone() },
+                TxOut { value: 0, script_pubkey: Runestone::new_diesel().encipher().unwrap() },
+                TxOut { value: 98_000, script_pubkey: wpkh_script.clone() },
+            ],
+        };
+        let estimated = estimate_signed_vsize(&tx, &prevouts);
+        
+        // Sign both inputs for real
+        let mut cache = SighashCache::new(&tx);
+        let script_code = wpkh_script.p2wpkh_script_code().unwrap();
+        let wpkh_sighash = cache.segwit_signature_hash(0, &script_code, 50_000, EcdsaSighashType::All).unwrap();
+        let mut ecdsa_signature = secp.sign_ecdsa(&Message::from_slice(wpkh_sighash.as_ref()).unwrap(), &secret)
+            .serialize_der()
+            .to_vec();
+        ecdsa_signature.push(EcdsaSighashType::All as u8);
+        let tr_sighash = cache.taproot_key_spend_signature_hash(1, &Prevouts::All(&prevouts), TapSighashType::Default).unwrap();
+        let tweaked = keypair.tap_tweak(&secp, None).to_inner();
+        let schnorr_signature = secp.sign_schnorr_no_aux_rand(&Message::from_slice(tr_sighash.as_ref()).unwrap(), &tweaked);
+        
+        let mut signed = tx.clone();
+        signed.input[0].witness = Witness::from_slice(&[ecdsa_signature, public.to_bytes()]);
+        signed.input[1].witness = Witness::from_slice(&[schnorr_signature.as_ref().to_vec()]);
+        
+        // vsize from the serialized sizes: base bytes weigh 4, witness bytes 1
+        let base_size = bdk::bitcoin::consensus::encode::serialize(&tx).len() as u64;
+        let total_size = bdk::bitcoin::consensus::encode::serialize(&signed).len() as u64;
+        let actual = (base_size * 3 + total_size + 3) / 4;
+        
+        // Placeholders are worst case, so the estimate may only be slightly high
+        assert!(estimated >= actual, "estimated {} vB, signed {} vB", estimated, actual);
+        assert!(estimated - actual <= 2, "estimated {} vB, signed {} vB", estimated, actual);
+    }
+    
+    #[test]
+    fn test_fee_rate_for_target() {
+        let histogram = vec![(50.0, 400_000), (20.0, 700_000), (5.0, 1_500_000)];
+        
+        // The first block fills up inside the 20 sat/vB bucket
+        assert_eq!(fee_rate_for_target(&histogram, 1), 21.0);
+        // Two blocks fill up inside the 5 sat/vB bucket
+        assert_eq!(fee_rate_for_target(&histogram, 2), 6.0);
+        // The whole mempool fits in three blocks
+        assert_eq!(fee_rate_for_target(&histogram, 3), MIN_FEE_RATE);
+        assert_eq!(fee_rate_for_target(&[], 1), MIN_FEE_RATE);
     }
 }
@@ -0,0 +1,150 @@
+//! LEB128 varints
+//!
+//! Runestone payloads, protostone messages and event frames all encode
+//! their integers as unsigned LEB128: seven bits per byte, low bits first,
+//! with the high bit set on every byte but the last. Values are u128, so an
+//! encoding takes at most `MAX_LENGTH` bytes, of which the last may only
+//! carry two bits.
+
+use anyhow::{anyhow, Context, Result};
+
+/// Longest encoding of a u128
+pub const MAX_LENGTH: usize = 19;
+
+/// Encode a u128 as a varint
+pub fn encode(value: u128) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(MAX_LENGTH);
+    encode_to_vec(value, &mut bytes);
+    bytes
+}
+
+/// Append the varint encoding of a u128 to `vec`
+pub fn encode_to_vec(mut value: u128, vec: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            vec.push(byte);
+            return;
+        }
+        vec.push(byte | 0x80);
+    }
+}
+
+/// Decode the varint at the start of `bytes`
+///
+/// Returns the value and the number of bytes it took. Fails if `bytes` ends
+/// before a byte without the continuation bit, or if the value doesn't fit
+/// in a u128.
+pub fn decode(bytes: &[u8]) -> Result<(u128, usize)> {
+    let mut value: u128 = 0;
+    for (index, byte) in bytes.iter().enumerate() {
+        let shift = 7 * index;
+        // Only the low two bits of the 19th byte fit in a u128
+        if index == MAX_LENGTH || (index == MAX_LENGTH - 1 && byte & 0x7f > 0b11) {
+            return Err(anyhow!("Varint too large"));
+        }
+        value |= u128::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok((value, index + 1));
+        }
+    }
+    Err(anyhow!("Truncated varint"))
+}
+
+/// Decode a payload that is a sequence of varints
+///
+/// Every byte must belong to a varint; errors name the offset of the varint
+/// that failed.
+pub fn decode_all(payload: &[u8]) -> Result<Vec<u128>> {
+    let mut values = Vec::new();
+    let mut position = 0;
+    while position < payload.len() {
+        let (value, length) = decode(&payload[position..])
+            .with_context(|| format!("Failed to decode varint at position {}", position))?;
+        values.push(value);
+        position += length;
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    /// A random value of random bit width, so every encoded length comes up
+    fn random_value(rng: &mut impl Rng) -> u128 {
+        let bits = rng.gen_range(0..=128);
+        match bits {
+            0 => 0,
+            128 => rng.gen(),
+            _ => rng.gen::<u128>() & ((1u128 << bits) - 1),
+        }
+    }
+
+    #[test]
+    fn test_known_encodings() {
+        assert_eq!(encode(0), vec![0x00]);
+        assert_eq!(encode(127), vec![0x7f]);
+        assert_eq!(encode(128), vec![0x80, 0x01]);
+        assert_eq!(encode(16383), vec![0xff, 0x7f]);
+        assert_eq!(encode(u128::MAX).len(), MAX_LENGTH);
+        assert_eq!(*encode(u128::MAX).last().unwrap(), 0b11);
+        assert_eq!(decode(&[0x80, 0x01, 0xff]).unwrap(), (128, 2));
+    }
+
+    #[test]
+    fn test_round_trip_property() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..10_000 {
+            let value = random_value(&mut rng);
+            let bytes = encode(value);
+
+            // Shortest encoding: one byte per started group of seven bits
+            let bits = 128 - value.leading_zeros() as usize;
+            assert_eq!(bytes.len(), bits.div_ceil(7).max(1), "value {}", value);
+            assert_eq!(decode(&bytes).unwrap(), (value, bytes.len()));
+
+            // Trailing bytes are left alone, and every strict prefix is truncated
+            let mut extended = bytes.clone();
+            extended.extend_from_slice(&[0xff, 0x00]);
+            assert_eq!(decode(&extended).unwrap(), (value, bytes.len()));
+            for end in 0..bytes.len() {
+                assert_eq!(decode(&bytes[..end]).unwrap_err().to_string(), "Truncated varint");
+            }
+        }
+    }
+
+    #[test]
+    fn test_sequence_property() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1_000 {
+            let values: Vec<u128> = (0..rng.gen_range(0..20)).map(|_| random_value(&mut rng)).collect();
+            let mut payload = Vec::new();
+            for value in &values {
+                encode_to_vec(*value, &mut payload);
+            }
+            assert_eq!(decode_all(&payload).unwrap(), values);
+
+            // A dangling continuation byte fails at its own offset
+            let position = payload.len();
+            payload.push(0x80);
+            let error = format!("{:#}", decode_all(&payload).unwrap_err());
+            assert_eq!(error, format!("Failed to decode varint at position {}: Truncated varint", position));
+        }
+    }
+
+    #[test]
+    fn test_oversized() {
+        // 19 bytes carrying more than 128 bits
+        let mut oversized = vec![0xff; MAX_LENGTH - 1];
+        oversized.push(0x04);
+        assert_eq!(decode(&oversized).unwrap_err().to_string(), "Varint too large");
+
+        // A 20th byte, even a zero one
+        let mut overlong = vec![0x80; MAX_LENGTH];
+        overlong.push(0x00);
+        assert_eq!(decode(&overlong).unwrap_err().to_string(), "Varint too large");
+    }
+}
@@ -1,20 +1,45 @@
 //! Custom Esplora backend implementation using Sandshrew RPC
 //!
 //! This module provides utility functions for interacting with the Sandshrew RPC API
-//! for blockchain data.
+//! for blockchain data. The backend can also talk to a standard esplora (or
+//! mempool.space) REST API directly.
 
 use anyhow::{Context, Result, anyhow};
 use bdk::bitcoin::{Transaction, Txid};
 use log::{debug, info};
+use reqwest::{Client, RequestBuilder};
+use serde_json::Value;
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::rpc::RpcClient;
 
+/// Number of confirmed transactions per page of esplora address history
+const CHAIN_PAGE_SIZE: usize = 25;
+
+/// Maximum number of history pages fetched for a single address
+const MAX_HISTORY_PAGES: usize = 1000;
+
+/// How the backend reaches esplora
+#[derive(Clone)]
+enum Transport {
+    /// `esplora_*` methods over Sandshrew JSON-RPC
+    Rpc(Arc<RpcClient>),
+    /// A standard esplora REST API
+    Rest {
+        /// HTTP client
+        client: Client,
+        /// Base URL without a trailing slash, e.g. `https://mempool.space/api`
+        base_url: String,
+    },
+}
+
 /// Custom Esplora backend using Sandshrew RPC
 #[derive(Clone)]
 pub struct SandshrewEsploraBackend {
-    /// RPC client for Sandshrew API
-    pub rpc_client: Arc<RpcClient>,
+    /// Transport to the esplora API
+    transport: Transport,
 }
 
 impl SandshrewEsploraBackend {
@@ -22,20 +47,63 @@ impl SandshrewEsploraBackend {
     pub fn new(rpc_client: Arc<RpcClient>) -> Self {
         info!("Creating Sandshrew Esplora backend");
         Self {
-            rpc_client,
+            transport: Transport::Rpc(rpc_client),
+        }
+    }
+    
+    /// Create a backend talking REST to a standard esplora instance
+    ///
+    /// `base_url` is the API root, e.g. `https://mempool.space/api`.
+    pub fn with_url(base_url: String) -> Self {
+        info!("Creating esplora REST backend for {}", base_url);
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .expect("Failed to create HTTP client");
+        
+        Self {
+            transport: Transport::Rest {
+                client,
+                base_url: base_url.trim_end_matches('/').to_string(),
+            },
         }
     }
     
+    /// Call `method` over JSON-RPC, or GET `path` from the REST API
+    async fn get_json(&self, method: &str, params: Value, path: String) -> Result<Value> {
+        match &self.transport {
+            Transport::Rpc(rpc_client) => rpc_client._call(method, params).await,
+            Transport::Rest { client, base_url } => {
+                let body = Self::send(client.get(format!("{}{}", base_url, path))).await?;
+                serde_json::from_str(&body).with_context(|| format!("Invalid JSON from esplora {}", path))
+            },
+        }
+    }
+    
+    /// Send a REST request, returning the body of a successful response
+    async fn send(request: RequestBuilder) -> Result<String> {
+        let response = request.send().await.context("Esplora request failed")?;
+        let status = response.status();
+        let body = response.text().await.context("Failed to read esplora response")?;
+        
+        if !status.is_success() {
+            return Err(anyhow!("Esplora returned {}: {}", status, body.trim()));
+        }
+        Ok(body)
+    }
+    
     /// Get transaction details from Sandshrew RPC
     pub async fn get_transaction_details(&self, txid: &Txid) -> Result<Transaction> {
         debug!("Getting transaction details for {}", txid);
         
-        // Use the esplora_tx method from Sandshrew RPC
-        let tx_hex = self.rpc_client._call("esplora_tx::hex", serde_json::json!([txid.to_string()])).await?;
-        let tx_hex = tx_hex.as_str()
-            .ok_or_else(|| anyhow!("Transaction hex not found in response"))?;
+        let (client, base_url) = match &self.transport {
+            // The RPC client falls back to Bitcoin Core and caches the result
+            Transport::Rpc(rpc_client) => return rpc_client.get_transaction(&txid.to_string()).await,
+            Transport::Rest { client, base_url } => (client, base_url),
+        };
+        let tx_hex = Self::send(client.get(format!("{}/tx/{}/hex", base_url, txid))).await?;
         
-        let tx = hex::decode(tx_hex)
+        let tx = hex::decode(tx_hex.trim())
             .context("Failed to decode transaction hex")?;
         
         let transaction = bdk::bitcoin::consensus::deserialize(&tx)
@@ -49,7 +117,7 @@ impl SandshrewEsploraBackend {
         debug!("Getting UTXOs for address {}", address);
         
         // Use the esplora_address::utxo method from Sandshrew RPC
-        let utxos = self.rpc_client._call("esplora_address::utxo", serde_json::json!([address])).await?;
+        let utxos = self.get_json("esplora_address::utxo", serde_json::json!([address]), format!("/address/{}/utxo", address)).await?;
         
         Ok(utxos)
     }
@@ -59,17 +127,81 @@ impl SandshrewEsploraBackend {
         debug!("Getting transaction history for address {}", address);
         
         // Use the esplora_address::txs method from Sandshrew RPC
-        let txs = self.rpc_client._call("esplora_address::txs", serde_json::json!([address])).await?;
+        let txs = self.get_json("esplora_address::txs", serde_json::json!([address]), format!("/address/{}/txs", address)).await?;
         
         Ok(txs)
     }
     
+    /// Get the complete transaction history for an address
+    ///
+    /// The first page holds the address's mempool transactions plus its most
+    /// recent confirmed ones; older confirmed transactions are fetched with
+    /// esplora's `last_seen_txid` cursor until a short page is returned.
+    /// Fails if the cursor stops advancing or `MAX_HISTORY_PAGES` is reached
+    /// rather than returning a silently truncated history.
+    pub async fn get_address_transactions_all(&self, address: &str) -> Result<Vec<serde_json::Value>> {
+        debug!("Getting full transaction history for address {}", address);
+        
+        let first_page = self.get_address_transactions(address).await?;
+        let mut txs = first_page.as_array()
+            .ok_or_else(|| anyhow!("Invalid address transactions response"))?
+            .clone();
+        
+        let confirmed: Vec<&serde_json::Value> = txs.iter()
+            .filter(|tx| tx["status"]["confirmed"].as_bool().unwrap_or(false))
+            .collect();
+        if confirmed.len() < CHAIN_PAGE_SIZE {
+            return Ok(txs);
+        }
+        let mut cursor = Self::txid_of(confirmed[confirmed.len() - 1])?;
+        let mut seen: HashSet<String> = txs.iter()
+            .filter_map(|tx| tx["txid"].as_str().map(|s| s.to_string()))
+            .collect();
+        
+        for page_number in 1..MAX_HISTORY_PAGES {
+            let page = self.get_json(
+                "esplora_address::txs:chain",
+                serde_json::json!([address, cursor]),
+                format!("/address/{}/txs/chain/{}", address, cursor),
+            ).await?;
+            let page = page.as_array()
+                .ok_or_else(|| anyhow!("Invalid address transactions response on page {}", page_number))?;
+            
+            let next = match page.last() {
+                Some(tx) => Self::txid_of(tx)?,
+                None => return Ok(txs),
+            };
+            if next == cursor {
+                return Err(anyhow!("History cursor for {} stopped advancing at {}", address, cursor));
+            }
+            
+            txs.extend(page.iter().filter(|tx| {
+                tx["txid"].as_str().map_or(true, |txid| seen.insert(txid.to_string()))
+            }).cloned());
+            
+            if page.len() < CHAIN_PAGE_SIZE {
+                debug!("Fetched {} transactions for {} in {} pages", txs.len(), address, page_number + 1);
+                return Ok(txs);
+            }
+            cursor = next;
+        }
+        
+        Err(anyhow!("Transaction history for {} exceeds {} pages", address, MAX_HISTORY_PAGES))
+    }
+    
+    /// Get the txid of an esplora transaction object
+    fn txid_of(tx: &serde_json::Value) -> Result<String> {
+        tx["txid"].as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow!("Transaction without txid in history page"))
+    }
+    
     /// Get mempool transactions for an address
     pub async fn get_address_mempool_transactions(&self, address: &str) -> Result<serde_json::Value> {
         debug!("Getting mempool transactions for address {}", address);
         
         // Use the esplora_address::txs:mempool method from Sandshrew RPC
-        let txs = self.rpc_client._call("esplora_address::txs:mempool", serde_json::json!([address])).await?;
+        let txs = self.get_json("esplora_address::txs:mempool", serde_json::json!([address]), format!("/address/{}/txs/mempool", address)).await?;
         
         Ok(txs)
     }
@@ -78,9 +210,94 @@ impl SandshrewEsploraBackend {
     pub async fn broadcast_transaction(&self, tx_hex: &str) -> Result<serde_json::Value> {
         debug!("Broadcasting transaction");
         
-        // Use the esplora_broadcast method from Sandshrew RPC
-        let result = self.rpc_client._call("esplora_broadcast", serde_json::json!([tx_hex])).await?;
+        match &self.transport {
+            // Use the esplora_broadcast method from Sandshrew RPC
+            Transport::Rpc(rpc_client) => rpc_client._call("esplora_broadcast", serde_json::json!([tx_hex])).await,
+            // The REST API answers with the bare txid
+            Transport::Rest { client, base_url } => {
+                let txid = Self::send(client.post(format!("{}/tx", base_url)).body(tx_hex.to_string())).await?;
+                Ok(Value::String(txid.trim().to_string()))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::mock::MockRpcServer;
+    use serde_json::{json, Value};
+    
+    fn confirmed_txs(prefix: &str, count: usize) -> Value {
+        let txs: Vec<Value> = (0..count)
+            .map(|i| json!({ "txid": format!("{}{}", prefix, i), "status": { "confirmed": true } }))
+            .collect();
+        json!(txs)
+    }
+    
+    #[tokio::test]
+    async fn test_get_address_transactions_all_follows_cursor() {
+        let server = MockRpcServer::start().await;
+        let backend = SandshrewEsploraBackend::new(Arc::new(RpcClient::new(server.config())));
+        
+        let mut first_page = vec![json!({ "txid": "mempool0", "status": { "confirmed": false } })];
+        first_page.extend(confirmed_txs("a", CHAIN_PAGE_SIZE).as_array().unwrap().clone());
+        server.set("esplora_address::txs", json!(first_page));
+        server.set_with_params("esplora_address::txs:chain", json!(["bc1qaddr", "a24"]), confirmed_txs("b", CHAIN_PAGE_SIZE));
+        server.set_with_params("esplora_address::txs:chain", json!(["bc1qaddr", "b24"]), confirmed_txs("c", 3));
+        
+        let txs = backend.get_address_transactions_all("bc1qaddr").await.unwrap();
+        assert_eq!(txs.len(), 1 + CHAIN_PAGE_SIZE * 2 + 3);
+        assert_eq!(txs.last().unwrap()["txid"], "c2");
+    }
+    
+    #[tokio::test]
+    async fn test_get_address_transactions_all_detects_stuck_cursor() {
+        let server = MockRpcServer::start().await;
+        let backend = SandshrewEsploraBackend::new(Arc::new(RpcClient::new(server.config())));
+        
+        // The chain endpoint keeps returning the first page
+        server.set("esplora_address::txs", confirmed_txs("a", CHAIN_PAGE_SIZE));
+        server.set("esplora_address::txs:chain", confirmed_txs("a", CHAIN_PAGE_SIZE));
         
-        Ok(result)
+        let err = backend.get_address_transactions_all("bc1qaddr").await.unwrap_err();
+        assert!(err.to_string().contains("stopped advancing"));
     }
-}
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_rest_backend() {
+        use axum::extract::Path;
+        use axum::routing::{get, post};
+        use axum::Router;
+        
+        let tx = Transaction {
+            version: 2,
+            lock_time: bdk::bitcoin::absolute::LockTime::ZERO,
+            input: vec![bdk::bitcoin::TxIn::default()],
+            output: vec![bdk::bitcoin::TxOut { value: 546, script_pubkey: bdk::bitcoin::ScriptBuf::new() }],
+        };
+        let tx_hex = hex::encode(bdk::bitcoin::consensus::serialize(&tx));
+        let app = Router::new()
+            .route("/api/tx/:txid/hex", get(move || async move { tx_hex }))
+            .route("/api/address/:address/utxo", get(|Path(address): Path<String>| async move {
+                axum::Json(json!([{ "txid": "11".repeat(32), "vout": 0, "value": 1000, "address": address }]))
+            }))
+            .route("/api/tx", post(|body: String| async move { format!("txid-of-{}\n", body.len()) }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/api/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        let backend = SandshrewEsploraBackend::with_url(base_url);
+        
+        assert_eq!(backend.get_transaction_details(&tx.txid()).await.unwrap(), tx);
+        let utxos = backend.get_address_utxos("bc1qaddr").await.unwrap();
+        assert_eq!(utxos[0]["address"], "bc1qaddr");
+        assert_eq!(utxos[0]["value"], 1000);
+        assert_eq!(backend.broadcast_transaction("abcd").await.unwrap(), json!("txid-of-4"));
+        
+        // Errors carry the HTTP status
+        let err = backend.get_address_mempool_transactions("bc1qaddr").await.unwrap_err();
+        assert!(err.to_string().contains("404"));
+    }
+}
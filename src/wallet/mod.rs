@@ -5,21 +5,53 @@
 //! - UTXO tracking and selection
 //! - Transaction signing
 //! - Persistent wallet state
+//! - Backup export and import of key material
 
 mod esplora_backend;
 
-use anyhow::{Context, Result};
-use bdk::bitcoin::Network;
-use bdk::database::MemoryDatabase;
+use anyhow::{anyhow, Context, Result};
+use bdk::bitcoin::absolute::LockTime;
+use bdk::bitcoin::psbt::PartiallySignedTransaction;
+use bdk::bitcoin::sighash::{EcdsaSighashType, TapSighashType};
+use bdk::bitcoin::{Address, Network, OutPoint, Script, Sequence, Transaction, TxOut, Txid};
+use bdk::database::{Database, MemoryDatabase};
+use bdk::keys::bip39::Mnemonic;
+use bdk::keys::{DerivableKey, ExtendedKey};
+use bdk::wallet::tx_builder::TxOrdering;
+use bdk::wallet::signer::TapLeavesOptions;
 use bdk::wallet::AddressIndex;
-use bdk::{Wallet, SyncOptions};
+use bdk::{FeeRate, KeychainKind, LocalUtxo, SignOptions, TransactionDetails, Wallet, SyncOptions};
 use log::{debug, info, warn, error};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
 
-use crate::rpc::RpcClient;
-use self::esplora_backend::SandshrewEsploraBackend;
+use crate::alkane_id::AlkaneId;
+use crate::rpc::{parse_protorune_balances, ProtoruneBalance, RpcClient, SyncStatus};
+use crate::runestone::diesel_id;
+pub use self::esplora_backend::SandshrewEsploraBackend;
+
+/// Watch-only descriptor for receive addresses
+const DEFAULT_DESCRIPTOR: &str = "wpkh([c258d2e4/84h/1h/0h]tpubDDYkZojQFQjht8Tm4jsS3iuEmKjTiEGjG6KnuFNKKJb5A6ZUCUZKdvLdSDWofKi4ToRCwb9poe1XdqfUnP4jaJjCB2Zwv11ZLgSbnZSNecE/0/*)";
+
+/// Watch-only descriptor for change addresses
+const DEFAULT_CHANGE_DESCRIPTOR: &str = "wpkh([c258d2e4/84h/1h/0h]tpubDDYkZojQFQjht8Tm4jsS3iuEmKjTiEGjG6KnuFNKKJb5A6ZUCUZKdvLdSDWofKi4ToRCwb9poe1XdqfUnP4jaJjCB2Zwv11ZLgSbnZSNecE/1/*)";
+
+/// Blocks between block subsidy halvings
+pub(crate) const HALVING_INTERVAL: u64 = 210_000;
+
+/// Subsidy of the first halving epoch in satoshis
+pub(crate) const INITIAL_SUBSIDY: u64 = 50 * 100_000_000;
+
+/// Descriptors of a throwaway testnet key, for tests that need signatures
+#[cfg(test)]
+pub(crate) const TEST_SIGNING_DESCRIPTORS: (&str, &str) = (
+    "wpkh(tprv8ZgxMBicQKsPdwM88UJeaCWa2ibCYrqzgktxtcRSKJLbsdZwrpv4wbpy6uko9BoNZEdpfWGVj8j3wD4L9BnkkWNEvbLSgG3RUtV3RZQKusn/84h/1h/0h/0/*)",
+    "wpkh(tprv8ZgxMBicQKsPdwM88UJeaCWa2ibCYrqzgktxtcRSKJLbsdZwrpv4wbpy6uko9BoNZEdpfWGVj8j3wD4L9BnkkWNEvbLSgG3RUtV3RZQKusn/84h/1h/0h/1/*)",
+);
 
 /// Wallet configuration
 pub struct WalletConfig {
@@ -31,11 +63,173 @@ pub struct WalletConfig {
     pub bitcoin_rpc_url: String,
     /// Metashrew RPC URL
     pub metashrew_rpc_url: String,
+    /// Base URL of a standard esplora REST API to sync from, instead of the
+    /// `esplora_*` JSON-RPC methods
+    pub esplora_url: Option<String>,
+}
+
+/// Key material of a wallet, as kept in the wallet file
+///
+/// Deliberately not `Debug`, so the mnemonic and private descriptors can't
+/// end up in a log line.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletBackup {
+    /// Descriptor of receive addresses
+    pub descriptor: String,
+    /// Descriptor of change addresses, if the wallet has one
+    pub change_descriptor: Option<String>,
+    /// BIP 39 mnemonic the descriptors were derived from, if known
+    pub mnemonic: Option<String>,
+}
+
+impl WalletBackup {
+    /// Build a backup from a BIP 39 mnemonic or an output descriptor
+    ///
+    /// A mnemonic yields BIP 84 descriptors for `network`. For a descriptor
+    /// ending in `/0/*`, the change descriptor is the same with `/1/*`. The
+    /// result is checked to parse with keys for `network`.
+    pub fn from_material(material: &str, network: Network) -> Result<Self> {
+        let material = material.trim();
+        let backup = match Mnemonic::parse(material) {
+            Ok(mnemonic) => {
+                let xkey: ExtendedKey = mnemonic.clone().into_extended_key()
+                    .map_err(|e| anyhow!("Failed to derive a key from the mnemonic: {}", e))?;
+                let xprv = xkey.into_xprv(network)
+                    .ok_or_else(|| anyhow!("Mnemonic did not yield a private key"))?;
+                let coin_type = if network == Network::Bitcoin { 0 } else { 1 };
+                Self {
+                    descriptor: format!("wpkh({}/84h/{}h/0h/0/*)", xprv, coin_type),
+                    change_descriptor: Some(format!("wpkh({}/84h/{}h/0h/1/*)", xprv, coin_type)),
+                    mnemonic: Some(mnemonic.to_string()),
+                }
+            },
+            Err(_) => {
+                let descriptor = material.split('#').next().unwrap_or(material);
+                Self {
+                    descriptor: descriptor.to_string(),
+                    change_descriptor: descriptor.strip_suffix("/0/*)").map(|prefix| format!("{}/1/*)", prefix)),
+                    mnemonic: None,
+                }
+            },
+        };
+        
+        backup.validate(network)?;
+        Ok(backup)
+    }
+    
+    /// Check that the descriptors parse and their keys belong to `network`
+    pub fn validate(&self, network: Network) -> Result<()> {
+        Wallet::new(&self.descriptor, self.change_descriptor.as_deref(), network, MemoryDatabase::default())
+            .map_err(|e| anyhow!("Invalid descriptor for {}: {}", network, e))?;
+        Ok(())
+    }
+    
+    /// Read a backup from a wallet file
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read wallet file {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid wallet file {}", path.display()))
+    }
+    
+    /// Write the backup to a wallet file, replacing it atomically
+    ///
+    /// On Unix the file is only readable by its owner.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let tmp_path = path.with_extension("tmp");
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        
+        let mut file = options.open(&tmp_path)
+            .with_context(|| format!("Failed to create {}", tmp_path.display()))?;
+        std::io::Write::write_all(&mut file, serde_json::to_string_pretty(self)?.as_bytes())
+            .and_then(|_| file.sync_all())
+            .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+        std::fs::rename(&tmp_path, path)
+            .with_context(|| format!("Failed to replace wallet file {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// An unspent output owned by the wallet
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Utxo {
+    /// Outpoint of the output
+    pub outpoint: OutPoint,
+    /// The output itself (value and script)
+    pub txout: TxOut,
+    /// Height of the block confirming the output, `None` if unconfirmed
+    pub height: Option<u32>,
+}
+
+/// Bitcoin and protorune balances of the wallet
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FullBalance {
+    /// Bitcoin balance
+    pub btc: bdk::Balance,
+    /// Protorune balances summed across revealed addresses, one per rune
+    pub runes: Vec<ProtoruneBalance>,
+}
+
+/// How UTXOs are screened with `ord_output` before being spent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrdCheck {
+    /// Exclude UTXOs with inscriptions, runes or rare sats, and fail when
+    /// ord can't be reached
+    Strict,
+    /// Like `Strict`, but a UTXO ord can't report on is spent with a warning
+    BestEffort,
+    /// Don't query ord; inscriptions and rare sats may be spent
+    Skip,
+}
+
+/// What a UTXO carries besides sats
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum UtxoContents {
+    /// Nothing but sats
+    Plain,
+    /// DIESEL and no other protorunes
+    Diesel,
+    /// Inscriptions, runes or non-DIESEL protorunes
+    Unsafe,
+}
+
+/// Errors raised while signing wallet transactions
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SigningError {
+    /// The input spends an output the wallet doesn't know
+    #[error("Input {index} does not spend a wallet output")]
+    UnknownInput {
+        /// Index of the offending input
+        index: usize,
+    },
+    /// The input requests a sighash type other than SIGHASH_ALL (or
+    /// SIGHASH_DEFAULT for taproot)
+    #[error("Input {index} requests sighash {sighash}, only SIGHASH_ALL (or SIGHASH_DEFAULT for taproot) is signed")]
+    SighashMismatch {
+        /// Index of the offending input
+        index: usize,
+        /// The requested sighash type
+        sighash: String,
+    },
+    /// The wallet holds no key able to sign the input
+    #[error("No key available to sign input {index}")]
+    MissingKey {
+        /// Index of the offending input
+        index: usize,
+    },
 }
 
 /// Bitcoin wallet manager
+///
+/// The BDK wallet sits behind a plain (non-async) mutex that is only taken
+/// through `with_wallet`, for in-memory work such as deriving an address or
+/// signing. Network calls happen before or after, never with the lock held,
+/// so a slow sync or UTXO lookup doesn't hold up `get_address`.
 pub struct WalletManager {
-    /// BDK wallet instance
+    /// BDK wallet instance, only locked through `with_wallet`
     wallet: Arc<Mutex<Wallet<MemoryDatabase>>>,
     /// Wallet configuration
     config: WalletConfig,
@@ -43,11 +237,76 @@ pub struct WalletManager {
     backend: SandshrewEsploraBackend,
     /// RPC client
     rpc_client: Arc<RpcClient>,
+    /// BIP 39 mnemonic of the wallet, if it was created from one
+    mnemonic: Option<String>,
+    /// Protorune balances of confirmed outpoints, which never change
+    protorune_cache: Mutex<HashMap<OutPoint, Vec<ProtoruneBalance>>>,
 }
 
 impl WalletManager {
     /// Create a new wallet manager
+    ///
+    /// Key material is read from the wallet file if it exists; otherwise the
+    /// wallet is watch-only over the default descriptors.
     pub async fn new(config: WalletConfig) -> Result<Self> {
+        let wallet_path = Path::new(&config.wallet_path);
+        if !wallet_path.exists() {
+            info!("No wallet file at {}, using the default watch-only wallet", config.wallet_path);
+            return Self::from_descriptors(config, DEFAULT_DESCRIPTOR, Some(DEFAULT_CHANGE_DESCRIPTOR)).await;
+        }
+        
+        info!("Loading wallet from {}", config.wallet_path);
+        let backup = WalletBackup::load(wallet_path)?;
+        let mut manager = Self::from_descriptors(config, &backup.descriptor, backup.change_descriptor.as_deref()).await?;
+        manager.mnemonic = backup.mnemonic;
+        Ok(manager)
+    }
+    
+    /// Replace the wallet file with key material from a mnemonic or descriptor
+    ///
+    /// The material is validated against the configured network before the
+    /// existing file is touched.
+    pub fn import(config: &WalletConfig, material: &str) -> Result<WalletBackup> {
+        let backup = WalletBackup::from_material(material, config.network)?;
+        backup.write(Path::new(&config.wallet_path))?;
+        
+        info!("Wallet file {} reinitialized", config.wallet_path);
+        Ok(backup)
+    }
+    
+    /// Export the wallet's descriptors for backup
+    ///
+    /// Without `include_private` the descriptors carry public keys only and
+    /// no mnemonic is returned.
+    pub async fn export(&self, include_private: bool) -> Result<WalletBackup> {
+        self.with_wallet(|wallet| -> Result<WalletBackup> {
+            let export_descriptor = |keychain: KeychainKind| -> Result<Option<String>> {
+                let descriptor = match wallet.public_descriptor(keychain)? {
+                    Some(descriptor) => descriptor,
+                    None => return Ok(None),
+                };
+                if include_private {
+                    let key_map = wallet.get_signers(keychain).as_key_map(wallet.secp_ctx());
+                    Ok(Some(descriptor.to_string_with_secret(&key_map)))
+                } else {
+                    Ok(Some(descriptor.to_string()))
+                }
+            };
+            
+            Ok(WalletBackup {
+                descriptor: export_descriptor(KeychainKind::External)?
+                    .ok_or_else(|| anyhow!("Wallet has no descriptor"))?,
+                change_descriptor: export_descriptor(KeychainKind::Internal)?,
+                mnemonic: if include_private { self.mnemonic.clone() } else { None },
+            })
+        })
+    }
+    
+    /// Create a wallet manager for the given descriptors
+    ///
+    /// Descriptors holding private keys give the wallet signers; public-only
+    /// descriptors make it watch-only.
+    pub async fn from_descriptors(config: WalletConfig, descriptor: &str, change_descriptor: Option<&str>) -> Result<Self> {
         info!("Initializing wallet manager");
         debug!("Wallet path: {}", config.wallet_path);
         debug!("Network: {:?}", config.network);
@@ -56,34 +315,23 @@ impl WalletManager {
         let rpc_config = crate::rpc::RpcConfig {
             bitcoin_rpc_url: config.bitcoin_rpc_url.clone(),
             metashrew_rpc_url: config.metashrew_rpc_url.clone(),
+            ..Default::default()
         };
         let rpc_client = Arc::new(RpcClient::new(rpc_config));
         
         // Create custom Esplora backend
-        let backend = SandshrewEsploraBackend::new(Arc::clone(&rpc_client));
-        
-        // Check if wallet file exists
-        let wallet_path = Path::new(&config.wallet_path);
-        let wallet = if wallet_path.exists() {
-            info!("Loading wallet from {}", config.wallet_path);
-            // TODO: Implement wallet loading from file
-            // For now, create a new wallet in memory
-            Wallet::new(
-                "wpkh([c258d2e4/84h/1h/0h]tpubDDYkZojQFQjht8Tm4jsS3iuEmKjTiEGjG6KnuFNKKJb5A6ZUCUZKdvLdSDWofKi4ToRCwb9poe1XdqfUnP4jaJjCB2Zwv11ZLgSbnZSNecE/0/*)",
-                Some("wpkh([c258d2e4/84h/1h/0h]tpubDDYkZojQFQjht8Tm4jsS3iuEmKjTiEGjG6KnuFNKKJb5A6ZUCUZKdvLdSDWofKi4ToRCwb9poe1XdqfUnP4jaJjCB2Zwv11ZLgSbnZSNecE/1/*)"),
-                config.network,
-                MemoryDatabase::default(),
-            )?
-        } else {
-            info!("Creating new wallet");
-            Wallet::new(
-                "wpkh([c258d2e4/84h/1h/0h]tpubDDYkZojQFQjht8Tm4jsS3iuEmKjTiEGjG6KnuFNKKJb5A6ZUCUZKdvLdSDWofKi4ToRCwb9poe1XdqfUnP4jaJjCB2Zwv11ZLgSbnZSNecE/0/*)",
-                Some("wpkh([c258d2e4/84h/1h/0h]tpubDDYkZojQFQjht8Tm4jsS3iuEmKjTiEGjG6KnuFNKKJb5A6ZUCUZKdvLdSDWofKi4ToRCwb9poe1XdqfUnP4jaJjCB2Zwv11ZLgSbnZSNecE/1/*)"),
-                config.network,
-                MemoryDatabase::default(),
-            )?
+        let backend = match &config.esplora_url {
+            Some(base_url) => SandshrewEsploraBackend::with_url(base_url.clone()),
+            None => SandshrewEsploraBackend::new(Arc::clone(&rpc_client)),
         };
         
+        let wallet = Wallet::new(
+            descriptor,
+            change_descriptor,
+            config.network,
+            MemoryDatabase::default(),
+        )?;
+        
         info!("Wallet initialized successfully");
         
         Ok(Self {
@@ -91,30 +339,63 @@ impl WalletManager {
             config,
             backend,
             rpc_client,
+            mnemonic: None,
+            protorune_cache: Mutex::new(HashMap::new()),
         })
     }
     
+    /// Run `f` with the wallet locked
+    ///
+    /// `f` can't await, so the lock is never held across a network call.
+    /// Callers fetch what they need first and lock only to read or update
+    /// the wallet.
+    fn with_wallet<T>(&self, f: impl FnOnce(&Wallet<MemoryDatabase>) -> T) -> T {
+        let wallet = self.wallet.lock().unwrap();
+        f(&wallet)
+    }
+    
     /// Get a new address from the wallet
     pub async fn get_address(&self) -> Result<String> {
-        let wallet = self.wallet.lock().await;
-        let address = wallet.get_address(AddressIndex::New)?;
+        let address = self.with_wallet(|wallet| wallet.get_address(AddressIndex::New))?;
+        Ok(address.to_string())
+    }
+    
+    /// Get the receive address at `index` without revealing it
+    ///
+    /// Unlike `get_address` this doesn't advance the address counter, so it
+    /// can re-derive known addresses or scan ahead of the last revealed one.
+    pub async fn get_address_at(&self, index: u32) -> Result<String> {
+        let address = self.with_wallet(|wallet| wallet.get_address(AddressIndex::Peek(index)))?;
+        Ok(address.to_string())
+    }
+    
+    /// Get the index of the last receive address handed out by `get_address`
+    pub async fn get_last_revealed_index(&self) -> Result<u32> {
+        let index = self.with_wallet(|wallet| wallet.database().get_last_index(KeychainKind::External))?;
+        index.ok_or_else(|| anyhow!("No receive address has been revealed yet"))
+    }
+    
+    /// Get a new change address from the wallet
+    pub async fn get_change_address(&self) -> Result<String> {
+        let address = self.with_wallet(|wallet| wallet.get_internal_address(AddressIndex::New))?;
         Ok(address.to_string())
     }
     
     /// Sync the wallet with the blockchain
-    pub async fn sync(&self) -> Result<()> {
+    ///
+    /// Returns the indexer sync status observed while syncing; a lagging
+    /// Metashrew means balances may be stale, and callers decide whether
+    /// that is acceptable.
+    pub async fn sync(&self) -> Result<SyncStatus> {
         info!("Syncing wallet with blockchain");
         
-        // First verify that Metashrew height is Bitcoin height + 1
-        let bitcoin_height = self.rpc_client.get_block_count().await?;
-        let metashrew_height = self.rpc_client.get_metashrew_height().await?;
-        
-        if metashrew_height != bitcoin_height + 1 {
+        // First check whether Metashrew height is Bitcoin height + 1
+        let sync_status = self.rpc_client.check_sync_status().await?;
+        if !sync_status.in_sync {
             warn!(
                 "Metashrew height ({}) is not Bitcoin height ({}) + 1",
-                metashrew_height, bitcoin_height
+                sync_status.metashrew_height, sync_status.bitcoin_height
             );
-            // Continue anyway, but log the warning
         }
         
         // For now, we're not actually syncing the wallet with the blockchain
@@ -130,16 +411,13 @@ impl WalletManager {
             balance.confirmed,
             balance.untrusted_pending);
         
-        Ok(())
+        Ok(sync_status)
     }
     
     /// Save wallet state to disk
     pub async fn save(&self) -> Result<()> {
         info!("Saving wallet state to {}", self.config.wallet_path);
         
-        // Serialize the wallet state
-        let wallet = self.wallet.lock().await;
-        
         // TODO: Implement proper wallet serialization
         // For now, this is just a placeholder
         // In a real implementation, we would:
@@ -153,8 +431,434 @@ impl WalletManager {
     
     /// Get the wallet balance
     pub async fn get_balance(&self) -> Result<bdk::Balance> {
-        let wallet = self.wallet.lock().await;
-        Ok(wallet.get_balance()?)
+        Ok(self.with_wallet(|wallet| wallet.get_balance())?)
+    }
+    
+    /// Get the wallet's Bitcoin balance along with its protorune balances
+    ///
+    /// Protorunes are queried with `alkanes_protorunesbyaddress` for every
+    /// revealed address; a rune held at several addresses is reported once
+    /// with the amounts summed, in order of first appearance.
+    pub async fn get_full_balance(&self) -> Result<FullBalance> {
+        let btc = self.get_balance().await?;
+        
+        let mut runes: Vec<ProtoruneBalance> = Vec::new();
+        for address in self.revealed_addresses().await? {
+            let protorunes = self.rpc_client.get_protorunes_by_address(&address.to_string()).await
+                .with_context(|| format!("Failed to get protorunes of {}", address))?;
+            for balance in parse_protorune_balances(&protorunes) {
+                match runes.iter_mut().find(|rune| (rune.block, rune.tx) == (balance.block, balance.tx)) {
+                    Some(rune) => {
+                        rune.amount = rune.amount.saturating_add(balance.amount);
+                        if rune.name.is_none() {
+                            rune.name = balance.name;
+                            rune.divisibility = balance.divisibility;
+                        }
+                    },
+                    None => runes.push(balance),
+                }
+            }
+        }
+        
+        Ok(FullBalance { btc, runes })
+    }
+    
+    /// List the wallet's unspent outputs
+    ///
+    /// The in-memory BDK database is not synced, so UTXOs are fetched from
+    /// esplora for every address the wallet has revealed, receive and change.
+    pub async fn list_utxos(&self) -> Result<Vec<Utxo>> {
+        let mut utxos: Vec<Utxo> = Vec::new();
+        
+        for address in self.revealed_addresses().await? {
+            let response = self.backend.get_address_utxos(&address.to_string()).await
+                .with_context(|| format!("Failed to get UTXOs of {}", address))?;
+            let entries = response.as_array()
+                .ok_or_else(|| anyhow!("Invalid UTXO response for {}", address))?;
+            
+            for entry in entries {
+                let outpoint = OutPoint {
+                    txid: entry["txid"].as_str()
+                        .and_then(|txid| Txid::from_str(txid).ok())
+                        .ok_or_else(|| anyhow!("Invalid txid in UTXO of {}", address))?,
+                    vout: entry["vout"].as_u64()
+                        .and_then(|vout| u32::try_from(vout).ok())
+                        .ok_or_else(|| anyhow!("Invalid vout in UTXO of {}", address))?,
+                };
+                let value = entry["value"].as_u64()
+                    .ok_or_else(|| anyhow!("Invalid value in UTXO of {}", address))?;
+                let height = entry["status"]["block_height"].as_u64()
+                    .filter(|_| entry["status"]["confirmed"].as_bool().unwrap_or(false))
+                    .and_then(|height| u32::try_from(height).ok());
+                
+                if !utxos.iter().any(|utxo| utxo.outpoint == outpoint) {
+                    utxos.push(Utxo {
+                        outpoint,
+                        txout: TxOut { value, script_pubkey: address.script_pubkey() },
+                        height,
+                    });
+                }
+            }
+        }
+        
+        debug!("Found {} UTXO(s)", utxos.len());
+        Ok(utxos)
+    }
+    
+    /// Get every receive and change address revealed so far
+    async fn revealed_addresses(&self) -> Result<Vec<Address>> {
+        self.with_wallet(|wallet| -> Result<Vec<Address>> {
+            let mut addresses = Vec::new();
+            
+            for keychain in [KeychainKind::External, KeychainKind::Internal] {
+                let last_index = wallet.database().get_last_index(keychain)?.unwrap_or(0);
+                for index in 0..=last_index {
+                    let info = match keychain {
+                        KeychainKind::External => wallet.get_address(AddressIndex::Peek(index))?,
+                        KeychainKind::Internal => wallet.get_internal_address(AddressIndex::Peek(index))?,
+                    };
+                    addresses.push(info.address);
+                }
+            }
+            
+            Ok(addresses)
+        })
+    }
+    
+    /// Check whether `script` pays to an address revealed by this wallet
+    pub async fn is_mine(&self, script: &Script) -> Result<bool> {
+        Ok(self.revealed_addresses().await?.iter().any(|address| address.script_pubkey() == *script))
+    }
+    
+    /// Drop UTXOs that are unsafe to spend as fee inputs
+    ///
+    /// A UTXO is dropped if `ord_output` reports inscriptions, runes or rare
+    /// sats on it (as far as `ord_check` allows), or if it holds any
+    /// protorune other than DIESEL. DIESEL-bearing outputs are kept so they
+    /// can be consolidated. Each distinct outpoint is only queried once per
+    /// call.
+    pub async fn filter_ordinal_safe(&self, utxos: Vec<Utxo>, ord_check: OrdCheck) -> Result<Vec<Utxo>> {
+        let classified = self.classify_utxos(utxos, ord_check).await?;
+        Ok(classified.into_iter()
+            .filter(|(_, contents)| *contents != UtxoContents::Unsafe)
+            .map(|(utxo, _)| utxo)
+            .collect())
+    }
+    
+    /// Keep only UTXOs carrying nothing but sats
+    ///
+    /// Like `filter_ordinal_safe`, but DIESEL-bearing outputs are dropped too,
+    /// for transactions that must not move any tokens.
+    pub async fn filter_plain(&self, utxos: Vec<Utxo>, ord_check: OrdCheck) -> Result<Vec<Utxo>> {
+        let classified = self.classify_utxos(utxos, ord_check).await?;
+        Ok(classified.into_iter()
+            .filter(|(_, contents)| *contents == UtxoContents::Plain)
+            .map(|(utxo, _)| utxo)
+            .collect())
+    }
+    
+    /// Classify the contents of each UTXO, querying each outpoint once
+    async fn classify_utxos(&self, utxos: Vec<Utxo>, ord_check: OrdCheck) -> Result<Vec<(Utxo, UtxoContents)>> {
+        let mut cache: HashMap<OutPoint, UtxoContents> = HashMap::new();
+        let mut classified = Vec::with_capacity(utxos.len());
+        
+        for utxo in utxos {
+            let contents = match cache.get(&utxo.outpoint) {
+                Some(contents) => *contents,
+                None => {
+                    let contents = self.utxo_contents(&utxo, ord_check).await?;
+                    cache.insert(utxo.outpoint, contents);
+                    contents
+                }
+            };
+            
+            if contents == UtxoContents::Unsafe {
+                info!("Excluding {} from spending: carries inscriptions, rare sats or non-DIESEL runes", utxo.outpoint);
+            }
+            classified.push((utxo, contents));
+        }
+        
+        Ok(classified)
+    }
+    
+    /// Fund, sign and finalize a transaction paying `outputs`
+    ///
+    /// Outputs keep their order so protostone vouts stay predictable; change,
+    /// if any, is appended after them. Inputs are chosen by BDK's coin
+    /// selection among ordinal-safe UTXOs only, screened per `ord_check`.
+    /// Every input gets `sequence`, which decides replaceability.
+    pub async fn fund_and_sign(&self, outputs: &[TxOut], fee_rate: f64, sequence: Sequence, lock_time: LockTime, ord_check: OrdCheck) -> Result<Transaction> {
+        // UTXOs are screened over the network before the wallet is locked
+        let unspendable = self.unsafe_outpoints(ord_check).await?;
+
+        let (psbt, details, finalized) = self.with_wallet(|wallet| -> Result<_> {
+            let (mut psbt, details) = Self::build_funded_psbt(wallet, outputs, fee_rate, unspendable, sequence, lock_time)?;
+            let finalized = wallet.sign(&mut psbt, SignOptions::default())
+                .context("Failed to sign transaction")?;
+            Ok((psbt, details, finalized))
+        })?;
+        if !finalized {
+            return Err(anyhow!("Wallet could not sign every input (is it watch-only?)"));
+        }
+
+        debug!("Funded transaction {} paying {:?} sats in fees", psbt.unsigned_tx.txid(), details.fee);
+        Ok(psbt.extract_tx())
+    }
+
+    /// Check whether the wallet holds no private keys
+    pub async fn is_watch_only(&self) -> bool {
+        self.with_wallet(Self::has_no_signers)
+    }
+    
+    /// Whether `wallet` holds no private keys
+    fn has_no_signers(wallet: &Wallet<MemoryDatabase>) -> bool {
+        wallet.get_signers(KeychainKind::External).signers().is_empty()
+            && wallet.get_signers(KeychainKind::Internal).signers().is_empty()
+    }
+    
+    /// Sign and finalize every input of a PSBT spending wallet outputs
+    ///
+    /// Each input must carry its `witness_utxo`. Inputs are first completed
+    /// with the wallet's derivation info (for taproot inputs, the internal
+    /// key and its origin), so a watch-only wallet still returns a PSBT that
+    /// external signers can complete; in that case nothing is signed and
+    /// `false` is returned. Taproot inputs are signed through the key path
+    /// with the tweaked key. Failures name the offending input through
+    /// `SigningError`.
+    pub async fn sign_psbt(&self, psbt: &mut PartiallySignedTransaction) -> Result<bool> {
+        self.with_wallet(|wallet| -> Result<bool> {
+            let watch_only = Self::has_no_signers(wallet);
+            
+            for (index, (input, txin)) in psbt.inputs.iter_mut().zip(&psbt.unsigned_tx.input).enumerate() {
+                let txout = input.witness_utxo.clone()
+                    .ok_or_else(|| anyhow!("Input {} is missing its witness UTXO", index))?;
+                
+                if let Some(sighash) = input.sighash_type {
+                    let signs_all = if txout.script_pubkey.is_v1_p2tr() {
+                        matches!(sighash.taproot_hash_ty(), Ok(TapSighashType::Default | TapSighashType::All))
+                    } else {
+                        sighash.ecdsa_hash_ty() == Ok(EcdsaSighashType::All)
+                    };
+                    if !signs_all {
+                        return Err(SigningError::SighashMismatch { index, sighash: sighash.to_string() }.into());
+                    }
+                }
+                
+                let keychain = wallet.database().get_path_from_script_pubkey(&txout.script_pubkey)?
+                    .map(|(keychain, _)| keychain)
+                    .ok_or(SigningError::UnknownInput { index })?;
+                let utxo = LocalUtxo {
+                    outpoint: txin.previous_output,
+                    txout: txout.clone(),
+                    keychain,
+                    is_spent: false,
+                };
+                let mut completed = wallet.get_psbt_input(utxo, input.sighash_type, true)
+                    .with_context(|| format!("Failed to complete input {}", index))?;
+                completed.witness_utxo = Some(txout);
+                *input = completed;
+            }
+            
+            if watch_only {
+                debug!("Wallet is watch-only, leaving {} input(s) unsigned", psbt.inputs.len());
+                return Ok(false);
+            }
+            
+            let sign_options = SignOptions {
+                trust_witness_utxo: true,
+                // Key path only: wallet descriptors commit to no scripts worth using
+                sign_with_tap_internal_key: true,
+                tap_leaves_options: TapLeavesOptions::None,
+                ..SignOptions::default()
+            };
+            wallet.sign(psbt, sign_options).context("Failed to sign transaction")?;
+            
+            if let Some(index) = psbt.inputs.iter().position(|input| input.final_script_witness.is_none() && input.final_script_sig.is_none()) {
+                return Err(SigningError::MissingKey { index }.into());
+            }
+            Ok(true)
+        })
+    }
+    
+    /// Get the outpoints of wallet UTXOs that must not be spent
+    async fn unsafe_outpoints(&self, ord_check: OrdCheck) -> Result<Vec<OutPoint>> {
+        let utxos = self.list_utxos().await?;
+        let safe = self.filter_ordinal_safe(utxos.clone(), ord_check).await?;
+        Ok(utxos.into_iter()
+            .filter(|utxo| !safe.contains(utxo))
+            .map(|utxo| utxo.outpoint)
+            .collect())
+    }
+
+    /// Run coin selection for `outputs`, excluding the `unspendable` outpoints
+    fn build_funded_psbt(
+        wallet: &Wallet<MemoryDatabase>,
+        outputs: &[TxOut],
+        fee_rate: f64,
+        unspendable: Vec<OutPoint>,
+        sequence: Sequence,
+        lock_time: LockTime,
+    ) -> Result<(PartiallySignedTransaction, TransactionDetails)> {
+        let mut builder = wallet.build_tx();
+        builder
+            .ordering(TxOrdering::Untouched)
+            .fee_rate(FeeRate::from_sat_per_vb(fee_rate as f32))
+            .unspendable(unspendable)
+            .nlocktime(lock_time);
+        for output in outputs {
+            builder.add_recipient(output.script_pubkey.clone(), output.value);
+        }
+        let (mut psbt, details) = builder.finish().context("Failed to fund transaction")?;
+        // BDK only sets replaceable sequences, so any sequence is applied here,
+        // before signing; it doesn't change the size or the fee
+        for input in &mut psbt.unsigned_tx.input {
+            input.sequence = sequence;
+        }
+        Ok((psbt, details))
+    }
+
+    /// Get the wallet UTXOs holding protorune `block:tx` and nothing else
+    ///
+    /// Each UTXO is returned with its raw balance of the rune. UTXOs that
+    /// also carry other protorunes are left out, as are those `ord_check`
+    /// finds inscriptions, runes or rare sats on.
+    pub async fn protorune_utxos(&self, block: u128, tx: u128, ord_check: OrdCheck) -> Result<Vec<(Utxo, u128)>> {
+        let rune_id = AlkaneId::new(block, tx);
+        let mut holdings = Vec::new();
+        for (utxo, balances) in self.protorune_holdings().await? {
+            if !holds_only_rune(&balances, &rune_id) || self.screen_ord_assets(&utxo.outpoint, ord_check).await? {
+                continue;
+            }
+            let amount = balances.iter().fold(0u128, |total, balance| total.saturating_add(balance.amount));
+            holdings.push((utxo, amount));
+        }
+        Ok(holdings)
+    }
+    
+    /// Find the wallet UTXOs carrying protorune `rune_id`
+    ///
+    /// Returns each outpoint with its raw amount of the rune, largest first,
+    /// including outputs that also carry other assets. A rune spread across
+    /// several outputs yields one entry per output.
+    pub async fn locate_rune(&self, rune_id: &AlkaneId) -> Result<Vec<(OutPoint, u128)>> {
+        let mut located: Vec<(OutPoint, u128)> = self.protorune_holdings().await?
+            .into_iter()
+            .filter_map(|(utxo, balances)| {
+                let amount = balances.iter()
+                    .filter(|balance| (balance.block, balance.tx) == (rune_id.block, rune_id.tx))
+                    .fold(0u128, |total, balance| total.saturating_add(balance.amount));
+                Some((utxo.outpoint, amount)).filter(|_| amount > 0)
+            })
+            .collect();
+        located.sort_by(|a, b| b.1.cmp(&a.1));
+        Ok(located)
+    }
+    
+    /// Get every wallet UTXO with its non-zero protorune balances
+    async fn protorune_holdings(&self) -> Result<Vec<(Utxo, Vec<ProtoruneBalance>)>> {
+        let mut holdings = Vec::new();
+        for utxo in self.list_utxos().await? {
+            let balances = self.protorune_balances(&utxo).await?;
+            holdings.push((utxo, balances));
+        }
+        Ok(holdings)
+    }
+    
+    /// Get the wallet's total balance of protorune `block:tx`
+    ///
+    /// Summed over `alkanes_protorunesbyaddress` for every revealed address,
+    /// so it includes outputs `protorune_utxos` would skip because they also
+    /// carry other assets.
+    pub async fn protorune_balance(&self, block: u128, tx: u128) -> Result<u128> {
+        let mut total = 0u128;
+        for address in self.revealed_addresses().await? {
+            let protorunes = self.rpc_client.get_protorunes_by_address(&address.to_string()).await
+                .with_context(|| format!("Failed to get protorunes of {}", address))?;
+            total = parse_protorune_balances(&protorunes)
+                .iter()
+                .filter(|balance| (balance.block, balance.tx) == (block, tx))
+                .fold(total, |total, balance| total.saturating_add(balance.amount));
+        }
+        Ok(total)
+    }
+    
+    /// Check whether `ord_output` reports inscriptions, runes or rare sats on an outpoint
+    async fn has_ord_assets(&self, outpoint: &OutPoint) -> Result<bool> {
+        let ord = self.rpc_client.get_ord_output(&outpoint.to_string()).await
+            .with_context(|| format!("Failed to get ordinal info for {}", outpoint))?;
+        
+        let has_inscriptions = ord.get("inscriptions")
+            .and_then(|v| v.as_array())
+            .map_or(false, |inscriptions| !inscriptions.is_empty());
+        let has_runes = match ord.get("runes") {
+            Some(serde_json::Value::Array(runes)) => !runes.is_empty(),
+            Some(serde_json::Value::Object(runes)) => !runes.is_empty(),
+            _ => false,
+        };
+        // Sat ranges are only reported by ord instances indexing sats
+        let has_rare_sats = ord.get("sat_ranges")
+            .and_then(|v| v.as_array())
+            .map_or(false, |ranges| ranges.iter().any(|range| match range.as_array().map(Vec::as_slice) {
+                Some([start, end]) => match (start.as_u64(), end.as_u64()) {
+                    (Some(start), Some(end)) => contains_rare_sat(start, end),
+                    _ => false,
+                },
+                _ => false,
+            }));
+        Ok(has_inscriptions || has_runes || has_rare_sats)
+    }
+    
+    /// Check an outpoint for inscriptions, runes or rare sats as `ord_check` says
+    async fn screen_ord_assets(&self, outpoint: &OutPoint, ord_check: OrdCheck) -> Result<bool> {
+        match ord_check {
+            OrdCheck::Skip => Ok(false),
+            OrdCheck::Strict => self.has_ord_assets(outpoint).await,
+            OrdCheck::BestEffort => Ok(self.has_ord_assets(outpoint).await.unwrap_or_else(|e| {
+                warn!("Spending {} without an ordinal check: {:#}", outpoint, e);
+                false
+            })),
+        }
+    }
+    
+    /// Get the non-zero protorune balances of a UTXO
+    ///
+    /// Confirmed UTXOs are looked up once and cached; unconfirmed ones may not
+    /// be indexed yet, so they are looked up every time.
+    async fn protorune_balances(&self, utxo: &Utxo) -> Result<Vec<ProtoruneBalance>> {
+        let outpoint = utxo.outpoint;
+        let cached = self.protorune_cache.lock().unwrap().get(&outpoint).cloned();
+        if let Some(balances) = cached {
+            return Ok(balances);
+        }
+        
+        let protorunes = self.rpc_client
+            .get_protorunes_by_outpoint(&outpoint.txid.to_string(), outpoint.vout).await
+            .with_context(|| format!("Failed to get protorunes for {}", outpoint))?;
+        let balances: Vec<ProtoruneBalance> = parse_protorune_balances(&protorunes)
+            .into_iter()
+            .filter(|balance| balance.amount > 0)
+            .collect();
+        
+        if utxo.height.is_some() {
+            self.protorune_cache.lock().unwrap().insert(outpoint, balances.clone());
+        }
+        Ok(balances)
+    }
+    
+    /// Check a single outpoint for inscriptions, rare sats and runes
+    async fn utxo_contents(&self, utxo: &Utxo, ord_check: OrdCheck) -> Result<UtxoContents> {
+        if self.screen_ord_assets(&utxo.outpoint, ord_check).await? {
+            return Ok(UtxoContents::Unsafe);
+        }
+        
+        let balances = self.protorune_balances(utxo).await?;
+        if balances.iter().any(|balance| (balance.block, balance.tx) != (diesel_id::BLOCK, diesel_id::TX)) {
+            Ok(UtxoContents::Unsafe)
+        } else if !balances.is_empty() {
+            Ok(UtxoContents::Diesel)
+        } else {
+            Ok(UtxoContents::Plain)
+        }
     }
     
     /// Get the RPC client
@@ -167,3 +871,423 @@ impl WalletManager {
         self.backend.clone()
     }
 }
+
+/// Whether `balances` are of protorune `rune_id` and nothing else
+fn holds_only_rune(balances: &[ProtoruneBalance], rune_id: &AlkaneId) -> bool {
+    !balances.is_empty() && balances.iter().all(|balance| (balance.block, balance.tx) == (rune_id.block, rune_id.tx))
+}
+
+/// Check whether the sat range `[start, end)` holds the first sat of a block
+///
+/// In ordinal theory the first sat of every block is uncommon or rarer, and
+/// worth far more than its face value.
+fn contains_rare_sat(start: u64, end: u64) -> bool {
+    let mut epoch_start = 0u64;
+    let mut subsidy = INITIAL_SUBSIDY;
+    while subsidy > 0 {
+        let epoch_end = epoch_start + subsidy * HALVING_INTERVAL;
+        if start < epoch_end {
+            // First sat of the first block starting at or after `start`
+            let block_start = epoch_start + (start - epoch_start).div_ceil(subsidy) * subsidy;
+            return block_start < end;
+        }
+        epoch_start = epoch_end;
+        subsidy /= 2;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::mock::MockRpcServer;
+    use bdk::bitcoin::ScriptBuf;
+    use serde_json::json;
+    
+    async fn test_wallet(server: &MockRpcServer) -> WalletManager {
+        WalletManager::new(WalletConfig {
+            wallet_path: "test_wallet.dat".to_string(),
+            network: Network::Testnet,
+            bitcoin_rpc_url: server.url.clone(),
+            metashrew_rpc_url: server.url.clone(),
+            esplora_url: None,
+        }).await.unwrap()
+    }
+    
+    fn utxo(byte: u8, vout: u32, value: u64) -> Utxo {
+        Utxo {
+            outpoint: OutPoint {
+                txid: Txid::from_str(&format!("{:02x}", byte).repeat(32)).unwrap(),
+                vout,
+            },
+            txout: TxOut { value, script_pubkey: ScriptBuf::new() },
+            height: None,
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_import_and_export_wallet() {
+        let server = MockRpcServer::start().await;
+        let path = std::env::temp_dir().join(format!("deezel_wallet_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let config = |network| WalletConfig {
+            wallet_path: path.to_string_lossy().to_string(),
+            network,
+            bitcoin_rpc_url: server.url.clone(),
+            metashrew_rpc_url: server.url.clone(),
+            esplora_url: None,
+        };
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        
+        // Invalid material is rejected before the file is written
+        assert!(WalletManager::import(&config(Network::Bitcoin), DEFAULT_DESCRIPTOR).is_err());
+        assert!(WalletManager::import(&config(Network::Testnet), "not a descriptor").is_err());
+        assert!(!path.exists());
+        
+        WalletManager::import(&config(Network::Testnet), mnemonic).unwrap();
+        let wallet = WalletManager::new(config(Network::Testnet)).await.unwrap();
+        assert!(!wallet.is_watch_only().await);
+        
+        let public = wallet.export(false).await.unwrap();
+        assert!(public.descriptor.contains("tpub"));
+        assert!(!public.descriptor.contains("tprv"));
+        assert!(public.change_descriptor.is_some());
+        assert!(public.mnemonic.is_none());
+        
+        let private = wallet.export(true).await.unwrap();
+        assert!(private.descriptor.contains("tprv"));
+        assert_eq!(private.mnemonic.as_deref(), Some(mnemonic));
+        
+        // Re-importing the public descriptor gives a watch-only wallet with
+        // the same addresses
+        let watch_only = WalletManager::import(&config(Network::Testnet), &public.descriptor).unwrap();
+        assert!(watch_only.change_descriptor.is_some());
+        let reloaded = WalletManager::new(config(Network::Testnet)).await.unwrap();
+        assert!(reloaded.is_watch_only().await);
+        assert_eq!(reloaded.get_address_at(0).await.unwrap(), wallet.get_address_at(0).await.unwrap());
+        
+        std::fs::remove_file(&path).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_filter_ordinal_safe() {
+        let server = MockRpcServer::start().await;
+        let wallet = test_wallet(&server).await;
+        
+        let clean = utxo(1, 0, 10_000);
+        let inscribed = utxo(2, 0, 10_000);
+        let foreign_rune = utxo(3, 1, 546);
+        let diesel = utxo(4, 0, 546);
+        
+        server.set("ord_output", json!({ "inscriptions": [], "runes": {} }));
+        server.set_with_params("ord_output", json!([inscribed.outpoint.to_string()]),
+            json!({ "inscriptions": [format!("{}i0", "22".repeat(32))], "runes": {} }));
+        
+        server.set("alkanes_protorunesbyoutpoint", json!([]));
+        server.set_with_params("alkanes_protorunesbyoutpoint",
+            json!([foreign_rune.outpoint.txid.to_string(), 1]),
+            json!([{ "rune": { "id": { "block": "2", "tx": "77" } }, "balance": "5" }]));
+        server.set_with_params("alkanes_protorunesbyoutpoint",
+            json!([diesel.outpoint.txid.to_string(), 0]),
+            json!([{ "rune": { "id": { "block": "2", "tx": "0" } }, "balance": "5" }]));
+        
+        let candidates = vec![clean.clone(), inscribed, foreign_rune, diesel.clone(), clean.clone()];
+        let safe = wallet.filter_ordinal_safe(candidates, OrdCheck::Strict).await.unwrap();
+        assert_eq!(safe, vec![clean.clone(), diesel, clean.clone()]);
+        
+        // The duplicated clean outpoint was only looked up once
+        let ord_calls = server.methods().iter().filter(|m| *m == "ord_output").count();
+        assert_eq!(ord_calls, 4);
+    }
+    
+    #[test]
+    fn test_contains_rare_sat() {
+        // First sats of the genesis block, block 1 and the first halving
+        assert!(contains_rare_sat(0, 1));
+        assert!(contains_rare_sat(INITIAL_SUBSIDY - 10, INITIAL_SUBSIDY + 1));
+        assert!(!contains_rare_sat(1, INITIAL_SUBSIDY));
+        let halving = INITIAL_SUBSIDY * HALVING_INTERVAL;
+        assert!(contains_rare_sat(halving, halving + 1));
+        assert!(!contains_rare_sat(halving + 1, halving + INITIAL_SUBSIDY / 2));
+        assert!(contains_rare_sat(halving + 1, halving + INITIAL_SUBSIDY / 2 + 1));
+        // Empty ranges hold nothing
+        assert!(!contains_rare_sat(INITIAL_SUBSIDY, INITIAL_SUBSIDY));
+    }
+    
+    #[tokio::test]
+    async fn test_ord_check_modes() {
+        let server = MockRpcServer::start().await;
+        let wallet = test_wallet(&server).await;
+        let clean = utxo(1, 0, 10_000);
+        let rare = utxo(2, 0, 10_000);
+        server.set("alkanes_protorunesbyoutpoint", json!([]));
+        
+        // ord is unreachable
+        let candidates = vec![clean.clone(), rare.clone()];
+        assert!(wallet.filter_plain(candidates.clone(), OrdCheck::Strict).await.is_err());
+        assert_eq!(wallet.filter_plain(candidates.clone(), OrdCheck::BestEffort).await.unwrap(), candidates);
+        
+        server.set("ord_output", json!({ "inscriptions": [], "runes": {}, "sat_ranges": [[1, 5_000]] }));
+        server.set_with_params("ord_output", json!([rare.outpoint.to_string()]),
+            json!({ "inscriptions": [], "runes": {}, "sat_ranges": [[INITIAL_SUBSIDY * 3, INITIAL_SUBSIDY * 3 + 10_000]] }));
+        for ord_check in [OrdCheck::Strict, OrdCheck::BestEffort] {
+            assert_eq!(wallet.filter_plain(candidates.clone(), ord_check).await.unwrap(), vec![clean.clone()]);
+        }
+        assert_eq!(wallet.filter_plain(candidates.clone(), OrdCheck::Skip).await.unwrap(), candidates);
+    }
+    
+    #[tokio::test]
+    async fn test_sign_psbt_reports_offending_input() {
+        use bdk::bitcoin::psbt::PsbtSighashType;
+        use bdk::bitcoin::TxIn;
+        
+        let server = MockRpcServer::start().await;
+        let (descriptor, change_descriptor) = TEST_SIGNING_DESCRIPTORS;
+        let wallet = WalletManager::from_descriptors(WalletConfig {
+            wallet_path: "test_wallet.dat".to_string(),
+            network: Network::Testnet,
+            bitcoin_rpc_url: server.url.clone(),
+            metashrew_rpc_url: server.url.clone(),
+            esplora_url: None,
+        }, descriptor, Some(change_descriptor)).await.unwrap();
+        assert!(!wallet.is_watch_only().await);
+        assert!(test_wallet(&server).await.is_watch_only().await);
+        
+        let own_script = Address::from_str(&wallet.get_address().await.unwrap()).unwrap().assume_checked().script_pubkey();
+        let psbt_spending = |scripts: &[ScriptBuf]| {
+            let tx = Transaction {
+                version: 2,
+                lock_time: LockTime::ZERO,
+                input: (0..scripts.len() as u32).map(|vout| TxIn {
+                    previous_output: utxo(1, vout, 0).outpoint,
+                    ..TxIn::default()
+                }).collect(),
+                output: vec![TxOut { value: 1_000, script_pubkey: own_script.clone() }],
+            };
+            let mut psbt = PartiallySignedTransaction::from_unsigned_tx(tx).unwrap();
+            for (input, script) in psbt.inputs.iter_mut().zip(scripts) {
+                input.witness_utxo = Some(TxOut { value: 10_000, script_pubkey: script.clone() });
+            }
+            psbt
+        };
+        
+        let mut psbt = psbt_spending(&[own_script.clone()]);
+        assert!(wallet.sign_psbt(&mut psbt).await.unwrap());
+        assert!(psbt.inputs[0].final_script_witness.is_some());
+        
+        let mut psbt = psbt_spending(&[own_script.clone(), ScriptBuf::from_bytes(vec![0; 22])]);
+        let err = wallet.sign_psbt(&mut psbt).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<SigningError>(), Some(&SigningError::UnknownInput { index: 1 }));
+        
+        let mut psbt = psbt_spending(&[own_script.clone(), own_script.clone()]);
+        psbt.inputs[1].sighash_type = Some(PsbtSighashType::from(EcdsaSighashType::None));
+        let err = wallet.sign_psbt(&mut psbt).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<SigningError>(), Some(SigningError::SighashMismatch { index: 1, .. })));
+    }
+    
+    #[tokio::test]
+    async fn test_sign_psbt_taproot_key_spend() {
+        use bdk::bitcoin::psbt::PsbtSighashType;
+        use bdk::bitcoin::secp256k1::{schnorr, Message, Secp256k1, XOnlyPublicKey};
+        use bdk::bitcoin::sighash::{Prevouts, SighashCache};
+        use bdk::bitcoin::TxIn;
+        
+        // BIP 86 descriptors of the test key
+        let server = MockRpcServer::start().await;
+        let descriptor = "tr(tprv8ZgxMBicQKsPdwM88UJeaCWa2ibCYrqzgktxtcRSKJLbsdZwrpv4wbpy6uko9BoNZEdpfWGVj8j3wD4L9BnkkWNEvbLSgG3RUtV3RZQKusn/86h/1h/0h/0/*)";
+        let change_descriptor = "tr(tprv8ZgxMBicQKsPdwM88UJeaCWa2ibCYrqzgktxtcRSKJLbsdZwrpv4wbpy6uko9BoNZEdpfWGVj8j3wD4L9BnkkWNEvbLSgG3RUtV3RZQKusn/86h/1h/0h/1/*)";
+        let wallet = WalletManager::from_descriptors(WalletConfig {
+            wallet_path: "test_wallet.dat".to_string(),
+            network: Network::Testnet,
+            bitcoin_rpc_url: server.url.clone(),
+            metashrew_rpc_url: server.url.clone(),
+            esplora_url: None,
+        }, descriptor, Some(change_descriptor)).await.unwrap();
+        let script = Address::from_str(&wallet.get_address().await.unwrap()).unwrap().assume_checked().script_pubkey();
+        assert!(script.is_v1_p2tr());
+        
+        let prevouts = vec![TxOut { value: 10_000, script_pubkey: script.clone() }];
+        let tx = Transaction {
+            version: 2,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn { previous_output: utxo(1, 0, 0).outpoint, ..TxIn::default() }],
+            output: vec![TxOut { value: 9_000, script_pubkey: script.clone() }],
+        };
+        let unsigned = || {
+            let mut psbt = PartiallySignedTransaction::from_unsigned_tx(tx.clone()).unwrap();
+            psbt.inputs[0].witness_utxo = Some(prevouts[0].clone());
+            psbt.inputs[0].sighash_type = Some(PsbtSighashType::from(TapSighashType::Default));
+            psbt
+        };
+        
+        let mut psbt = unsigned();
+        assert!(wallet.sign_psbt(&mut psbt).await.unwrap());
+        assert!(psbt.inputs[0].tap_internal_key.is_some());
+        let signed = psbt.extract_tx();
+        
+        // A lone 64-byte signature by the output key over the key-spend sighash
+        let witness: Vec<&[u8]> = signed.input[0].witness.iter().collect();
+        assert_eq!(witness.len(), 1);
+        assert_eq!(witness[0].len(), 64);
+        let sighash = SighashCache::new(&tx)
+            .taproot_key_spend_signature_hash(0, &Prevouts::All(&prevouts), TapSighashType::Default)
+            .unwrap();
+        let output_key = XOnlyPublicKey::from_slice(&script.as_bytes()[2..]).unwrap();
+        Secp256k1::verification_only()
+            .verify_schnorr(&schnorr::Signature::from_slice(witness[0]).unwrap(), &Message::from_slice(sighash.as_ref()).unwrap(), &output_key)
+            .unwrap();
+        
+        // Sighashes not committing to the whole transaction are refused
+        let mut psbt = unsigned();
+        psbt.inputs[0].sighash_type = Some(PsbtSighashType::from(TapSighashType::SinglePlusAnyoneCanPay));
+        let err = wallet.sign_psbt(&mut psbt).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<SigningError>(), Some(SigningError::SighashMismatch { index: 0, .. })));
+    }
+    
+    #[tokio::test]
+    async fn test_peek_does_not_advance_address_index() {
+        let server = MockRpcServer::start().await;
+        let wallet = test_wallet(&server).await;
+        assert!(wallet.get_last_revealed_index().await.is_err());
+        
+        let first = wallet.get_address().await.unwrap();
+        assert_eq!(wallet.get_last_revealed_index().await.unwrap(), 0);
+        assert_eq!(wallet.get_address_at(0).await.unwrap(), first);
+        
+        // Peeking ahead leaves the counter alone
+        let peeked = wallet.get_address_at(1).await.unwrap();
+        wallet.get_address_at(20).await.unwrap();
+        assert_eq!(wallet.get_last_revealed_index().await.unwrap(), 0);
+        
+        // New advances it, to the address peeked before
+        assert_eq!(wallet.get_address().await.unwrap(), peeked);
+        assert_eq!(wallet.get_last_revealed_index().await.unwrap(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_get_address_not_blocked_by_sync() {
+        // A node that accepts connections but never answers keeps sync in flight
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                connections.push(socket);
+            }
+        });
+        let wallet = WalletManager::new(WalletConfig {
+            wallet_path: "test_wallet.dat".to_string(),
+            network: Network::Testnet,
+            bitcoin_rpc_url: url.clone(),
+            metashrew_rpc_url: url,
+            esplora_url: None,
+        }).await.unwrap();
+        
+        let sync = wallet.sync();
+        tokio::pin!(sync);
+        let pending = tokio::time::timeout(std::time::Duration::from_millis(100), &mut sync).await;
+        assert!(pending.is_err(), "sync should still be waiting on the node");
+        
+        let (first, second) = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            tokio::join!(wallet.get_address(), wallet.get_address())
+        }).await.expect("get_address blocked while sync was in flight");
+        assert_ne!(first.unwrap(), second.unwrap());
+    }
+    
+    #[tokio::test]
+    async fn test_list_utxos_from_revealed_addresses() {
+        let server = MockRpcServer::start().await;
+        let wallet = test_wallet(&server).await;
+        let receive = wallet.get_address().await.unwrap();
+        
+        server.set("esplora_address::utxo", json!([]));
+        server.set_with_params("esplora_address::utxo", json!([receive]), json!([
+            { "txid": "11".repeat(32), "vout": 0, "value": 10_000, "status": { "confirmed": true } },
+            { "txid": "22".repeat(32), "vout": 3, "value": 546, "status": { "confirmed": false } },
+        ]));
+        
+        let utxos = wallet.list_utxos().await.unwrap();
+        assert_eq!(utxos.len(), 2);
+        assert_eq!(utxos[1].outpoint.vout, 3);
+        assert_eq!(utxos[0].txout.value, 10_000);
+        assert_eq!(utxos[0].txout.script_pubkey, Address::from_str(&receive).unwrap().assume_checked().script_pubkey());
+    }
+    
+    #[tokio::test]
+    async fn test_get_full_balance_sums_runes_across_addresses() {
+        let server = MockRpcServer::start().await;
+        let wallet = test_wallet(&server).await;
+        let receive = wallet.get_address().await.unwrap();
+        let change = wallet.get_change_address().await.unwrap();
+        
+        server.set("alkanes_protorunesbyaddress", json!([]));
+        server.set_with_params("alkanes_protorunesbyaddress", json!([receive]), json!([
+            { "rune": { "id": { "block": "2", "tx": "0" }, "name": "DIESEL", "divisibility": 8 }, "balance": "1500" },
+            { "rune": { "id": { "block": "2", "tx": "7" } }, "balance": "3" },
+        ]));
+        server.set_with_params("alkanes_protorunesbyaddress", json!([change]), json!([
+            { "rune": { "id": { "block": "2", "tx": "0" }, "name": "DIESEL", "divisibility": 8 }, "balance": "500" },
+        ]));
+        
+        let balance = wallet.get_full_balance().await.unwrap();
+        assert_eq!(balance.btc, bdk::Balance::default());
+        assert_eq!(balance.runes, vec![
+            ProtoruneBalance { block: 2, tx: 0, name: Some("DIESEL".to_string()), divisibility: 8, amount: 2000 },
+            ProtoruneBalance { block: 2, tx: 7, name: None, divisibility: 0, amount: 3 },
+        ]);
+    }
+    
+    #[tokio::test]
+    async fn test_locate_rune() {
+        let server = MockRpcServer::start().await;
+        let wallet = test_wallet(&server).await;
+        let receive = wallet.get_address().await.unwrap();
+        let confirmed = json!({ "confirmed": true, "block_height": 100 });
+        
+        server.set("esplora_address::utxo", json!([]));
+        server.set_with_params("esplora_address::utxo", json!([receive]), json!([
+            { "txid": "11".repeat(32), "vout": 0, "value": 546, "status": confirmed },
+            { "txid": "22".repeat(32), "vout": 1, "value": 546, "status": confirmed },
+            { "txid": "33".repeat(32), "vout": 2, "value": 546, "status": { "confirmed": false } },
+            { "txid": "44".repeat(32), "vout": 0, "value": 10_000, "status": confirmed },
+        ]));
+        server.set("ord_output", json!({ "inscriptions": [], "runes": {} }));
+        server.set("alkanes_protorunesbyoutpoint", json!([]));
+        server.set_with_params("alkanes_protorunesbyoutpoint", json!(["11".repeat(32), 0]),
+            json!([{ "rune": { "id": { "block": "2", "tx": "0" } }, "balance": "600" }]));
+        server.set_with_params("alkanes_protorunesbyoutpoint", json!(["22".repeat(32), 1]), json!([
+            { "rune": { "id": { "block": "2", "tx": "0" } }, "balance": "1000" },
+            { "rune": { "id": { "block": "2", "tx": "7" } }, "balance": "5" },
+        ]));
+        server.set_with_params("alkanes_protorunesbyoutpoint", json!(["33".repeat(32), 2]),
+            json!([{ "rune": { "id": { "block": "2", "tx": "0" } }, "balance": "200" }]));
+        let outpoint = |byte: &str, vout| OutPoint { txid: Txid::from_str(&byte.repeat(32)).unwrap(), vout };
+        
+        // Spread across three outputs, one of them shared with another rune
+        let located = wallet.locate_rune(&AlkaneId::new(2, 0)).await.unwrap();
+        assert_eq!(located, vec![(outpoint("22", 1), 1000), (outpoint("11", 0), 600), (outpoint("33", 2), 200)]);
+        assert_eq!(wallet.locate_rune(&AlkaneId::new(2, 7)).await.unwrap(), vec![(outpoint("22", 1), 5)]);
+        assert!(wallet.locate_rune(&AlkaneId::new(9, 9)).await.unwrap().is_empty());
+        
+        // Only the unconfirmed output is looked up again
+        let lookups = server.methods().iter().filter(|m| *m == "alkanes_protorunesbyoutpoint").count();
+        assert_eq!(lookups, 4 + 1 + 1);
+        
+        // The shared output can't be spent to move 2:0 alone
+        let spendable = |holdings: Vec<(Utxo, u128)>| -> Vec<OutPoint> {
+            holdings.into_iter().map(|(utxo, _)| utxo.outpoint).collect()
+        };
+        assert_eq!(spendable(wallet.protorune_utxos(2, 0, OrdCheck::Strict).await.unwrap()),
+            vec![outpoint("11", 0), outpoint("33", 2)]);
+        
+        // ord is unreachable: only a strict check refuses to spend
+        server.unset("ord_output");
+        assert!(wallet.protorune_utxos(2, 0, OrdCheck::Strict).await.is_err());
+        assert_eq!(spendable(wallet.protorune_utxos(2, 0, OrdCheck::BestEffort).await.unwrap()),
+            vec![outpoint("11", 0), outpoint("33", 2)]);
+        let ord_lookups = server.methods().iter().filter(|m| *m == "ord_output").count();
+        assert_eq!(spendable(wallet.protorune_utxos(2, 0, OrdCheck::Skip).await.unwrap()),
+            vec![outpoint("11", 0), outpoint("33", 2)]);
+        assert_eq!(server.methods().iter().filter(|m| *m == "ord_output").count(), ord_lookups);
+    }
+}